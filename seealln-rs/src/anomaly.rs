@@ -0,0 +1,245 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::interceptor::Action;
+
+// Heuristics for runaway agent loops that stay under the rate limit. Each looks at what
+// happened within SEEALLN_ANOMALY_WINDOW_MS (default 30000):
+//
+//   repeated_click     SEEALLN_ANOMALY_CLICKS (10) clicks in a row at the same point
+//   oscillating_moves  SEEALLN_ANOMALY_OSCILLATIONS (8) moves in a row bouncing between
+//                      the same two points
+//   repeated_denials   SEEALLN_ANOMALY_DENIALS (5) actions refused by the guardrails
+//
+// A hit disarms (see HandsState::observe), emits an `anomaly` event and is audited; the
+// agent has to re-arm, which a human can notice. SEEALLN_ANOMALY=0 turns detection off.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Thresholds {
+    pub clicks: usize,
+    pub oscillations: usize,
+    pub denials: usize,
+    pub window_ms: u64,
+}
+
+impl Thresholds {
+    // None when disabled.
+    pub fn from_env() -> Option<Self> {
+        if std::env::var("SEEALLN_ANOMALY").ok().as_deref() == Some("0") {
+            return None;
+        }
+        let num = |name: &str, default: u64| {
+            std::env::var(name)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+        Some(Self {
+            clicks: num("SEEALLN_ANOMALY_CLICKS", 10) as usize,
+            oscillations: num("SEEALLN_ANOMALY_OSCILLATIONS", 8) as usize,
+            denials: num("SEEALLN_ANOMALY_DENIALS", 5) as usize,
+            window_ms: num("SEEALLN_ANOMALY_WINDOW_MS", 30_000),
+        })
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Anomaly {
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Detector {
+    // Pointer position after the last executed move, when known.
+    pointer: Option<(i32, i32)>,
+    // Consecutive clicks of `button` at `pointer`.
+    clicks: VecDeque<Instant>,
+    button: Option<String>,
+    // Consecutive executed moves, newest last.
+    moves: VecDeque<(Instant, (i32, i32))>,
+    denials: VecDeque<Instant>,
+}
+
+// Keeps the `n` newest entries that are still inside the window.
+fn trim<T>(queue: &mut VecDeque<T>, n: usize, window: Duration, now: Instant, at: impl Fn(&T) -> Instant) {
+    while queue.len() > n || queue.front().is_some_and(|e| now.duration_since(at(e)) > window) {
+        queue.pop_front();
+    }
+}
+
+impl Detector {
+    pub fn executed(&mut self, t: &Thresholds, action: &Action, now: Instant) -> Option<Anomaly> {
+        let window = Duration::from_millis(t.window_ms);
+        match action {
+            Action::Move { x, y } => {
+                if self.pointer != Some((*x, *y)) {
+                    self.clicks.clear();
+                }
+                self.pointer = Some((*x, *y));
+                self.moves.push_back((now, (*x, *y)));
+                trim(&mut self.moves, t.oscillations + 1, window, now, |m| m.0);
+                // Every move returns to where the pointer was two moves earlier, and leaves.
+                let points: Vec<(i32, i32)> = self.moves.iter().map(|m| m.1).collect();
+                let bouncing = points.len() > t.oscillations
+                    && points.windows(3).all(|w| w[2] == w[0] && w[1] != w[0]);
+                if bouncing {
+                    self.moves.clear();
+                    let (a, b) = (points[0], points[1]);
+                    return Some(Anomaly {
+                        kind: "oscillating_moves",
+                        detail: format!("{} moves between {a:?} and {b:?}", t.oscillations),
+                    });
+                }
+            }
+            Action::Click { button } => {
+                self.moves.clear();
+                if self.button.as_ref() != Some(button) {
+                    self.clicks.clear();
+                    self.button = Some(button.clone());
+                }
+                self.clicks.push_back(now);
+                trim(&mut self.clicks, t.clicks, window, now, |c| *c);
+                if self.clicks.len() >= t.clicks {
+                    self.clicks.clear();
+                    let at = self.pointer.map(|p| format!(" at {p:?}")).unwrap_or_default();
+                    return Some(Anomaly {
+                        kind: "repeated_click",
+                        detail: format!("{} {button} clicks in a row{at}", t.clicks),
+                    });
+                }
+            }
+            // Anything else breaks both streaks; gestures also move the pointer somewhere unknown.
+            _ => {
+                self.clicks.clear();
+                self.moves.clear();
+                if matches!(action, Action::Gesture { .. }) {
+                    self.pointer = None;
+                }
+            }
+        }
+        None
+    }
+
+    pub fn denied(&mut self, t: &Thresholds, now: Instant) -> Option<Anomaly> {
+        self.denials.push_back(now);
+        trim(&mut self.denials, t.denials, Duration::from_millis(t.window_ms), now, |d| *d);
+        if self.denials.len() < t.denials {
+            return None;
+        }
+        self.denials.clear();
+        Some(Anomaly {
+            kind: "repeated_denials",
+            detail: format!("{} actions refused by guardrails within {} ms", t.denials, t.window_ms),
+        })
+    }
+
+    // A fresh arming starts with a clean history.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T: Thresholds = Thresholds {
+        clicks: 3,
+        oscillations: 4,
+        denials: 3,
+        window_ms: 1000,
+    };
+
+    fn click() -> Action {
+        Action::Click { button: "left".to_string() }
+    }
+
+    fn at(t0: Instant, ms: u64) -> Instant {
+        t0 + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn repeated_click() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        d.executed(&T, &Action::Move { x: 5, y: 5 }, t0);
+        assert!(d.executed(&T, &click(), at(t0, 1)).is_none());
+        assert!(d.executed(&T, &click(), at(t0, 2)).is_none());
+        let hit = d.executed(&T, &click(), at(t0, 3)).unwrap();
+        assert_eq!(hit.kind, "repeated_click");
+        assert!(hit.detail.contains("(5, 5)"));
+        // The streak starts over after a hit.
+        assert!(d.executed(&T, &click(), at(t0, 4)).is_none());
+    }
+
+    #[test]
+    fn clicks_elsewhere_or_with_another_button_break_the_streak() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        d.executed(&T, &click(), t0);
+        d.executed(&T, &click(), at(t0, 1));
+        d.executed(&T, &Action::Move { x: 1, y: 1 }, at(t0, 2));
+        assert!(d.executed(&T, &click(), at(t0, 3)).is_none());
+        assert!(d.executed(&T, &Action::Click { button: "right".to_string() }, at(t0, 4)).is_none());
+        assert!(d.executed(&T, &click(), at(t0, 5)).is_none());
+        assert!(d.executed(&T, &click(), at(t0, 6)).is_none());
+        assert!(d.executed(&T, &click(), at(t0, 7)).is_some());
+    }
+
+    #[test]
+    fn oscillating_moves() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        let points = [(0, 0), (100, 0), (0, 0), (100, 0)];
+        for (i, (x, y)) in points.into_iter().enumerate() {
+            assert!(d.executed(&T, &Action::Move { x, y }, at(t0, i as u64)).is_none());
+        }
+        let hit = d.executed(&T, &Action::Move { x: 0, y: 0 }, at(t0, 4)).unwrap();
+        assert_eq!(hit.kind, "oscillating_moves");
+
+        // Moves that go somewhere new are not a bounce.
+        let mut d = Detector::default();
+        for (i, x) in [0, 100, 0, 100, 50].into_iter().enumerate() {
+            assert!(d.executed(&T, &Action::Move { x, y: 0 }, at(t0, i as u64)).is_none());
+        }
+    }
+
+    #[test]
+    fn repeated_denials() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        assert!(d.denied(&T, t0).is_none());
+        assert!(d.denied(&T, at(t0, 1)).is_none());
+        assert_eq!(d.denied(&T, at(t0, 2)).unwrap().kind, "repeated_denials");
+        assert!(d.denied(&T, at(t0, 3)).is_none());
+    }
+
+    #[test]
+    fn old_entries_fall_out_of_the_window() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        d.denied(&T, t0);
+        d.denied(&T, at(t0, 10));
+        assert!(d.denied(&T, at(t0, 1500)).is_none());
+        assert!(d.denied(&T, at(t0, 1501)).is_none());
+        assert!(d.denied(&T, at(t0, 1502)).is_some());
+
+        d.executed(&T, &click(), t0);
+        d.executed(&T, &click(), at(t0, 10));
+        assert!(d.executed(&T, &click(), at(t0, 2000)).is_none());
+    }
+
+    #[test]
+    fn other_actions_reset_the_streaks() {
+        let (mut d, t0) = (Detector::default(), Instant::now());
+        d.executed(&T, &click(), t0);
+        d.executed(&T, &click(), at(t0, 1));
+        d.executed(&T, &Action::Key { combo: "enter".to_string() }, at(t0, 2));
+        assert!(d.executed(&T, &click(), at(t0, 3)).is_none());
+
+        for (i, x) in [0, 100, 0].into_iter().enumerate() {
+            d.executed(&T, &Action::Move { x, y: 0 }, at(t0, 10 + i as u64));
+        }
+        d.executed(&T, &Action::Scroll { dx: 0, dy: 1 }, at(t0, 20));
+        assert!(d.executed(&T, &Action::Move { x: 100, y: 0 }, at(t0, 21)).is_none());
+        assert!(d.executed(&T, &Action::Move { x: 0, y: 0 }, at(t0, 22)).is_none());
+    }
+}
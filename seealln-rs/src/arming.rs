@@ -0,0 +1,515 @@
+use serde_json::json;
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{origin::Origin, session::ActiveSession};
+
+// Arming lifecycle of the hands routes, including the kill switch:
+//
+//   Disarmed --arm--> Armed --disarm | ttl expiry--> Disarmed
+//   Armed --arm--> Armed                    (new token, new recorded session)
+//   Disarmed | Armed --kill--> Killed --reset--> Disarmed
+//
+// Arming while killed and resetting while not killed are refused. Killing twice keeps the
+// first record, so the original reason is not overwritten by a panicked second kill.
+#[derive(Debug)]
+pub enum Arming {
+    Disarmed {
+        // "startup", "disarm", "expired", "anomaly", "unsupervised", "revoked" or "reset".
+        reason: &'static str,
+        at: SystemTime,
+    },
+    Armed {
+        token: String,
+        // The /hands/arm caller; the token is only accepted from there.
+        origin: Origin,
+        profile: Profile,
+        // Only valid while a supervision stream watches it (`/hands/arm?supervised=1`).
+        supervised: bool,
+        // When this arming began; /hands/extend cannot push `until` past it plus max_total().
+        since: Instant,
+        until: Instant,
+        at: SystemTime,
+        session: Option<ActiveSession>,
+    },
+    Killed {
+        cause: KillCause,
+        at: SystemTime,
+    },
+}
+
+// Why and by whom the kill switch was engaged, for /safety/status, events and the audit.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct KillCause {
+    // What triggered it: one of KILL_SOURCES for /safety/kill callers, "anomaly" or "server"
+    // for the server's own fail-safes.
+    pub source: String,
+    pub reason: String,
+    // Who: an operator name, an agent id, a helper process.
+    pub by: String,
+}
+
+// Sources a /safety/kill caller may declare (the hotkey, watchdog and physical-input helpers
+// all report through the endpoint; "cluster" is a kill forwarded by a peer, see cluster.rs).
+pub const KILL_SOURCES: &[&str] = &["endpoint", "hotkey", "watchdog", "physical_input", "cluster"];
+
+// Guardrail bundle chosen per arming (`/hands/arm?profile=`), so a risky task can run more
+// carefully, or a trusted one faster, without touching the global config:
+//
+//   safe          actions cost SAFE_COST_FACTOR times their weight; every action, moves
+//                 included, needs a confirmation, whose answer carries the interceptor preview
+//                 (dry run); a confirmation is only usable SAFE_CONFIRM_DELAY after it was issued
+//   standard      the global settings (default)
+//   unrestricted  no rate limit; only offered with SEEALLN_ALLOW_UNRESTRICTED=1
+//
+// Interceptors, the kill switch and anomaly detection apply under every profile.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Profile {
+    Safe,
+    #[default]
+    Standard,
+    Unrestricted,
+}
+
+const SAFE_COST_FACTOR: f64 = 4.0;
+const SAFE_CONFIRM_DELAY: Duration = Duration::from_millis(1500);
+
+impl Profile {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "safe" => Some(Profile::Safe),
+            "standard" => Some(Profile::Standard),
+            "unrestricted" => Some(Profile::Unrestricted),
+            _ => None,
+        }
+    }
+
+    // Refusal for a profile this server does not offer.
+    pub fn allowed(self) -> Result<(), &'static str> {
+        match self {
+            Profile::Unrestricted if std::env::var("SEEALLN_ALLOW_UNRESTRICTED").ok().as_deref() != Some("1") => {
+                Err("profile unrestricted needs SEEALLN_ALLOW_UNRESTRICTED=1")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Multiplier on the rate-limit cost of an action.
+    pub fn cost_factor(self) -> f64 {
+        match self {
+            Profile::Safe => SAFE_COST_FACTOR,
+            Profile::Standard => 1.0,
+            Profile::Unrestricted => 0.0,
+        }
+    }
+
+    // Whether every action needs a confirmation, not just clicks, typing, keys and the like.
+    pub fn confirm_all(self) -> bool {
+        self == Profile::Safe
+    }
+
+    // How long a fresh confirmation waits before it can be used.
+    pub fn confirm_delay(self) -> Duration {
+        match self {
+            Profile::Safe => SAFE_CONFIRM_DELAY,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+// Who is behind an arming, as given to /hands/arm (`task_id`, `agent`, `run_url`), so the actions
+// of several agents sharing one machine can be told apart. Free text, never interpreted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Meta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub task_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_url: Option<String>,
+}
+
+pub const MAX_META: usize = 200;
+
+impl Meta {
+    // Trimmed fields; an over-long value or one with control characters is refused.
+    pub fn new(task_id: Option<String>, agent: Option<String>, run_url: Option<String>) -> Result<Self, String> {
+        let field = |name: &str, value: Option<String>| -> Result<Option<String>, String> {
+            let Some(value) = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty()) else {
+                return Ok(None);
+            };
+            if value.chars().count() > MAX_META || value.chars().any(char::is_control) {
+                return Err(format!("{name} must be at most {MAX_META} printable characters"));
+            }
+            Ok(Some(value))
+        };
+        Ok(Self {
+            task_id: field("task_id", task_id)?,
+            agent: field("agent", agent)?,
+            run_url: field("run_url", run_url)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.task_id.is_none() && self.agent.is_none() && self.run_url.is_none()
+    }
+}
+
+impl KillCause {
+    pub fn server(reason: &str) -> Self {
+        Self {
+            source: "server".to_string(),
+            reason: reason.to_string(),
+            by: "seealln".to_string(),
+        }
+    }
+}
+
+impl Default for Arming {
+    fn default() -> Self {
+        Arming::Disarmed {
+            reason: "startup",
+            at: SystemTime::now(),
+        }
+    }
+}
+
+// Longest an arming may last counting /hands/extend renewals (SEEALLN_ARM_MAX_TOTAL_MS,
+// default one hour); past that the client has to arm again.
+pub fn max_total() -> Duration {
+    static MAX: OnceLock<Duration> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        let ms = std::env::var("SEEALLN_ARM_MAX_TOTAL_MS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(3_600_000u64);
+        Duration::from_millis(ms.clamp(5_000, 86_400_000))
+    })
+}
+
+fn unix_ms(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+impl Arming {
+    fn disarmed(reason: &'static str) -> Self {
+        Arming::Disarmed {
+            reason,
+            at: SystemTime::now(),
+        }
+    }
+
+    pub fn is_killed(&self) -> bool {
+        matches!(self, Arming::Killed { .. })
+    }
+
+    pub fn is_armed(&self) -> bool {
+        matches!(self, Arming::Armed { .. })
+    }
+
+    // Moves an Armed state whose ttl has passed to Disarmed; returns its session to end.
+    pub fn expire(&mut self, now: Instant) -> Option<ActiveSession> {
+        match self {
+            Arming::Armed { until, .. } if now > *until => self.leave("expired"),
+            _ => None,
+        }
+    }
+
+    pub fn accepts(&self, token: &str) -> bool {
+        matches!(self, Arming::Armed { token: t, until, .. } if t == token && Instant::now() <= *until)
+    }
+
+    // Time left on `token`, if it is the live one.
+    pub fn expires_in(&self, token: &str) -> Option<Duration> {
+        match self {
+            Arming::Armed { until, .. } if self.accepts(token) => Some(until.saturating_duration_since(Instant::now())),
+            _ => None,
+        }
+    }
+
+    // Id of the recorded session, when armed and recording.
+    pub fn session_id(&self) -> Option<&str> {
+        match self {
+            Arming::Armed { session, .. } => session.as_ref().map(|s| s.id.as_str()),
+            _ => None,
+        }
+    }
+
+    // The origin the current token is bound to, when armed.
+    pub fn origin(&self) -> Option<&Origin> {
+        match self {
+            Arming::Armed { origin, .. } => Some(origin),
+            _ => None,
+        }
+    }
+
+    // Standard when not armed.
+    pub fn profile(&self) -> Profile {
+        match self {
+            Arming::Armed { profile, .. } => *profile,
+            _ => Profile::Standard,
+        }
+    }
+
+    // For a supervised arming: the id of its recorded session (what a supervision stream
+    // names), the origin it is bound to and when it began.
+    pub fn supervision(&self) -> Option<(Option<&str>, &Origin, Instant)> {
+        match self {
+            Arming::Armed { supervised: true, session, origin, since, .. } => Some((session.as_ref().map(|s| s.id.as_str()), origin, *since)),
+            _ => None,
+        }
+    }
+
+    pub fn session_mut(&mut self) -> Option<&mut ActiveSession> {
+        match self {
+            Arming::Armed { session, .. } => session.as_mut(),
+            _ => None,
+        }
+    }
+
+    // Returns the session of the previous arming, which the caller ends. `start` only runs
+    // when the transition is allowed.
+    pub fn arm(
+        &mut self,
+        token: String,
+        origin: Origin,
+        profile: Profile,
+        supervised: bool,
+        ttl: Duration,
+        start: impl FnOnce() -> Option<ActiveSession>,
+    ) -> Result<Option<ActiveSession>, &'static str> {
+        if self.is_killed() {
+            return Err("killed; POST /safety/reset first");
+        }
+        let previous = self.leave("rearm");
+        let now = Instant::now();
+        *self = Arming::Armed {
+            token,
+            origin,
+            profile,
+            supervised,
+            since: now,
+            until: now + ttl,
+            at: SystemTime::now(),
+            session: start(),
+        };
+        Ok(previous)
+    }
+
+    // Moves the expiry of `token`'s arming to `ttl` from now, but not past max_total() after it
+    // was armed and never earlier than it already is. Returns the time left afterwards and
+    // whether the cap cut the renewal short.
+    pub fn extend(&mut self, token: &str, ttl: Duration) -> Result<(Duration, bool), &'static str> {
+        if !self.accepts(token) {
+            return Err("not armed");
+        }
+        let Arming::Armed { since, until, .. } = self else {
+            return Err("not armed");
+        };
+        let now = Instant::now();
+        let cap = *since + max_total();
+        if cap <= *until {
+            return Err("maximum arming duration reached; arm again");
+        }
+        let wanted = now + ttl;
+        *until = wanted.min(cap).max(*until);
+        Ok((until.saturating_duration_since(now), wanted > cap))
+    }
+
+    // Disarming is idempotent and leaves a Killed state alone.
+    pub fn disarm(&mut self) -> Option<ActiveSession> {
+        match self {
+            Arming::Armed { .. } => self.leave("disarm"),
+            _ => None,
+        }
+    }
+
+    // Ends the arming named by its token or its recorded session id (POST /hands/token/revoke).
+    pub fn revoke(&mut self, token: Option<&str>, session: Option<&str>) -> Result<Option<ActiveSession>, &'static str> {
+        let matches = match &*self {
+            Arming::Armed { token: t, session: s, .. } => {
+                token.is_some_and(|token| t == token) || session.is_some_and(|id| s.as_ref().is_some_and(|s| s.id == id))
+            }
+            _ => false,
+        };
+        if !matches {
+            return Err("no live arming with that token or session");
+        }
+        Ok(self.leave("revoked"))
+    }
+
+    // Server-initiated disarm (anomaly.rs, lost supervision); only an Armed state can be paused.
+    pub fn pause(&mut self, reason: &'static str) -> Result<Option<ActiveSession>, &'static str> {
+        match self {
+            Arming::Armed { .. } => Ok(self.leave(reason)),
+            _ => Err("not armed"),
+        }
+    }
+
+    pub fn kill(&mut self, cause: KillCause) -> Result<Option<ActiveSession>, &'static str> {
+        if self.is_killed() {
+            return Err("already killed");
+        }
+        let session = match std::mem::take(self) {
+            Arming::Armed { session, .. } => session,
+            _ => None,
+        };
+        *self = Arming::Killed {
+            cause,
+            at: SystemTime::now(),
+        };
+        Ok(session)
+    }
+
+    pub fn reset(&mut self) -> Result<(), &'static str> {
+        if !self.is_killed() {
+            return Err("not killed");
+        }
+        *self = Arming::disarmed("reset");
+        Ok(())
+    }
+
+    fn leave(&mut self, reason: &'static str) -> Option<ActiveSession> {
+        match std::mem::replace(self, Arming::disarmed(reason)) {
+            Arming::Armed { session, .. } => session,
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    // State dump for /safety/status (never includes the token).
+    pub fn status(&self) -> serde_json::Value {
+        match self {
+            Arming::Disarmed { reason, at } => json!({"state": "disarmed", "reason": reason, "since_unix_ms": unix_ms(*at)}),
+            Arming::Armed { origin, profile, supervised, since, until, at, session, .. } => json!({
+                "state": "armed",
+                "bound_to": origin,
+                "binding": origin.strength(),
+                "profile": profile,
+                "supervised": supervised,
+                "since_unix_ms": unix_ms(*at),
+                "expires_in_ms": until.saturating_duration_since(Instant::now()).as_millis() as u64,
+                "extendable_to_ms": (*since + max_total()).saturating_duration_since(Instant::now()).as_millis() as u64,
+                "session": session.as_ref().map(|s| &s.id),
+            }),
+            Arming::Killed { cause, at } => json!({
+                "state": "killed",
+                "source": cause.source,
+                "reason": cause.reason,
+                "by": cause.by,
+                "since_unix_ms": unix_ms(*at),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn armed(ttl: Duration, session: Option<&str>) -> Arming {
+        let mut arming = Arming::default();
+        let session = session.map(ActiveSession::detached);
+        arming.arm("tok".to_string(), Origin::default(), Profile::Standard, false, ttl, || session).unwrap();
+        arming
+    }
+
+    fn cause(reason: &str) -> KillCause {
+        KillCause {
+            source: "endpoint".to_string(),
+            reason: reason.to_string(),
+            by: "test".to_string(),
+        }
+    }
+
+    fn disarm_reason(arming: &Arming) -> Option<&'static str> {
+        match arming {
+            Arming::Disarmed { reason, .. } => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn arm_refused_while_killed() {
+        let mut arming = Arming::default();
+        arming.kill(cause("stop")).unwrap();
+        let started = std::cell::Cell::new(false);
+        let result = arming.arm("tok".to_string(), Origin::default(), Profile::Standard, false, Duration::from_secs(60), || {
+            started.set(true);
+            None
+        });
+        assert!(result.is_err());
+        assert!(!started.get(), "no session is started for a refused arming");
+        assert!(arming.is_killed());
+    }
+
+    #[test]
+    fn second_kill_keeps_first_cause() {
+        let mut arming = armed(Duration::from_secs(60), Some("s1"));
+        let session = arming.kill(cause("first")).unwrap();
+        assert_eq!(session.map(|s| s.id), Some("s1".to_string()));
+        assert!(arming.kill(cause("second")).is_err());
+        assert_eq!(arming.status()["reason"], "first");
+    }
+
+    #[test]
+    fn reset_only_when_killed() {
+        let mut arming = armed(Duration::from_secs(60), None);
+        assert!(arming.reset().is_err());
+        assert!(arming.accepts("tok"));
+        arming.kill(cause("stop")).unwrap();
+        arming.reset().unwrap();
+        assert_eq!(disarm_reason(&arming), Some("reset"));
+    }
+
+    #[test]
+    fn extend_is_capped_at_max_total() {
+        let mut arming = armed(Duration::from_secs(1), None);
+        assert!(arming.extend("wrong", Duration::from_secs(10)).is_err());
+        let (left, capped) = arming.extend("tok", max_total() * 2).unwrap();
+        assert!(capped);
+        assert!(left <= max_total());
+        assert!(arming.extend("tok", Duration::from_secs(10)).is_err(), "nothing left to extend past the cap");
+        // A shorter renewal never pulls the expiry in.
+        let mut arming = armed(Duration::from_secs(600), None);
+        let (left, capped) = arming.extend("tok", Duration::from_secs(1)).unwrap();
+        assert!(!capped);
+        assert!(left > Duration::from_secs(500));
+    }
+
+    #[test]
+    fn revoke_by_token_or_session() {
+        let mut arming = armed(Duration::from_secs(60), Some("s1"));
+        assert!(arming.revoke(None, Some("s2")).is_err());
+        assert!(arming.revoke(Some("other"), None).is_err());
+        assert!(arming.accepts("tok"));
+        let session = arming.revoke(None, Some("s1")).unwrap();
+        assert_eq!(session.map(|s| s.id), Some("s1".to_string()));
+        assert_eq!(disarm_reason(&arming), Some("revoked"));
+        assert!(arming.revoke(Some("tok"), None).is_err(), "already revoked");
+
+        let mut arming = armed(Duration::from_secs(60), None);
+        assert!(arming.revoke(Some("tok"), None).unwrap().is_none());
+        assert!(!arming.is_armed());
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let mut arming = armed(Duration::from_secs(60), Some("s1"));
+        assert!(arming.expire(Instant::now()).is_none());
+        assert!(arming.is_armed());
+        let session = arming.expire(Instant::now() + Duration::from_secs(61));
+        assert_eq!(session.map(|s| s.id), Some("s1".to_string()));
+        assert_eq!(disarm_reason(&arming), Some("expired"));
+        assert!(!arming.accepts("tok"));
+        // Expiry never touches a killed state.
+        arming.kill(cause("stop")).unwrap();
+        assert!(arming.expire(Instant::now() + Duration::from_secs(3600)).is_none());
+        assert!(arming.is_killed());
+    }
+}
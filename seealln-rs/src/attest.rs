@@ -0,0 +1,136 @@
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    path::Path,
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+use crate::secrets::{self, Secret};
+
+// Detached Ed25519 signatures over served frames and recordings (feature `frame-signing`), so
+// evidence can be shown to come from this host unmodified. On when the feature is built and
+// either a `signing_key` secret (SEEALLN_SIGNING_KEY, 32-byte seed in hex) is set or
+// SEEALLN_SIGN_FRAMES=1, which keeps a generated seed in <data dir>/signing.key. The public
+// key is in GET /version.
+//
+// What is signed is `seealln-frame:v1:<signed_at unix ms>:<hex sha256 of the bytes>`:
+//
+//   snapshots, /snapshot/next   x-seealln-frame-signature: t=<unix ms>,sig=<base64>
+//   stream parts                X-Seealln-Frame-Signature, same format
+//   recordings                  <file>.sig next to each frame image and, when the session
+//                               ends, the timeline: {alg, public_key, signed_at_ms, sha256,
+//                               signature}
+//
+// Files are signed as written before encryption at rest, i.e. what /admin/export returns.
+pub const HEADER: &str = "x-seealln-frame-signature";
+
+#[cfg(feature = "frame-signing")]
+type Key = ed25519_dalek::SigningKey;
+#[cfg(not(feature = "frame-signing"))]
+type Key = ();
+
+fn key_slot() -> &'static OnceLock<Option<Key>> {
+    static KEY: OnceLock<Option<Key>> = OnceLock::new();
+    &KEY
+}
+
+fn seed(data_dir: &Path) -> Option<[u8; 32]> {
+    if let Some(hex_seed) = secrets::get(Secret::SigningKey) {
+        let seed = hex::decode(hex_seed.trim()).ok().and_then(|b| <[u8; 32]>::try_from(b).ok());
+        if seed.is_none() {
+            warn!("signing_key must be 64 hex digits; frames are not signed");
+        }
+        return seed;
+    }
+    if std::env::var("SEEALLN_SIGN_FRAMES").ok().as_deref() != Some("1") {
+        return None;
+    }
+    let path = data_dir.join("signing.key");
+    if let Some(seed) = std::fs::read_to_string(&path).ok().and_then(|s| hex::decode(s.trim()).ok()) {
+        return <[u8; 32]>::try_from(seed).ok();
+    }
+    let mut seed = [0u8; 32];
+    getrandom::getrandom(&mut seed).expect("os rng");
+    let written = std::fs::create_dir_all(data_dir).and_then(|_| std::fs::write(&path, hex::encode(seed)));
+    if let Err(err) = written {
+        warn!(%err, path = %path.display(), "signing key not saved; it changes on restart");
+    }
+    Some(seed)
+}
+
+// Loads or creates the key; called once at startup.
+pub fn init(data_dir: &Path) {
+    let seed = seed(data_dir);
+    #[cfg(feature = "frame-signing")]
+    let key = seed.map(|s| ed25519_dalek::SigningKey::from_bytes(&s));
+    #[cfg(not(feature = "frame-signing"))]
+    let key: Option<Key> = {
+        if seed.is_some() {
+            warn!("frame signing is configured but this build lacks the `frame-signing` feature");
+        }
+        None
+    };
+    let _ = key_slot().set(key);
+    if let Some(public) = public_key() {
+        info!(%public, "signing frames (ed25519)");
+    }
+}
+
+// Base64 of the Ed25519 public key; None when not signing.
+#[cfg(feature = "frame-signing")]
+pub fn public_key() -> Option<String> {
+    use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+    key_slot().get()?.as_ref().map(|k| B64.encode(k.verifying_key().to_bytes()))
+}
+
+#[cfg(not(feature = "frame-signing"))]
+pub fn public_key() -> Option<String> {
+    None
+}
+
+#[cfg(feature = "frame-signing")]
+fn sign_message(message: &[u8]) -> Option<String> {
+    use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+    use ed25519_dalek::Signer;
+    let key = key_slot().get()?.as_ref()?;
+    Some(B64.encode(key.sign(message).to_bytes()))
+}
+
+#[cfg(not(feature = "frame-signing"))]
+fn sign_message(_message: &[u8]) -> Option<String> {
+    None
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+// (signed_at, hex sha256, base64 signature) of `data`; None when not signing.
+fn sign(data: &[u8]) -> Option<(u64, String, String)> {
+    key_slot().get()?.as_ref()?;
+    let at = unix_ms();
+    let digest = hex::encode(Sha256::digest(data));
+    let sig = sign_message(format!("seealln-frame:v1:{at}:{digest}").as_bytes())?;
+    Some((at, digest, sig))
+}
+
+// Next link of a hash chain: hex sha256 of `<prev>:<entry>` (empty prev for the first link).
+// Recordings and frame history chain their frames this way, so a frame deleted from or
+// moved within the sequence breaks every link after it. Unlike signatures, always on.
+pub fn link(prev: Option<&str>, entry: &str) -> String {
+    hex::encode(Sha256::digest(format!("{}:{entry}", prev.unwrap_or_default()).as_bytes()))
+}
+
+// Value of HEADER for a frame body.
+pub fn header_value(data: &[u8]) -> Option<String> {
+    sign(data).map(|(at, _, sig)| format!("t={at},sig={sig}"))
+}
+
+// Contents of a `.sig` sidecar for a stored file.
+pub fn sidecar(data: &[u8]) -> Option<Vec<u8>> {
+    let (at, digest, sig) = sign(data)?;
+    let doc = json!({"alg": "ed25519", "public_key": public_key(), "signed_at_ms": at, "sha256": digest, "signature": sig});
+    serde_json::to_vec(&doc).ok()
+}
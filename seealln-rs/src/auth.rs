@@ -0,0 +1,40 @@
+use axum::http::{HeaderMap, StatusCode};
+
+use crate::secrets::{self, Secret};
+
+// Shared-secret API key for privileged endpoints (e.g. decrypting exports).
+// Keychain entry `api_key` or SEEALLN_API_KEY; when unset, those endpoints stay closed.
+pub fn api_key() -> Option<String> {
+    secrets::get(Secret::ApiKey)
+}
+
+// Constant-time comparison so the key can't be recovered byte by byte via timing.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+pub fn require_api_key(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+    let Some(key) = api_key() else {
+        return Err((StatusCode::FORBIDDEN, "SEEALLN_API_KEY not configured"));
+    };
+    require_bearer(headers, &key)
+}
+
+pub fn require_bearer(headers: &HeaderMap, key: &str) -> Result<(), (StatusCode, &'static str)> {
+    match bearer(headers) {
+        Some(given) if ct_eq(given.as_bytes(), key.as_bytes()) => Ok(()),
+        Some(_) => Err((StatusCode::UNAUTHORIZED, "invalid api key")),
+        None => Err((StatusCode::UNAUTHORIZED, "missing authorization: Bearer <api key>")),
+    }
+}
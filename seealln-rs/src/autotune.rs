@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+// Stream quality auto-tuning (`/stream?target_ms=80`): keeps per-frame capture + encode time
+// under the target by trading JPEG quality first, then resolution. Recovers in the reverse
+// order once frames are comfortably fast again. Times are smoothed so one slow frame does
+// not trigger a step.
+const MIN_QUALITY: u8 = 35;
+const QUALITY_STEP: u8 = 10;
+const MIN_SCALE: f32 = 0.25;
+const SCALE_STEP: f32 = 0.8;
+// Frames to wait after a step before judging again, so the average reflects the new settings.
+const SETTLE_FRAMES: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct AutoTune {
+    target: Duration,
+    max_quality: u8,
+    pub quality: u8,
+    pub scale: f32,
+    avg_ms: Option<f64>,
+    settle: u32,
+}
+
+impl AutoTune {
+    pub fn new(target_ms: u64, max_quality: u8) -> Self {
+        Self {
+            target: Duration::from_millis(target_ms.clamp(10, 2000)),
+            max_quality,
+            quality: max_quality,
+            scale: 1.0,
+            avg_ms: None,
+            settle: 0,
+        }
+    }
+
+    pub fn target_ms(&self) -> u64 {
+        self.target.as_millis() as u64
+    }
+
+    // Feeds the capture + encode time of the frame just produced.
+    pub fn observe(&mut self, took: Duration) {
+        let ms = took.as_secs_f64() * 1000.0;
+        let avg = match self.avg_ms {
+            Some(avg) => avg * 0.7 + ms * 0.3,
+            None => ms,
+        };
+        self.avg_ms = Some(avg);
+        if self.settle > 0 {
+            self.settle -= 1;
+            return;
+        }
+
+        let target = self.target.as_secs_f64() * 1000.0;
+        if avg > target {
+            if self.quality > MIN_QUALITY {
+                self.quality = self.quality.saturating_sub(QUALITY_STEP).max(MIN_QUALITY);
+            } else if self.scale > MIN_SCALE {
+                self.scale = (self.scale * SCALE_STEP).max(MIN_SCALE);
+            } else {
+                return;
+            }
+        } else if avg < target * 0.6 {
+            if self.scale < 1.0 {
+                self.scale = (self.scale / SCALE_STEP).min(1.0);
+            } else if self.quality < self.max_quality {
+                self.quality = (self.quality + QUALITY_STEP).min(self.max_quality);
+            } else {
+                return;
+            }
+        } else {
+            return;
+        }
+        self.settle = SETTLE_FRAMES;
+    }
+}
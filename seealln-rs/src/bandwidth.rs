@@ -0,0 +1,178 @@
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Mutex, MutexGuard, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{origin::Origin, store::civil_date};
+
+// Bytes served per client on the frame routes (/snapshot, /snapshot/next, /stream), for
+// /stats. A client is its x-seealln-client-id when sent, else its peer address ("loopback"
+// for local callers), so agents behind one NAT can tell themselves apart by sending an id.
+//
+// Optional caps, per client, for monitoring over metered links:
+//
+//   SEEALLN_CLIENT_MAX_KBPS   sustained rate (kilobytes/s). Streams slow down to fit (fps
+//                             drops, nothing is skipped); snapshots over it answer 429 with
+//                             retry-after. Bursts of BURST_SECS worth of bytes are allowed.
+//   SEEALLN_CLIENT_DAILY_MB   bytes per UTC day. Past it snapshots answer 429 and open
+//                             streams end; the count resets at midnight UTC.
+//
+// Both default to 0 (off). Counters live in memory and reset on restart; the least recently
+// seen clients are forgotten past MAX_CLIENTS.
+const MAX_CLIENTS: usize = 256;
+const BURST_SECS: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Caps {
+    // Bytes per second.
+    rate: Option<f64>,
+    daily: Option<u64>,
+}
+
+fn caps() -> Caps {
+    static CAPS: OnceLock<Caps> = OnceLock::new();
+    *CAPS.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).ok().and_then(|s| s.trim().parse::<u64>().ok()).filter(|v| *v > 0);
+        Caps {
+            rate: var("SEEALLN_CLIENT_MAX_KBPS").map(|kb| kb as f64 * 1024.0),
+            daily: var("SEEALLN_CLIENT_DAILY_MB").map(|mb| mb * 1024 * 1024),
+        }
+    })
+}
+
+struct Client {
+    total: u64,
+    routes: BTreeMap<&'static str, u64>,
+    day: String,
+    today: u64,
+    // Token bucket in bytes; negative while a stream has sent ahead of its rate.
+    tokens: f64,
+    refilled: Instant,
+    seen: Instant,
+    seen_unix_ms: u64,
+    throttled: u64,
+    refused: u64,
+}
+
+fn clients() -> MutexGuard<'static, HashMap<String, Client>> {
+    static CLIENTS: OnceLock<Mutex<HashMap<String, Client>>> = OnceLock::new();
+    CLIENTS.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn now_unix() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+pub fn client_key(origin: &Origin) -> String {
+    match (&origin.client_id, &origin.peer) {
+        (Some(id), _) => format!("id:{id}"),
+        (None, Some(peer)) => peer.clone(),
+        (None, None) => "unknown".to_string(),
+    }
+}
+
+// The entry for `key` with its bucket refilled and its day rolled over.
+fn entry<'a>(map: &'a mut HashMap<String, Client>, key: &str, caps: Caps) -> &'a mut Client {
+    if !map.contains_key(key) && map.len() >= MAX_CLIENTS {
+        if let Some(oldest) = map.iter().min_by_key(|(_, c)| c.seen).map(|(k, _)| k.clone()) {
+            map.remove(&oldest);
+        }
+    }
+    let today = civil_date(now_unix().as_secs());
+    let client = map.entry(key.to_string()).or_insert_with(|| Client {
+        total: 0,
+        routes: BTreeMap::new(),
+        day: today.clone(),
+        today: 0,
+        tokens: caps.rate.map_or(0.0, |r| r * BURST_SECS),
+        refilled: Instant::now(),
+        seen: Instant::now(),
+        seen_unix_ms: 0,
+        throttled: 0,
+        refused: 0,
+    });
+    if client.day != today {
+        client.day = today;
+        client.today = 0;
+    }
+    if let Some(rate) = caps.rate {
+        let elapsed = client.refilled.elapsed().as_secs_f64();
+        client.tokens = (client.tokens + elapsed * rate).min(rate * BURST_SECS);
+    }
+    client.refilled = Instant::now();
+    client.seen = Instant::now();
+    client.seen_unix_ms = now_unix().as_millis() as u64;
+    client
+}
+
+// Whether `key` may be sent another frame now; Err carries the reason and, when waiting
+// helps, how long. Refusals are counted.
+pub fn admit(key: &str) -> Result<(), (String, Option<Duration>)> {
+    let caps = caps();
+    if caps.rate.is_none() && caps.daily.is_none() {
+        return Ok(());
+    }
+    let mut map = clients();
+    let client = entry(&mut map, key, caps);
+    if let Some(daily) = caps.daily.filter(|d| client.today >= *d) {
+        client.refused += 1;
+        return Err((format!("daily bandwidth quota of {} MB used up", daily / (1024 * 1024)), None));
+    }
+    if let Some(rate) = caps.rate.filter(|_| client.tokens < 0.0) {
+        client.refused += 1;
+        let wait = Duration::from_secs_f64(-client.tokens / rate);
+        return Err(("bandwidth cap reached".to_string(), Some(wait)));
+    }
+    Ok(())
+}
+
+// Counts `bytes` sent to `key` on `route` and returns how long the sender should pause before
+// the next frame to stay within the rate cap (zero without one).
+pub fn charge(key: &str, route: &'static str, bytes: usize) -> Duration {
+    let caps = caps();
+    let mut map = clients();
+    let client = entry(&mut map, key, caps);
+    client.total += bytes as u64;
+    client.today += bytes as u64;
+    *client.routes.entry(route).or_default() += bytes as u64;
+    let Some(rate) = caps.rate else {
+        return Duration::ZERO;
+    };
+    client.tokens -= bytes as f64;
+    if client.tokens >= 0.0 {
+        return Duration::ZERO;
+    }
+    client.throttled += 1;
+    Duration::from_secs_f64(-client.tokens / rate)
+}
+
+// Per-client totals for /stats, busiest first.
+pub fn json() -> serde_json::Value {
+    let caps = caps();
+    let map = clients();
+    let mut list: Vec<_> = map
+        .iter()
+        .map(|(key, c)| {
+            json!({
+                "client": key,
+                "bytes": c.total,
+                "by_route": c.routes,
+                "today_bytes": c.today,
+                "throttled": c.throttled,
+                "refused": c.refused,
+                "last_seen_unix_ms": c.seen_unix_ms,
+            })
+        })
+        .collect();
+    list.sort_by_key(|c| std::cmp::Reverse(c["bytes"].as_u64().unwrap_or(0)));
+    json!({
+        "total_bytes": map.values().map(|c| c.total).sum::<u64>(),
+        "caps": {
+            "max_kbps": caps.rate.map(|r| (r / 1024.0) as u64),
+            "daily_mb": caps.daily.map(|d| d / (1024 * 1024)),
+        },
+        "clients": list,
+    })
+}
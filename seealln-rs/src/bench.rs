@@ -0,0 +1,163 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    capture::{self, encode_jpeg, scale_image, Fallback},
+    hands::{confirmed, require_local_only},
+};
+
+// POST /bench: times capture (grab + pixel conversion) and, for every quality x scale
+// combination, resize + JPEG encode, over N iterations. Reports latency percentiles and the
+// frame rate each combination could sustain, so fps/q can be picked for the hardware.
+// CPU-heavy, so local-only, confirm-gated and one run at a time.
+const MAX_ITERATIONS: usize = 100;
+const MAX_COMBOS: usize = 12;
+
+#[derive(Debug, Deserialize)]
+pub struct BenchReq {
+    iterations: Option<usize>,
+    qualities: Option<Vec<u8>>,
+    scales: Option<Vec<f32>>,
+    display: Option<usize>,
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+fn ms(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn percentiles(samples: &mut [Duration]) -> Value {
+    samples.sort();
+    let at = |p: f64| ms(samples[((samples.len() - 1) as f64 * p).round() as usize]);
+    let mean = samples.iter().sum::<Duration>() / samples.len() as u32;
+    json!({"p50_ms": at(0.5), "p90_ms": at(0.9), "p99_ms": at(0.99), "max_ms": at(1.0), "mean_ms": ms(mean)})
+}
+
+struct Plan {
+    iterations: usize,
+    qualities: Vec<u8>,
+    scales: Vec<f32>,
+    display: Option<usize>,
+}
+
+fn plan(req: BenchReq) -> Result<Plan, String> {
+    let qualities = req.qualities.unwrap_or_else(|| vec![50, 70, 85]);
+    let scales = req.scales.unwrap_or_else(|| vec![1.0, 0.5]);
+    if qualities.is_empty() || scales.is_empty() || qualities.len() * scales.len() > MAX_COMBOS {
+        return Err(format!("need 1..={MAX_COMBOS} quality x scale combinations"));
+    }
+    if let Some(s) = scales.iter().find(|s| !(0.1..=1.0).contains(*s)) {
+        return Err(format!("scale {s} outside 0.1..=1.0"));
+    }
+    if let Some(i) = req.display.filter(|i| *i >= capture::displays().len()) {
+        return Err(format!("unknown display {i}"));
+    }
+    Ok(Plan {
+        iterations: req.iterations.unwrap_or(10).clamp(1, MAX_ITERATIONS),
+        qualities,
+        scales,
+        display: req.display,
+    })
+}
+
+fn run(plan: Plan) -> Result<Value, String> {
+    let Plan { iterations, qualities, scales, display } = plan;
+    let mut grab = Vec::with_capacity(iterations);
+    let mut combos: Vec<(u8, f32, Vec<Duration>, usize)> = qualities
+        .iter()
+        .flat_map(|q| scales.iter().map(move |s| (*q, *s, Vec::with_capacity(iterations), 0)))
+        .collect();
+    let mut size = (0, 0);
+    for _ in 0..iterations {
+        // A benchmark of the placeholder would be meaningless, so capture errors abort.
+        let t = Instant::now();
+        let frame = capture::capture_frame(display, Fallback::Fail)?;
+        grab.push(t.elapsed());
+        if frame.source != capture::Source::Live {
+            return Err(format!("capture is failing (served {})", frame.source.as_str()));
+        }
+        size = frame.image.dimensions();
+
+        for (q, scale, times, bytes) in combos.iter_mut() {
+            let t = Instant::now();
+            let jpeg = encode_jpeg(&scale_image(frame.image.clone(), *scale), *q)?;
+            times.push(t.elapsed());
+            *bytes += jpeg.len();
+        }
+    }
+
+    let grab_mean = grab.iter().sum::<Duration>() / iterations as u32;
+    let results: Vec<Value> = combos
+        .iter_mut()
+        .map(|(q, scale, times, bytes)| {
+            let encode_mean = times.iter().sum::<Duration>() / iterations as u32;
+            let per_frame = grab_mean + encode_mean;
+            json!({
+                "quality": capture::clamp_quality(*q),
+                "scale": scale,
+                "encode": percentiles(times),
+                "avg_bytes": *bytes / iterations,
+                "frame_ms": ms(per_frame),
+                "max_fps": 1.0 / per_frame.as_secs_f64().max(1e-6),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "ok": true,
+        "iterations": iterations,
+        "width": size.0,
+        "height": size.1,
+        "capture": percentiles(&mut grab),
+        "results": results,
+    }))
+}
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// Clears RUNNING when the run ends, even if the client went away meanwhile.
+struct Running;
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+pub async fn bench(headers: HeaderMap, Json(req): Json<BenchReq>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    let plan = match plan(req) {
+        Ok(plan) => plan,
+        Err(err) => return fail(StatusCode::BAD_REQUEST, err),
+    };
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return fail(StatusCode::CONFLICT, "a benchmark is already running");
+    }
+    let running = Running;
+    let result = tokio::task::spawn_blocking(move || {
+        let _running = running;
+        run(plan)
+    })
+    .await;
+    match result {
+        Ok(Ok(report)) => (StatusCode::OK, Json(report)).into_response(),
+        Ok(Err(err)) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+        Err(err) => fail(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
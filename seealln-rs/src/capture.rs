@@ -0,0 +1,767 @@
+#[cfg(feature = "capture")]
+use tracing::error;
+
+use serde_json::json;
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tracing::info;
+
+use crate::{events::Events, gpu, jpeg, pool, privacy, sim, vdisplay};
+
+pub fn clamp<T: PartialOrd>(v: T, lo: T, hi: T) -> T {
+    if v < lo {
+        lo
+    } else if v > hi {
+        hi
+    } else {
+        v
+    }
+}
+
+// Frame used when the screen cannot be captured. `placeholder` keeps endpoints serving the
+// test pattern (the historical behaviour), `fail` surfaces the error (503 over HTTP) and
+// `last_good_frame` repeats the most recent real frame of that display, if any.
+// Global default: SEEALLN_CAPTURE_FALLBACK; snapshot/stream accept ?fallback=.
+// Whatever the policy, a failure within SEEALLN_LAST_GOOD_MAX_AGE_MS (default 2000, 0 = off)
+// of the last real frame is treated as transient and that frame is served instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback {
+    Fail,
+    Placeholder,
+    LastGoodFrame,
+}
+
+impl Fallback {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "fail" => Some(Self::Fail),
+            "placeholder" => Some(Self::Placeholder),
+            "last_good_frame" | "last_good" => Some(Self::LastGoodFrame),
+            _ => None,
+        }
+    }
+
+    pub fn global() -> Self {
+        std::env::var("SEEALLN_CAPTURE_FALLBACK")
+            .ok()
+            .and_then(|v| Self::parse(&v))
+            .unwrap_or(Self::Placeholder)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Source {
+    Live,
+    LastGood { age: Duration },
+    Placeholder,
+}
+
+impl Source {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Source::Live => "live",
+            Source::LastGood { .. } => "last_good_frame",
+            Source::Placeholder => "placeholder",
+        }
+    }
+
+    // Age of the served frame for x-seealln-frame-age-ms; None for live frames.
+    pub fn age_ms(&self) -> Option<u64> {
+        match self {
+            Source::LastGood { age } => Some(age.as_millis() as u64),
+            _ => None,
+        }
+    }
+}
+
+fn last_good_max_age() -> Duration {
+    Duration::from_millis(
+        std::env::var("SEEALLN_LAST_GOOD_MAX_AGE_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2000),
+    )
+}
+
+pub struct Frame {
+    pub image: image::RgbImage,
+    pub source: Source,
+}
+
+// Reference point of the monotonic capture clock (/time `mono_ms`, recorded frame times);
+// touched at startup so it is the server start.
+pub fn mono_epoch() -> Instant {
+    static EPOCH: OnceLock<Instant> = OnceLock::new();
+    *EPOCH.get_or_init(Instant::now)
+}
+
+// `at` on the monotonic clock: ms since server start. Unlike wall time it never jumps.
+pub fn mono_ms(at: Instant) -> f64 {
+    at.saturating_duration_since(mono_epoch()).as_secs_f64() * 1000.0
+}
+
+// Ranges client-chosen settings are clamped to. The defaults are cautious; on a LAN with fast
+// hardware they can be widened:
+//
+//   SEEALLN_QUALITY_MIN / _MAX   JPEG quality, default 30-90 (1-100). Streams stop at 85
+//                                unless _MAX is set.
+//   SEEALLN_FPS_MIN / _MAX       /stream?fps=, default 1-15 (0.1-120).
+//
+// A bad or inverted pair is ignored (with a warning) and the defaults apply.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub quality: (u8, u8),
+    pub stream_quality_max: u8,
+    pub fps: (f32, f32),
+}
+
+fn range<T: std::str::FromStr + PartialOrd + Copy + std::fmt::Display>(name: &str, default: (T, T), bounds: (T, T)) -> (T, T) {
+    let read = |suffix: &str, fallback: T| {
+        std::env::var(format!("{name}_{suffix}")).ok().map(|v| v.trim().parse::<T>().ok()).unwrap_or(Some(fallback))
+    };
+    match (read("MIN", default.0), read("MAX", default.1)) {
+        (Some(lo), Some(hi)) if bounds.0 <= lo && lo <= hi && hi <= bounds.1 => (lo, hi),
+        _ => {
+            tracing::warn!("{name}_MIN/_MAX must satisfy {} <= min <= max <= {}; using {}-{}", bounds.0, bounds.1, default.0, default.1);
+            default
+        }
+    }
+}
+
+pub fn limits() -> &'static Limits {
+    static LIMITS: OnceLock<Limits> = OnceLock::new();
+    LIMITS.get_or_init(|| {
+        let quality = range("SEEALLN_QUALITY", (30u8, 90), (1, 100));
+        let stream_quality_max = if std::env::var_os("SEEALLN_QUALITY_MAX").is_some() { quality.1 } else { quality.1.min(85).max(quality.0) };
+        Limits {
+            quality,
+            stream_quality_max,
+            fps: range("SEEALLN_FPS", (1.0f32, 15.0), (0.1, 120.0)),
+        }
+    })
+}
+
+// Clamps a requested JPEG quality to the configured range.
+pub fn clamp_quality(quality: u8) -> u8 {
+    let (lo, hi) = limits().quality;
+    clamp(quality, lo, hi)
+}
+
+pub fn encode_jpeg(img: &image::RgbImage, quality: u8) -> Result<Vec<u8>, String> {
+    let quality = clamp_quality(quality);
+    let mut out = Vec::new();
+    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
+    encoder.encode_image(img).map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+// As encode_jpeg, with progressive / subsampling / restart options (see jpeg.rs); plain
+// baseline options use image's encoder.
+pub fn encode_jpeg_with(img: &image::RgbImage, quality: u8, opts: &jpeg::Options) -> Result<Vec<u8>, String> {
+    if opts.is_baseline() {
+        return encode_jpeg(img, quality);
+    }
+    jpeg::encode(img, clamp_quality(quality), opts)
+}
+
+// Downscales by `scale` (0 < scale <= 1); 1.0 returns the image unchanged. On the GPU when
+// there is one (see gpu.rs).
+pub fn scale_image(img: image::RgbImage, scale: f32) -> image::RgbImage {
+    if scale >= 1.0 {
+        return img;
+    }
+    let w = ((img.width() as f32 * scale) as u32).max(1);
+    let h = ((img.height() as f32 * scale) as u32).max(1);
+    let scaled = gpu::resize(&img, w, h).unwrap_or_else(|| image::imageops::resize(&img, w, h, image::imageops::FilterType::Triangle));
+    pool::BYTES.give(img.into_raw());
+    scaled
+}
+
+// Current screen as RGB, in screen pixel coordinates, under the global fallback policy.
+pub fn capture_rgb() -> Result<image::RgbImage, String> {
+    capture_frame(None, Fallback::global()).map(|f| f.image)
+}
+
+// Budget knobs for laptops and small machines:
+//
+//   SEEALLN_CAPTURE_MAX_WIDTH / _MAX_HEIGHT   served frames (snapshots, streams) are shrunk to
+//       fit, reported as x-seealln-scale; analysis endpoints keep screen pixels so coordinates
+//       stay exact. 0 (default) = native size.
+//   SEEALLN_FRAME_CACHE_MAX_BYTES   bound on the in-memory last-good frames (one per display);
+//       oldest first are dropped, 0 (default) = no bound.
+//   SEEALLN_LOW_POWER=1   once nothing has captured for LOW_POWER_IDLE (no streams, snapshots
+//       or watches), background polling (display layout) slows to LOW_POWER_POLL.
+const LOW_POWER_IDLE: Duration = Duration::from_secs(30);
+pub const LOW_POWER_POLL: Duration = Duration::from_secs(30);
+
+fn env_u64(name: &str) -> u64 {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(0)
+}
+
+// Downscale factor (<= 1) that fits a `w` x `h` frame into the served size limit.
+pub fn serve_scale((w, h): (u32, u32)) -> f32 {
+    static LIMIT: OnceLock<(u64, u64)> = OnceLock::new();
+    let (max_w, max_h) = *LIMIT.get_or_init(|| (env_u64("SEEALLN_CAPTURE_MAX_WIDTH"), env_u64("SEEALLN_CAPTURE_MAX_HEIGHT")));
+    let fit = |max: u64, v: u32| if max == 0 || v == 0 { 1.0 } else { (max as f32 / v as f32).min(1.0) };
+    fit(max_w, w).min(fit(max_h, h))
+}
+
+pub fn low_power() -> bool {
+    static ON: OnceLock<bool> = OnceLock::new();
+    *ON.get_or_init(|| std::env::var("SEEALLN_LOW_POWER").ok().as_deref() == Some("1"))
+}
+
+fn last_activity() -> std::sync::MutexGuard<'static, Instant> {
+    static LAST: OnceLock<Mutex<Instant>> = OnceLock::new();
+    LAST.get_or_init(|| Mutex::new(Instant::now())).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Time since the last capture request.
+pub fn idle_for() -> Duration {
+    last_activity().elapsed()
+}
+
+// Whether background work should slow down now.
+pub fn resting() -> bool {
+    low_power() && idle_for() >= LOW_POWER_IDLE
+}
+
+// `display` indexes `displays()`; None is the primary display.
+pub fn capture_frame(display: Option<usize>, fallback: Fallback) -> Result<Frame, String> {
+    *last_activity() = Instant::now();
+    let err = match grab_live(display) {
+        Ok(mut image) => {
+            privacy::apply(display, &mut image);
+            remember(display, &image);
+            return Ok(Frame {
+                image,
+                source: Source::Live,
+            });
+        }
+        Err(err) => err,
+    };
+    if let Some((image, age)) = last_good(display).filter(|(_, age)| *age <= last_good_max_age()) {
+        return Ok(Frame {
+            image,
+            source: Source::LastGood { age },
+        });
+    }
+    match fallback {
+        Fallback::Fail => Err(err),
+        Fallback::Placeholder => Ok(Frame {
+            image: placeholder(),
+            source: Source::Placeholder,
+        }),
+        Fallback::LastGoodFrame => last_good(display)
+            .map(|(image, age)| Frame {
+                image,
+                source: Source::LastGood { age },
+            })
+            .ok_or_else(|| format!("{err} (no earlier frame)")),
+    }
+}
+
+fn grab_live(display: Option<usize>) -> Result<image::RgbImage, String> {
+    if sim::active() {
+        return Ok(sim::render());
+    }
+    // Without DISPLAY the primary display is the default virtual one.
+    if display.is_none() {
+        if let Some(name) = vdisplay::default_display() {
+            return vdisplay::grab(&name).inspect_err(|err| {
+                tracing::warn!(%err, %name, "virtual display capture failed");
+            });
+        }
+    }
+    // Virtual displays are numbered after the physical ones.
+    if let Some(i) = display {
+        let physical = physical_displays().len();
+        if i >= physical {
+            if let Some((name, _, _)) = vdisplay::list().into_iter().nth(i - physical) {
+                return vdisplay::grab(&name).inspect_err(|err| {
+                    tracing::warn!(%err, %name, "virtual display capture failed");
+                });
+            }
+        }
+    }
+
+    #[cfg(feature = "capture")]
+    {
+        match workers::grab(display) {
+            Ok(img) => {
+                record_ok();
+                Ok(img)
+            }
+            Err(err) => {
+                let screen = display;
+                error!(%err, ?screen, "capture failed");
+                record_failure(err.clone());
+                Err(err)
+            }
+        }
+    }
+    #[cfg(not(feature = "capture"))]
+    {
+        let _ = display;
+        Err("screen capture disabled in this build".to_string())
+    }
+}
+
+// Most recent live frame per display.
+type LastGood = std::collections::HashMap<Option<usize>, (image::RgbImage, Instant)>;
+
+fn last_good_frames() -> std::sync::MutexGuard<'static, LastGood> {
+    static LAST_GOOD: std::sync::OnceLock<Mutex<LastGood>> = std::sync::OnceLock::new();
+    LAST_GOOD.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn remember(display: Option<usize>, image: &image::RgbImage) {
+    static BUDGET: OnceLock<u64> = OnceLock::new();
+    let budget = *BUDGET.get_or_init(|| env_u64("SEEALLN_FRAME_CACHE_MAX_BYTES"));
+    let mut frames = last_good_frames();
+    frames.insert(display, (image.clone(), Instant::now()));
+    if budget == 0 {
+        return;
+    }
+    let bytes = |frames: &LastGood| frames.values().map(|(img, _)| img.as_raw().len() as u64).sum::<u64>();
+    while bytes(&frames) > budget {
+        let Some(oldest) = frames.iter().min_by_key(|(_, (_, at))| *at).map(|(k, _)| *k) else {
+            break;
+        };
+        frames.remove(&oldest);
+    }
+}
+
+fn last_good(display: Option<usize>) -> Option<(image::RgbImage, Duration)> {
+    last_good_frames().get(&display).map(|(img, at)| (img.clone(), at.elapsed()))
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DisplayInfo {
+    pub index: usize,
+    pub width: u32,
+    pub height: u32,
+    pub placeholder: bool,
+    // X display name (":99") of a virtual display started via POST /displays/virtual.
+    #[serde(rename = "virtual", skip_serializing_if = "Option::is_none")]
+    pub virtual_display: Option<String>,
+}
+
+pub fn displays() -> Vec<DisplayInfo> {
+    let mut all = physical_displays();
+    let offset = all.len();
+    all.extend(vdisplay::list().into_iter().enumerate().map(|(i, (name, width, height))| DisplayInfo {
+        index: offset + i,
+        width,
+        height,
+        placeholder: false,
+        virtual_display: Some(name),
+    }));
+    all
+}
+
+fn physical_displays() -> Vec<DisplayInfo> {
+    if sim::active() {
+        let scene = sim::monitor();
+        return vec![DisplayInfo {
+            index: 0,
+            width: scene.w as u32,
+            height: scene.h as u32,
+            placeholder: false,
+            virtual_display: None,
+        }];
+    }
+
+    #[cfg(feature = "capture")]
+    {
+        if let Ok(all) = scrap::Display::all() {
+            if !all.is_empty() {
+                return all
+                    .iter()
+                    .enumerate()
+                    .map(|(index, d)| DisplayInfo {
+                        index,
+                        width: d.width() as u32,
+                        height: d.height() as u32,
+                        placeholder: false,
+                        virtual_display: None,
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    let p = placeholder();
+    vec![DisplayInfo {
+        index: 0,
+        width: p.width(),
+        height: p.height(),
+        placeholder: true,
+        virtual_display: None,
+    }]
+}
+
+// Backend health as seen by real capture attempts (all displays together).
+struct Health {
+    consecutive_failures: u32,
+    total_failures: u64,
+    last_ok: Option<Instant>,
+    last_error: Option<String>,
+    recoveries: u64,
+}
+
+static HEALTH: Mutex<Health> = Mutex::new(Health {
+    consecutive_failures: 0,
+    total_failures: 0,
+    last_ok: None,
+    last_error: None,
+    recoveries: 0,
+});
+
+fn health() -> std::sync::MutexGuard<'static, Health> {
+    HEALTH.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(feature = "capture")]
+fn record_ok() {
+    let mut h = health();
+    h.consecutive_failures = 0;
+    h.last_ok = Some(Instant::now());
+}
+
+#[cfg(feature = "capture")]
+fn record_failure(err: String) {
+    let mut h = health();
+    h.consecutive_failures += 1;
+    h.total_failures += 1;
+    h.last_error = Some(err);
+}
+
+pub fn health_json() -> serde_json::Value {
+    let h = health();
+    json!({
+        "consecutive_failures": h.consecutive_failures,
+        "total_failures": h.total_failures,
+        "last_ok_ms_ago": h.last_ok.map(|t| t.elapsed().as_millis() as u64),
+        "last_error": h.last_error,
+        "recoveries": h.recoveries,
+        "suspended": suspended(),
+        "gpu": gpu::adapter(),
+        "text_free": privacy::text_free(),
+        "excluded_windows": privacy::excluded_count(),
+    })
+}
+
+// Watchdog: once SEEALLN_CAPTURE_FAIL_THRESHOLD (default 3) captures in a row have failed or
+// timed out, emit `capture_degraded`, drop the capture workers and probe the primary display
+// with backoff. Success emits `capture_recovered`; giving up emits `capture_recovery_failed`
+// (forwarded by the webhook by default) once per outage, and the next check starts over.
+pub fn spawn_capture_watchdog(events: Events) {
+    #[cfg(feature = "capture")]
+    {
+        let threshold: u32 = std::env::var("SEEALLN_CAPTURE_FAIL_THRESHOLD")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(3)
+            .max(1);
+        let every = std::env::var("SEEALLN_CAPTURE_WATCHDOG_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::from_secs(10))
+            .max(Duration::from_secs(1));
+
+        tokio::spawn(async move {
+            let mut escalated = false;
+            let mut tick = tokio::time::interval(every);
+            loop {
+                tick.tick().await;
+                let (failures, error) = {
+                    let h = health();
+                    (h.consecutive_failures, h.last_error.clone())
+                };
+                if failures < threshold {
+                    escalated = false;
+                    continue;
+                }
+                events.emit("capture_degraded", json!({"consecutive_failures": failures, "error": error}));
+
+                let mut recovered = false;
+                for attempt in 0..3u32 {
+                    workers::reset();
+                    let probe = tokio::task::spawn_blocking(|| workers::grab(None)).await;
+                    if let Ok(Ok(_)) = probe {
+                        recovered = true;
+                        break;
+                    }
+                    tokio::time::sleep(Duration::from_secs(1 << attempt)).await;
+                }
+
+                if recovered {
+                    record_ok();
+                    health().recoveries += 1;
+                    escalated = false;
+                    info!("capture backend recovered");
+                    events.emit("capture_recovered", json!({"after_failures": failures}));
+                } else if !escalated {
+                    escalated = true;
+                    error!(failures, "capture backend recovery failed");
+                    events.emit("capture_recovery_failed", json!({"consecutive_failures": failures, "error": health().last_error}));
+                }
+            }
+        });
+    }
+    #[cfg(not(feature = "capture"))]
+    let _ = events;
+}
+
+#[cfg(feature = "capture")]
+static SUSPENDED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[cfg(feature = "capture")]
+fn suspended() -> bool {
+    SUSPENDED.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+#[cfg(not(feature = "capture"))]
+fn suspended() -> bool {
+    false
+}
+
+// Idle suspension: once nothing has captured for SEEALLN_CAPTURE_IDLE_SECS (default 60; 0
+// keeps capture up), no stream is open (paused ones included) and no session is recording,
+// the capture workers are dropped so the OS capture sessions (and their GPU/CPU cost) go
+// away, and `capture_suspended` is emitted. The next capture starts a worker again; a freshly
+// opened capturer throws away its first frame (often black or stale) before answering, and
+// `capture_resumed` is emitted at the next check.
+pub fn spawn_idle_suspend(events: Events) {
+    #[cfg(feature = "capture")]
+    {
+        let secs: u64 = std::env::var("SEEALLN_CAPTURE_IDLE_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(60);
+        if secs == 0 {
+            return;
+        }
+        let idle = Duration::from_secs(secs);
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(5).min(idle));
+            loop {
+                tick.tick().await;
+                let quiet = idle_for() >= idle && crate::stats::open_streams() == 0 && !crate::session::recording();
+                if quiet && !suspended() {
+                    workers::reset();
+                    SUSPENDED.store(true, std::sync::atomic::Ordering::Relaxed);
+                    info!(idle_secs = secs, "capture suspended (no consumers)");
+                    events.emit("capture_suspended", json!({"idle_secs": secs}));
+                } else if !quiet && suspended() {
+                    SUSPENDED.store(false, std::sync::atomic::Ordering::Relaxed);
+                    info!("capture resumed");
+                    events.emit("capture_resumed", json!({}));
+                }
+            }
+        });
+    }
+    #[cfg(not(feature = "capture"))]
+    let _ = events;
+}
+
+// Polls the display layout (SEEALLN_DISPLAY_POLL_MS, default 2000, LOW_POWER_POLL while
+// resting in low-power mode; 0 disables) and emits
+// display_added / display_removed / display_changed events. Any change drops the capture
+// workers so the next frame is taken with a capturer built for the new layout.
+pub fn spawn_display_monitor(events: Events) {
+    let ms: u64 = std::env::var("SEEALLN_DISPLAY_POLL_MS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(2000);
+    if ms == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let poll = || async { tokio::task::spawn_blocking(displays).await.unwrap_or_default() };
+        let mut prev = poll().await;
+        let mut polled = Instant::now();
+        let mut tick = tokio::time::interval(Duration::from_millis(ms.max(250)));
+        loop {
+            tick.tick().await;
+            if resting() && polled.elapsed() < LOW_POWER_POLL {
+                continue;
+            }
+            polled = Instant::now();
+            let now = poll().await;
+            if now == prev {
+                continue;
+            }
+            for i in 0..prev.len().max(now.len()) {
+                match (prev.get(i), now.get(i)) {
+                    (None, Some(d)) => events.emit("display_added", json!(d)),
+                    (Some(_), None) => events.emit("display_removed", json!({"index": i})),
+                    (Some(a), Some(b)) if a != b => events.emit(
+                        "display_changed",
+                        json!({"index": i, "from": {"width": a.width, "height": a.height}, "to": b}),
+                    ),
+                    _ => {}
+                }
+            }
+            info!(displays = now.len(), "display layout changed; rebuilding capture");
+            #[cfg(feature = "capture")]
+            workers::reset();
+            prev = now;
+        }
+    });
+}
+
+// Fallback placeholder (keeps endpoints stable)
+fn placeholder() -> image::RgbImage {
+    let width = 640;
+    let height = 360;
+    let mut imgbuf = image::RgbImage::new(width, height);
+    for (i, p) in imgbuf.pixels_mut().enumerate() {
+        let x = (i as u32) % width;
+        let y = (i as u32) / width;
+        let v = (((x ^ y) & 0x3F) as u8).saturating_add(16);
+        *p = image::Rgb([v, v, v.saturating_add(8)]);
+    }
+    imgbuf
+}
+
+// One long-lived capture thread per display, started on first use. The thread owns its
+// `scrap::Capturer` (not Send on every platform), so requests for different displays run
+// concurrently and nothing is re-created per request. Requests that queue up while a frame
+// is being grabbed are answered with that same frame.
+#[cfg(feature = "capture")]
+mod workers {
+    use image::RgbImage;
+    use std::{
+        collections::HashMap,
+        io::ErrorKind,
+        sync::{mpsc, Mutex, OnceLock},
+        thread,
+        time::Duration,
+    };
+
+    type Reply = mpsc::SyncSender<Result<RgbImage, String>>;
+
+    static WORKERS: OnceLock<Mutex<HashMap<Option<usize>, mpsc::Sender<Reply>>>> = OnceLock::new();
+
+    // Drops every worker; each thread exits (releasing its capturer) once its queue closes.
+    pub fn reset() {
+        if let Some(workers) = WORKERS.get() {
+            workers.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+    }
+
+    pub fn grab(display: Option<usize>) -> Result<RgbImage, String> {
+        let (reply, rx) = mpsc::sync_channel(1);
+        {
+            let mut workers = WORKERS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+            let tx = workers.entry(display).or_insert_with(|| spawn(display));
+            if tx.send(reply.clone()).is_err() {
+                // The worker died (e.g. panicked inside the platform backend); start a new one.
+                let tx = spawn(display);
+                tx.send(reply).map_err(|_| "capture worker unavailable".to_string())?;
+                workers.insert(display, tx);
+            }
+        }
+        rx.recv_timeout(Duration::from_secs(5))
+            .map_err(|_| "capture worker timeout".to_string())?
+    }
+
+    fn spawn(display: Option<usize>) -> mpsc::Sender<Reply> {
+        let (tx, rx) = mpsc::channel::<Reply>();
+        let name = match display {
+            Some(i) => format!("capture-{i}"),
+            None => "capture-primary".to_string(),
+        };
+        let _ = thread::Builder::new().name(name).spawn(move || run(display, rx));
+        tx
+    }
+
+    fn run(display: Option<usize>, rx: mpsc::Receiver<Reply>) {
+        let mut capturer: Option<scrap::Capturer> = None;
+        while let Ok(first) = rx.recv() {
+            let mut waiting = vec![first];
+            let result = frame(&mut capturer, display);
+            if result.is_err() {
+                // Rebuild on the next request (display reconfigured, session changed, ...).
+                capturer = None;
+            }
+            waiting.extend(rx.try_iter());
+            for reply in waiting {
+                let _ = reply.send(result.clone());
+            }
+        }
+    }
+
+    fn open(display: Option<usize>) -> Result<scrap::Capturer, String> {
+        let display = match display {
+            None => scrap::Display::primary().map_err(|e| format!("display: {e}"))?,
+            Some(i) => scrap::Display::all()
+                .map_err(|e| format!("display: {e}"))?
+                .into_iter()
+                .nth(i)
+                .ok_or_else(|| format!("display {i}: not found"))?,
+        };
+        scrap::Capturer::new(display).map_err(|e| format!("capturer: {e}"))
+    }
+
+    fn frame(capturer: &mut Option<scrap::Capturer>, display: Option<usize>) -> Result<RgbImage, String> {
+        let fresh = capturer.is_none();
+        if fresh {
+            *capturer = Some(open(display)?);
+        }
+        let capturer = capturer.as_mut().expect("capturer opened above");
+        // Warm-up: the first frame of a new capture session is often black or stale.
+        if fresh {
+            crate::pool::BYTES.give(grab_bgra(capturer)?);
+        }
+        let (w, h) = (capturer.width(), capturer.height());
+        let frame = grab_bgra(capturer)?;
+
+        // Convert BGRA -> RGB. Rows may be padded (stride > w * 4).
+        let stride = frame.len() / h.max(1);
+        if let Some(rgb) = crate::gpu::bgra_to_rgb(&frame, w as u32, h as u32, stride as u32) {
+            crate::pool::BYTES.give(frame);
+            return Ok(rgb);
+        }
+        let mut rgb = crate::pool::BYTES.take(w * h * 3);
+        for y in 0..h {
+            for x in 0..w {
+                let s = y * stride + x * 4;
+                let d = (y * w + x) * 3;
+                rgb[d] = frame[s + 2];
+                rgb[d + 1] = frame[s + 1];
+                rgb[d + 2] = frame[s];
+            }
+        }
+        crate::pool::BYTES.give(frame);
+
+        RgbImage::from_raw(w as u32, h as u32, rgb).ok_or_else(|| "rgb buffer: invalid".to_string())
+    }
+
+    fn grab_bgra(capturer: &mut scrap::Capturer) -> Result<Vec<u8>, String> {
+        // scrap returns BGRA. We must copy the frame bytes because `frame()` borrows from `capturer`.
+        let mut frame_copy: Option<Vec<u8>> = None;
+        for _ in 0..50 {
+            match capturer.frame() {
+                Ok(buf) => {
+                    let mut copy = crate::pool::BYTES.with_capacity(buf.len());
+                    copy.extend_from_slice(&buf);
+                    frame_copy = Some(copy);
+                    break;
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(10));
+                    continue;
+                }
+                Err(e) => return Err(format!("frame: {e}")),
+            }
+        }
+        frame_copy.ok_or_else(|| "frame: timeout".to_string())
+    }
+}
@@ -0,0 +1,155 @@
+use image::RgbImage;
+use serde::Serialize;
+use std::time::Duration;
+
+use crate::{
+    capture::{self, Fallback},
+    hands::ScopeRect,
+    vision,
+};
+
+// Text caret detection by frame differencing: a blinking caret is a thin vertical bar that
+// appears and disappears (about every 500 ms on most toolkits) while the pixels around it stay
+// put. Frames are sampled every SAMPLE_INTERVAL over the window; pixels that change between
+// samples are grouped into connected blobs, and a blob of caret shape (at most MAX_WIDTH wide,
+// MIN_HEIGHT..=MAX_HEIGHT tall, mostly filled) that toggled at least twice is the caret.
+// Anything else that moved (video, animations) is counted and otherwise ignored; with several
+// caret-shaped blobs the most toggled wins. Toolkits that do not blink (or with blinking turned
+// off) report no caret.
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(1200);
+pub const MAX_WINDOW: Duration = Duration::from_millis(5000);
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+const THRESHOLD: u8 = 48;
+const MAX_WIDTH: u32 = 4;
+const MIN_HEIGHT: u32 = 6;
+const MAX_HEIGHT: u32 = 80;
+// Larger change sets are not worth labeling: something big is animating.
+const MAX_CHANGED: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Caret {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    // Times the blob flipped between samples.
+    pub toggles: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub caret: Option<Caret>,
+    pub frames: usize,
+    pub window_ms: u64,
+    // Changed blobs that were not the caret.
+    pub other_changes: usize,
+}
+
+// Samples `display` (cropped to `region`) for `window` and looks for a blinking caret.
+// Blocking; call from spawn_blocking.
+pub fn detect(display: Option<usize>, region: Option<ScopeRect>, window: Duration) -> Result<Report, String> {
+    let window = window.clamp(SAMPLE_INTERVAL * 2, MAX_WINDOW);
+    let count = (window.as_millis() / SAMPLE_INTERVAL.as_millis()) as usize + 1;
+    let mut frames = Vec::with_capacity(count);
+    let mut origin = (0, 0);
+    for i in 0..count {
+        if i > 0 {
+            std::thread::sleep(SAMPLE_INTERVAL);
+        }
+        let frame = capture::capture_frame(display, Fallback::Fail)?;
+        let (img, ox, oy) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        origin = (ox, oy);
+        frames.push(img);
+    }
+    let (caret, other_changes) = locate(&frames);
+    Ok(Report {
+        caret: caret.map(|c| Caret { x: c.x + origin.0, y: c.y + origin.1, ..c }),
+        frames: frames.len(),
+        window_ms: window.as_millis() as u64,
+        other_changes,
+    })
+}
+
+fn changed(a: &RgbImage, b: &RgbImage, x: u32, y: u32) -> bool {
+    let (p, q) = (a.get_pixel(x, y).0, b.get_pixel(x, y).0);
+    (0..3).any(|c| p[c].abs_diff(q[c]) > THRESHOLD)
+}
+
+// Caret (image coordinates) and the number of other changed blobs.
+fn locate(frames: &[RgbImage]) -> (Option<Caret>, usize) {
+    let Some(first) = frames.first() else {
+        return (None, 0);
+    };
+    let (w, h) = first.dimensions();
+    if frames.iter().any(|f| f.dimensions() != (w, h)) {
+        // The display changed size mid-window.
+        return (None, 0);
+    }
+    // Per pixel: number of sample pairs in which it changed.
+    let mut flips = vec![0u32; (w * h) as usize];
+    for pair in frames.windows(2) {
+        for y in 0..h {
+            for x in 0..w {
+                if changed(&pair[0], &pair[1], x, y) {
+                    flips[(y * w + x) as usize] += 1;
+                }
+            }
+        }
+    }
+    if flips.iter().filter(|n| **n > 0).count() > MAX_CHANGED {
+        return (None, 1);
+    }
+
+    let mut seen = vec![false; flips.len()];
+    let mut best: Option<Caret> = None;
+    let mut others = 0;
+    for start in 0..flips.len() {
+        if flips[start] == 0 || seen[start] {
+            continue;
+        }
+        // Flood fill one 8-connected blob.
+        let (mut x0, mut y0, mut x1, mut y1) = (u32::MAX, u32::MAX, 0, 0);
+        let (mut pixels, mut toggles) = (0u32, 0u32);
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i as u32 % w, i as u32 / w);
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+            pixels += 1;
+            toggles = toggles.max(flips[i]);
+            for (dx, dy) in [(-1i32, -1i32), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+                if nx < 0 || ny < 0 || nx >= w as i32 || ny >= h as i32 {
+                    continue;
+                }
+                let j = (ny as u32 * w + nx as u32) as usize;
+                if flips[j] > 0 && !seen[j] {
+                    seen[j] = true;
+                    stack.push(j);
+                }
+            }
+        }
+        let (bw, bh) = (x1 - x0 + 1, y1 - y0 + 1);
+        let caret_shaped = bw <= MAX_WIDTH
+            && (MIN_HEIGHT..=MAX_HEIGHT).contains(&bh)
+            && pixels * 10 >= bw * bh * 7
+            && toggles >= 2;
+        if !caret_shaped {
+            others += 1;
+            continue;
+        }
+        let candidate = Caret { x: x0 as i32, y: y0 as i32, w: bw as i32, h: bh as i32, toggles };
+        match best {
+            Some(b) if b.toggles >= toggles => others += 1,
+            Some(_) => {
+                others += 1;
+                best = Some(candidate);
+            }
+            None => best = Some(candidate),
+        }
+    }
+    (best, others)
+}
@@ -0,0 +1,440 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{
+    confirm::Intent,
+    hands::{require_local_only, HandsState},
+    interceptor::{Action, BrowserOp},
+    origin::Origin,
+};
+
+// Chrome DevTools Protocol bridge (feature `cdp`): a small, fixed subset of DOM-level
+// browser actions behind the same gate as /hands. Navigations and clicks are hands actions
+// (Action::Browser): interceptors, the policy script, the audit and session recording see them
+// like any other.
+//
+//   GET  /cdp/targets                                  page targets (id, title, url)
+//   POST /cdp/query    {selector, target?, limit?}     matching nodes with boxes (read-only)
+//   POST /cdp/navigate {token, url, target?}           http(s) only; SEEALLN_CDP_ALLOW_HOSTS
+//   POST /cdp/click    {token, selector, index?, target?}
+//
+// The browser is found at SEEALLN_CDP_URL (default http://127.0.0.1:9222, i.e. started with
+// --remote-debugging-port=9222) and must be on loopback. No script evaluation is exposed.
+const DEFAULT_URL: &str = "http://127.0.0.1:9222";
+const TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_MESSAGE: usize = 16 * 1024 * 1024;
+
+type CdpResult<T> = Result<T, String>;
+
+fn endpoint() -> CdpResult<String> {
+    let url = std::env::var("SEEALLN_CDP_URL").unwrap_or_else(|_| DEFAULT_URL.to_string());
+    let hostport = url
+        .strip_prefix("http://")
+        .ok_or("SEEALLN_CDP_URL must be http://host:port")?
+        .trim_end_matches('/');
+    loopback(hostport)?;
+    Ok(hostport.to_string())
+}
+
+// `host:port` of a loopback address; the bridge never talks to remote browsers.
+fn loopback(hostport: &str) -> CdpResult<()> {
+    let host = hostport.rsplit_once(':').map(|(h, _)| h).unwrap_or(hostport);
+    match host {
+        "127.0.0.1" | "localhost" | "[::1]" => Ok(()),
+        _ => Err(format!("cdp endpoint {host} is not loopback")),
+    }
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    let mut out = [0u8; N];
+    for chunk in out.chunks_mut(8) {
+        let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+        h.write_u128(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_nanos());
+        let v = h.finish().to_le_bytes();
+        chunk.copy_from_slice(&v[..chunk.len()]);
+    }
+    out
+}
+
+async fn http_get_json(hostport: &str, path: &str) -> CdpResult<serde_json::Value> {
+    let fut = async {
+        let mut stream = TcpStream::connect(hostport).await.map_err(|e| format!("cdp connect {hostport}: {e}"))?;
+        let req = format!("GET {path} HTTP/1.0\r\nHost: {hostport}\r\n\r\n");
+        stream.write_all(req.as_bytes()).await.map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        stream.take(MAX_MESSAGE as u64).read_to_end(&mut buf).await.map_err(|e| e.to_string())?;
+        let text = String::from_utf8_lossy(&buf);
+        let (head, body) = text.split_once("\r\n\r\n").ok_or("cdp: malformed http response")?;
+        if !head.starts_with("HTTP/1.1 200") && !head.starts_with("HTTP/1.0 200") {
+            return Err(format!("cdp: {}", head.lines().next().unwrap_or_default()));
+        }
+        serde_json::from_str(body).map_err(|e| format!("cdp: {e}"))
+    };
+    tokio::time::timeout(TIMEOUT, fut).await.map_err(|_| "cdp: timeout".to_string())?
+}
+
+// Minimal RFC 6455 client: enough for CDP's text messages over a loopback socket.
+struct Conn {
+    stream: TcpStream,
+    next_id: u64,
+}
+
+impl Conn {
+    async fn connect(ws_url: &str) -> CdpResult<Self> {
+        let rest = ws_url.strip_prefix("ws://").ok_or("cdp: expected ws:// debugger url")?;
+        let (hostport, path) = rest.split_once('/').map(|(h, p)| (h, format!("/{p}"))).unwrap_or((rest, "/".to_string()));
+        loopback(hostport)?;
+
+        let mut stream = TcpStream::connect(hostport).await.map_err(|e| format!("cdp connect {hostport}: {e}"))?;
+        let key = B64.encode(random_bytes::<16>());
+        let req = format!(
+            "GET {path} HTTP/1.1\r\nHost: {hostport}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        );
+        stream.write_all(req.as_bytes()).await.map_err(|e| e.to_string())?;
+
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() > 8192 {
+                return Err("cdp: handshake response too large".to_string());
+            }
+            head.push(stream.read_u8().await.map_err(|e| format!("cdp handshake: {e}"))?);
+        }
+        let status = String::from_utf8_lossy(&head).lines().next().unwrap_or_default().to_string();
+        if !status.contains(" 101 ") {
+            return Err(format!("cdp handshake: {status}"));
+        }
+        Ok(Self { stream, next_id: 1 })
+    }
+
+    async fn send_text(&mut self, text: &str) -> CdpResult<()> {
+        let payload = text.as_bytes();
+        let mut frame = vec![0x81u8];
+        match payload.len() {
+            n if n < 126 => frame.push(0x80 | n as u8),
+            n if n <= u16::MAX as usize => {
+                frame.push(0x80 | 126);
+                frame.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            n => {
+                frame.push(0x80 | 127);
+                frame.extend_from_slice(&(n as u64).to_be_bytes());
+            }
+        }
+        let mask = random_bytes::<4>();
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream.write_all(&frame).await.map_err(|e| e.to_string())
+    }
+
+    async fn send_control(&mut self, opcode: u8, payload: &[u8]) -> CdpResult<()> {
+        let mask = random_bytes::<4>();
+        let mut frame = vec![0x80 | opcode, 0x80 | payload.len().min(125) as u8];
+        frame.extend_from_slice(&mask);
+        frame.extend(payload.iter().take(125).enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.stream.write_all(&frame).await.map_err(|e| e.to_string())
+    }
+
+    // Next complete text message (reassembling fragments, answering pings).
+    async fn recv_text(&mut self) -> CdpResult<String> {
+        let mut message = Vec::new();
+        loop {
+            let mut hdr = [0u8; 2];
+            self.stream.read_exact(&mut hdr).await.map_err(|e| format!("cdp read: {e}"))?;
+            let (fin, opcode, masked) = (hdr[0] & 0x80 != 0, hdr[0] & 0x0f, hdr[1] & 0x80 != 0);
+            let len = match hdr[1] & 0x7f {
+                126 => self.stream.read_u16().await.map_err(|e| e.to_string())? as usize,
+                127 => self.stream.read_u64().await.map_err(|e| e.to_string())? as usize,
+                n => n as usize,
+            };
+            if message.len() + len > MAX_MESSAGE {
+                return Err("cdp: message too large".to_string());
+            }
+            let mut mask = [0u8; 4];
+            if masked {
+                self.stream.read_exact(&mut mask).await.map_err(|e| e.to_string())?;
+            }
+            let mut payload = vec![0u8; len];
+            self.stream.read_exact(&mut payload).await.map_err(|e| e.to_string())?;
+            if masked {
+                payload.iter_mut().enumerate().for_each(|(i, b)| *b ^= mask[i % 4]);
+            }
+            match opcode {
+                0x0..=0x2 => {
+                    message.extend_from_slice(&payload);
+                    if fin {
+                        return String::from_utf8(message).map_err(|e| e.to_string());
+                    }
+                }
+                0x8 => return Err("cdp: connection closed by browser".to_string()),
+                0x9 => self.send_control(0xA, &payload).await?,
+                _ => {}
+            }
+        }
+    }
+
+    // One CDP command; events arriving in between are skipped.
+    async fn call(&mut self, method: &str, params: serde_json::Value) -> CdpResult<serde_json::Value> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let fut = async {
+            self.send_text(&json!({"id": id, "method": method, "params": params}).to_string()).await?;
+            loop {
+                let msg: serde_json::Value = serde_json::from_str(&self.recv_text().await?).map_err(|e| e.to_string())?;
+                if msg["id"].as_u64() != Some(id) {
+                    continue;
+                }
+                if let Some(err) = msg.get("error") {
+                    return Err(format!("{method}: {}", err["message"].as_str().unwrap_or("error")));
+                }
+                return Ok(msg["result"].clone());
+            }
+        };
+        tokio::time::timeout(TIMEOUT, fut).await.map_err(|_| format!("{method}: timeout"))?
+    }
+}
+
+async fn targets() -> CdpResult<Vec<serde_json::Value>> {
+    let list = http_get_json(&endpoint()?, "/json/list").await?;
+    Ok(list
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|t| t["type"] == "page")
+        .cloned()
+        .collect())
+}
+
+// Attaches to the requested page target (default: the first page).
+async fn attach(target: Option<&str>) -> CdpResult<Conn> {
+    let pages = targets().await?;
+    let page = match target {
+        Some(id) => pages.iter().find(|t| t["id"] == id),
+        None => pages.first(),
+    }
+    .ok_or("cdp: no matching page target")?;
+    let ws = page["webSocketDebuggerUrl"].as_str().ok_or("cdp: target has no debugger url (already attached?)")?;
+    Conn::connect(ws).await
+}
+
+async fn query_nodes(conn: &mut Conn, selector: &str) -> CdpResult<Vec<u64>> {
+    let doc = conn.call("DOM.getDocument", json!({"depth": 0})).await?;
+    let root = doc["root"]["nodeId"].as_u64().ok_or("cdp: no document")?;
+    let found = conn.call("DOM.querySelectorAll", json!({"nodeId": root, "selector": selector})).await?;
+    Ok(found["nodeIds"].as_array().into_iter().flatten().filter_map(|n| n.as_u64()).collect())
+}
+
+// Bounding box of the node's first content quad, in viewport CSS pixels.
+async fn node_box(conn: &mut Conn, node: u64) -> Option<(f64, f64, f64, f64)> {
+    let quads = conn.call("DOM.getContentQuads", json!({"nodeId": node})).await.ok()?;
+    let q: Vec<f64> = quads["quads"][0].as_array()?.iter().filter_map(|v| v.as_f64()).collect();
+    if q.len() != 8 {
+        return None;
+    }
+    let xs = [q[0], q[2], q[4], q[6]];
+    let ys = [q[1], q[3], q[5], q[7]];
+    let min = |v: &[f64]| v.iter().cloned().fold(f64::MAX, f64::min);
+    let max = |v: &[f64]| v.iter().cloned().fold(f64::MIN, f64::max);
+    Some((min(&xs), min(&ys), max(&xs) - min(&xs), max(&ys) - min(&ys)))
+}
+
+// Navigation guard: http(s) only, optionally limited to SEEALLN_CDP_ALLOW_HOSTS
+// (comma-separated host names; subdomains match).
+fn check_url(url: &str) -> CdpResult<()> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .ok_or("only http(s) urls can be opened")?;
+    let host = rest
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('@')
+        .next()
+        .unwrap_or_default();
+    let host = host.rsplit_once(':').map(|(h, _)| h).unwrap_or(host).to_lowercase();
+    let Ok(allow) = std::env::var("SEEALLN_CDP_ALLOW_HOSTS") else {
+        return Ok(());
+    };
+    let allowed = allow
+        .split(',')
+        .map(|s| s.trim().to_lowercase())
+        .filter(|s| !s.is_empty())
+        .any(|a| host == a || host.ends_with(&format!(".{a}")));
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!("host {host} not in SEEALLN_CDP_ALLOW_HOSTS"))
+    }
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+// Runs a browser action for HandsState::perform, which has already put it through the
+// interceptors; the url is checked again in case the policy script rewrote it.
+pub async fn execute(op: &BrowserOp) -> Result<(), (StatusCode, String)> {
+    let gateway = |err: String| (StatusCode::BAD_GATEWAY, err);
+    match op {
+        BrowserOp::Navigate { url, target } => {
+            check_url(url).map_err(|err| (StatusCode::FORBIDDEN, err))?;
+            let mut conn = attach(target.as_deref()).await.map_err(gateway)?;
+            let nav = conn.call("Page.navigate", json!({"url": url})).await.map_err(gateway)?;
+            match nav["errorText"].as_str() {
+                Some(err) => Err(gateway(err.to_string())),
+                None => Ok(()),
+            }
+        }
+        BrowserOp::Click { selector, index, target } => click(selector, *index, target.as_deref()).await.map_err(gateway),
+    }
+}
+
+async fn click(selector: &str, index: usize, target: Option<&str>) -> CdpResult<()> {
+    let mut conn = attach(target).await?;
+    let node = *query_nodes(&mut conn, selector)
+        .await?
+        .get(index)
+        .ok_or_else(|| format!("no node #{index} for selector"))?;
+    conn.call("DOM.scrollIntoViewIfNeeded", json!({"nodeId": node})).await?;
+    let (x, y, w, h) = node_box(&mut conn, node).await.ok_or("node is not rendered")?;
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    conn.call("Input.dispatchMouseEvent", json!({"type": "mouseMoved", "x": cx, "y": cy})).await?;
+    for kind in ["mousePressed", "mouseReleased"] {
+        conn.call(
+            "Input.dispatchMouseEvent",
+            json!({"type": kind, "x": cx, "y": cy, "button": "left", "clickCount": 1}),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+// Same gate as a /hands action: arming token (kill switch + rate limit) and a confirmation for
+// `route`, then HandsState::perform (interceptors, policy script, audit, session recording).
+async fn run(state: &HandsState, headers: &HeaderMap, origin: &Origin, token: &str, route: &str, action: Action) -> Response {
+    if let Err(msg) = state.consume_action(token, origin, &action) {
+        return fail(StatusCode::FORBIDDEN, msg);
+    }
+    if let Err(msg) = state.check_confirmation(headers, token, Intent::Route(route)) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, msg);
+    }
+    match state.perform(token, action).await {
+        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
+        Err((code, err)) => fail(code, err),
+    }
+}
+
+pub async fn cdp_targets(headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    match targets().await {
+        Ok(pages) => {
+            let pages: Vec<_> = pages
+                .iter()
+                .map(|t| json!({"id": t["id"], "title": t["title"], "url": t["url"]}))
+                .collect();
+            (StatusCode::OK, Json(json!({"ok": true, "targets": pages}))).into_response()
+        }
+        Err(err) => fail(StatusCode::BAD_GATEWAY, err),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct QueryReq {
+    selector: String,
+    target: Option<String>,
+    limit: Option<usize>,
+}
+
+pub async fn cdp_query(headers: HeaderMap, Json(req): Json<QueryReq>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let limit = req.limit.unwrap_or(20).clamp(1, 100);
+    let result = async {
+        let mut conn = attach(req.target.as_deref()).await?;
+        let ids = query_nodes(&mut conn, &req.selector).await?;
+        let mut nodes = Vec::new();
+        for (index, id) in ids.iter().take(limit).enumerate() {
+            let desc = conn.call("DOM.describeNode", json!({"nodeId": id})).await?;
+            let attrs: serde_json::Map<String, serde_json::Value> = desc["node"]["attributes"]
+                .as_array()
+                .map(|a| {
+                    a.chunks(2)
+                        .filter_map(|kv| Some((kv[0].as_str()?.to_string(), kv.get(1)?.clone())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let html = conn.call("DOM.getOuterHTML", json!({"nodeId": id})).await?;
+            let html: String = html["outerHTML"].as_str().unwrap_or_default().chars().take(300).collect();
+            let bbox = node_box(&mut conn, *id).await.map(|(x, y, w, h)| json!({"x": x, "y": y, "w": w, "h": h}));
+            nodes.push(json!({"index": index, "node_name": desc["node"]["nodeName"], "attributes": attrs, "box": bbox, "html": html}));
+        }
+        Ok::<_, String>(json!({"ok": true, "count": ids.len(), "nodes": nodes}))
+    }
+    .await;
+    match result {
+        Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+        Err(err) => fail(StatusCode::BAD_GATEWAY, err),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NavigateReq {
+    token: String,
+    url: String,
+    target: Option<String>,
+}
+
+pub async fn cdp_navigate(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<NavigateReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if let Err(err) = check_url(&req.url) {
+        return fail(StatusCode::FORBIDDEN, err);
+    }
+    let action = Action::Browser { op: BrowserOp::Navigate { url: req.url, target: req.target } };
+    run(&state, &headers, &origin, &req.token, "/cdp/navigate", action).await
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClickReq {
+    token: String,
+    selector: String,
+    index: Option<usize>,
+    target: Option<String>,
+}
+
+pub async fn cdp_click(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<ClickReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let action = Action::Browser { op: BrowserOp::Click { selector: req.selector, index: req.index.unwrap_or(0), target: req.target } };
+    run(&state, &headers, &origin, &req.token, "/cdp/click", action).await
+}
@@ -0,0 +1,269 @@
+use axum::{extract::State, http::HeaderMap, response::IntoResponse, Json};
+use serde_json::json;
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::broadcast,
+};
+use tracing::{info, warn};
+
+use crate::{
+    events::Events,
+    hands::{require_local_only, HandsState},
+    secrets::{self, Secret},
+};
+
+// Several instances on one machine (one per display, seat or user session) find each other
+// through a shared registry directory: each writes <dir>/<pid>.json with its loopback address
+// and identity, refreshed every HEARTBEAT, and entries older than STALE are ignored.
+//
+//   SEEALLN_CLUSTER=1              enable, registry in <temp dir>/seealln-cluster
+//   SEEALLN_CLUSTER_DIR=/run/...   enable with an explicit registry directory
+//   SEEALLN_INSTANCE=alice-seat1   name shown in /cluster/status (default user@display)
+//   SEEALLN_CLUSTER_KILL=1         a kill here also kills every peer (POST /safety/kill with
+//                                  source "cluster"; those are not forwarded again)
+//
+// GET /cluster/status lists this instance and every live peer with its /safety/status. Peers
+// with an eyes key need the same key as this instance.
+struct Cluster {
+    dir: PathBuf,
+    id: String,
+    propagate_kill: bool,
+}
+
+static CLUSTER: OnceLock<Cluster> = OnceLock::new();
+
+const HEARTBEAT: Duration = Duration::from_secs(10);
+const STALE: Duration = Duration::from_secs(30);
+const PEER_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_RESPONSE: u64 = 1 << 20;
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+fn display() -> Option<String> {
+    env("WAYLAND_DISPLAY").or_else(|| env("DISPLAY"))
+}
+
+fn default_id() -> String {
+    let user = env("USER").or_else(|| env("USERNAME")).unwrap_or_else(|| "user".to_string());
+    let place = env("XDG_SEAT").or_else(display).unwrap_or_else(|| std::process::id().to_string());
+    format!("{user}@{place}")
+}
+
+fn entry_path(dir: &std::path::Path) -> PathBuf {
+    dir.join(format!("{}.json", std::process::id()))
+}
+
+// Registers this instance (reachable at `local`) and starts the heartbeat and, with
+// SEEALLN_CLUSTER_KILL=1, kill propagation. Nothing happens unless the cluster is enabled.
+pub fn spawn(local: SocketAddr, events: &Events, hands: HandsState) {
+    let dir = match (env("SEEALLN_CLUSTER_DIR"), env("SEEALLN_CLUSTER").as_deref()) {
+        (Some(dir), _) => PathBuf::from(dir),
+        (None, Some("1")) => std::env::temp_dir().join("seealln-cluster"),
+        _ => return,
+    };
+    if let Err(err) = create_registry(&dir) {
+        warn!(%err, dir = %dir.display(), "cluster registry unavailable; running standalone");
+        return;
+    }
+    let cluster = Cluster {
+        dir,
+        id: env("SEEALLN_INSTANCE").unwrap_or_else(default_id),
+        propagate_kill: env("SEEALLN_CLUSTER_KILL").as_deref() == Some("1"),
+    };
+    // Peers connect over loopback even when this instance listens on every interface.
+    let port = local.port();
+    let addr = if local.ip().is_unspecified() || local.ip().is_loopback() {
+        format!("127.0.0.1:{port}")
+    } else {
+        local.to_string()
+    };
+    let entry = json!({
+        "id": cluster.id,
+        "pid": std::process::id(),
+        "addr": addr,
+        "user": env("USER").or_else(|| env("USERNAME")),
+        "display": display(),
+        "seat": env("XDG_SEAT"),
+        "started_unix_ms": unix_ms(),
+    });
+    info!(id = %cluster.id, dir = %cluster.dir.display(), kill_propagation = cluster.propagate_kill, "cluster enabled");
+    let propagate = cluster.propagate_kill;
+    if CLUSTER.set(cluster).is_err() {
+        return;
+    }
+    tokio::spawn(heartbeat(entry));
+    if propagate {
+        tokio::spawn(propagate_kills(events.subscribe(), hands));
+    }
+}
+
+// World-writable with the sticky bit, like /tmp, so instances of different users can share it.
+fn create_registry(dir: &std::path::Path) -> std::io::Result<()> {
+    if dir.is_dir() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o1777))?;
+    }
+    Ok(())
+}
+
+async fn heartbeat(mut entry: serde_json::Value) {
+    let Some(c) = CLUSTER.get() else { return };
+    let path = entry_path(&c.dir);
+    let mut tick = tokio::time::interval(HEARTBEAT);
+    loop {
+        tick.tick().await;
+        entry["heartbeat_unix_ms"] = json!(unix_ms());
+        // Write then rename so readers never see half an entry.
+        let tmp = path.with_extension("tmp");
+        let written = std::fs::write(&tmp, entry.to_string()).and_then(|_| std::fs::rename(&tmp, &path));
+        if let Err(err) = written {
+            warn!(%err, file = %path.display(), "cluster heartbeat failed");
+        }
+    }
+}
+
+// Called on shutdown so peers drop this instance at once instead of after STALE.
+pub fn leave() {
+    if let Some(c) = CLUSTER.get() {
+        let _ = std::fs::remove_file(entry_path(&c.dir));
+    }
+}
+
+// Live peers, excluding this instance.
+fn peers(c: &Cluster) -> Vec<serde_json::Value> {
+    let Ok(entries) = std::fs::read_dir(&c.dir) else {
+        return Vec::new();
+    };
+    let now = unix_ms();
+    let own = entry_path(&c.dir);
+    let mut peers: Vec<serde_json::Value> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "json") && *p != own)
+        .filter_map(|p| serde_json::from_slice::<serde_json::Value>(&std::fs::read(p).ok()?).ok())
+        .filter(|e| e["heartbeat_unix_ms"].as_u64().is_some_and(|t| now.saturating_sub(t) <= STALE.as_millis() as u64))
+        .filter(|e| e["addr"].is_string())
+        .collect();
+    peers.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    peers
+}
+
+// Minimal HTTP/1.0 exchange with a peer on loopback; returns the status and JSON body.
+async fn request(addr: &str, method: &str, path: &str, body: Option<&serde_json::Value>) -> Result<(u16, serde_json::Value), String> {
+    let fut = async {
+        let mut stream = TcpStream::connect(addr).await.map_err(|e| format!("connect {addr}: {e}"))?;
+        let mut req = format!("{method} {path} HTTP/1.0\r\nHost: {addr}\r\n");
+        if let Some(key) = secrets::get(Secret::EyesKey) {
+            req.push_str(&format!("Authorization: Bearer {key}\r\n"));
+        }
+        let body = body.map(|b| b.to_string()).unwrap_or_default();
+        if !body.is_empty() {
+            req.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", body.len()));
+        }
+        req.push_str("\r\n");
+        req.push_str(&body);
+        stream.write_all(req.as_bytes()).await.map_err(|e| e.to_string())?;
+        let mut buf = Vec::new();
+        stream.take(MAX_RESPONSE).read_to_end(&mut buf).await.map_err(|e| e.to_string())?;
+        let text = String::from_utf8_lossy(&buf);
+        let (head, body) = text.split_once("\r\n\r\n").ok_or("malformed http response")?;
+        let status = head
+            .split_whitespace()
+            .nth(1)
+            .and_then(|s| s.parse::<u16>().ok())
+            .ok_or("malformed http status line")?;
+        let json = serde_json::from_str(body).unwrap_or_else(|_| json!({"body": body.trim()}));
+        Ok((status, json))
+    };
+    tokio::time::timeout(PEER_TIMEOUT, fut).await.map_err(|_| "timeout".to_string())?
+}
+
+async fn propagate_kills(mut rx: broadcast::Receiver<serde_json::Value>, hands: HandsState) {
+    let Some(c) = CLUSTER.get() else { return };
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            // A missed event may have been a kill; forwarding again is harmless.
+            Err(broadcast::error::RecvError::Lagged(_)) if hands.is_killed() => json!({"type": "kill", "reason": "missed kill event"}),
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        // Kills that came from a peer are not sent around again.
+        if event["type"] != "kill" || event["source"] == "cluster" || event["already_killed"] == true {
+            continue;
+        }
+        let reason = format!("{} (killed on {})", event["reason"].as_str().unwrap_or("unspecified"), c.id);
+        let body = json!({"source": "cluster", "reason": reason, "by": c.id});
+        for peer in peers(c) {
+            let addr = peer["addr"].as_str().unwrap_or_default();
+            match request(addr, "POST", "/safety/kill", Some(&body)).await {
+                Ok((200, _)) => info!(peer = %peer["id"], "kill propagated"),
+                Ok((status, resp)) => warn!(peer = %peer["id"], status, error = %resp["error"], "kill propagation refused"),
+                Err(err) => warn!(peer = %peer["id"], %err, "kill propagation failed"),
+            }
+        }
+    }
+}
+
+pub async fn cluster_status(State(hands): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let Some(c) = CLUSTER.get() else {
+        return Json(json!({"ok": true, "enabled": false})).into_response();
+    };
+    let local = json!({
+        "id": c.id,
+        "pid": std::process::id(),
+        "killed": hands.is_killed(),
+        "arming": hands.arming_status(),
+    });
+    let peers = futures::future::join_all(peers(c).into_iter().map(|mut peer| async move {
+        let addr = peer["addr"].as_str().unwrap_or_default().to_string();
+        match request(&addr, "GET", "/safety/status", None).await {
+            Ok((200, status)) => {
+                peer["reachable"] = json!(true);
+                peer["killed"] = status["killed"].clone();
+                peer["arming"] = status["arming"].clone();
+            }
+            Ok((code, resp)) => {
+                peer["reachable"] = json!(true);
+                peer["error"] = json!(format!("{code}: {}", resp["error"].as_str().unwrap_or("request failed")));
+            }
+            Err(err) => {
+                peer["reachable"] = json!(false);
+                peer["error"] = json!(err);
+            }
+        }
+        peer
+    }))
+    .await;
+    let any_killed = local["killed"] == true || peers.iter().any(|p| p["killed"] == true);
+    Json(json!({
+        "ok": true,
+        "enabled": true,
+        "kill_propagation": c.propagate_kill,
+        "any_killed": any_killed,
+        "self": local,
+        "peers": peers,
+    }))
+    .into_response()
+}
@@ -0,0 +1,265 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{
+    confirm::Intent,
+    hands::{require_local_only, HandsState},
+    interceptor::Action,
+    keys,
+    origin::Origin,
+    overrides,
+};
+
+// xdotool compatibility: a constrained subset of its command-line syntax, translated into
+// hands actions that run through the normal guarded pipeline.
+//
+//   mousemove [--sync] [--clearmodifiers] X Y
+//   click [--repeat N] [--delay MS] [--clearmodifiers] 1|2|3
+//   type [--delay MS] [--clearmodifiers] TEXT...    (remaining args, joined by spaces)
+//   key [--repeat N] [--delay MS] [--clearmodifiers] COMBO...
+//   sleep SECONDS
+//
+// Commands chain like xdotool's ("mousemove 10 20 click 1"); statements are separated by
+// newlines or `;` and may start with a literal `xdotool`. Window/desktop commands, relative
+// moves and scroll buttons are rejected rather than approximated.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cmd {
+    Act(Action),
+    Sleep(Duration),
+}
+
+const MAX_COMMANDS: usize = 200;
+const MAX_REPEAT: u32 = 20;
+const MAX_SLEEP: Duration = Duration::from_secs(10);
+
+// Shell-like split into statements of words: quotes ('..' / ".."), backslash escapes,
+// `#` comments, and `;`/newline separators outside quotes.
+fn tokenize(script: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut statements = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = script.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => w.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let w = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => w.push(c),
+                            Some(c) => {
+                                w.push('\\');
+                                w.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => w.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err("trailing backslash".to_string()),
+            },
+            '#' if word.is_none() => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                words.extend(word.take());
+                statements.push(std::mem::take(&mut words));
+            }
+            ';' | '\n' => {
+                words.extend(word.take());
+                statements.push(std::mem::take(&mut words));
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word.take());
+    statements.push(words);
+    statements.retain(|s| !s.is_empty());
+    Ok(statements)
+}
+
+struct Options {
+    repeat: u32,
+    delay: Option<Duration>,
+}
+
+type Args = std::iter::Peekable<std::vec::IntoIter<String>>;
+
+// Leading --flags of one command; `valued` lists the ones it accepts that take a value.
+fn options(args: &mut Args, cmd: &str, valued: &[&str]) -> Result<Options, String> {
+    let mut opts = Options { repeat: 1, delay: None };
+    while let Some(flag) = args.next_if(|a| a.starts_with("--")) {
+        match flag.as_str() {
+            "--sync" | "--clearmodifiers" => {}
+            f if valued.contains(&f) => {
+                let value = args.next().ok_or_else(|| format!("{cmd}: {f} needs a value"))?;
+                let n: u64 = value.parse().map_err(|_| format!("{cmd}: invalid {f} '{value}'"))?;
+                match f {
+                    "--repeat" => opts.repeat = (n as u32).clamp(1, MAX_REPEAT),
+                    _ => opts.delay = Some(Duration::from_millis(n).min(MAX_SLEEP)),
+                }
+            }
+            f => return Err(format!("{cmd}: unsupported option {f}")),
+        }
+    }
+    Ok(opts)
+}
+
+fn int(cmd: &str, arg: Option<String>) -> Result<i32, String> {
+    let arg = arg.ok_or_else(|| format!("{cmd}: missing coordinate"))?;
+    arg.parse().map_err(|_| format!("{cmd}: invalid coordinate '{arg}'"))
+}
+
+fn repeated(out: &mut Vec<Cmd>, opts: &Options, actions: &[Action]) {
+    for i in 0..opts.repeat {
+        for (j, action) in actions.iter().enumerate() {
+            if i + j as u32 > 0 {
+                if let Some(delay) = opts.delay {
+                    out.push(Cmd::Sleep(delay));
+                }
+            }
+            out.push(Cmd::Act(action.clone()));
+        }
+    }
+}
+
+pub fn translate(script: &str) -> Result<Vec<Cmd>, String> {
+    let mut out = Vec::new();
+    for statement in tokenize(script)? {
+        let mut args = statement.into_iter().peekable();
+        args.next_if(|a| a == "xdotool");
+        while let Some(cmd) = args.next() {
+            match cmd.as_str() {
+                "mousemove" => {
+                    options(&mut args, &cmd, &[])?;
+                    let x = int(&cmd, args.next())?;
+                    let y = int(&cmd, args.next())?;
+                    out.push(Cmd::Act(Action::Move { x, y }));
+                }
+                "click" => {
+                    let opts = options(&mut args, &cmd, &["--repeat", "--delay"])?;
+                    let button = match args.next().as_deref() {
+                        Some("1") => "left",
+                        Some("2") => "middle",
+                        Some("3") => "right",
+                        Some(b) => return Err(format!("click: unsupported button '{b}'")),
+                        None => return Err("click: missing button".to_string()),
+                    };
+                    repeated(&mut out, &opts, &[Action::Click { button: button.to_string() }]);
+                }
+                "type" => {
+                    // --delay is per character in xdotool; typing speed is the backend's.
+                    options(&mut args, &cmd, &["--delay"])?;
+                    let text = args.by_ref().collect::<Vec<_>>().join(" ");
+                    out.push(Cmd::Act(Action::Type { text }));
+                }
+                "key" => {
+                    let opts = options(&mut args, &cmd, &["--repeat", "--delay"])?;
+                    let combos: Vec<Action> = args
+                        .by_ref()
+                        .map(|combo| keys::parse_combo(&combo).map(|_| Action::Key { combo }))
+                        .collect::<Result<_, _>>()?;
+                    if combos.is_empty() {
+                        return Err("key: missing keysym".to_string());
+                    }
+                    repeated(&mut out, &opts, &combos);
+                }
+                "sleep" => {
+                    let arg = args.next().ok_or("sleep: missing seconds")?;
+                    let secs: f64 = arg.parse().map_err(|_| format!("sleep: invalid seconds '{arg}'"))?;
+                    let secs = secs.clamp(0.0, MAX_SLEEP.as_secs_f64());
+                    out.push(Cmd::Sleep(Duration::from_secs_f64(secs)));
+                }
+                other => return Err(format!("unsupported xdotool command '{other}'")),
+            }
+            if out.len() > MAX_COMMANDS {
+                return Err(format!("too many commands (max {MAX_COMMANDS})"));
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct XdotoolReq {
+    token: String,
+    script: String,
+}
+
+pub async fn compat_xdotool(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<XdotoolReq>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let overrides = match overrides::from_headers(&headers) {
+        Ok(overrides) => overrides,
+        Err(err) => return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    let cmds = match translate(&req.script) {
+        Ok(cmds) => cmds,
+        Err(err) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    // Same rule as /hands: anything beyond pointer moves needs a confirmation (for this route).
+    let needs_confirm = cmds
+        .iter()
+        .any(|c| matches!(c, Cmd::Act(a) if !matches!(a, Action::Move { .. })));
+    if needs_confirm {
+        if let Err(msg) = state.check_confirmation(&headers, &req.token, Intent::Route("/compat/xdotool")) {
+            return (StatusCode::PRECONDITION_REQUIRED, Json(json!({"ok": false, "error": msg}))).into_response();
+        }
+    }
+
+    let mut results = Vec::new();
+    for cmd in cmds {
+        let action = match cmd {
+            Cmd::Sleep(d) => {
+                tokio::time::sleep(d).await;
+                continue;
+            }
+            Cmd::Act(action) => action,
+        };
+        if let Err(msg) = state.consume_action(&req.token, &origin, &action) {
+            results.push(json!({"ok": false, "action": action.summary(), "error": msg}));
+            return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg, "steps": results}))).into_response();
+        }
+        match state.perform_with(&req.token, action.clone(), &overrides).await {
+            Ok(resolved) => results.push(json!({"ok": true, "action": resolved.summary()})),
+            Err((code, err)) => {
+                results.push(json!({"ok": false, "action": action.summary(), "error": err}));
+                return (code, Json(json!({"ok": false, "error": err, "steps": results}))).into_response();
+            }
+        }
+    }
+    (StatusCode::OK, Json(json!({"ok": true, "steps": results}))).into_response()
+}
@@ -0,0 +1,544 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+use crate::{
+    auth::require_api_key,
+    hands::{require_local_only, HandsState, MAX_SCROLL},
+    interceptor::Action,
+    origin::Origin,
+};
+
+// Per-action confirmation. Clicks, typing, keys and the batch endpoints no longer take a static
+// `x-seealln-confirm: yes`; the client first asks for a confirmation of one specific action:
+//
+//   POST /hands/confirm {"token": "<arming token>", "action": {"kind": "click", "button": "left"}}
+//   POST /hands/confirm {"token": "<arming token>", "route": "/scripts/run"}
+//     -> {"confirm": "c3f1...", "expires_in_ms": 10000}
+//
+// and sends the result as `x-seealln-confirm: c3f1...` with that action. A confirmation is
+// single use, expires after SEEALLN_CONFIRM_TTL_MS (default 10000), is bound to the arming
+// token and the action (type/paste: the exact text; key: the combo; click: the button) or
+// route, and is void once the token is disarmed, re-armed or killed.
+//
+// With SEEALLN_CONFIRM_APPROVAL=1 a human approves each one: /hands/confirm answers 202 with a
+// request id, a `confirm_requested` event goes out on /events, an operator calls
+// POST /admin/confirmations/:id/approve (or /deny), and the client collects the confirmation
+// from GET /hands/confirm/:id. Unanswered requests lapse after two minutes.
+//
+// Under the safe arming profile (see arming.rs) moves and scrolls need one too, /hands/confirm
+// answers with the action as the interceptors would run it (`preview`) or their refusal, and
+// the confirmation only becomes usable after a short delay (`usable_in_ms`).
+//
+// An operator undoing the agent's last action (/hands/undo without a token) confirms that one
+// entry of the undo stack: POST /admin/confirmations/undo (API key) answers with a confirmation
+// for whatever is on top, void once something else is.
+//
+// SEEALLN_CONFIRM_MODE=legacy also accepts the old `x-seealln-confirm: yes`, for clients not
+// updated yet. Operator endpoints (/safety/reset, /admin/*, /scopes, /bench, virtual displays)
+// keep the static header.
+pub const HEADER: &str = "x-seealln-confirm";
+
+// Stands in for the arming token in confirmations issued to an operator (arming tokens are hex).
+pub const OPERATOR: &str = "operator";
+
+const PENDING_TTL: Duration = Duration::from_secs(120);
+const MAX_PENDING: usize = 32;
+
+// Routes a confirmation can name instead of a single action (/hands/undo: whatever the last
+// action was).
+const ROUTES: &[&str] = &["/compat/xdotool", "/tools/invoke", "/scripts/run", "/cdp/navigate", "/cdp/click", "/hands/undo"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    Token,
+    Legacy,
+}
+
+pub fn mode() -> Mode {
+    static MODE: OnceLock<Mode> = OnceLock::new();
+    *MODE.get_or_init(|| match std::env::var("SEEALLN_CONFIRM_MODE").ok().as_deref().map(str::trim) {
+        None | Some("") | Some("token") => Mode::Token,
+        Some("legacy") => Mode::Legacy,
+        Some(other) => {
+            warn!(mode = other, "unknown SEEALLN_CONFIRM_MODE (token, legacy); using token");
+            Mode::Token
+        }
+    })
+}
+
+fn ttl() -> Duration {
+    static TTL: OnceLock<Duration> = OnceLock::new();
+    *TTL.get_or_init(|| {
+        let ms = std::env::var("SEEALLN_CONFIRM_TTL_MS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(10_000u64);
+        Duration::from_millis(ms.clamp(1_000, 120_000))
+    })
+}
+
+fn approval_required() -> bool {
+    std::env::var("SEEALLN_CONFIRM_APPROVAL").ok().as_deref() == Some("1")
+}
+
+// For /health.
+pub fn policy() -> serde_json::Value {
+    json!({"header": HEADER, "mode": mode(), "ttl_ms": ttl().as_millis() as u64, "approval": approval_required()})
+}
+
+// What a confirmation covers.
+pub enum Intent<'a> {
+    Action(&'a Action),
+    // A batch endpoint (/scripts/run, /sessions/<id>/replay, ...), as its request path.
+    Route(&'a str),
+    // An operator's undo of one undo stack entry, by id (see hands_undo).
+    Undo(u64),
+}
+
+impl Intent<'_> {
+    // The parts a confirmation is bound to. Pasted and typed text count as the same action
+    // (the typing mode may pick either); gesture timing and out-of-range scrolls are adjusted by
+    // the handler, so they are left out or clamped the same way.
+    fn fingerprint(&self) -> serde_json::Value {
+        match self {
+            Intent::Action(Action::Type { text } | Action::Paste { text }) => json!({"kind": "type", "text": text}),
+            Intent::Action(Action::Scroll { dx, dy }) => {
+                json!({"kind": "scroll", "dx": (*dx).clamp(-MAX_SCROLL, MAX_SCROLL), "dy": (*dy).clamp(-MAX_SCROLL, MAX_SCROLL)})
+            }
+            Intent::Action(Action::Gesture { gesture, .. }) => json!({"kind": "gesture", "gesture": gesture}),
+            Intent::Action(action) => serde_json::to_value(action).unwrap_or_default(),
+            Intent::Route(path) => json!({"route": path}),
+            Intent::Undo(id) => json!({"undo": id}),
+        }
+    }
+
+    fn digest(&self) -> String {
+        hex::encode(Sha256::digest(self.fingerprint().to_string()))
+    }
+}
+
+fn known_route(path: &str) -> bool {
+    ROUTES.contains(&path)
+        || path
+            .strip_prefix("/sessions/")
+            .and_then(|rest| rest.strip_suffix("/replay"))
+            .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+}
+
+fn random_id() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("os rng");
+    hex::encode(bytes)
+}
+
+struct Issued {
+    arming: String,
+    digest: String,
+    usable: Instant,
+    expires: Instant,
+}
+
+#[derive(Clone, Copy)]
+enum Decision {
+    Waiting,
+    Approved,
+    Denied,
+}
+
+struct Pending {
+    arming: String,
+    digest: String,
+    // Redacted description for the operator.
+    summary: serde_json::Value,
+    origin: Origin,
+    created: Instant,
+    decision: Decision,
+}
+
+#[derive(Default)]
+struct Inner {
+    issued: HashMap<String, Issued>,
+    pending: HashMap<String, Pending>,
+}
+
+impl Inner {
+    fn prune(&mut self, now: Instant) {
+        self.issued.retain(|_, c| c.expires > now);
+        self.pending.retain(|_, p| now.saturating_duration_since(p.created) < PENDING_TTL);
+    }
+
+    fn issue(&mut self, arming: String, digest: String, delay: Duration) -> String {
+        let id = random_id();
+        let now = Instant::now();
+        self.issued.insert(id.clone(), Issued { arming, digest, usable: now + delay, expires: now + delay + ttl() });
+        id
+    }
+}
+
+// Outstanding confirmations and approval requests; lives in HandsState.
+#[derive(Clone, Default)]
+pub struct Confirmations {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl Confirmations {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.prune(Instant::now());
+        inner
+    }
+
+    // Checks (and uses up) the confirmation a request carries for `intent`.
+    pub fn check(&self, headers: &HeaderMap, arming: &str, intent: &Intent) -> Result<(), &'static str> {
+        self.check_in(mode(), headers, arming, intent)
+    }
+
+    fn check_in(&self, mode: Mode, headers: &HeaderMap, arming: &str, intent: &Intent) -> Result<(), &'static str> {
+        let Some(value) = headers.get(HEADER).and_then(|v| v.to_str().ok()).map(str::trim) else {
+            return Err("missing x-seealln-confirm (POST /hands/confirm first)");
+        };
+        if value.eq_ignore_ascii_case("yes") {
+            return match mode {
+                Mode::Legacy => Ok(()),
+                Mode::Token => Err("x-seealln-confirm: yes is not accepted; POST /hands/confirm for a confirmation"),
+            };
+        }
+        // Removed before the comparison: a confirmation presented with the wrong action is gone.
+        let mut inner = self.lock();
+        let Some(issued) = inner.issued.remove(value) else {
+            return Err("unknown, used or expired confirmation");
+        };
+        if issued.arming != arming || issued.digest != intent.digest() {
+            return Err("confirmation was issued for a different action");
+        }
+        // Presented too early, it stays valid for a later retry.
+        if Instant::now() < issued.usable {
+            inner.issued.insert(value.to_string(), issued);
+            return Err("confirmation not usable yet (safe profile delay)");
+        }
+        Ok(())
+    }
+
+    // Voids everything outstanding (disarm, re-arm, kill).
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.issued.clear();
+        inner.pending.clear();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmReq {
+    token: String,
+    action: Option<Action>,
+    route: Option<String>,
+}
+
+pub async fn hands_confirm(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<ConfirmReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let (intent, summary) = match (&req.action, &req.route) {
+        (Some(action), None) => (Intent::Action(action), state.redact(action)),
+        (None, Some(route)) if known_route(route) => (Intent::Route(route), json!({"route": route})),
+        (None, Some(route)) => {
+            let error = format!("{route} takes no confirmation ({}, /sessions/<id>/replay)", ROUTES.join(", "));
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+        _ => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "give either action or route"}))).into_response()
+        }
+    };
+    // A live token presented from its own origin; nothing is charged against the rate limit.
+    if let Err(msg) = state.consume(&req.token, &origin, 0.0) {
+        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    // The safe profile's dry run: an action the interceptors would refuse gets no confirmation.
+    let profile = state.profile();
+    let preview = match (&req.action, profile.confirm_all()) {
+        (Some(action), true) => match state.preview(action.clone()) {
+            Ok(previewed) => Some(state.redact(&previewed)),
+            Err(denial) => return (denial.status, Json(json!({"ok": false, "error": denial.error, "preview": "denied"}))).into_response(),
+        },
+        _ => None,
+    };
+    let digest = intent.digest();
+    let confirmations = state.confirmations();
+
+    if !approval_required() {
+        let delay = profile.confirm_delay();
+        let confirm = confirmations.lock().issue(req.token, digest, delay);
+        let ttl_ms = ttl().as_millis() as u64;
+        let usable_in_ms = delay.as_millis() as u64;
+        return (
+            StatusCode::OK,
+            Json(json!({"ok": true, "confirm": confirm, "expires_in_ms": ttl_ms + usable_in_ms, "usable_in_ms": usable_in_ms, "preview": preview})),
+        )
+            .into_response();
+    }
+
+    let id = {
+        let mut inner = confirmations.lock();
+        let waiting = inner.pending.values().filter(|p| matches!(p.decision, Decision::Waiting)).count();
+        if waiting >= MAX_PENDING {
+            return (StatusCode::TOO_MANY_REQUESTS, Json(json!({"ok": false, "error": "too many confirmations awaiting approval"}))).into_response();
+        }
+        let id = random_id();
+        let pending = Pending {
+            arming: req.token,
+            digest,
+            summary: summary.clone(),
+            origin: origin.clone(),
+            created: Instant::now(),
+            decision: Decision::Waiting,
+        };
+        inner.pending.insert(id.clone(), pending);
+        id
+    };
+    state.audit(json!({"event": "confirm_requested", "id": id, "intent": summary, "origin": origin}));
+    state.emit("confirm_requested", json!({"id": id, "intent": summary, "origin": origin}));
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "ok": true,
+            "pending": id,
+            "poll": format!("/hands/confirm/{id}"),
+            "expires_in_ms": PENDING_TTL.as_millis() as u64,
+            "preview": preview,
+        })),
+    )
+        .into_response()
+}
+
+// Polled by the client after a 202; hands out the confirmation once approved.
+pub async fn hands_confirm_poll(State(state): State<HandsState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    // Read before the confirmations lock; arming takes the two locks the other way round.
+    let delay = state.profile().confirm_delay();
+    let mut inner = state.confirmations().lock();
+    match inner.pending.get(&id).map(|p| p.decision) {
+        None => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": "unknown or expired confirmation request"}))).into_response(),
+        Some(Decision::Waiting) => (StatusCode::ACCEPTED, Json(json!({"ok": true, "state": "pending"}))).into_response(),
+        Some(Decision::Denied) => {
+            inner.pending.remove(&id);
+            (StatusCode::FORBIDDEN, Json(json!({"ok": false, "state": "denied", "error": "confirmation denied by the operator"}))).into_response()
+        }
+        Some(Decision::Approved) => {
+            let Some(pending) = inner.pending.remove(&id) else {
+                return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": "unknown or expired confirmation request"}))).into_response();
+            };
+            // The ttl starts now, not at approval, so a slow poll does not waste it.
+            let confirm = inner.issue(pending.arming, pending.digest, delay);
+            let ttl_ms = ttl().as_millis() as u64;
+            let usable_in_ms = delay.as_millis() as u64;
+            let body = json!({"ok": true, "state": "approved", "confirm": confirm, "expires_in_ms": ttl_ms + usable_in_ms, "usable_in_ms": usable_in_ms});
+            (StatusCode::OK, Json(body)).into_response()
+        }
+    }
+}
+
+fn operator_check(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+    require_local_only(headers)?;
+    require_api_key(headers)
+}
+
+// A confirmation for an operator's POST /hands/undo, bound to the action now on top of the
+// undo stack.
+pub async fn admin_confirm_undo(State(state): State<HandsState>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = operator_check(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let Some((id, kind)) = state.undo_top() else {
+        return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": "nothing to undo"}))).into_response();
+    };
+    let confirm = state.confirmations().lock().issue(OPERATOR.to_string(), Intent::Undo(id).digest(), Duration::ZERO);
+    state.audit(json!({"event": "undo_confirm_issued", "undo": id, "kind": kind}));
+    let ttl_ms = ttl().as_millis() as u64;
+    (StatusCode::OK, Json(json!({"ok": true, "confirm": confirm, "expires_in_ms": ttl_ms, "undo": {"id": id, "kind": kind}}))).into_response()
+}
+
+// Requests awaiting an operator decision.
+pub async fn admin_confirmations(State(state): State<HandsState>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = operator_check(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let inner = state.confirmations().lock();
+    let waiting: Vec<_> = inner
+        .pending
+        .iter()
+        .filter(|(_, p)| matches!(p.decision, Decision::Waiting))
+        .map(|(id, p)| json!({"id": id, "intent": p.summary, "origin": p.origin, "age_ms": p.created.elapsed().as_millis() as u64}))
+        .collect();
+    (StatusCode::OK, Json(json!({"ok": true, "pending": waiting}))).into_response()
+}
+
+pub async fn admin_confirm_approve(State(state): State<HandsState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    decide(&state, &headers, &id, true)
+}
+
+pub async fn admin_confirm_deny(State(state): State<HandsState>, headers: HeaderMap, Path(id): Path<String>) -> Response {
+    decide(&state, &headers, &id, false)
+}
+
+fn decide(state: &HandsState, headers: &HeaderMap, id: &str, approve: bool) -> Response {
+    if let Err((code, msg)) = operator_check(headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let summary = {
+        let mut inner = state.confirmations().lock();
+        match inner.pending.get_mut(id) {
+            Some(p) if matches!(p.decision, Decision::Waiting) => {
+                p.decision = if approve { Decision::Approved } else { Decision::Denied };
+                p.summary.clone()
+            }
+            Some(_) => return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": "already decided"}))).into_response(),
+            None => {
+                return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": "unknown or expired confirmation request"}))).into_response()
+            }
+        }
+    };
+    let decision = if approve { "approved" } else { "denied" };
+    state.audit(json!({"event": "confirm_decided", "id": id, "decision": decision, "intent": summary}));
+    state.emit("confirm_decided", json!({"id": id, "decision": decision}));
+    (StatusCode::OK, Json(json!({"ok": true, "id": id, "decision": decision}))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn presenting(confirm: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(HEADER, confirm.parse().unwrap());
+        headers
+    }
+
+    fn click(button: &str) -> Action {
+        Action::Click { button: button.to_string() }
+    }
+
+    fn issue(confirmations: &Confirmations, arming: &str, intent: &Intent, delay: Duration) -> String {
+        confirmations.lock().issue(arming.to_string(), intent.digest(), delay)
+    }
+
+    #[test]
+    fn single_use() {
+        let confirmations = Confirmations::default();
+        let left = click("left");
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        let headers = presenting(&id);
+        assert!(confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)).is_ok());
+        assert_eq!(
+            confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)),
+            Err("unknown, used or expired confirmation")
+        );
+    }
+
+    #[test]
+    fn wrong_action_uses_it_up() {
+        let confirmations = Confirmations::default();
+        let (left, right) = (click("left"), click("right"));
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        let headers = presenting(&id);
+        assert_eq!(
+            confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&right)),
+            Err("confirmation was issued for a different action")
+        );
+        assert!(confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)).is_err());
+
+        // A route confirmation does not cover another route either.
+        let id = issue(&confirmations, "tok", &Intent::Route("/scripts/run"), Duration::ZERO);
+        assert!(confirmations.check_in(Mode::Token, &presenting(&id), "tok", &Intent::Route("/tools/invoke")).is_err());
+    }
+
+    #[test]
+    fn bound_to_the_arming() {
+        let confirmations = Confirmations::default();
+        let left = click("left");
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        let headers = presenting(&id);
+        assert!(confirmations.check_in(Mode::Token, &headers, "other", &Intent::Action(&left)).is_err());
+        assert!(confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)).is_err());
+
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        confirmations.clear();
+        assert!(confirmations.check_in(Mode::Token, &presenting(&id), "tok", &Intent::Action(&left)).is_err());
+    }
+
+    #[test]
+    fn operator_undo_names_one_entry() {
+        let confirmations = Confirmations::default();
+        let id = issue(&confirmations, OPERATOR, &Intent::Undo(3), Duration::ZERO);
+        assert_eq!(
+            confirmations.check_in(Mode::Token, &presenting(&id), OPERATOR, &Intent::Undo(4)),
+            Err("confirmation was issued for a different action")
+        );
+
+        let id = issue(&confirmations, OPERATOR, &Intent::Undo(3), Duration::ZERO);
+        assert!(confirmations.check_in(Mode::Token, &presenting(&id), "tok", &Intent::Undo(3)).is_err());
+        let id = issue(&confirmations, OPERATOR, &Intent::Undo(3), Duration::ZERO);
+        assert!(confirmations.check_in(Mode::Token, &presenting(&id), OPERATOR, &Intent::Undo(3)).is_ok());
+    }
+
+    #[test]
+    fn expires() {
+        let confirmations = Confirmations::default();
+        let left = click("left");
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        confirmations.lock().issued.get_mut(&id).unwrap().expires = Instant::now();
+        assert_eq!(
+            confirmations.check_in(Mode::Token, &presenting(&id), "tok", &Intent::Action(&left)),
+            Err("unknown, used or expired confirmation")
+        );
+    }
+
+    #[test]
+    fn safe_profile_delay_keeps_it_for_a_retry() {
+        let confirmations = Confirmations::default();
+        let left = click("left");
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::from_secs(60));
+        let headers = presenting(&id);
+        for _ in 0..2 {
+            assert_eq!(
+                confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)),
+                Err("confirmation not usable yet (safe profile delay)")
+            );
+        }
+        confirmations.lock().issued.get_mut(&id).unwrap().usable = Instant::now();
+        assert!(confirmations.check_in(Mode::Token, &headers, "tok", &Intent::Action(&left)).is_ok());
+    }
+
+    #[test]
+    fn legacy_yes_only_in_legacy_mode() {
+        let confirmations = Confirmations::default();
+        let left = click("left");
+        let yes = presenting("yes");
+        assert!(confirmations.check_in(Mode::Legacy, &yes, "tok", &Intent::Action(&left)).is_ok());
+        assert!(confirmations.check_in(Mode::Token, &yes, "tok", &Intent::Action(&left)).is_err());
+        assert!(confirmations.check_in(Mode::Legacy, &HeaderMap::new(), "tok", &Intent::Action(&left)).is_err());
+        // Legacy mode still takes real confirmations.
+        let id = issue(&confirmations, "tok", &Intent::Action(&left), Duration::ZERO);
+        assert!(confirmations.check_in(Mode::Legacy, &presenting(&id), "tok", &Intent::Action(&left)).is_ok());
+    }
+}
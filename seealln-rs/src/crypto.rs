@@ -0,0 +1,142 @@
+// Encryption at rest for the data dir (frames, recordings, audit entries).
+// XChaCha20-Poly1305 with a random 24-byte nonce per blob/line; the key comes from
+// the keychain entry `store_key` / SEEALLN_STORE_KEY (64 hex chars or base64 of 32 bytes)
+// or SEEALLN_STORE_KEY_FILE.
+
+#[cfg(feature = "encrypt")]
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+#[cfg(feature = "encrypt")]
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    XChaCha20Poly1305, XNonce,
+};
+use tracing::warn;
+
+use crate::secrets::{self, Secret};
+
+// Whole-file blobs start with this magic; audit lines with LINE_PREFIX.
+pub const BLOB_MAGIC: &[u8; 4] = b"SAE1";
+pub const LINE_PREFIX: &str = "enc1:";
+
+#[derive(Clone)]
+pub struct Cipher {
+    #[cfg(feature = "encrypt")]
+    aead: XChaCha20Poly1305,
+}
+
+fn key_material() -> Option<String> {
+    if let Some(k) = secrets::get(Secret::StoreKey) {
+        return Some(k);
+    }
+    let path = std::env::var("SEEALLN_STORE_KEY_FILE").ok().filter(|s| !s.trim().is_empty())?;
+    match std::fs::read_to_string(path.trim()) {
+        Ok(s) => Some(s.trim().to_string()),
+        Err(err) => {
+            warn!(%err, "store key file unreadable; encryption at rest disabled");
+            None
+        }
+    }
+}
+
+impl Cipher {
+    #[cfg(feature = "encrypt")]
+    pub fn from_env() -> Option<Self> {
+        let raw = key_material()?;
+        let key = if raw.len() == 64 && raw.bytes().all(|b| b.is_ascii_hexdigit()) {
+            hex::decode(&raw).ok()
+        } else {
+            B64.decode(raw.as_bytes()).ok()
+        };
+        match key {
+            Some(k) if k.len() == 32 => Some(Self {
+                aead: XChaCha20Poly1305::new_from_slice(&k).expect("32-byte key"),
+            }),
+            _ => {
+                warn!("store key must be 32 bytes (64 hex chars or base64); encryption at rest disabled");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "encrypt"))]
+    pub fn from_env() -> Option<Self> {
+        if key_material().is_some() {
+            warn!("store key set but built without the `encrypt` feature; data is stored in plaintext");
+        }
+        None
+    }
+
+    #[cfg(feature = "encrypt")]
+    fn seal_raw(&self, plain: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; 24];
+        getrandom::getrandom(&mut nonce).expect("os rng");
+        let ct = self
+            .aead
+            .encrypt(XNonce::from_slice(&nonce), plain)
+            .expect("xchacha20poly1305 encrypt");
+        let mut out = Vec::with_capacity(nonce.len() + ct.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ct);
+        out
+    }
+
+    #[cfg(feature = "encrypt")]
+    fn open_raw(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        if sealed.len() < 24 {
+            return Err("ciphertext too short".to_string());
+        }
+        let (nonce, ct) = sealed.split_at(24);
+        self.aead
+            .decrypt(XNonce::from_slice(nonce), ct)
+            .map_err(|_| "decrypt failed (wrong key or corrupted data)".to_string())
+    }
+
+    #[cfg(not(feature = "encrypt"))]
+    fn open_raw(&self, _sealed: &[u8]) -> Result<Vec<u8>, String> {
+        Err("built without the `encrypt` feature".to_string())
+    }
+
+    // Without the `encrypt` feature a Cipher is never constructed, so sealing is a no-op there.
+    pub fn seal_blob(&self, plain: &[u8]) -> Vec<u8> {
+        #[cfg(feature = "encrypt")]
+        return [BLOB_MAGIC.as_slice(), &self.seal_raw(plain)].concat();
+
+        #[cfg(not(feature = "encrypt"))]
+        plain.to_vec()
+    }
+
+    pub fn seal_line(&self, line: &str) -> String {
+        #[cfg(feature = "encrypt")]
+        return format!("{LINE_PREFIX}{}", B64.encode(self.seal_raw(line.as_bytes())));
+
+        #[cfg(not(feature = "encrypt"))]
+        line.to_string()
+    }
+
+    // Decrypts a blob, or a newline-separated file of sealed lines. Plaintext content
+    // (written before encryption was enabled) passes through unchanged.
+    pub fn open_file(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if let Some(sealed) = data.strip_prefix(BLOB_MAGIC.as_slice()) {
+            return self.open_raw(sealed);
+        }
+        let text = String::from_utf8_lossy(data);
+        if !text.contains(LINE_PREFIX) {
+            return Ok(data.to_vec());
+        }
+        let mut out = Vec::with_capacity(data.len());
+        for line in text.lines() {
+            match line.strip_prefix(LINE_PREFIX) {
+                #[cfg(feature = "encrypt")]
+                Some(b64) => {
+                    let sealed = B64.decode(b64).map_err(|e| e.to_string())?;
+                    out.extend(self.open_raw(&sealed)?);
+                }
+                #[cfg(not(feature = "encrypt"))]
+                Some(_) => return Err("built without the `encrypt` feature".to_string()),
+                None => out.extend_from_slice(line.as_bytes()),
+            }
+            out.push(b'\n');
+        }
+        Ok(out)
+    }
+}
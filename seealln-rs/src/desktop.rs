@@ -0,0 +1,65 @@
+use crate::hands::ScopeRect;
+
+// Monitor layout of the virtual desktop, in the coordinate space pointer moves use: the
+// primary monitor's top-left is (0, 0), monitors left of or above it have negative
+// coordinates. SEEALLN_MONITORS="x,y,w,h;x,y,w,h" overrides detection (unusual X setups,
+// headless tests); otherwise `hands` builds ask the OS. Empty when unknown.
+pub fn monitors() -> Vec<ScopeRect> {
+    if crate::sim::active() {
+        return vec![crate::sim::monitor()];
+    }
+    if let Some(spec) = std::env::var("SEEALLN_MONITORS").ok().filter(|s| !s.trim().is_empty()) {
+        let parsed = parse(&spec);
+        if !parsed.is_empty() {
+            return parsed;
+        }
+        tracing::warn!(%spec, "ignoring malformed SEEALLN_MONITORS");
+    }
+    detected()
+}
+
+fn parse(spec: &str) -> Vec<ScopeRect> {
+    spec.split(';')
+        .filter_map(|item| {
+            let n: Vec<i32> = item.split(',').map(|v| v.trim().parse().ok()).collect::<Option<_>>()?;
+            match n[..] {
+                [x, y, w, h] if w > 0 && h > 0 => Some(ScopeRect { x, y, w, h }),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "hands")]
+fn detected() -> Vec<ScopeRect> {
+    match display_info::DisplayInfo::all() {
+        Ok(all) => all
+            .iter()
+            .map(|d| ScopeRect {
+                x: d.x,
+                y: d.y,
+                w: d.width as i32,
+                h: d.height as i32,
+            })
+            .filter(|m| m.w > 0 && m.h > 0)
+            .collect(),
+        Err(err) => {
+            tracing::debug!(%err, "monitor layout unavailable");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(not(feature = "hands"))]
+fn detected() -> Vec<ScopeRect> {
+    Vec::new()
+}
+
+// (x, y) when it is on a monitor, else the nearest point on any monitor. The gaps between
+// monitors of different sizes are off-screen too, so this is not a bounding-box clamp.
+pub fn clamp(monitors: &[ScopeRect], x: i32, y: i32) -> Option<(i32, i32)> {
+    monitors.iter().map(|m| m.clamp_point(x, y)).min_by_key(|&(cx, cy)| {
+        let (dx, dy) = (i64::from(cx) - i64::from(x), i64::from(cy) - i64::from(y));
+        dx * dx + dy * dy
+    })
+}
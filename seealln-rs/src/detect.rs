@@ -0,0 +1,157 @@
+use image::RgbImage;
+
+// On-device UI element detection (feature `detect`): a small YOLO-style ONNX model, run
+// in-process with tract, finds buttons, inputs, checkboxes and links without the frame
+// leaving the machine. The model is SEEALLN_DETECT_MODEL (no default, none is bundled);
+// class names, in the model's class order, are SEEALLN_DETECT_LABELS (default
+// button,input,checkbox,link); the square input side is SEEALLN_DETECT_SIZE (default 640).
+//
+// Expected output: one tensor of [1, 4 + classes, anchors] (YOLOv8 export) or its
+// transpose, rows of (cx, cy, w, h, class scores...) in input pixels.
+const DEFAULT_LABELS: &str = "button,input,checkbox,link";
+pub const DEFAULT_MIN_SCORE: f32 = 0.35;
+
+// A detected element, in screen pixel coordinates.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Element {
+    pub label: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub score: f32,
+}
+
+pub fn labels() -> Vec<String> {
+    std::env::var("SEEALLN_DETECT_LABELS")
+        .unwrap_or_else(|_| DEFAULT_LABELS.to_string())
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+pub fn available() -> bool {
+    cfg!(feature = "detect") && std::env::var_os("SEEALLN_DETECT_MODEL").is_some()
+}
+
+// Elements in `img` scoring at least `min_score`, offset by (ox, oy) to screen coordinates,
+// best first.
+#[cfg(feature = "detect")]
+pub fn elements(img: &RgbImage, ox: i32, oy: i32, min_score: f32) -> Result<Vec<Element>, String> {
+    model::run(img, ox, oy, min_score)
+}
+
+#[cfg(not(feature = "detect"))]
+pub fn elements(_img: &RgbImage, _ox: i32, _oy: i32, _min_score: f32) -> Result<Vec<Element>, String> {
+    Err("detect feature disabled".to_string())
+}
+
+#[cfg(feature = "detect")]
+mod model {
+    use image::{imageops, RgbImage};
+    use std::sync::OnceLock;
+    use tract_onnx::prelude::*;
+
+    use super::{labels, Element};
+
+    // Overlapping boxes of one class above this IoU are the same element.
+    const NMS_IOU: f32 = 0.45;
+
+    struct Model {
+        plan: TypedRunnableModel<TypedModel>,
+        size: u32,
+        labels: Vec<String>,
+    }
+
+    // Loaded on first use and kept; a load error is kept too (the model file does not
+    // change under a running server).
+    fn model() -> Result<&'static Model, String> {
+        static MODEL: OnceLock<Result<Model, String>> = OnceLock::new();
+        MODEL.get_or_init(load).as_ref().map_err(Clone::clone)
+    }
+
+    fn load() -> Result<Model, String> {
+        let path = std::env::var("SEEALLN_DETECT_MODEL").map_err(|_| "SEEALLN_DETECT_MODEL is not set".to_string())?;
+        let size: u32 = std::env::var("SEEALLN_DETECT_SIZE")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|s| (32..=2048).contains(s))
+            .unwrap_or(640);
+        let plan = tract_onnx::onnx()
+            .model_for_path(&path)
+            .and_then(|m| m.with_input_fact(0, f32::fact([1, 3, size as usize, size as usize]).into()))
+            .and_then(|m| m.into_optimized())
+            .and_then(|m| m.into_runnable())
+            .map_err(|e| format!("detect model {path}: {e}"))?;
+        Ok(Model { plan, size, labels: labels() })
+    }
+
+    pub fn run(img: &RgbImage, ox: i32, oy: i32, min_score: f32) -> Result<Vec<Element>, String> {
+        let m = model()?;
+        let s = m.size as usize;
+        // Stretched rather than letterboxed; boxes are scaled back per axis.
+        let small = imageops::resize(img, m.size, m.size, imageops::FilterType::Triangle);
+        let input: Tensor =
+            tract_ndarray::Array4::from_shape_fn((1, 3, s, s), |(_, c, y, x)| small.get_pixel(x as u32, y as u32)[c] as f32 / 255.0)
+                .into();
+        let out = m.plan.run(tvec!(input.into())).map_err(|e| format!("detect: {e}"))?;
+        let out = out[0].to_array_view::<f32>().map_err(|e| format!("detect: {e}"))?;
+        let shape = out.shape();
+        if shape.len() != 3 || shape[0] != 1 {
+            return Err(format!("detect: unexpected output shape {shape:?}"));
+        }
+        // Attributes are the shorter axis: 4 + a handful of classes vs thousands of anchors.
+        let transposed = shape[1] > shape[2];
+        let (attrs, anchors) = if transposed { (shape[2], shape[1]) } else { (shape[1], shape[2]) };
+        if attrs <= 4 {
+            return Err(format!("detect: unexpected output shape {shape:?}"));
+        }
+        let at = |a: usize, i: usize| if transposed { out[[0, i, a]] } else { out[[0, a, i]] };
+
+        let (sx, sy) = (img.width() as f32 / m.size as f32, img.height() as f32 / m.size as f32);
+        let mut found: Vec<(usize, Element)> = Vec::new();
+        for i in 0..anchors {
+            let Some((class, score)) = (4..attrs).map(|a| (a - 4, at(a, i))).max_by(|a, b| a.1.total_cmp(&b.1)) else {
+                continue;
+            };
+            if score < min_score {
+                continue;
+            }
+            let (cx, cy, w, h) = (at(0, i) * sx, at(1, i) * sy, at(2, i) * sx, at(3, i) * sy);
+            let label = m.labels.get(class).cloned().unwrap_or_else(|| format!("class{class}"));
+            found.push((
+                class,
+                Element {
+                    label,
+                    x: ox + (cx - w / 2.0).round() as i32,
+                    y: oy + (cy - h / 2.0).round() as i32,
+                    w: w.round() as i32,
+                    h: h.round() as i32,
+                    score,
+                },
+            ));
+        }
+        Ok(nms(found))
+    }
+
+    fn iou(a: &Element, b: &Element) -> f32 {
+        let iw = ((a.x + a.w).min(b.x + b.w) - a.x.max(b.x)).max(0) as f32;
+        let ih = ((a.y + a.h).min(b.y + b.h) - a.y.max(b.y)).max(0) as f32;
+        let inter = iw * ih;
+        let union = (a.w * a.h + b.w * b.h) as f32 - inter;
+        if union <= 0.0 { 0.0 } else { inter / union }
+    }
+
+    // Greedy per-class non-maximum suppression; result sorted by score.
+    fn nms(mut found: Vec<(usize, Element)>) -> Vec<Element> {
+        found.sort_by(|a, b| b.1.score.total_cmp(&a.1.score));
+        let mut kept: Vec<(usize, Element)> = Vec::new();
+        for (class, el) in found {
+            if kept.iter().all(|(c, k)| *c != class || iou(k, &el) <= NMS_IOU) {
+                kept.push((class, el));
+            }
+        }
+        kept.into_iter().map(|(_, el)| el).collect()
+    }
+}
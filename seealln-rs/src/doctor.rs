@@ -0,0 +1,193 @@
+use serde::Serialize;
+use serde_json::json;
+use std::time::Instant;
+
+use crate::{
+    capture::{self, Fallback},
+    input::{self, Input},
+    permissions::{self, Grant},
+    sim,
+};
+
+// `seealln doctor [--no-prompt] [--json]`: checks what the server needs from this machine,
+// for the environment problems most support requests come down to:
+//
+//   session      display server (X11 / Wayland) and what the input backend reaches there
+//   permissions  macOS privacy permissions; missing ones are prompted for unless --no-prompt
+//   capture      one frame from every display
+//   input        the input backend opens (nothing is injected)
+//   port         SEEALLN_BIND:SEEALLN_PORT is free
+//
+// Prints one line per check, or the whole report as JSON with --json. Exits 1 when a check
+// failed; warnings do not count.
+fn usage() -> i32 {
+    eprintln!("usage: seealln doctor [--no-prompt] [--json]");
+    2
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+    Skip,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    area: &'static str,
+    status: Status,
+    detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hint: Option<String>,
+}
+
+fn check(area: &'static str, status: Status, detail: impl Into<String>) -> Check {
+    Check {
+        area,
+        status,
+        detail: detail.into(),
+        hint: None,
+    }
+}
+
+impl Check {
+    fn hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+pub fn cli(args: &[String]) -> i32 {
+    let (mut prompt, mut as_json) = (true, false);
+    for arg in args {
+        match arg.as_str() {
+            "--no-prompt" => prompt = false,
+            "--json" => as_json = true,
+            _ => return usage(),
+        }
+    }
+    let input = Input::from_env();
+    let mut checks = session(&input);
+    checks.extend(permissions(prompt));
+    checks.extend(capture());
+    checks.push(injection(&input));
+    checks.push(port());
+
+    let failed = checks.iter().any(|c| c.status == Status::Fail);
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&json!({"ok": !failed, "checks": checks})).unwrap_or_default());
+    } else {
+        for c in &checks {
+            let status = match c.status {
+                Status::Ok => "ok",
+                Status::Warn => "WARN",
+                Status::Fail => "FAIL",
+                Status::Skip => "skip",
+            };
+            println!("{status:<5} {:<12} {}", c.area, c.detail);
+            if let Some(hint) = &c.hint {
+                println!("{:<18} -> {hint}", "");
+            }
+        }
+    }
+    i32::from(failed)
+}
+
+fn session(input: &Input) -> Vec<Check> {
+    if !cfg!(target_os = "linux") {
+        return vec![check("session", Status::Ok, std::env::consts::OS)];
+    }
+    let Some(kind) = input::session_type() else {
+        return vec![check("session", Status::Warn, "no display server found (DISPLAY and WAYLAND_DISPLAY unset)")
+            .hint("run inside the desktop session, or export DISPLAY / WAYLAND_DISPLAY")];
+    };
+    let display = ["WAYLAND_DISPLAY", "DISPLAY"]
+        .iter()
+        .filter_map(|k| std::env::var(k).ok().map(|v| format!("{k}={v}")))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let mut checks = vec![check("session", Status::Ok, format!("{kind} ({display})"))];
+    if kind == "wayland" && input.capabilities().x11_only {
+        checks.push(
+            check("session", Status::Warn, format!("input backend {} only reaches XWayland windows", input.name()))
+                .hint("use SEEALLN_INPUT_BACKEND=ydotool, or wayland on wlroots compositors"),
+        );
+    }
+    checks
+}
+
+fn permissions(prompt: bool) -> Vec<Check> {
+    let granted = if prompt { permissions::request() } else { permissions::check() };
+    let Some(granted) = granted else {
+        return vec![check("permissions", Status::Skip, "nothing to grant on this platform")];
+    };
+    let exe = std::env::current_exe().map(|p| p.display().to_string()).unwrap_or_else(|_| "seealln".to_string());
+    granted
+        .all()
+        .into_iter()
+        .map(|(name, grant)| match grant {
+            Grant::Granted => check("permissions", Status::Ok, name),
+            _ => check("permissions", Status::Fail, format!("{name} not granted")).hint(format!(
+                "allow {exe} (or its terminal) in {}, then restart seealln",
+                permissions::pane(name)
+            )),
+        })
+        .collect()
+}
+
+fn capture() -> Vec<Check> {
+    if !cfg!(feature = "capture") && !sim::active() {
+        return vec![check("capture", Status::Skip, "built without the capture feature")];
+    }
+    capture::displays()
+        .into_iter()
+        .map(|d| {
+            let name = format!("display {} ({}x{})", d.index, d.width, d.height);
+            if d.placeholder {
+                return check("capture", Status::Fail, format!("{name}: no display found")).hint("run inside the desktop session");
+            }
+            let started = Instant::now();
+            match capture::capture_frame(Some(d.index), Fallback::Fail) {
+                Ok(_) => check("capture", Status::Ok, format!("{name}: captured in {} ms", started.elapsed().as_millis())),
+                Err(err) => {
+                    let failed = check("capture", Status::Fail, format!("{name}: {err}"));
+                    if cfg!(target_os = "macos") {
+                        failed.hint(format!("grant Screen Recording in {}", permissions::pane("screen_recording")))
+                    } else {
+                        failed
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+fn injection(input: &Input) -> Check {
+    if !cfg!(feature = "hands") && !sim::active() {
+        return check("input", Status::Skip, "built without the hands feature");
+    }
+    match input.status() {
+        Ok(()) => check("input", Status::Ok, format!("backend {} ready", input.name())),
+        Err(err) => {
+            let failed = check("input", Status::Fail, format!("backend {}: {err}", input.name()));
+            match input::Failure::classify(&err) {
+                Some(failure) => failed.hint(failure.hint()),
+                None => failed,
+            }
+        }
+    }
+}
+
+fn port() -> Check {
+    if std::env::var_os("LISTEN_FDS").is_some() {
+        return check("port", Status::Skip, "socket passed by systemd");
+    }
+    let addr = crate::listen_addr();
+    match std::net::TcpListener::bind(addr) {
+        Ok(_) => check("port", Status::Ok, format!("{addr} is free")),
+        Err(err) => check("port", Status::Fail, format!("{addr}: {err}"))
+            .hint("is seealln already running? Otherwise pick another SEEALLN_PORT"),
+    }
+}
@@ -0,0 +1,108 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::Request,
+    http::{header, HeaderMap, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+
+// Binary bodies for JSON endpoints: a client whose Accept header prefers
+// application/msgpack (also application/x-msgpack, application/vnd.msgpack) or
+// application/cbor gets the same document in that encoding. Only application/json responses
+// are converted; images, streams and SSE pass through untouched, and JSON stays the default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl Encoding {
+    fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "application/json" | "application/*" | "*/*" => Some(Encoding::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Encoding::MsgPack),
+            "application/cbor" => Some(Encoding::Cbor),
+            _ => None,
+        }
+    }
+
+    fn mime(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::MsgPack => "application/msgpack",
+            Encoding::Cbor => "application/cbor",
+        }
+    }
+}
+
+// Highest-q encoding we produce; ties go to the earlier range, so "application/msgpack,
+// application/json" means msgpack.
+fn preferred(headers: &HeaderMap) -> Encoding {
+    let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return Encoding::Json;
+    };
+    let mut ranges: Vec<(Encoding, f32)> = accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let encoding = Encoding::from_mime(parts.next().unwrap_or_default())?;
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            Some((encoding, q))
+        })
+        .filter(|(_, q)| *q > 0.0)
+        .collect();
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.first().map_or(Encoding::Json, |(e, _)| *e)
+}
+
+fn is_json(resp: &Response) -> bool {
+    resp.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|ct| ct.split(';').next().unwrap_or_default().trim() == "application/json")
+}
+
+fn encode(value: &serde_json::Value, encoding: Encoding) -> Result<Vec<u8>, String> {
+    match encoding {
+        Encoding::Json => serde_json::to_vec(value).map_err(|e| e.to_string()),
+        // Maps keep their field names (not positional arrays), so the shape matches the JSON.
+        Encoding::MsgPack => rmp_serde::to_vec_named(value).map_err(|e| e.to_string()),
+        Encoding::Cbor => {
+            let mut out = Vec::new();
+            ciborium::into_writer(value, &mut out).map_err(|e| e.to_string())?;
+            Ok(out)
+        }
+    }
+}
+
+pub async fn negotiate(req: Request, next: Next) -> Response {
+    let encoding = preferred(req.headers());
+    let mut resp = next.run(req).await;
+    if encoding == Encoding::Json || !is_json(&resp) {
+        return resp;
+    }
+    resp.headers_mut().append(header::VARY, HeaderValue::from_static("accept"));
+    let (mut parts, body) = resp.into_parts();
+    // JSON bodies here are small, fully built documents; nothing streams.
+    let Ok(bytes) = to_bytes(body, usize::MAX).await else {
+        return Response::from_parts(parts, Body::empty());
+    };
+    let converted = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .map_err(|e| e.to_string())
+        .and_then(|value| encode(&value, encoding));
+    match converted {
+        Ok(buf) => {
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(encoding.mime()));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, Body::from(buf))
+        }
+        Err(err) => {
+            tracing::warn!(%err, "binary re-encoding failed; sending JSON");
+            Response::from_parts(parts, Body::from(bytes))
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    Json,
+};
+use futures::stream;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    convert::Infallible,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{
+    capture,
+    hands::{require_local_only, HandsState},
+    watch,
+};
+
+// In-process event bus, exposed as Server-Sent Events on GET /events. Every event is a JSON
+// object with `type` and `ts_ms`; the SSE event name is the type. Slow subscribers skip
+// ahead and get a `lagged` event with the number of dropped events.
+//
+//   ?types=kill,region_changed        only these event types
+//   ?watch=name:x,y,w,h[:threshold];...  per-subscriber region watches (see watch.rs), with
+//                                       `interval_ms` and `display`
+#[derive(Clone)]
+pub struct Events {
+    tx: broadcast::Sender<serde_json::Value>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(256);
+        Self { tx }
+    }
+}
+
+impl Events {
+    pub fn emit(&self, kind: &str, data: serde_json::Value) {
+        // No subscribers is fine.
+        let _ = self.tx.send(stamp(kind, data));
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<serde_json::Value> {
+        self.tx.subscribe()
+    }
+}
+
+// `data` as an event of `kind`: adds `type` and `ts_ms`.
+pub fn stamp(kind: &str, mut data: serde_json::Value) -> serde_json::Value {
+    if !data.is_object() {
+        data = json!({});
+    }
+    data["type"] = json!(kind);
+    data["ts_ms"] = json!(SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64);
+    data
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsParams {
+    types: Option<String>,
+    watch: Option<String>,
+    interval_ms: Option<u64>,
+    display: Option<usize>,
+}
+
+fn sse_event(data: &serde_json::Value) -> Event {
+    Event::default()
+        .event(data["type"].as_str().unwrap_or("message"))
+        .data(data.to_string())
+}
+
+pub async fn events_sse(
+    State(events): State<Events>,
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<EventsParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let watches = match params.watch.as_deref().map(watch::parse).transpose() {
+        Ok(watches) => watches,
+        Err(err) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    if let Some(i) = params.display.filter(|i| *i >= capture::displays().len()) {
+        return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": format!("unknown display {i}")}))).into_response();
+    }
+    let types: Option<Vec<String>> = params
+        .types
+        .map(|t| t.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect());
+    // Without watches the channel stays empty (its sender is dropped at once).
+    let watched = match watches {
+        Some(regions) => watch::spawn(hands, params.display, regions, watch::interval(params.interval_ms)),
+        None => mpsc::channel(1).1,
+    };
+    let state = (events.subscribe(), watched, types);
+    let body = stream::unfold(state, |(mut rx, mut watched, types)| async move {
+        loop {
+            let data = tokio::select! {
+                received = rx.recv() => match received {
+                    Ok(data) => data,
+                    Err(broadcast::error::RecvError::Lagged(n)) => json!({"type": "lagged", "dropped": n}),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                },
+                Some(data) = watched.recv() => data,
+            };
+            let wanted = match &types {
+                Some(types) => types.iter().any(|t| data["type"] == t.as_str()),
+                None => true,
+            };
+            if wanted {
+                return Some((Ok::<_, Infallible>(sse_event(&data)), (rx, watched, types)));
+            }
+        }
+    });
+    Sse::new(body)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(15)))
+        .into_response()
+}
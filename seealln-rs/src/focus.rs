@@ -0,0 +1,18 @@
+// Focused application lookup, used as interceptor context (e.g. app denylists).
+// Needs the `focus` feature; without it focus is always unknown.
+
+#[cfg(feature = "focus")]
+pub fn focused_app() -> Option<String> {
+    if crate::sim::active() {
+        return crate::sim::focused_app();
+    }
+    active_win_pos_rs::get_active_window()
+        .ok()
+        .map(|w| w.app_name)
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(not(feature = "focus"))]
+pub fn focused_app() -> Option<String> {
+    crate::sim::focused_app()
+}
@@ -0,0 +1,121 @@
+use axum::http::{header, HeaderMap, StatusCode};
+use image::{ImageEncoder, RgbImage};
+use serde::Deserialize;
+
+use crate::{capture::encode_jpeg_with, jpeg, zstd};
+
+// Snapshot output formats. `?format=` picks one explicitly; otherwise the Accept header is
+// consulted only when it rules JPEG out (browsers list image/avif but also accept */*, and a
+// multi-second AVIF encode is not what an <img> tag wants). AVIF needs the `avif` feature;
+// its speed (1 slowest/smallest .. 10 fastest) is SEEALLN_AVIF_SPEED, default 8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[serde(alias = "jpg")]
+    Jpeg,
+    Png,
+    Avif,
+    // Lossless BGRA8 pixels (width x height x 4, no padding), zstd-compressed; for CV
+    // pipelines that need exact pixels. `+` in a query string decodes to a space.
+    #[serde(rename = "raw+zstd", alias = "raw zstd")]
+    RawZstd,
+}
+
+impl Format {
+    pub fn mime(self) -> &'static str {
+        match self {
+            Format::Jpeg => "image/jpeg",
+            Format::Png => "image/png",
+            Format::Avif => "image/avif",
+            Format::RawZstd => "application/zstd",
+        }
+    }
+
+    pub fn ext(self) -> &'static str {
+        match self {
+            Format::Jpeg => "jpg",
+            Format::Png => "png",
+            Format::Avif => "avif",
+            Format::RawZstd => "bgra.zst",
+        }
+    }
+
+    pub fn available(self) -> bool {
+        self != Format::Avif || cfg!(feature = "avif")
+    }
+
+    fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "image/jpeg" | "image/jpg" => Some(Format::Jpeg),
+            "image/png" => Some(Format::Png),
+            "image/avif" => Some(Format::Avif),
+            "application/zstd" => Some(Format::RawZstd),
+            _ => None,
+        }
+    }
+}
+
+// Err: 501 for a format this build lacks, 406 when Accept allows nothing we produce.
+pub fn negotiate(requested: Option<Format>, headers: &HeaderMap) -> Result<Format, (StatusCode, String)> {
+    if let Some(format) = requested {
+        return if format.available() {
+            Ok(format)
+        } else {
+            Err((StatusCode::NOT_IMPLEMENTED, "avif feature disabled".to_string()))
+        };
+    }
+    let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return Ok(Format::Jpeg);
+    };
+    // (mime, q) pairs, best first; q=0 means "not acceptable".
+    let mut ranges: Vec<(&str, f32)> = accept
+        .split(',')
+        .map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let mime = parts.next().unwrap_or_default();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+            (mime, q)
+        })
+        .filter(|(_, q)| *q > 0.0)
+        .collect();
+    if ranges.is_empty() || ranges.iter().any(|(m, _)| matches!(*m, "*/*" | "image/*" | "image/jpeg" | "image/jpg")) {
+        return Ok(Format::Jpeg);
+    }
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges
+        .iter()
+        .filter_map(|(m, _)| Format::from_mime(m))
+        .find(|f| f.available())
+        .ok_or_else(|| (StatusCode::NOT_ACCEPTABLE, format!("no acceptable image format in `{accept}`")))
+}
+
+pub fn encode(img: &RgbImage, format: Format, quality: u8, opts: &jpeg::Options) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    match format {
+        Format::Jpeg => return encode_jpeg_with(img, quality, opts),
+        Format::Png => image::codecs::png::PngEncoder::new(&mut out)
+            .write_image(img.as_raw(), img.width(), img.height(), image::ExtendedColorType::Rgb8)
+            .map_err(|e| e.to_string())?,
+        #[cfg(feature = "avif")]
+        Format::Avif => {
+            let speed = std::env::var("SEEALLN_AVIF_SPEED")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(8u8)
+                .clamp(1, 10);
+            image::codecs::avif::AvifEncoder::new_with_speed_quality(&mut out, speed, quality)
+                .write_image(img.as_raw(), img.width(), img.height(), image::ExtendedColorType::Rgb8)
+                .map_err(|e| e.to_string())?
+        }
+        #[cfg(not(feature = "avif"))]
+        Format::Avif => return Err("avif feature disabled".to_string()),
+        Format::RawZstd => {
+            let bgra: Vec<u8> = img.pixels().flat_map(|p| [p[2], p[1], p[0], 255]).collect();
+            out = zstd::compress(&bgra);
+        }
+    }
+    Ok(out)
+}
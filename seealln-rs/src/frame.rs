@@ -0,0 +1,414 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{
+    capture::{self, Fallback},
+    caret, detect,
+    hands::{require_local_only, HandsState, ScopeRect},
+    ocr, sim, theme, vision,
+};
+
+// Cheap "has anything changed?" checks without shipping frames: GET /frame/phash returns the
+// perceptual hash of the current frame (or a region of it); POST /frame/similar compares it
+// with a client-side image or an earlier hash. Similarity is 1 - hamming distance / 64;
+// `changed` is distance > threshold (default 6 bits).
+const DEFAULT_THRESHOLD: u32 = 6;
+
+#[derive(Debug, Deserialize)]
+pub struct PhashParams {
+    display: Option<usize>,
+    // "x,y,w,h" in screen pixels.
+    region: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimilarReq {
+    // 16 hex digits, as returned by /frame/phash.
+    hash: Option<String>,
+    // PNG/JPEG, "base64:<data>" or a path under SEEALLN_TEMPLATE_DIR.
+    image: Option<String>,
+    display: Option<usize>,
+    region: Option<ScopeRect>,
+    threshold: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OcrParams {
+    display: Option<usize>,
+    region: Option<String>,
+    #[serde(default)]
+    layout: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColorsParams {
+    display: Option<usize>,
+    // Region as separate fields (all four or none), or `region=x,y,w,h`.
+    x: Option<i32>,
+    y: Option<i32>,
+    w: Option<i32>,
+    h: Option<i32>,
+    region: Option<String>,
+    bins: Option<usize>,
+    top: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CaretParams {
+    display: Option<usize>,
+    region: Option<String>,
+    // Sampling window, default 1200 ms, max 5000.
+    window_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DetectParams {
+    display: Option<usize>,
+    region: Option<String>,
+    min_score: Option<f32>,
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+pub fn parse_region(s: &str) -> Result<ScopeRect, String> {
+    let v: Vec<i32> = s
+        .split(',')
+        .map(|p| p.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("region must be x,y,w,h (got `{s}`)"))?;
+    match v[..] {
+        [x, y, w, h] if w > 0 && h > 0 => Ok(ScopeRect { x, y, w, h }),
+        _ => Err(format!("region must be x,y,w,h with positive size (got `{s}`)")),
+    }
+}
+
+// `?region=` parsed and narrowed to what the hands scope lets capture show.
+fn confined_region(hands: &HandsState, raw: Option<&str>) -> Result<Option<ScopeRect>, (StatusCode, String)> {
+    let region = raw.map(parse_region).transpose().map_err(|err| (StatusCode::BAD_REQUEST, err))?;
+    hands.confine(region).map_err(|err| (StatusCode::FORBIDDEN, err))
+}
+
+fn hex(hash: u64) -> String {
+    format!("{hash:016x}")
+}
+
+fn unknown_display(display: Option<usize>) -> Option<Response> {
+    let i = display.filter(|i| *i >= capture::displays().len())?;
+    Some(fail(StatusCode::NOT_FOUND, format!("unknown display {i}")))
+}
+
+// Hash of the current frame (cropped to `region`) and where the frame came from.
+async fn current(display: Option<usize>, region: Option<ScopeRect>) -> Result<(u64, capture::Source), Response> {
+    if let Some(resp) = unknown_display(display) {
+        return Err(resp);
+    }
+    tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, _, _) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        Ok((vision::phash(&img), frame.source))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+    .map_err(|err| fail(StatusCode::SERVICE_UNAVAILABLE, err))
+}
+
+pub async fn frame_phash(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<PhashParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let region = match confined_region(&hands, params.region.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    match current(params.display, region).await {
+        Ok((hash, source)) => {
+            (StatusCode::OK, Json(json!({"ok": true, "phash": hex(hash), "capture": source.as_str()}))).into_response()
+        }
+        Err(resp) => resp,
+    }
+}
+
+pub async fn frame_similar(State(hands): State<HandsState>, headers: HeaderMap, Json(req): Json<SimilarReq>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let other = match (&req.hash, &req.image) {
+        (Some(h), None) => match u64::from_str_radix(h.trim(), 16) {
+            Ok(v) if h.trim().len() == 16 => v,
+            _ => return fail(StatusCode::BAD_REQUEST, "hash must be 16 hex digits"),
+        },
+        (None, Some(spec)) => {
+            let spec = spec.clone();
+            match tokio::task::spawn_blocking(move || vision::load_template(&spec).map(|img| vision::phash(&img))).await {
+                Ok(Ok(v)) => v,
+                Ok(Err(err)) => return fail(StatusCode::BAD_REQUEST, err),
+                Err(err) => return fail(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+            }
+        }
+        _ => return fail(StatusCode::BAD_REQUEST, "give exactly one of `hash` or `image`"),
+    };
+    let region = match hands.confine(req.region) {
+        Ok(region) => region,
+        Err(err) => return fail(StatusCode::FORBIDDEN, err),
+    };
+    let (hash, source) = match current(req.display, region).await {
+        Ok(v) => v,
+        Err(resp) => return resp,
+    };
+    let distance = (hash ^ other).count_ones();
+    let threshold = req.threshold.unwrap_or(DEFAULT_THRESHOLD).min(64);
+    (
+        StatusCode::OK,
+        Json(json!({
+            "ok": true,
+            "phash": hex(hash),
+            "other": hex(other),
+            "distance": distance,
+            "similarity": 1.0 - distance as f64 / 64.0,
+            "changed": distance > threshold,
+            "capture": source.as_str(),
+        })),
+    )
+        .into_response()
+}
+
+// GET /ocr: text of the current frame (or `region`). Default: a flat word list (sparse-text
+// segmentation, best for scattered UI labels). `layout=true`: paragraphs and lines in reading
+// order with boxes and font-size estimates. Boxes are in screen pixels.
+pub async fn frame_ocr(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<OcrParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let region = match confined_region(&hands, params.region.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let (display, layout) = (params.display, params.layout);
+    let result = tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, ox, oy) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        let body = if layout {
+            json!({"paragraphs": ocr::layout(&img, ox, oy)?})
+        } else {
+            let words: Vec<ocr::Word> = ocr::words(&img)?
+                .into_iter()
+                .map(|mut w| {
+                    w.x += ox;
+                    w.y += oy;
+                    w
+                })
+                .collect();
+            json!({"words": words})
+        };
+        Ok((body, frame.source))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((mut body, source)) => {
+            body["ok"] = json!(true);
+            body["capture"] = json!(source.as_str());
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(err) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    }
+}
+
+// GET /detect/elements: UI elements (buttons, inputs, checkboxes, links) in the current
+// frame (or `region`) from the local detection model, best first. 501 without the `detect`
+// feature; 503 when no model is configured or it fails to load.
+pub async fn detect_elements(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<DetectParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !cfg!(feature = "detect") {
+        return fail(StatusCode::NOT_IMPLEMENTED, "detect feature disabled");
+    }
+    let region = match confined_region(&hands, params.region.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let display = params.display;
+    let min_score = params.min_score.unwrap_or(detect::DEFAULT_MIN_SCORE).clamp(0.0, 1.0);
+    let result = tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, ox, oy) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        Ok((detect::elements(&img, ox, oy, min_score)?, frame.source))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((elements, source)) => (
+            StatusCode::OK,
+            Json(json!({"ok": true, "elements": elements, "labels": detect::labels(), "capture": source.as_str()})),
+        )
+            .into_response(),
+        Err(err) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    }
+}
+
+const DEFAULT_BINS: usize = 16;
+const DEFAULT_TOP: usize = 5;
+const MAX_TOP: usize = 32;
+
+// GET /analyze/colors: per-channel histograms, mean and dominant colors (with a coarse name,
+// "red", "green", ...) of the current frame or a region, for cheap state checks such as an
+// error banner or a success toast.
+pub async fn analyze_colors(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<ColorsParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let raw = match (params.x, params.y, params.w, params.h, params.region) {
+        (None, None, None, None, region) => region,
+        (Some(x), Some(y), Some(w), Some(h), None) => Some(format!("{x},{y},{w},{h}")),
+        _ => return fail(StatusCode::BAD_REQUEST, "give all of x, y, w, h, or `region`, or neither"),
+    };
+    let region = match confined_region(&hands, raw.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let display = params.display;
+    let bins = params.bins.unwrap_or(DEFAULT_BINS);
+    let top = params.top.unwrap_or(DEFAULT_TOP).clamp(1, MAX_TOP);
+    let result = tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, ox, oy) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        let area = ScopeRect { x: ox, y: oy, w: img.width() as i32, h: img.height() as i32 };
+        Ok((vision::colors(&img, bins, top), area, frame.source))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((colors, area, source)) => {
+            let mut body = json!(colors);
+            body["ok"] = json!(true);
+            body["region"] = json!(area);
+            body["capture"] = json!(source.as_str());
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(err) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    }
+}
+
+// GET /analyze/theme: dark or light, estimated from the luminance of the frame (or region,
+// narrowed to the scope), with the OS preference alongside where it can be read (see
+// theme.rs). The frame wins: an app can ignore the OS setting.
+pub async fn analyze_theme(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<PhashParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let region = match confined_region(&hands, params.region.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let display = params.display;
+    let result = tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, ox, oy) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        let area = ScopeRect { x: ox, y: oy, w: img.width() as i32, h: img.height() as i32 };
+        let os = if sim::active() { None } else { theme::os_preference() };
+        Ok((theme::estimate(&img), os, area, frame.source))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((estimate, os, area, source)) => {
+            let mut body = json!(estimate);
+            body["ok"] = json!(true);
+            body["os"] = json!(os.map(|(theme, source)| json!({"theme": theme, "source": source})));
+            body["agrees"] = json!(os.map(|(theme, _)| theme == estimate.theme));
+            body["region"] = json!(area);
+            body["capture"] = json!(source.as_str());
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Err(err) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    }
+}
+
+// GET /detect/caret: where the blinking text caret is, found by sampling the frame (or region)
+// for `window_ms` (see caret.rs). `found: false` means no field shows a caret, or its toolkit
+// does not blink. Best with a region around the field: a full screen takes longer to scan.
+pub async fn detect_caret(
+    State(hands): State<HandsState>,
+    headers: HeaderMap,
+    Query(params): Query<CaretParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let region = match confined_region(&hands, params.region.as_deref()) {
+        Ok(region) => region,
+        Err((code, err)) => return fail(code, err),
+    };
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let display = params.display;
+    let window = params.window_ms.map(Duration::from_millis).unwrap_or(caret::DEFAULT_WINDOW);
+    match tokio::task::spawn_blocking(move || caret::detect(display, region, window)).await {
+        Ok(Ok(report)) => {
+            let mut body = json!(report);
+            body["ok"] = json!(true);
+            body["found"] = json!(report.caret.is_some());
+            (StatusCode::OK, Json(body)).into_response()
+        }
+        Ok(Err(err)) => fail(StatusCode::SERVICE_UNAVAILABLE, err),
+        Err(err) => fail(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
@@ -0,0 +1,311 @@
+use image::RgbImage;
+
+// GPU pixel work (feature `gpu`, wgpu compute): BGRA -> RGB conversion of captured frames and
+// downscaling for served frames, which cap the stream rate on the CPU at 4K and above. The
+// device is set up on first use (Vulkan, Metal, DX12 or GL, whichever wgpu finds); with no
+// adapter, SEEALLN_GPU=0, frames under MIN_PIXELS or any GPU error, callers get None and do
+// the work on the CPU as before. /health reports the adapter as `capture_health.gpu`.
+//
+// One shader does both: each output pixel is the box average of its source footprint (a
+// plain copy when the sizes match), read from BGRA or packed RGB, written as packed RGB.
+// Downscaled frames are close to, not bit-identical with, the CPU's triangle filter.
+#[cfg(feature = "gpu")]
+const MIN_PIXELS: u64 = 1920 * 1080;
+
+// BGRA rows of `stride` bytes to RGB.
+#[cfg(feature = "gpu")]
+pub fn bgra_to_rgb(bgra: &[u8], w: u32, h: u32, stride: u32) -> Option<RgbImage> {
+    if (w as u64) * (h as u64) < MIN_PIXELS {
+        return None;
+    }
+    imp::run(bgra, imp::Layout { w, h, stride, bpp: 4, bgr: true }, (w, h))
+}
+
+// Only capture converts BGRA frames.
+#[cfg(all(feature = "capture", not(feature = "gpu")))]
+pub fn bgra_to_rgb(_bgra: &[u8], _w: u32, _h: u32, _stride: u32) -> Option<RgbImage> {
+    None
+}
+
+// `img` shrunk to `w` x `h`.
+#[cfg(feature = "gpu")]
+pub fn resize(img: &RgbImage, w: u32, h: u32) -> Option<RgbImage> {
+    let (sw, sh) = img.dimensions();
+    if (sw as u64) * (sh as u64) < MIN_PIXELS {
+        return None;
+    }
+    imp::run(img.as_raw(), imp::Layout { w: sw, h: sh, stride: sw * 3, bpp: 3, bgr: false }, (w, h))
+}
+
+#[cfg(not(feature = "gpu"))]
+pub fn resize(_img: &RgbImage, _w: u32, _h: u32) -> Option<RgbImage> {
+    None
+}
+
+// Adapter in use, e.g. "NVIDIA GeForce RTX 3060 (Vulkan)"; None when frames go through the CPU
+// or no large frame has been processed yet.
+#[cfg(feature = "gpu")]
+pub fn adapter() -> Option<String> {
+    imp::ready().map(|g| g.name.clone())
+}
+
+#[cfg(not(feature = "gpu"))]
+pub fn adapter() -> Option<String> {
+    None
+}
+
+#[cfg(feature = "gpu")]
+mod imp {
+    use image::RgbImage;
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, OnceLock,
+    };
+    use tracing::{info, warn};
+    use wgpu::util::DeviceExt;
+
+    const WORKGROUP: u32 = 64;
+    const MAX_GROUPS: u32 = 65535;
+
+    // Each invocation writes 4 output pixels as 3 packed words.
+    const SHADER: &str = r#"
+struct Params {
+    src_w: u32, src_h: u32, stride: u32, bpp: u32,
+    bgr: u32, dst_w: u32, dst_h: u32, row: u32,
+}
+@group(0) @binding(0) var<uniform> p: Params;
+@group(0) @binding(1) var<storage, read> src: array<u32>;
+@group(0) @binding(2) var<storage, read_write> dst: array<u32>;
+
+fn byte_at(i: u32) -> u32 {
+    return (src[i >> 2u] >> ((i & 3u) * 8u)) & 0xffu;
+}
+
+fn pixel(x: u32, y: u32) -> vec3<u32> {
+    let o = y * p.stride + x * p.bpp;
+    let a = byte_at(o);
+    let b = byte_at(o + 1u);
+    let c = byte_at(o + 2u);
+    if (p.bgr == 1u) {
+        return vec3<u32>(c, b, a);
+    }
+    return vec3<u32>(a, b, c);
+}
+
+fn sample(i: u32) -> vec3<u32> {
+    if (i >= p.dst_w * p.dst_h) {
+        return vec3<u32>(0u);
+    }
+    let dx = i % p.dst_w;
+    let dy = i / p.dst_w;
+    let x0 = dx * p.src_w / p.dst_w;
+    let x1 = max((dx + 1u) * p.src_w / p.dst_w, x0 + 1u);
+    let y0 = dy * p.src_h / p.dst_h;
+    let y1 = max((dy + 1u) * p.src_h / p.dst_h, y0 + 1u);
+    var sum = vec3<u32>(0u);
+    for (var y = y0; y < y1; y = y + 1u) {
+        for (var x = x0; x < x1; x = x + 1u) {
+            sum = sum + pixel(x, y);
+        }
+    }
+    return sum / ((x1 - x0) * (y1 - y0));
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let q = id.y * p.row + id.x;
+    if (q * 4u >= p.dst_w * p.dst_h) {
+        return;
+    }
+    let a = sample(q * 4u);
+    let b = sample(q * 4u + 1u);
+    let c = sample(q * 4u + 2u);
+    let d = sample(q * 4u + 3u);
+    dst[q * 3u] = a.x | (a.y << 8u) | (a.z << 16u) | (b.x << 24u);
+    dst[q * 3u + 1u] = b.y | (b.z << 8u) | (c.x << 16u) | (c.y << 24u);
+    dst[q * 3u + 2u] = c.z | (d.x << 8u) | (d.y << 16u) | (d.z << 24u);
+}
+"#;
+
+    pub struct Gpu {
+        pub name: String,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        pipeline: wgpu::ComputePipeline,
+        max_binding: u64,
+    }
+
+    // Source pixel layout.
+    pub struct Layout {
+        pub w: u32,
+        pub h: u32,
+        pub stride: u32,
+        pub bpp: u32,
+        pub bgr: bool,
+    }
+
+    // Set after an uncaptured device error; everything goes through the CPU from then on.
+    static BROKEN: AtomicBool = AtomicBool::new(false);
+
+    fn init() -> Option<Gpu> {
+        if std::env::var("SEEALLN_GPU").ok().as_deref().map(str::trim) == Some("0") {
+            return None;
+        }
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter = futures::executor::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: None,
+            force_fallback_adapter: false,
+        }));
+        let Some(adapter) = adapter else {
+            info!("no GPU adapter; frames are converted and scaled on the CPU");
+            return None;
+        };
+        let info = adapter.get_info();
+        if info.device_type == wgpu::DeviceType::Cpu {
+            info!(adapter = %info.name, "only a software GPU adapter; staying on the CPU");
+            return None;
+        }
+        let limits = adapter.limits();
+        let device = futures::executor::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("seealln"),
+                required_features: wgpu::Features::empty(),
+                required_limits: limits.clone(),
+                memory_hints: wgpu::MemoryHints::Performance,
+            },
+            None,
+        ));
+        let (device, queue) = match device {
+            Ok(dq) => dq,
+            Err(err) => {
+                warn!(%err, adapter = %info.name, "GPU device unavailable; staying on the CPU");
+                return None;
+            }
+        };
+        device.on_uncaptured_error(Box::new(|err| {
+            warn!(%err, "GPU error; converting and scaling on the CPU from now on");
+            BROKEN.store(true, Ordering::Relaxed);
+        }));
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("seealln-pixels"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("seealln-pixels"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+            compilation_options: Default::default(),
+            cache: None,
+        });
+        let name = format!("{} ({:?})", info.name, info.backend);
+        info!(adapter = %name, "converting and scaling frames on the GPU");
+        let max_binding = (limits.max_storage_buffer_binding_size as u64).min(limits.max_buffer_size);
+        Some(Gpu { name, device, queue, pipeline, max_binding })
+    }
+
+    static GPU: OnceLock<Option<Gpu>> = OnceLock::new();
+
+    fn gpu() -> Option<&'static Gpu> {
+        if BROKEN.load(Ordering::Relaxed) {
+            return None;
+        }
+        GPU.get_or_init(init).as_ref()
+    }
+
+    // The device if already set up and working, without setting it up.
+    pub fn ready() -> Option<&'static Gpu> {
+        GPU.get()?.as_ref().filter(|_| !BROKEN.load(Ordering::Relaxed))
+    }
+
+    fn words(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    // Samples `src` (laid out as `layout`) into a `dst_w` x `dst_h` RGB image.
+    pub fn run(src: &[u8], layout: Layout, (dst_w, dst_h): (u32, u32)) -> Option<RgbImage> {
+        let gpu = gpu()?;
+        if dst_w == 0 || dst_h == 0 || (src.len() as u64) < layout.stride as u64 * layout.h as u64 {
+            return None;
+        }
+        let quads = (dst_w as u64 * dst_h as u64).div_ceil(4);
+        let out_len = quads * 12;
+        let in_len = (src.len() as u64).next_multiple_of(4);
+        if in_len > gpu.max_binding || out_len > gpu.max_binding {
+            return None;
+        }
+        let groups = quads.div_ceil(WORKGROUP as u64) as u32;
+        let (gx, gy) = (groups.min(MAX_GROUPS), groups.div_ceil(MAX_GROUPS));
+        if gy > MAX_GROUPS {
+            return None;
+        }
+
+        let device = &gpu.device;
+        let params = words(&[layout.w, layout.h, layout.stride, layout.bpp, layout.bgr as u32, dst_w, dst_h, gx * WORKGROUP]);
+        let params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: &params,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        // Storage buffers are read as whole words.
+        let padded: Vec<u8>;
+        let contents = if src.len() as u64 == in_len {
+            src
+        } else {
+            padded = src.iter().copied().chain(std::iter::repeat(0)).take(in_len as usize).collect();
+            &padded
+        };
+        let input = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: out_len,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: out_len,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &gpu.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: params.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: input.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: output.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+            pass.set_pipeline(&gpu.pipeline);
+            pass.set_bind_group(0, &bind, &[]);
+            pass.dispatch_workgroups(gx, gy, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &readback, 0, out_len);
+        gpu.queue.submit(Some(encoder.finish()));
+
+        let slice = readback.slice(..);
+        let (tx, rx) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |r| {
+            let _ = tx.send(r);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        rx.recv().ok()?.ok()?;
+        if BROKEN.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut rgb = slice.get_mapped_range().to_vec();
+        readback.unmap();
+        rgb.truncate(dst_w as usize * dst_h as usize * 3);
+        RgbImage::from_raw(dst_w, dst_h, rgb)
+    }
+}
@@ -1,34 +1,204 @@
 use axum::{
     extract::{Query, State},
     http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde::Deserialize;
 use serde_json::json;
 use std::{
-    sync::{Arc, Mutex},
+    collections::{BTreeMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
     time::{Duration, Instant},
 };
+use tracing::{error, warn};
+
+use crate::{
+    anomaly::{Anomaly, Detector, Thresholds},
+    arming::{Arming, KillCause, Meta, Profile, KILL_SOURCES},
+    auth::require_api_key,
+    capture::{self, Fallback},
+    caret,
+    confirm::{self, Confirmations, Intent},
+    desktop,
+    events::Events,
+    focus,
+    input::{self, Input},
+    keys, layout,
+    policy,
+    ratelimit::{self, Bucket, Limit, Weights},
+    interceptor::{
+        Action, ActionContext, ActionInterceptor, AppDenylist, BrowserOp, Denial, Gesture, ScopeClamp, ScreenClamp,
+        SensitiveText,
+    },
+    origin::Origin,
+    overrides::{self, Override},
+    redact::Redactor,
+    scopes::{Preset, Presets},
+    session::{ActiveSession, Recorder},
+    sim, stats,
+    store::Store,
+    trail, vision,
+};
 
 #[derive(Clone, Default)]
 pub struct HandsState {
     inner: Arc<Mutex<HandsInner>>,
+    // Audit sink for arming/safety/action events (None = no audit trail).
+    store: Option<Store>,
+    // Guardrails and policy hooks, run in order for every action.
+    interceptors: Arc<Vec<Arc<dyn ActionInterceptor>>>,
+    // Session timeline recorder (None = recording disabled).
+    recorder: Option<Recorder>,
+    // Named scope presets (in memory only without a store).
+    presets: Presets,
+    // Where actions are injected (SEEALLN_INPUT_BACKEND; none without a store).
+    input: Input,
+    // How typed text appears in audit entries (see redact.rs).
+    redactor: Redactor,
+    // Kill/reset/anomaly notifications for GET /events subscribers.
+    events: Events,
+    // Runaway-loop heuristics (None = off; see anomaly.rs).
+    anomaly: Option<Thresholds>,
+    // Per-action confirmations handed out by /hands/confirm (see confirm.rs).
+    confirmations: Confirmations,
+    // Actions run one at a time, in arrival order (tokio's mutex is fair).
+    turn: Arc<tokio::sync::Mutex<()>>,
+    // Metadata of the latest arming, added to audit entries and events until disarm or reset.
+    // Kept outside `inner` so `audit` never needs the state lock (it is called under it).
+    meta: Arc<Mutex<Option<Meta>>>,
+}
+
+// What happened to an executed action, for the hands endpoints' responses.
+pub struct Receipt {
+    // As executed, after interceptor rewrites.
+    pub action: Action,
+    // As requested.
+    pub requested: Action,
+    // Waiting for the actions ahead of it.
+    pub queue: Duration,
+    // Injection alone.
+    pub took: Duration,
+    // sha256 of the session recording frame taken after it.
+    pub recorded: Option<String>,
+}
+
+impl Receipt {
+    // Whether a clamp interceptor moved the action's coordinates (the scope or the screen
+    // edge); other rewrites do not count.
+    pub fn clamped(&self) -> bool {
+        matches!(self.action, Action::Move { .. } | Action::Gesture { .. }) && self.action != self.requested
+    }
+
+    pub fn json(&self, pointer: Option<(i32, i32)>, phash: Option<u64>) -> serde_json::Value {
+        let clamped = self.clamped();
+        json!({
+            "kind": self.action.kind(),
+            "pointer": pointer.map(|(x, y)| json!({"x": x, "y": y})),
+            "queue_ms": self.queue.as_secs_f64() * 1000.0,
+            "duration_ms": self.took.as_secs_f64() * 1000.0,
+            "clamped": clamped,
+            "requested": clamped.then(|| json!(self.requested)),
+            "frame": {
+                "phash": phash.map(|h| format!("{h:016x}")),
+                "recorded_sha256": self.recorded,
+            },
+        })
+    }
 }
 
 #[derive(Default)]
 struct HandsInner {
-    armed_until: Option<Instant>,
-    token: Option<String>,
-    // Simple rate limit: max actions within a window
-    window_start: Option<Instant>,
-    window_actions: u32,
-
-    // Safety kill switch: when true, all hands actions are forbidden.
-    killed: bool,
+    // Disarmed / Armed / Killed (see arming.rs).
+    arming: Arming,
+    // Action rate limit, weighted by action kind (see ratelimit.rs; PATCH /admin/limits).
+    bucket: Bucket,
+    limit: Limit,
+    weights: Weights,
 
     // Optional scope/region lock (inclusive min, exclusive max)
     scope: Option<ScopeRect>,
+    // Whether the scope also limits what capture endpoints show (see `visible_region`).
+    confine_capture: bool,
+    // Refuse out-of-scope moves rather than clamping them.
+    strict_scope: bool,
+
+    // Action history for the anomaly heuristics.
+    detector: Detector,
+
+    // Last executed actions (summaries), fed to interceptors as context.
+    recent: VecDeque<serde_json::Value>,
+    // Where the last executed move or tap left the pointer.
+    pointer: Option<(i32, i32)>,
+    // Recent pointer path and clicks, for stream overlays (see trail.rs).
+    trail: VecDeque<trail::Mark>,
+    // Whether a supervision stream has watched the current (supervised) arming yet.
+    supervisor_seen: bool,
+    // Executed actions of the current arming, oldest first; emptied whenever the arming
+    // changes, so nothing done under one arming is undone under the next.
+    undo: VecDeque<Undo>,
+    // Last undo entry id handed out.
+    undo_seq: u64,
+}
+
+// An executed action on the undo stack.
+struct Undo {
+    // What an operator's undo confirmation is bound to (see confirm.rs).
+    id: u64,
+    kind: &'static str,
+    // What reverses it (None: irreversible).
+    steps: Option<Vec<Action>>,
+    // The application focused when it ran; it is only undone while that one still is.
+    app: Option<String>,
+}
+
+impl HandsInner {
+    // Applies an elapsed ttl (see Arming::expire); returns the session to end.
+    fn expire(&mut self) -> Option<ActiveSession> {
+        let armed = self.arming.is_armed();
+        let expired = self.arming.expire(Instant::now());
+        if armed && !self.arming.is_armed() {
+            self.undo.clear();
+        }
+        expired
+    }
+}
+
+const RECENT_ACTIONS: usize = 20;
+
+// How far back POST /hands/undo can go.
+const UNDO_DEPTH: usize = 20;
+// Longer typed text is not undone with backspaces.
+const UNDO_MAX_CHARS: usize = 500;
+
+// How often a supervised arming checks for its supervision stream between actions.
+const SUPERVISION_POLL: Duration = Duration::from_millis(500);
+// How long after arming the first supervision stream may take to attach.
+const SUPERVISION_GRACE: Duration = Duration::from_secs(30);
+
+// The actions that reverse `action`, given where the pointer was before it. Clicks, keys,
+// gestures and browser actions are irreversible: what they did is up to the application.
+fn inverse(action: &Action, pointer_before: Option<(i32, i32)>) -> Option<Vec<Action>> {
+    match action {
+        Action::Move { .. } => pointer_before.map(|(x, y)| vec![Action::Move { x, y }]),
+        Action::Type { text } | Action::Paste { text } => {
+            let chars = text.chars().count();
+            (chars <= UNDO_MAX_CHARS).then(|| vec![Action::Key { combo: "backspace".to_string() }; chars])
+        }
+        Action::Scroll { dx, dy } => Some(vec![Action::Scroll { dx: -dx, dy: -dy }]),
+        Action::Click { .. } | Action::Key { .. } | Action::Gesture { .. } | Action::Browser { .. } => None,
+    }
+}
+
+// Browser actions go to the page over cdp instead of through the input backend.
+async fn browse(op: &BrowserOp) -> Result<(), (StatusCode, String)> {
+    #[cfg(feature = "cdp")]
+    return crate::cdp::execute(op).await;
+    #[cfg(not(feature = "cdp"))]
+    {
+        let _ = op;
+        Err((StatusCode::NOT_IMPLEMENTED, "built without the cdp feature".to_string()))
+    }
 }
 
 #[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
@@ -51,103 +221,687 @@ impl ScopeRect {
     pub fn contains(&self, x: i32, y: i32) -> bool {
         x >= self.x && y >= self.y && x < self.x.saturating_add(self.w) && y < self.y.saturating_add(self.h)
     }
+
+    // Overlap of two rects; None when they do not overlap.
+    pub fn intersect(&self, other: &ScopeRect) -> Option<ScopeRect> {
+        let x0 = self.x.max(other.x);
+        let y0 = self.y.max(other.y);
+        let x1 = self.x.saturating_add(self.w).min(other.x.saturating_add(other.w));
+        let y1 = self.y.saturating_add(self.h).min(other.y.saturating_add(other.h));
+        (x1 > x0 && y1 > y0).then(|| ScopeRect { x: x0, y: y0, w: x1 - x0, h: y1 - y0 })
+    }
+}
+
+// Default for `confine_capture` when /scope/set does not say (SEEALLN_SCOPE_CONFINE_CAPTURE=1).
+fn confine_capture_default() -> bool {
+    std::env::var("SEEALLN_SCOPE_CONFINE_CAPTURE").ok().as_deref() == Some("1")
+}
+
+fn strict_scope_default() -> bool {
+    std::env::var("SEEALLN_SCOPE_STRICT").ok().as_deref() == Some("1")
 }
 
 impl HandsState {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn with_store(store: Store) -> Self {
+        let redactor = Redactor::load(store.root());
+        Self {
+            recorder: Recorder::from_env(store.clone(), redactor.clone()),
+            presets: Presets::load(store.root()),
+            redactor,
+            anomaly: Thresholds::from_env(),
+            input: Input::from_env(),
+            inner: Arc::new(Mutex::new(HandsInner {
+                strict_scope: strict_scope_default(),
+                limit: Limit::from_env(),
+                weights: Weights::from_env(),
+                ..HandsInner::default()
+            })),
+            store: Some(store),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_events(mut self, events: Events) -> Self {
+        self.events = events;
+        self
+    }
+
+    // Appends an interceptor to the pipeline (runs after the ones already registered).
+    pub fn with_interceptor(mut self, interceptor: impl ActionInterceptor + 'static) -> Self {
+        Arc::make_mut(&mut self.interceptors).push(Arc::new(interceptor));
+        self
+    }
+
+    // Built-in guardrails: display clamp, scope clamp, sensitive-text check, app denylist.
+    pub fn with_default_interceptors(self) -> Self {
+        let state = self
+            .with_interceptor(ScreenClamp)
+            .with_interceptor(ScopeClamp)
+            .with_interceptor(SensitiveText::default());
+        let state = match AppDenylist::from_env() {
+            Some(denylist) => state.with_interceptor(denylist),
+            None => state,
+        };
+        // Custom policy script last, so it sees the action after built-in clamping; what it
+        // rewrites goes through the built-in guardrails again.
+        match policy::from_env(state.interceptors.clone()) {
+            Some(script) => state.with_interceptor(script),
+            None => state,
+        }
+    }
+
+    pub fn record_recent(&self, summary: serde_json::Value) {
+        let mut inner = self.lock();
+        if inner.recent.len() == RECENT_ACTIONS {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back(summary);
+    }
+
+    pub fn context(&self) -> ActionContext {
+        ActionContext {
+            scope: self.get_scope(),
+            strict_scope: self.lock().strict_scope,
+            focused_app: focus::focused_app(),
+            recent: self.lock().recent.iter().cloned().collect(),
+        }
+    }
+
+    // Runs the interceptors only (no execution), returning the action as it would run.
+    pub fn preview(&self, mut action: Action) -> Result<Action, Denial> {
+        let ctx = self.context();
+        for interceptor in self.interceptors.iter() {
+            interceptor.before(&mut action, &ctx)?;
+        }
+        Ok(action)
+    }
+
+    // Interceptors, execution, audit and session recording for an action already authorized by
+    // `token`, which must still be the live arming when the action's turn comes.
+    // Returns the action as executed (after interceptor rewrites).
+    pub async fn perform(&self, token: &str, action: Action) -> Result<Action, (StatusCode, String)> {
+        self.perform_with(token, action, &[]).await
+    }
+
+    // `perform`, with operator overrides that may lift individual interceptor denials
+    // (see overrides.rs).
+    pub async fn perform_with(&self, token: &str, action: Action, overrides: &[Override]) -> Result<Action, (StatusCode, String)> {
+        self.perform_by(token, action, overrides, None).await.map(|receipt| receipt.action)
+    }
+
+    // `perform_with`, given up with 408 `expired` if the action has not started by `deadline`
+    // (still queued behind other actions): a click landing seconds late may hit a screen
+    // that has changed since the agent looked.
+    pub async fn perform_by(
+        &self,
+        token: &str,
+        action: Action,
+        overrides: &[Override],
+        deadline: Option<Instant>,
+    ) -> Result<Receipt, (StatusCode, String)> {
+        self.perform_inner(Some(token), action, overrides, deadline, true).await
+    }
+
+    // `perform`, kept off the undo stack and not tied to an arming: moves the server makes on
+    // its own (/selftest).
+    pub async fn perform_untracked(&self, action: Action) -> Result<Action, (StatusCode, String)> {
+        self.perform_inner(None, action, &[], None, false).await.map(|receipt| receipt.action)
+    }
+
+    // `token`: the arming that authorized the action, re-checked once it holds the turn (None
+    // for server-initiated and operator moves). `undoable`: push how to reverse the action onto
+    // the undo stack (not for undo's own steps).
+    async fn perform_inner(
+        &self,
+        token: Option<&str>,
+        mut action: Action,
+        overrides: &[Override],
+        deadline: Option<Instant>,
+        undoable: bool,
+    ) -> Result<Receipt, (StatusCode, String)> {
+        let requested = action.clone();
+        let queued = Instant::now();
+        let turn = match deadline {
+            Some(at) => match tokio::time::timeout_at(at.into(), self.turn.lock()).await {
+                Ok(turn) => turn,
+                Err(_) => return Err(self.expire(&action, queued)),
+            },
+            None => self.turn.lock().await,
+        };
+        let ctx = self.context();
+        for interceptor in self.interceptors.iter() {
+            if let Err(denial) = interceptor.before(&mut action, &ctx) {
+                let lifted = overrides
+                    .iter()
+                    .find(|o| o.covers(interceptor.name(), &action, &ctx) && overrides::take_use(o));
+                if let Some(o) = lifted {
+                    warn!(id = %o.id, rule = %o.rule, "guardrail lifted by operator override");
+                    self.audit(json!({"event": "override_used", "override": {"id": o.id, "rule": o.rule, "app": o.app, "kind": o.kind, "note": o.note}, "action": self.redactor.action(&action), "denial": denial.error}));
+                    self.emit("override_used", json!({"id": o.id, "rule": o.rule, "note": o.note}));
+                    continue;
+                }
+                if denial.error.starts_with("scope_violation") {
+                    self.audit(json!({"event": "scope_violation", "action": self.redactor.action(&action), "scope": ctx.scope}));
+                }
+                self.audit(json!({"event": "action_denied", "action": self.redactor.action(&action), "by": interceptor.name(), "error": denial.error}));
+                stats::rejection(self.session_id().as_deref(), interceptor.name());
+                self.observe(|detector, t| detector.denied(t, Instant::now()));
+                return Err((denial.status, denial.error));
+            }
+        }
+
+        // The kill switch may have been pulled while this action waited on interceptors.
+        if self.is_killed() {
+            self.audit(json!({"event": "action_denied", "action": self.redactor.action(&action), "by": "kill_switch", "error": "killed"}));
+            stats::rejection(None, "killed");
+            return Err((StatusCode::FORBIDDEN, "killed".to_string()));
+        }
+        // Likewise the arming may have been disarmed, revoked, re-armed or run out while the
+        // action queued for its turn; consume() only vouched for it back then.
+        if token.is_some_and(|token| !self.is_armed(token)) {
+            self.audit(json!({"event": "action_denied", "action": self.redactor.action(&action), "by": "arming", "error": "not armed"}));
+            stats::rejection(None, "not armed");
+            return Err((StatusCode::FORBIDDEN, "not armed".to_string()));
+        }
+
+        if deadline.is_some_and(|at| Instant::now() >= at) {
+            return Err(self.expire(&action, queued));
+        }
+        let (queue, started) = (queued.elapsed(), Instant::now());
+        let pointer_before = self.lock().pointer;
+        let result = match &action {
+            Action::Browser { op } => browse(op).await,
+            _ => self.input.execute(&action),
+        };
+        let took = started.elapsed();
+        drop(turn);
+        let observed = result.clone().map_err(|(_, e)| e);
+        for interceptor in self.interceptors.iter() {
+            interceptor.after(&action, &observed, &ctx);
+        }
+        self.audit(json!({"event": "action", "action": self.redactor.action(&action), "ok": observed.is_ok(), "error": observed.as_ref().err()}));
+        result?;
+
+        stats::action(self.session_id().as_deref(), action.kind());
+        self.observe(|detector, t| detector.executed(t, &action, Instant::now()));
+        self.record_recent(action.summary());
+        if let Action::Move { x, y } | Action::Gesture { gesture: Gesture::Tap { x, y }, .. } = &action {
+            self.lock().pointer = Some((*x, *y));
+        }
+        if undoable {
+            let mut inner = self.lock();
+            if inner.undo.len() >= UNDO_DEPTH {
+                inner.undo.pop_front();
+            }
+            inner.undo_seq += 1;
+            let id = inner.undo_seq;
+            inner.undo.push_back(Undo { id, kind: action.kind(), steps: inverse(&action, pointer_before), app: ctx.focused_app.clone() });
+        }
+        self.mark_trail(&action);
+        let step = self.lock().arming.session_mut().map(ActiveSession::next);
+        let recorded = match (step, &self.recorder) {
+            (Some(step), Some(recorder)) => recorder.action(&step, &action).await,
+            _ => None,
+        };
+        Ok(Receipt { action, requested, queue, took, recorded })
+    }
+
+    fn expire(&self, action: &Action, queued: Instant) -> (StatusCode, String) {
+        let waited_ms = queued.elapsed().as_millis() as u64;
+        self.audit(json!({"event": "action_expired", "action": self.redactor.action(action), "waited_ms": waited_ms}));
+        stats::rejection(self.session_id().as_deref(), "expired");
+        (StatusCode::REQUEST_TIMEOUT, "expired".to_string())
+    }
+
+    // Feeds the anomaly heuristics; a hit disarms so the agent has to re-arm.
+    fn observe(&self, check: impl FnOnce(&mut Detector, &Thresholds) -> Option<Anomaly>) {
+        let Some(thresholds) = &self.anomaly else {
+            return;
+        };
+        let mut inner = self.lock();
+        let Some(anomaly) = check(&mut inner.detector, thresholds) else {
+            return;
+        };
+        let Ok(session) = inner.arming.pause("anomaly") else {
+            return;
+        };
+        inner.undo.clear();
+        self.end_session(session, "anomaly");
+        drop(inner);
+        warn!(kind = anomaly.kind, detail = %anomaly.detail, "action anomaly; hands disarmed");
+        self.audit(json!({"event": "anomaly", "kind": anomaly.kind, "detail": anomaly.detail}));
+        self.emit("anomaly", json!({"kind": anomaly.kind, "detail": anomaly.detail}));
+    }
+
+    // Never fails: if a panic poisoned the lock, HandsInner may be half-updated (armed with a
+    // stale token, a scope without its flags), so the guard is recovered with the kill switch
+    // engaged and arming cleared. Actions stay refused until /safety/reset.
+    fn lock(&self) -> MutexGuard<'_, HandsInner> {
+        match self.inner.lock() {
+            Ok(inner) => inner,
+            Err(poisoned) => {
+                let mut inner = poisoned.into_inner();
+                self.inner.clear_poison();
+                let cause = KillCause::server("state_poisoned");
+                if let Ok(session) = inner.arming.kill(cause.clone()) {
+                    inner.undo.clear();
+                    self.end_session(session, "kill");
+                }
+                error!("hands state lock poisoned by a panic; kill switch engaged");
+                self.report_kill(&cause, false);
+                inner
+            }
+        }
+    }
+
+    pub fn confirmations(&self) -> &Confirmations {
+        &self.confirmations
+    }
+
+    // Id and kind of the action the next undo would reverse.
+    pub fn undo_top(&self) -> Option<(u64, &'static str)> {
+        self.lock().undo.back().map(|u| (u.id, u.kind))
+    }
+
+    // An action as it appears in audit entries (typed text hashed; see redact.rs).
+    pub fn redact(&self, action: &Action) -> serde_json::Value {
+        self.redactor.action(action)
+    }
+
+    // The per-action confirmation gate; uses up the confirmation the request carries.
+    pub fn check_confirmation(&self, headers: &HeaderMap, token: &str, intent: Intent) -> Result<(), &'static str> {
+        let checked = self.confirmations.check(headers, token, &intent);
+        if checked.is_err() {
+            stats::rejection(self.session_id().as_deref(), "unconfirmed");
+        }
+        checked
+    }
+
+    pub fn audit(&self, event: serde_json::Value) {
+        if let Some(store) = &self.store {
+            store.audit(self.attribute(event));
+        }
+    }
+
+    // An /events notification, attributed like audit entries.
+    pub fn emit(&self, kind: &str, data: serde_json::Value) {
+        self.events.emit(kind, self.attribute(data));
+    }
+
+    // Adds the arming metadata, if any, as `meta`.
+    fn attribute(&self, mut entry: serde_json::Value) -> serde_json::Value {
+        let meta = self.meta.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        if let (Some(meta), Some(fields)) = (meta, entry.as_object_mut()) {
+            fields.entry("meta").or_insert_with(|| json!(meta));
+        }
+        entry
+    }
+
+    fn set_meta(&self, meta: Option<Meta>) {
+        *self.meta.lock().unwrap_or_else(|e| e.into_inner()) = meta.filter(|m| !m.is_empty());
     }
 
     pub fn is_killed(&self) -> bool {
-        self.inner.lock().unwrap().killed
+        self.lock().arming.is_killed()
     }
 
-    pub fn kill(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.killed = true;
-        inner.armed_until = None;
-        inner.token = None;
-        inner.window_start = None;
-        inner.window_actions = 0;
+    // False when the kill switch was already engaged (the first kill's record is kept; the
+    // repeat is still audited).
+    pub fn kill(&self, cause: KillCause) -> bool {
+        let killed = {
+            let mut inner = self.lock();
+            match inner.arming.kill(cause.clone()) {
+                Ok(session) => {
+                    inner.bucket = Bucket::default();
+                    inner.undo.clear();
+                    self.confirmations.clear();
+                    self.end_session(session, "kill");
+                    true
+                }
+                Err(_) => false,
+            }
+        };
+        self.report_kill(&cause, !killed);
+        killed
     }
 
-    pub fn reset_kill(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.killed = false;
+    fn report_kill(&self, cause: &KillCause, already: bool) {
+        self.audit(json!({"event": "kill", "source": cause.source, "reason": cause.reason, "by": cause.by, "already_killed": already}));
+        self.emit("kill", json!({"source": cause.source, "reason": cause.reason, "by": cause.by, "already_killed": already}));
+    }
+
+    fn end_session(&self, session: Option<ActiveSession>, reason: &str) {
+        if let (Some(session), Some(recorder)) = (session, &self.recorder) {
+            recorder.end(&session, reason);
+        }
+    }
+
+    pub fn reset_kill(&self) -> Result<(), &'static str> {
+        self.lock().arming.reset()?;
+        self.audit(json!({"event": "kill_reset"}));
+        self.emit("kill_reset", json!({}));
+        self.set_meta(None);
+        Ok(())
+    }
+
+    // Arming state for /safety/status, with an elapsed ttl applied first.
+    pub fn arming_status(&self) -> serde_json::Value {
+        let mut inner = self.lock();
+        let expired = inner.expire();
+        self.end_session(expired, "expired");
+        let mut status = inner.arming.status();
+        drop(inner);
+        if let Some(meta) = self.meta.lock().unwrap_or_else(|e| e.into_inner()).as_ref() {
+            status["meta"] = json!(meta);
+        }
+        status
     }
 
     pub fn set_scope(&self, scope: Option<ScopeRect>) {
-        let mut inner = self.inner.lock().unwrap();
+        let mut inner = self.lock();
         inner.scope = scope;
     }
 
+    fn mark_trail(&self, action: &Action) {
+        let mut inner = self.lock();
+        let inner = &mut *inner;
+        match action {
+            Action::Move { x, y } => trail::push(&mut inner.trail, *x, *y, trail::Kind::Point),
+            Action::Click { .. } => {
+                if let Some((x, y)) = inner.pointer {
+                    trail::push(&mut inner.trail, x, y, trail::Kind::Click);
+                }
+            }
+            Action::Gesture { gesture, .. } => match *gesture {
+                Gesture::Tap { x, y } => trail::push(&mut inner.trail, x, y, trail::Kind::Click),
+                Gesture::Swipe { x, y, to_x, to_y } => {
+                    trail::push(&mut inner.trail, x, y, trail::Kind::Point);
+                    trail::push(&mut inner.trail, to_x, to_y, trail::Kind::Point);
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // Pointer path and clicks of the last trail::MAX_AGE, oldest first.
+    pub fn trail(&self) -> Vec<trail::Mark> {
+        self.lock().trail.iter().copied().collect()
+    }
+
+    // Last pointer position this server moved to; None before the first move.
+    pub fn pointer(&self) -> Option<(i32, i32)> {
+        self.lock().pointer
+    }
+
     pub fn get_scope(&self) -> Option<ScopeRect> {
-        self.inner.lock().unwrap().scope
+        self.lock().scope
     }
 
-    pub fn is_armed(&self, token: &str) -> bool {
-        let now = Instant::now();
-        let inner = self.inner.lock().unwrap();
-        match (&inner.armed_until, &inner.token) {
-            (Some(until), Some(t)) if now <= *until && t == token => true,
-            _ => false,
+    pub fn input(&self) -> &Input {
+        &self.input
+    }
+
+    pub fn presets(&self) -> &Presets {
+        &self.presets
+    }
+
+    // Applies a named preset as the current scope; None if there is no such preset.
+    pub fn apply_preset(&self, name: &str) -> Option<Preset> {
+        let preset = self.presets.get(name)?;
+        self.set_scope(Some(preset.scope));
+        self.set_confine_capture(preset.confine_capture.unwrap_or_else(confine_capture_default));
+        Some(preset)
+    }
+
+    pub fn set_confine_capture(&self, confine: bool) {
+        self.lock().confine_capture = confine;
+    }
+
+    pub fn set_strict_scope(&self, strict: bool) {
+        self.lock().strict_scope = strict;
+    }
+
+    pub fn is_strict_scope(&self) -> bool {
+        self.lock().strict_scope
+    }
+
+    // What capture endpoints may show: the scope when it confines capture, else everything.
+    pub fn visible_region(&self) -> Option<ScopeRect> {
+        let inner = self.lock();
+        inner.scope.filter(|_| inner.confine_capture)
+    }
+
+    // A requested capture region narrowed to the visible region. Err when they don't overlap.
+    pub fn confine(&self, region: Option<ScopeRect>) -> Result<Option<ScopeRect>, String> {
+        match (region, self.visible_region()) {
+            (region, None) => Ok(region),
+            (None, visible) => Ok(visible),
+            (Some(r), Some(v)) => r.intersect(&v).map(Some).ok_or_else(|| "region is outside the scope".to_string()),
         }
     }
 
-    pub fn arm(&self, ttl: Duration, token: String) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.armed_until = Some(Instant::now() + ttl);
-        inner.token = Some(token);
+    pub fn is_armed(&self, token: &str) -> bool {
+        let mut inner = self.lock();
+        let expired = inner.expire();
+        self.end_session(expired, "expired");
+        inner.arming.accepts(token)
+    }
+
+    // Returns the id of the recorded session started by this arming, if recording. Refused
+    // while killed.
+    pub fn arm(
+        &self,
+        ttl: Duration,
+        token: String,
+        origin: Origin,
+        profile: Profile,
+        supervised: bool,
+        meta: Meta,
+    ) -> Result<Option<String>, &'static str> {
+        let mut inner = self.lock();
+        let previous = inner.arming.arm(token, origin, profile, supervised, ttl, || self.recorder.as_ref().map(Recorder::start))?;
+        self.set_meta(Some(meta));
+        inner.detector.reset();
+        inner.supervisor_seen = false;
+        inner.undo.clear();
+        self.confirmations.clear();
+        self.end_session(previous, "rearm");
+        Ok(inner.arming.session_mut().map(|s| s.id.clone()))
     }
 
     pub fn disarm(&self) {
-        let mut inner = self.inner.lock().unwrap();
-        inner.armed_until = None;
-        inner.token = None;
-        inner.window_start = None;
-        inner.window_actions = 0;
+        let mut inner = self.lock();
+        let session = inner.arming.disarm();
+        inner.bucket = Bucket::default();
+        inner.undo.clear();
+        self.confirmations.clear();
+        self.end_session(session, "disarm");
+        drop(inner);
+        self.set_meta(None);
     }
 
-    pub fn consume_action(&self, token: &str) -> Result<(), &'static str> {
-        // Enforce kill switch + arming + basic rate limiting to prevent runaway loops.
-        if self.is_killed() {
-            return Err("killed");
+    // A supervised arming needs an unpaused stream supervising its session from another client
+    // (see stats::supervisors). Until the first one attaches, within SUPERVISION_GRACE of
+    // arming, actions are refused; once none is left (the supervisor closed or paused /stream,
+    // or lost the connection), or none came in time, it disarms: nobody is watching, so
+    // autonomous input stops. Checked before every action and every SUPERVISION_POLL in between.
+    fn check_supervision(&self) -> Result<(), &'static str> {
+        let mut inner = self.lock();
+        let Some((session, origin, since)) = inner.arming.supervision() else {
+            return Ok(());
+        };
+        if session.is_some_and(|id| stats::supervisors(id, origin) > 0) {
+            inner.supervisor_seen = true;
+            return Ok(());
         }
-        if !self.is_armed(token) {
+        if !inner.supervisor_seen && since.elapsed() < SUPERVISION_GRACE {
+            return Err("waiting for a supervision stream (GET /stream?supervise=<session>)");
+        }
+        let Ok(session) = inner.arming.pause("unsupervised") else {
             return Err("not armed");
+        };
+        inner.undo.clear();
+        let id = session.as_ref().map(|s| s.id.clone());
+        self.end_session(session, "unsupervised");
+        drop(inner);
+        warn!("no supervision stream; hands disarmed");
+        self.audit(json!({"event": "unsupervised", "session": id}));
+        self.emit("unsupervised", json!({"session": id}));
+        Err("not armed")
+    }
+
+    // Whether a stream from `origin` may supervise the arming recorded as `session`: it has to
+    // be the live supervised arming, watched from another client than the one it is bound to.
+    pub fn supervisable(&self, session: &str, origin: &Origin) -> Result<(), &'static str> {
+        let inner = self.lock();
+        match inner.arming.supervision() {
+            Some((Some(id), bound, _)) if id == session => {
+                if bound == origin {
+                    return Err("a supervision stream has to come from another client than the arming (x-seealln-client-id)");
+                }
+                Ok(())
+            }
+            _ => Err("no supervised arming with that session"),
         }
+    }
 
-        let max_actions = std::env::var("SEEALLN_HANDS_MAX_ACTIONS")
-            .ok()
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or(20);
-        let window_ms = std::env::var("SEEALLN_HANDS_WINDOW_MS")
-            .ok()
-            .and_then(|s| s.parse::<u64>().ok())
-            .unwrap_or(10_000);
+    pub fn records_sessions(&self) -> bool {
+        self.recorder.is_some()
+    }
 
-        let now = Instant::now();
-        let mut inner = self.inner.lock().unwrap();
+    pub fn spawn_supervision_watch(&self) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(SUPERVISION_POLL);
+            loop {
+                tick.tick().await;
+                let _ = state.check_supervision();
+            }
+        });
+    }
+
+    // Renews the live arming (see Arming::extend) after the same checks as an action, minus
+    // the rate-limit charge.
+    pub fn extend(&self, token: &str, origin: &Origin, ttl: Duration) -> Result<(Duration, bool), (StatusCode, &'static str)> {
+        self.consume(token, origin, 0.0).map_err(|msg| (StatusCode::FORBIDDEN, msg))?;
+        self.lock().arming.extend(token, ttl).map_err(|msg| (StatusCode::CONFLICT, msg))
+    }
 
-        let reset = match inner.window_start {
-            None => true,
-            Some(t0) => now.duration_since(t0) > Duration::from_millis(window_ms),
+    // What a token holder may still do, for GET /hands/token/introspect. Nothing is charged
+    // and nothing is audited; a token presented from another client is reported as invalid.
+    pub fn introspect(&self, token: &str, origin: &Origin) -> serde_json::Value {
+        let mut inner = self.lock();
+        let expired = inner.expire();
+        self.end_session(expired, "expired");
+        if inner.arming.is_killed() {
+            return json!({"valid": false, "reason": "killed"});
+        }
+        let Some(expires_in) = inner.arming.expires_in(token) else {
+            return json!({"valid": false, "reason": "unknown or expired token"});
         };
-        if reset {
-            inner.window_start = Some(now);
-            inner.window_actions = 0;
+        if inner.arming.origin().is_some_and(|bound| bound != origin) {
+            return json!({"valid": false, "reason": "token bound to a different client"});
         }
+        let limit = inner.limit;
+        json!({
+            "valid": true,
+            "expires_in_ms": expires_in.as_millis() as u64,
+            "session": inner.arming.session_id(),
+            "profile": inner.arming.profile(),
+            "budget": {"tokens": inner.bucket.tokens(limit, Instant::now()), "limit": limit, "weights": inner.weights},
+        })
+    }
 
-        if inner.window_actions >= max_actions {
-            return Err("rate limited");
+    // Ends one arming, named by its token or recorded session, without the kill switch.
+    // Returns the revoked session id, if it was recording.
+    pub fn revoke(&self, token: Option<&str>, session: Option<&str>, reason: Option<&str>) -> Result<Option<String>, &'static str> {
+        let mut inner = self.lock();
+        let expired = inner.expire();
+        self.end_session(expired, "expired");
+        let revoked = inner.arming.revoke(token, session)?;
+        let id = revoked.as_ref().map(|s| s.id.clone());
+        inner.bucket = Bucket::default();
+        inner.undo.clear();
+        self.confirmations.clear();
+        self.end_session(revoked, "revoked");
+        drop(inner);
+        // Audited before the metadata is dropped, so the entry names the revoked arming.
+        self.audit(json!({"event": "token_revoked", "session": id, "reason": reason}));
+        self.emit("token_revoked", json!({"session": id, "reason": reason}));
+        self.set_meta(None);
+        Ok(id)
+    }
+
+    // Profile of the current arming (standard when not armed).
+    pub fn profile(&self) -> Profile {
+        self.lock().arming.profile()
+    }
+
+    pub fn consume_action(&self, token: &str, origin: &Origin, action: &Action) -> Result<(), &'static str> {
+        let cost = self.weights().cost(action);
+        self.consume(token, origin, cost)
+    }
+
+    // Kill switch, arming, token origin and the rate limit for an action costing `cost` tokens
+    // (see ratelimit.rs), scaled by the arming profile.
+    pub fn consume(&self, token: &str, origin: &Origin, cost: f64) -> Result<(), &'static str> {
+        // Enforce kill switch + arming + rate limiting to prevent runaway loops.
+        if self.is_killed() {
+            stats::rejection(None, "killed");
+            return Err("killed");
+        }
+        let supervision = self.check_supervision();
+        if !self.is_armed(token) {
+            stats::rejection(None, "not armed");
+            return Err("not armed");
+        }
+        if let Err(msg) = supervision {
+            stats::rejection(None, "unsupervised");
+            return Err(msg);
         }
 
-        inner.window_actions += 1;
+        let mut inner = self.lock();
+        if let Some(bound) = inner.arming.origin().filter(|bound| *bound != origin).cloned() {
+            let session = inner.arming.session_mut().map(|s| s.id.clone());
+            drop(inner);
+            warn!(?bound, presented = ?origin, "arming token used from another origin");
+            self.audit(json!({"event": "token_origin_mismatch", "bound_to": bound, "presented": origin, "session": session}));
+            stats::rejection(session.as_deref(), "origin mismatch");
+            return Err("token bound to a different client");
+        }
+        let limit = inner.limit;
+        let cost = cost * inner.arming.profile().cost_factor();
+        if !inner.bucket.take(limit, cost, Instant::now()) {
+            let session = inner.arming.session_mut().map(|s| s.id.clone());
+            drop(inner);
+            stats::rejection(session.as_deref(), "rate limited");
+            return Err("rate limited");
+        }
         Ok(())
     }
+
+    // Id of the recorded session of the current arming, if any.
+    fn session_id(&self) -> Option<String> {
+        self.lock().arming.session_mut().map(|s| s.id.clone())
+    }
+
+    pub fn limit(&self) -> Limit {
+        self.lock().limit
+    }
+
+    pub fn weights(&self) -> Weights {
+        self.lock().weights
+    }
+
+    pub fn set_limits(&self, limit: Limit, weights: Weights) {
+        let mut inner = self.lock();
+        inner.limit = limit;
+        inner.weights = weights;
+    }
 }
 
-fn require_local_only(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
+pub(crate) fn require_local_only(headers: &HeaderMap) -> Result<(), (StatusCode, &'static str)> {
     // Bind is localhost by default, but we still add a belt-and-suspenders header check.
     // If user exposes it accidentally, this prevents blind remote control unless they disable it.
     // User can set SEEALLN_ALLOW_REMOTE=1 to bypass (not recommended).
@@ -162,32 +916,110 @@ fn require_local_only(headers: &HeaderMap) -> Result<(), (StatusCode, &'static s
     Ok(())
 }
 
+pub(crate) fn confirmed(headers: &HeaderMap) -> bool {
+    headers
+        .get("x-seealln-confirm")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.eq_ignore_ascii_case("yes"))
+        .unwrap_or(false)
+}
+
+// 128 bits from the OS RNG, hex encoded; the token is the only secret between arm and act.
 fn gen_token() -> String {
-    // Simple random token; good enough for local, short-lived arming.
-    // NOTE: We avoid adding extra deps for now.
-    let t = Instant::now();
-    format!("t{}", t.elapsed().as_nanos())
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("os rng");
+    hex::encode(bytes)
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ArmParams {
     ttl_ms: Option<u64>,
+    // Named scope preset to apply before arming.
+    scope: Option<String>,
+    // Guardrail profile: safe, standard (default) or unrestricted (see arming.rs).
+    profile: Option<String>,
+    // Tie the arming to a supervision stream (`/stream?supervise=<session id>`) from another
+    // client: disarmed when none attaches within SUPERVISION_GRACE or the last one goes.
+    supervised: Option<bool>,
+    // Attribution echoed in audit entries and events (see arming::Meta).
+    task_id: Option<String>,
+    agent: Option<String>,
+    run_url: Option<String>,
 }
 
 pub async fn hands_arm(
     State(state): State<HandsState>,
     headers: HeaderMap,
+    origin: Origin,
     Query(params): Query<ArmParams>,
 ) -> impl IntoResponse {
     if let Err((code, msg)) = require_local_only(&headers) {
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
 
+    // Checked before the preset is applied; `arm` refuses a killed state again under the lock.
+    if state.is_killed() {
+        return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": "killed; POST /safety/reset first"}))).into_response();
+    }
+
+    let profile = match params.profile.as_deref().map(|name| (name, Profile::parse(name))) {
+        None => Profile::Standard,
+        Some((_, Some(profile))) => profile,
+        Some((name, None)) => {
+            let error = format!("unknown profile {name} (safe, standard, unrestricted)");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+    };
+    if let Err(msg) = profile.allowed() {
+        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let meta = match Meta::new(params.task_id, params.agent, params.run_url) {
+        Ok(meta) => meta,
+        Err(error) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response(),
+    };
+    let supervised = params.supervised.unwrap_or(false);
+    if supervised && !state.records_sessions() {
+        let error = "supervised arming needs session recording: the supervision stream names the session";
+        return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": error}))).into_response();
+    }
+
+    if let Some(name) = &params.scope {
+        if state.apply_preset(name).is_none() {
+            return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": format!("unknown scope preset {name}")}))).into_response();
+        }
+    }
+
     let ttl = Duration::from_millis(params.ttl_ms.unwrap_or(30_000).clamp(5_000, 300_000));
     let token = gen_token();
-    state.arm(ttl, token.clone());
-
-    (StatusCode::OK, Json(json!({"ok": true, "armed": true, "ttl_ms": ttl.as_millis(), "token": token}))).into_response()
+    let session = match state.arm(ttl, token.clone(), origin.clone(), profile, supervised, meta) {
+        Ok(session) => session,
+        Err(msg) => return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": msg}))).into_response(),
+    };
+    state.audit(json!({
+        "event": "arm",
+        "ttl_ms": ttl.as_millis() as u64,
+        "session": session,
+        "scope_preset": params.scope,
+        "profile": profile,
+        "supervised": supervised,
+        "origin": origin,
+    }));
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "ok": true,
+            "armed": true,
+            "ttl_ms": ttl.as_millis(),
+            "token": token,
+            "session": session,
+            "scope": state.get_scope(),
+            "profile": profile,
+            "supervised": supervised,
+            "supervise_within_ms": supervised.then_some(SUPERVISION_GRACE.as_millis() as u64),
+        })),
+    )
+        .into_response()
 }
 
 pub async fn hands_disarm(State(state): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
@@ -195,16 +1027,137 @@ pub async fn hands_disarm(State(state): State<HandsState>, headers: HeaderMap) -
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
     state.disarm();
+    state.audit(json!({"event": "disarm"}));
     (StatusCode::OK, Json(json!({"ok": true, "armed": false}))).into_response()
 }
 
-// Safety endpoints
-pub async fn safety_kill(State(state): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
+#[derive(Debug, Deserialize)]
+pub struct ExtendReq {
+    token: String,
+    // New time left, as for /hands/arm (default 30000, 5000-300000).
+    ttl_ms: Option<u64>,
+}
+
+// Sliding ttl for tasks that outlast one arming, up to arming::max_total() in all.
+pub async fn hands_extend(State(state): State<HandsState>, headers: HeaderMap, origin: Origin, Json(req): Json<ExtendReq>) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let ttl = Duration::from_millis(req.ttl_ms.unwrap_or(30_000).clamp(5_000, 300_000));
+    let (left, capped) = match state.extend(&req.token, &origin, ttl) {
+        Ok(extended) => extended,
+        Err((code, msg)) => return (code, Json(json!({"ok": false, "error": msg}))).into_response(),
+    };
+    let expires_in_ms = left.as_millis() as u64;
+    state.audit(json!({"event": "arm_extended", "ttl_ms": ttl.as_millis() as u64, "expires_in_ms": expires_in_ms, "capped": capped, "session": state.session_id()}));
+    (StatusCode::OK, Json(json!({"ok": true, "expires_in_ms": expires_in_ms, "capped": capped}))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IntrospectParams {
+    token: String,
+}
+
+// Lets a client check its token before acting instead of learning of expiry from a rejection.
+pub async fn hands_token_introspect(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Query(params): Query<IntrospectParams>,
+) -> impl IntoResponse {
     if let Err((code, msg)) = require_local_only(&headers) {
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
-    state.kill();
-    (StatusCode::OK, Json(json!({"ok": true, "killed": true}))).into_response()
+    let mut body = state.introspect(&params.token, &origin);
+    body["ok"] = json!(true);
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevokeReq {
+    // The arming token, or the id of its recorded session (for a supervisor without the token).
+    token: Option<String>,
+    session: Option<String>,
+    reason: Option<String>,
+}
+
+pub async fn hands_token_revoke(State(state): State<HandsState>, headers: HeaderMap, Json(req): Json<RevokeReq>) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if req.token.is_none() && req.session.is_none() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "give token or session"}))).into_response();
+    }
+    let reason = req.reason.map(|s| s.trim().chars().take(MAX_KILL_NOTE).collect::<String>()).filter(|s| !s.is_empty());
+    match state.revoke(req.token.as_deref(), req.session.as_deref(), reason.as_deref()) {
+        Ok(session) => (StatusCode::OK, Json(json!({"ok": true, "revoked": true, "session": session}))).into_response(),
+        Err(msg) => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": msg}))).into_response(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct KillParams {
+    // Why, e.g. "agent looping on the save dialog".
+    reason: Option<String>,
+    // Who: operator name, agent id, helper process.
+    by: Option<String>,
+    // What triggered it: endpoint (default), hotkey, watchdog, physical_input.
+    source: Option<String>,
+}
+
+const MAX_KILL_NOTE: usize = 200;
+
+// Safety endpoints. Context (`reason`, `by`, optional `source`) comes from the query string or
+// a JSON body. A kill without `reason` or `by` is still applied, recorded as "unspecified",
+// and answered with 400 so callers fix the request; the switch itself is never refused.
+pub async fn safety_kill(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    // Both optional so a malformed query string or body never stops the kill.
+    query: Option<Query<KillParams>>,
+    body: Option<Json<KillParams>>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let Query(query) = query.unwrap_or_default();
+    let Json(body) = body.unwrap_or_default();
+    let note = |s: Option<String>| {
+        s.map(|s| s.trim().chars().take(MAX_KILL_NOTE).collect::<String>()).filter(|s| !s.is_empty())
+    };
+    let reason = note(body.reason.or(query.reason));
+    let by = note(body.by.or(query.by));
+    let source = note(body.source.or(query.source));
+
+    let mut problems = Vec::new();
+    if reason.is_none() {
+        problems.push("reason is required".to_string());
+    }
+    if by.is_none() {
+        problems.push("by is required".to_string());
+    }
+    let source = match source {
+        Some(s) if KILL_SOURCES.contains(&s.as_str()) => s,
+        Some(s) => {
+            problems.push(format!("unknown source {s} ({})", KILL_SOURCES.join(", ")));
+            "endpoint".to_string()
+        }
+        None => "endpoint".to_string(),
+    };
+    let cause = KillCause {
+        source,
+        reason: reason.unwrap_or_else(|| "unspecified".to_string()),
+        by: by.unwrap_or_else(|| "unspecified".to_string()),
+    };
+
+    // A second kill is still a success; the first one's record is kept.
+    let already = !state.kill(cause);
+    let arming = state.arming_status();
+    if !problems.is_empty() {
+        let error = format!("killed, but {}", problems.join("; "));
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "killed": true, "already_killed": already, "error": error, "arming": arming}))).into_response();
+    }
+    (StatusCode::OK, Json(json!({"ok": true, "killed": true, "already_killed": already, "arming": arming}))).into_response()
 }
 
 pub async fn safety_reset(State(state): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
@@ -226,21 +1179,190 @@ pub async fn safety_reset(State(state): State<HandsState>, headers: HeaderMap) -
             .into_response();
     }
 
-    state.reset_kill();
+    if let Err(msg) = state.reset_kill() {
+        return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
     (StatusCode::OK, Json(json!({"ok": true, "killed": false}))).into_response()
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct UndoReq {
+    // The arming token, for the agent backing out its own action; omitted by an operator.
+    token: Option<String>,
+}
+
+// Reverses the last executed action of the current arming, for an agent or a supervisor
+// backing out a mistake: moves go back to where the pointer was, typed or pasted text is erased
+// with backspaces, scrolls scroll back. Steps back one action per call, and stops at an
+// irreversible one (click, key, gesture) with 409 rather than reaching past it, or when another
+// application has the focus than when the action ran (the backspaces would land there).
+//
+// The caller is either the holder of the live token, charged for the reverse actions and with a
+// confirmation for the route /hands/undo (see confirm.rs), or an operator with the api key and
+// a confirmation from POST /admin/confirmations/undo for the entry on top. Refused while killed;
+// the reverse actions go through the interceptors like any other.
+pub async fn hands_undo(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    body: Option<Json<UndoReq>>,
+) -> impl IntoResponse {
+    let fail = |code: StatusCode, err: &str| (code, Json(json!({"ok": false, "error": err}))).into_response();
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    let Json(req) = body.unwrap_or_default();
+    let weights = state.weights();
+    let (top, cost) = match state.lock().undo.back() {
+        Some(last) => (Some(last.id), last.steps.as_ref().map_or(0.0, |steps| steps.iter().map(|s| weights.cost(s)).sum())),
+        None => (None, 0.0),
+    };
+    if let Err((code, msg)) = authorize_undo(&state, &headers, &origin, req.token.as_deref(), top, cost) {
+        return fail(code, msg);
+    }
+
+    let focused = focus::focused_app();
+    let (kind, steps) = {
+        let mut inner = state.lock();
+        let Some(last) = inner.undo.pop_back() else {
+            return fail(StatusCode::CONFLICT, "nothing to undo");
+        };
+        let kind = last.kind;
+        // An action that cannot be undone (here) stays on top: nothing before it can be either.
+        let refused = match &last.steps {
+            // What was authorized (and confirmed) is no longer the last action.
+            _ if Some(last.id) != top => Some(format!("the last action changed since the undo was authorized (now {kind})")),
+            None => Some(format!("the last action ({kind}) cannot be undone")),
+            Some(_) if last.app != focused => Some(format!(
+                "the focus moved since the last action ({kind}): it ran in {}, {} is focused now",
+                last.app.as_deref().unwrap_or("an unknown application"),
+                focused.as_deref().unwrap_or("an unknown application"),
+            )),
+            Some(_) => None,
+        };
+        if let Some(error) = refused {
+            inner.undo.push_back(last);
+            return fail(StatusCode::CONFLICT, &error);
+        }
+        (kind, last.steps.unwrap_or_default())
+    };
+    let by = if req.token.is_some() { "agent" } else { "operator" };
+    for (done, step) in steps.iter().enumerate() {
+        if let Err((code, err)) = state.perform_inner(req.token.as_deref(), step.clone(), &[], None, false).await {
+            state.audit(json!({"event": "undo", "undid": kind, "by": by, "ok": false, "steps": done, "error": err}));
+            return (code, Json(json!({"ok": false, "error": err, "undid": kind, "steps": done}))).into_response();
+        }
+    }
+    state.audit(json!({"event": "undo", "undid": kind, "by": by, "ok": true, "steps": steps.len()}));
+    let remaining = state.lock().undo.len();
+    (StatusCode::OK, Json(json!({"ok": true, "undid": kind, "steps": steps.len(), "remaining": remaining}))).into_response()
+}
+
+// The token holder goes through the same gate as an action; an operator needs the api key and
+// a confirmation for undo entry `top`, the one on top when the request came in.
+fn authorize_undo(
+    state: &HandsState,
+    headers: &HeaderMap,
+    origin: &Origin,
+    token: Option<&str>,
+    top: Option<u64>,
+    cost: f64,
+) -> Result<(), (StatusCode, &'static str)> {
+    let Some(token) = token else {
+        require_api_key(headers)?;
+        if state.is_killed() {
+            return Err((StatusCode::CONFLICT, "killed; POST /safety/reset first"));
+        }
+        let Some(top) = top else {
+            return Err((StatusCode::CONFLICT, "nothing to undo"));
+        };
+        return state
+            .check_confirmation(headers, confirm::OPERATOR, Intent::Undo(top))
+            .map_err(|msg| (StatusCode::PRECONDITION_REQUIRED, msg));
+    };
+    state.consume(token, origin, cost).map_err(|msg| (StatusCode::FORBIDDEN, msg))?;
+    state.check_confirmation(headers, token, Intent::Route("/hands/undo")).map_err(|msg| (StatusCode::PRECONDITION_REQUIRED, msg))
+}
+
 pub async fn safety_status(State(state): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
     if let Err((code, msg)) = require_local_only(&headers) {
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
-    (StatusCode::OK, Json(json!({"ok": true, "killed": state.is_killed(), "scope": state.get_scope()}))).into_response()
+    (
+        StatusCode::OK,
+        Json(json!({"ok": true, "killed": state.is_killed(), "arming": state.arming_status(), "scope": state.get_scope(), "strict_scope": state.is_strict_scope(), "capture_region": state.visible_region(), "rate_limit": {"limit": state.limit(), "weights": state.weights()}, "anomaly_thresholds": state.anomaly})),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LimitsReq {
+    max_actions: Option<u32>,
+    window_ms: Option<u64>,
+    // Per-kind costs to change, e.g. {"move": 0.1, "char": 0.02}.
+    #[serde(default)]
+    weights: BTreeMap<String, f64>,
+}
+
+// Adjusts the hands rate limit without a restart. Changes last until the process exits.
+pub async fn admin_limits(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    Json(req): Json<LimitsReq>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err((code, msg)) = require_api_key(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !confirmed(&headers) {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({"ok": false, "error": "missing x-seealln-confirm: yes"})),
+        )
+            .into_response();
+    }
+
+    let mut limit = state.limit();
+    if let Some(n) = req.max_actions {
+        let (min, max) = ratelimit::MAX_ACTIONS_RANGE;
+        if !(min..=max).contains(&n) {
+            let error = format!("max_actions must be between {min} and {max}");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+        limit.max_actions = n;
+    }
+    if let Some(ms) = req.window_ms {
+        let (min, max) = ratelimit::WINDOW_MS_RANGE;
+        if !(min..=max).contains(&ms) {
+            let error = format!("window_ms must be between {min} and {max}");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+        limit.window_ms = ms;
+    }
+    let mut weights = state.weights();
+    for (name, weight) in &req.weights {
+        if let Err(error) = weights.set(name, *weight) {
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+    }
+
+    state.set_limits(limit, weights);
+    state.audit(json!({"event": "limits_changed", "limit": limit, "weights": weights}));
+    (StatusCode::OK, Json(json!({"ok": true, "limit": limit, "weights": weights}))).into_response()
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ScopeReq {
     // null to clear
     scope: Option<ScopeRect>,
+    // Named preset instead of `scope` (see scopes.rs).
+    name: Option<String>,
+    // Also crop snapshots/streams/frame analysis to the scope (default SEEALLN_SCOPE_CONFINE_CAPTURE).
+    confine_capture: Option<bool>,
+    // Refuse out-of-scope moves with `scope_violation` instead of clamping (default SEEALLN_SCOPE_STRICT).
+    strict: Option<bool>,
 }
 
 pub async fn scope_set(
@@ -252,7 +1374,22 @@ pub async fn scope_set(
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
 
-    if let Some(s) = req.scope {
+    let (scope, preset_confine) = match (&req.name, req.scope) {
+        (Some(_), Some(_)) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "give `scope` or `name`, not both"})))
+                .into_response();
+        }
+        (Some(name), None) => match state.presets().get(name) {
+            Some(preset) => (Some(preset.scope), preset.confine_capture),
+            None => {
+                return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": format!("unknown scope preset {name}")})))
+                    .into_response();
+            }
+        },
+        (None, scope) => (scope, None),
+    };
+
+    if let Some(s) = scope {
         if s.w <= 0 || s.h <= 0 {
             return (
                 StatusCode::BAD_REQUEST,
@@ -262,8 +1399,17 @@ pub async fn scope_set(
         }
     }
 
-    state.set_scope(req.scope);
-    (StatusCode::OK, Json(json!({"ok": true, "scope": state.get_scope()}))).into_response()
+    let confine = req.confine_capture.or(preset_confine).unwrap_or_else(confine_capture_default);
+    state.set_scope(scope);
+    let strict = req.strict.unwrap_or_else(strict_scope_default);
+    state.set_confine_capture(confine);
+    state.set_strict_scope(strict);
+    state.audit(json!({"event": "scope_set", "scope": scope, "name": req.name, "confine_capture": confine, "strict": strict}));
+    (
+        StatusCode::OK,
+        Json(json!({"ok": true, "scope": state.get_scope(), "strict_scope": strict, "capture_region": state.visible_region()})),
+    )
+        .into_response()
 }
 
 #[derive(Debug, Deserialize)]
@@ -272,194 +1418,322 @@ pub struct MoveReq {
     x: i32,
     y: i32,
     token: String,
+    // Give up (408 `expired`) unless the action starts within this many ms.
+    deadline_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct ClickReq {
     pub button: Option<String>,
     pub token: String,
+    // Check afterwards that keyboard focus landed where the click was (see `verify_focus`).
+    #[serde(default)]
+    pub verify_focus: bool,
+    pub deadline_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct TypeReq {
     text: String,
     token: String,
+    // keys | paste | auto (see `type_action`).
+    mode: Option<String>,
+    deadline_ms: Option<u64>,
 }
 
-fn reject_sensitive_text(text: &str) -> bool {
-    // Guardrail: if it looks like login/MFA/captcha, bail.
-    let t = text.to_lowercase();
-    ["password", "contrase", "otp", "2fa", "mfa", "captcha", "verification code", "c	digo"]
-        .iter()
-        .any(|k| t.contains(k))
+#[derive(Debug, Deserialize)]
+pub struct KeyReq {
+    combo: String,
+    token: String,
+    deadline_ms: Option<u64>,
 }
 
-#[cfg(feature = "hands")]
-fn enigo_click(button: Option<&str>) -> Result<(), String> {
-    use enigo::{Button, Coordinate, Direction, Enigo, Mouse, Settings};
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
-    let b = match button.unwrap_or("left") {
-        "left" => Button::Left,
-        "right" => Button::Right,
-        "middle" => Button::Middle,
-        _ => return Err("invalid button".to_string()),
-    };
-    enigo.button(b, Direction::Click).map_err(|e| e.to_string())
+#[derive(Debug, Deserialize)]
+pub struct ScrollReq {
+    #[serde(default)]
+    dx: i32,
+    #[serde(default)]
+    dy: i32,
+    token: String,
+    deadline_ms: Option<u64>,
 }
 
-#[cfg(feature = "hands")]
-fn enigo_move(x: i32, y: i32) -> Result<(), String> {
-    use enigo::{Coordinate, Enigo, Mouse, Settings};
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
-    enigo
-        .move_mouse(x, y, Coordinate::Abs)
-        .map_err(|e| e.to_string())
+// Wheel notches per scroll action, either axis.
+pub const MAX_SCROLL: i32 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct GestureReq {
+    // {"type": "tap" | "swipe" | "pinch" | "two_finger_scroll", ...} (see `Gesture`).
+    #[serde(flatten)]
+    gesture: Gesture,
+    // Time from touch-down to lift-off.
+    duration_ms: Option<u32>,
+    token: String,
+    deadline_ms: Option<u64>,
 }
 
-#[cfg(feature = "hands")]
-fn enigo_type(text: &str) -> Result<(), String> {
-    use enigo::{Enigo, Keyboard, Settings};
-    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| e.to_string())?;
-    enigo.text(text).map_err(|e| e.to_string())
+pub const MAX_GESTURE_MS: u32 = 5000;
+
+// What the input backend supports on this machine, so clients can skip calls that would
+// only fail at runtime. Everything is false when the backend cannot be opened.
+fn capabilities(input: &Input) -> serde_json::Value {
+    let ready = input.ready();
+    let ok = ready.is_ok();
+    let caps = input.capabilities();
+    let session = input::session_type();
+    let mut notes = Vec::new();
+    if ok && caps.x11_only && session == Some("wayland") {
+        notes.push("wayland session: input reaches XWayland windows only");
+    }
+    let monitors = desktop::monitors();
+    json!({
+        "ok": true,
+        "available": ok,
+        "backend": input.name(),
+        "error": ready.err(),
+        "platform": std::env::consts::OS,
+        "session": session,
+        "unicode_typing": ok && caps.unicode_typing,
+        // `mode` of /hands/type; paste uses the clipboard and this shortcut.
+        "typing_modes": if ok { vec!["keys", "paste", "auto"] } else { Vec::new() },
+        "paste_combo": input::paste_combo(),
+        "buttons": if ok { vec!["left", "right", "middle"] } else { Vec::new() },
+        "scroll": {
+            "vertical": ok,
+            "horizontal": ok && caps.horizontal_scroll,
+            // Whole wheel notches only; no pixel-precise scrolling.
+            "unit": "notch",
+            "max_per_action": MAX_SCROLL,
+        },
+        "keys": {
+            "names": keys::NAMES,
+            "function_keys": "f1-f20",
+            // "any", or "layout" when limited to the keys of `keyboard_layout`.
+            "characters": match (ok, caps.any_key_char) {
+                (false, _) => None,
+                (true, true) => Some("any"),
+                (true, false) => Some("layout"),
+            },
+            "max_combo": keys::MAX_KEYS,
+        },
+        // Used by key-code backends (ydotool); SEEALLN_KEYBOARD_LAYOUT forces one.
+        "keyboard_layout": layout::active(),
+        "pointer": {
+            // Moves are absolute, in virtual-desktop coordinates (see desktop.rs).
+            "absolute": ok,
+            "relative": false,
+            "multi_display": ok && monitors.len() > 1,
+            "monitors": monitors,
+        },
+        // /hands/gesture; Windows touch injection only (feature `touch`).
+        "gestures": ok && input::gestures_supported(),
+        "notes": notes,
+    })
 }
 
-pub async fn hands_move(
-    State(state): State<HandsState>,
-    headers: HeaderMap,
-    Json(req): Json<MoveReq>,
-) -> impl IntoResponse {
+pub async fn hands_capabilities(State(state): State<HandsState>, headers: HeaderMap) -> impl IntoResponse {
     if let Err((code, msg)) = require_local_only(&headers) {
         return (code, Json(json!({"ok": false, "error": msg}))).into_response();
     }
+    let input = state.input.clone();
+    match tokio::task::spawn_blocking(move || capabilities(&input)).await {
+        Ok(caps) => (StatusCode::OK, Json(caps)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"ok": false, "error": e.to_string()}))).into_response(),
+    }
+}
 
-    if let Err(msg) = state.consume_action(&req.token) {
-        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg}))).into_response();
+// Shared pipeline for every hands action: local-only check, kill/arming/rate limit,
+// confirmation (everything but moves), then `HandsState::perform`. `deadline_ms` (from the
+// request body) is counted from arrival; see `perform_by`. Answers with the action's receipt.
+async fn run_action(
+    state: &HandsState,
+    headers: &HeaderMap,
+    origin: &Origin,
+    token: &str,
+    require_confirm: bool,
+    deadline_ms: Option<u64>,
+    action: Action,
+) -> Response {
+    match act(state, headers, origin, token, require_confirm, deadline_ms, action).await {
+        Ok(body) => (StatusCode::OK, Json(body)).into_response(),
+        Err(resp) => resp,
     }
+}
 
-    // Guardrail: clamp to a sane range to avoid overflow; actual screen bounds are OS-specific.
-    let mut x = req.x.clamp(-10_000, 10_000);
-    let mut y = req.y.clamp(-10_000, 10_000);
+// `run_action`, with the success body left open for additions.
+async fn act(
+    state: &HandsState,
+    headers: &HeaderMap,
+    origin: &Origin,
+    token: &str,
+    require_confirm: bool,
+    deadline_ms: Option<u64>,
+    action: Action,
+) -> Result<serde_json::Value, Response> {
+    let deadline = deadline_ms.map(|ms| Instant::now() + Duration::from_millis(ms));
+    let fail = |code: StatusCode, err: &str| (code, Json(json!({"ok": false, "error": err}))).into_response();
+    if let Err((code, msg)) = require_local_only(headers) {
+        return Err(fail(code, msg));
+    }
+    let overrides = overrides::from_headers(headers).map_err(|err| fail(StatusCode::FORBIDDEN, &err))?;
 
-    // Apply scope (if set), otherwise clamp to main display.
-    #[cfg(feature = "hands")]
-    {
-        use enigo::{Enigo, Mouse, Settings};
-        let en = Enigo::new(&Settings::default()).map_err(|e| e.to_string());
-        if let Ok(mut enigo) = en {
-            if let Ok((w, h)) = enigo.main_display() {
-                // screen clamp
-                x = x.clamp(0, w.saturating_sub(1));
-                y = y.clamp(0, h.saturating_sub(1));
-            }
-        }
+    if let Err(msg) = state.consume_action(token, origin, &action) {
+        return Err(fail(StatusCode::FORBIDDEN, msg));
+    }
 
-        if let Some(scope) = state.get_scope() {
-            let (cx, cy) = scope.clamp_point(x, y);
-            x = cx;
-            y = cy;
+    // Extra guardrail: a confirmation for this exact action (see confirm.rs); the safe profile
+    // asks for one on every action.
+    if require_confirm || state.profile().confirm_all() {
+        if let Err(msg) = state.check_confirmation(headers, token, Intent::Action(&action)) {
+            return Err(fail(StatusCode::PRECONDITION_REQUIRED, msg));
         }
     }
 
-    #[cfg(feature = "hands")]
-    match enigo_move(x, y) {
-        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
-        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"ok": false, "error": err}))).into_response(),
-    }
+    let receipt = state.perform_by(token, action, &overrides, deadline).await.map_err(|(code, err)| match input::failure(&err) {
+        // A backend problem the operator can fix: say what it is and how.
+        Some(failure) => (code, Json(json!({"ok": false, "error": err, "code": failure.code(), "hint": failure.hint()}))).into_response(),
+        None => fail(code, &err),
+    })?;
+    let phash = frame_phash(state).await;
+    Ok(json!({"ok": true, "receipt": receipt.json(state.pointer(), phash)}))
+}
 
-    #[cfg(not(feature = "hands"))]
-    (StatusCode::NOT_IMPLEMENTED, Json(json!({"ok": false, "error": "hands feature disabled"}))).into_response()
+// Perceptual hash (as /frame/phash) of the visible screen right after an action, so an agent
+// can tell whether it looks like what it expected.
+async fn frame_phash(state: &HandsState) -> Option<u64> {
+    let visible = state.visible_region();
+    tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(None, Fallback::Fail).ok()?;
+        let (img, _, _) = vision::crop(&frame.image, visible);
+        (img.width() > 0 && img.height() > 0).then(|| vision::phash(&img))
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+pub async fn hands_move(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<MoveReq>,
+) -> impl IntoResponse {
+    run_action(&state, &headers, &origin, &req.token, false, req.deadline_ms, Action::Move { x: req.x, y: req.y }).await
 }
 
 pub async fn hands_click(
     State(state): State<HandsState>,
     headers: HeaderMap,
+    origin: Origin,
     Json(req): Json<ClickReq>,
 ) -> impl IntoResponse {
-    if let Err((code, msg)) = require_local_only(&headers) {
-        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    let button = req.button.unwrap_or_else(|| "left".to_string());
+    let mut body = match act(&state, &headers, &origin, &req.token, true, req.deadline_ms, Action::Click { button }).await {
+        Ok(body) => body,
+        Err(resp) => return resp,
+    };
+    if req.verify_focus {
+        let (focused, detail) = verify_focus(&state).await;
+        body["focused"] = json!(focused);
+        body["focus"] = detail;
     }
+    (StatusCode::OK, Json(body)).into_response()
+}
 
-    if let Err(msg) = state.consume_action(&req.token) {
-        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg}))).into_response();
+// Half-size of the area around the pointer searched for a caret: wide enough for a caret at
+// the end of existing text, short enough to skip other fields.
+const FOCUS_SEARCH: (i32, i32) = (320, 48);
+
+// Whether a text field under the pointer took keyboard focus, and how that was decided. No
+// accessibility API is consulted: a blinking caret near the click point (caret.rs) counts as
+// focus. The synthetic desktop answers from its own state.
+async fn verify_focus(state: &HandsState) -> (bool, serde_json::Value) {
+    if sim::active() {
+        let focused = sim::input_focused();
+        return (focused, json!({"method": "simulation"}));
     }
-
-    // Extra guardrail: require explicit header to reduce accidental clicks
-    let confirm = headers
-        .get("x-seealln-confirm")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.eq_ignore_ascii_case("yes"))
-        .unwrap_or(false);
-
-    if !confirm {
-        return (
-            StatusCode::PRECONDITION_REQUIRED,
-            Json(json!({"ok": false, "error": "missing x-seealln-confirm: yes"})),
-        )
-            .into_response();
+    let area = state.pointer().map(|(x, y)| ScopeRect {
+        x: x - FOCUS_SEARCH.0,
+        y: y - FOCUS_SEARCH.1,
+        w: FOCUS_SEARCH.0 * 2,
+        h: FOCUS_SEARCH.1 * 2,
+    });
+    let region = match state.confine(area) {
+        Ok(region) => region,
+        Err(err) => return (false, json!({"method": "caret", "error": err})),
+    };
+    match tokio::task::spawn_blocking(move || caret::detect(None, region, caret::DEFAULT_WINDOW)).await {
+        Ok(Ok(report)) => (report.caret.is_some(), json!({"method": "caret", "caret": report.caret, "region": region})),
+        Ok(Err(err)) => (false, json!({"method": "caret", "error": err})),
+        Err(err) => (false, json!({"method": "caret", "error": err.to_string()})),
     }
+}
 
-    #[cfg(feature = "hands")]
-    match enigo_click(req.button.as_deref()) {
-        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
-        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"ok": false, "error": err}))).into_response(),
+// How text is delivered: "keys" injects it key by key; "paste" goes through the clipboard and
+// the paste shortcut, for CJK, emoji and other text keycodes cannot produce; "auto" pastes
+// only text the backend would not type reliably. Default SEEALLN_TYPE_MODE, else keys.
+pub fn type_action(input: &Input, text: String, mode: Option<&str>) -> Result<Action, String> {
+    let default = std::env::var("SEEALLN_TYPE_MODE").ok();
+    match mode.or(default.as_deref()).map(str::trim).unwrap_or("keys") {
+        "keys" => Ok(Action::Type { text }),
+        "paste" => Ok(Action::Paste { text }),
+        "auto" if needs_paste(&text, input.capabilities().unicode_typing) => Ok(Action::Paste { text }),
+        "auto" => Ok(Action::Type { text }),
+        other => Err(format!("unknown typing mode '{other}' (keys, paste, auto)")),
     }
+}
 
-    #[cfg(not(feature = "hands"))]
-    (StatusCode::NOT_IMPLEMENTED, Json(json!({"ok": false, "error": "hands feature disabled"}))).into_response()
+// Latin, Greek, Cyrillic and general punctuation map to keysyms reliably; CJK, emoji and
+// complex scripts do not. Backends without Unicode typing manage ASCII only.
+fn needs_paste(text: &str, unicode_typing: bool) -> bool {
+    text.chars().any(|c| {
+        if unicode_typing {
+            (c as u32) >= 0x0530 && !('\u{2000}'..='\u{206F}').contains(&c)
+        } else {
+            !c.is_ascii()
+        }
+    })
 }
 
 pub async fn hands_type(
     State(state): State<HandsState>,
     headers: HeaderMap,
+    origin: Origin,
     Json(req): Json<TypeReq>,
 ) -> impl IntoResponse {
-    if let Err((code, msg)) = require_local_only(&headers) {
-        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
-    }
-
-    if let Err(msg) = state.consume_action(&req.token) {
-        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": msg}))).into_response();
-    }
-
-    // Extra guardrail: require explicit header to reduce accidental typing
-    let confirm = headers
-        .get("x-seealln-confirm")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.eq_ignore_ascii_case("yes"))
-        .unwrap_or(false);
-
-    if !confirm {
-        return (
-            StatusCode::PRECONDITION_REQUIRED,
-            Json(json!({"ok": false, "error": "missing x-seealln-confirm: yes"})),
-        )
-            .into_response();
-    }
-
-    let text = req.text;
+    let action = match type_action(state.input(), req.text, req.mode.as_deref()) {
+        Ok(action) => action,
+        Err(err) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    run_action(&state, &headers, &origin, &req.token, true, req.deadline_ms, action).await
+}
 
-    // Guardrails
-    if text.len() > 200 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({"ok": false, "error": "text too long (max 200)"})),
-        )
-            .into_response();
-    }
-    if reject_sensitive_text(&text) {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({"ok": false, "error": "looks like login/MFA/CAPTCHA; refusing"})),
-        )
-            .into_response();
-    }
+pub async fn hands_key(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<KeyReq>,
+) -> impl IntoResponse {
+    run_action(&state, &headers, &origin, &req.token, true, req.deadline_ms, Action::Key { combo: req.combo }).await
+}
 
-    #[cfg(feature = "hands")]
-    match enigo_type(&text) {
-        Ok(_) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
-        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"ok": false, "error": err}))).into_response(),
-    }
+pub async fn hands_scroll(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<ScrollReq>,
+) -> impl IntoResponse {
+    let (dx, dy) = (req.dx.clamp(-MAX_SCROLL, MAX_SCROLL), req.dy.clamp(-MAX_SCROLL, MAX_SCROLL));
+    run_action(&state, &headers, &origin, &req.token, true, req.deadline_ms, Action::Scroll { dx, dy }).await
+}
 
-    #[cfg(not(feature = "hands"))]
-    (StatusCode::NOT_IMPLEMENTED, Json(json!({"ok": false, "error": "hands feature disabled"}))).into_response()
+pub async fn hands_gesture(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<GestureReq>,
+) -> impl IntoResponse {
+    let default_ms = if matches!(req.gesture, Gesture::Tap { .. }) { 50 } else { 300 };
+    let duration_ms = req.duration_ms.unwrap_or(default_ms).min(MAX_GESTURE_MS);
+    run_action(&state, &headers, &origin, &req.token, true, req.deadline_ms, Action::Gesture { gesture: req.gesture, duration_ms }).await
 }
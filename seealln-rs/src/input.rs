@@ -0,0 +1,718 @@
+use axum::http::StatusCode;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{interceptor::Action, keys};
+
+// Input injection behind `InputBackend`, chosen at startup with SEEALLN_INPUT_BACKEND:
+//
+//   enigo    (default) SendInput on Windows, CGEvent on macOS, XTest on X11
+//   xdotool  the xdotool binary (SEEALLN_XDOTOOL): XTest through a separate process, for
+//            hosts where enigo's own X11 connection misbehaves
+//   ydotool  the ydotool 1.x client (SEEALLN_YDOTOOL) talking to a running ydotoold:
+//            kernel-level uinput events, so it works under any Wayland compositor and on
+//            the console; needs access to /dev/uinput. Sends key codes, so typing follows
+//            the keyboard layout in layout.rs
+//   wayland  (feature `wayland`, default on a Wayland session) virtual-pointer protocol plus
+//            wtype for the keyboard; see wayland.rs
+//
+// Every backend needs the `hands` feature; without it all actions are 501. Simulation mode
+// (sim.rs) replaces the backend with the synthetic desktop, with or without `hands`.
+pub trait InputBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Err when the backend cannot inject here (no display, missing binary or daemon, ...).
+    fn ready(&self) -> Result<(), String>;
+
+    fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)>;
+
+    fn capabilities(&self) -> Capabilities;
+
+    // Where the OS has the pointer, in desktop coordinates; backends that only inject
+    // (uinput, the Wayland virtual pointer) cannot tell.
+    fn location(&self) -> Result<(i32, i32), String> {
+        Err(format!("{} backend cannot read the pointer position", self.name()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Capabilities {
+    // `type` accepts any Unicode text, not only ASCII.
+    pub unicode_typing: bool,
+    pub horizontal_scroll: bool,
+    // Any single character works as a key, not only those of the keyboard layout (layout.rs).
+    pub any_key_char: bool,
+    // Injects through X11, so on a Wayland session only XWayland windows receive input.
+    pub x11_only: bool,
+}
+
+// Known causes of backend failures. An execute or readiness error that matches one answers
+// 503 with the error prefixed by its code ("no_display: ..."); the hands endpoints and /health
+// add the code and a remediation hint (see `failure`). Anything else stays an opaque 500.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    // macOS: the binary (or the terminal running it) is not trusted for Accessibility.
+    AccessibilityDenied,
+    // No rights on the injection device or display (uinput, X authority).
+    PermissionDenied,
+    // No display server to connect to (DISPLAY / WAYLAND_DISPLAY unset, server down).
+    NoDisplay,
+    // The compositor lacks the protocol the backend needs.
+    ProtocolUnsupported,
+    // The backend's helper binary is not installed.
+    BackendMissing,
+    // ydotool's daemon is not running.
+    DaemonNotRunning,
+}
+
+const FAILURES: &[Failure] = &[
+    Failure::AccessibilityDenied,
+    Failure::PermissionDenied,
+    Failure::NoDisplay,
+    Failure::ProtocolUnsupported,
+    Failure::BackendMissing,
+    Failure::DaemonNotRunning,
+];
+
+impl Failure {
+    // First match wins, so the more specific causes come first.
+    pub fn classify(error: &str) -> Option<Self> {
+        let e = error.to_ascii_lowercase();
+        let any = |needles: &[&str]| needles.iter().any(|n| e.contains(n));
+        if cfg!(target_os = "macos") && any(&["permission", "accessibility", "not trusted"]) {
+            Some(Failure::AccessibilityDenied)
+        } else if any(&["/dev/uinput", "permission denied", "os error 13", "operation not permitted", "authorization required"]) {
+            Some(Failure::PermissionDenied)
+        } else if any(&["cannot open display", "can't open display", "no display", "establish the connection", "find wayland compositor"]) {
+            Some(Failure::NoDisplay)
+        } else if any(&["does not offer"]) {
+            Some(Failure::ProtocolUnsupported)
+        } else if any(&["no such file or directory", "os error 2", "not found"]) {
+            Some(Failure::BackendMissing)
+        } else if any(&["ydotoold", "connection refused", "failed to connect to socket"]) {
+            Some(Failure::DaemonNotRunning)
+        } else {
+            None
+        }
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Failure::AccessibilityDenied => "accessibility_denied",
+            Failure::PermissionDenied => "permission_denied",
+            Failure::NoDisplay => "no_display",
+            Failure::ProtocolUnsupported => "protocol_unsupported",
+            Failure::BackendMissing => "backend_missing",
+            Failure::DaemonNotRunning => "daemon_not_running",
+        }
+    }
+
+    pub fn hint(self) -> &'static str {
+        match self {
+            Failure::AccessibilityDenied => {
+                "allow seealln (or the terminal that starts it) in System Settings > Privacy & Security > Accessibility, then restart it"
+            }
+            Failure::PermissionDenied => {
+                "give the server user access to /dev/uinput (e.g. the input group) or to the X display (xhost, XAUTHORITY)"
+            }
+            Failure::NoDisplay => "run inside the desktop session, or set DISPLAY / WAYLAND_DISPLAY to it",
+            Failure::ProtocolUnsupported => "this compositor has no virtual-pointer protocol; use SEEALLN_INPUT_BACKEND=ydotool",
+            Failure::BackendMissing => "install the backend's binary or point SEEALLN_XDOTOOL / SEEALLN_YDOTOOL / SEEALLN_WTYPE at it",
+            Failure::DaemonNotRunning => "start ydotoold (e.g. systemctl --user start ydotool) and check YDOTOOL_SOCKET",
+        }
+    }
+
+    // `{"code", "hint"}` for a response body.
+    pub fn json(self) -> serde_json::Value {
+        serde_json::json!({"code": self.code(), "hint": self.hint()})
+    }
+}
+
+// A 500 from a backend that matches a known cause becomes 503 with the code in front.
+fn classified((status, error): (StatusCode, String)) -> (StatusCode, String) {
+    match Failure::classify(&error).filter(|_| status == StatusCode::INTERNAL_SERVER_ERROR) {
+        Some(failure) => (StatusCode::SERVICE_UNAVAILABLE, format!("{}: {error}", failure.code())),
+        None => (status, error),
+    }
+}
+
+// The cause behind an error from `Input::execute`, read back from its code prefix.
+pub fn failure(error: &str) -> Option<Failure> {
+    let (code, _) = error.split_once(": ")?;
+    FAILURES.iter().copied().find(|f| f.code() == code)
+}
+
+#[cfg(feature = "hands")]
+fn internal(e: impl std::fmt::Display) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+// Browser actions run over cdp (see HandsState::perform), never through an input backend.
+pub(crate) fn not_input() -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, "browser actions do not go through the input backend".to_string())
+}
+
+// Last readiness probe and when it ran.
+type Probe = Option<(Instant, Result<(), String>)>;
+
+#[derive(Clone)]
+pub struct Input {
+    backend: Arc<dyn InputBackend>,
+    // Last readiness probe, so /health polling does not open a connection every time.
+    probe: Arc<Mutex<Probe>>,
+}
+
+// How long a readiness probe result is reused by `status`.
+const PROBE_TTL: Duration = Duration::from_secs(5);
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::with(Arc::new(Disabled))
+    }
+}
+
+impl std::ops::Deref for Input {
+    type Target = dyn InputBackend;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.backend
+    }
+}
+
+impl Input {
+    fn with(backend: Arc<dyn InputBackend>) -> Self {
+        Self {
+            backend,
+            probe: Arc::default(),
+        }
+    }
+
+    // An unknown backend name disables input rather than falling back to another injector.
+    pub fn from_env() -> Self {
+        if crate::sim::active() {
+            return Self::with(Arc::new(crate::sim::SimInput));
+        }
+        let name = std::env::var("SEEALLN_INPUT_BACKEND").unwrap_or_default().trim().to_ascii_lowercase();
+        match backend(&name) {
+            Some(b) => Self::with(b),
+            None => {
+                tracing::error!(backend = %name, "unknown SEEALLN_INPUT_BACKEND; hands disabled");
+                Self::default()
+            }
+        }
+    }
+
+    // `ready`, reusing a recent result. Blocking; call from spawn_blocking.
+    pub fn status(&self) -> Result<(), String> {
+        let mut probe = self.probe.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, result)) = probe.as_ref().filter(|(at, _)| at.elapsed() < PROBE_TTL) {
+            return result.clone();
+        }
+        let result = self.backend.ready();
+        *probe = Some((Instant::now(), result.clone()));
+        result
+    }
+
+    // The backend's `execute`, with known failures classified.
+    pub fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+        self.backend.execute(action).map_err(classified)
+    }
+}
+
+// Display server of the session: "wayland", "x11", or None off Linux / when unknown.
+pub fn session_type() -> Option<&'static str> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    match std::env::var("XDG_SESSION_TYPE").ok().as_deref() {
+        Some("wayland") => Some("wayland"),
+        Some("x11") => Some("x11"),
+        _ if std::env::var_os("WAYLAND_DISPLAY").is_some() => Some("wayland"),
+        _ if std::env::var_os("DISPLAY").is_some() || crate::vdisplay::default_display().is_some() => Some("x11"),
+        _ => None,
+    }
+}
+
+// Types `text` by putting it on the clipboard and pressing the paste shortcut, for CJK,
+// emoji and other text that per-key injection cannot produce. The previous clipboard text is
+// put back afterwards; non-text clipboard contents (images, files) are not preserved.
+//
+//   SEEALLN_PASTE_COMBO     shortcut, default super+v on macOS, ctrl+v elsewhere (terminals
+//                           usually want ctrl+shift+v)
+//   SEEALLN_PASTE_SETTLE_MS time the target gets to read the clipboard, default 150
+#[cfg(feature = "hands")]
+pub(crate) fn paste(backend: &dyn InputBackend, text: &str) -> Result<(), (StatusCode, String)> {
+    let mut clipboard = arboard::Clipboard::new().map_err(internal)?;
+    let previous = clipboard.get_text().ok();
+    clipboard.set_text(text).map_err(internal)?;
+    let result = backend.execute(&Action::Key { combo: paste_combo() });
+    // The target reads the clipboard asynchronously, after the shortcut arrives.
+    let settle = std::env::var("SEEALLN_PASTE_SETTLE_MS").ok().and_then(|s| s.trim().parse().ok()).unwrap_or(150);
+    std::thread::sleep(Duration::from_millis(settle));
+    let _ = match previous {
+        Some(previous) => clipboard.set_text(previous),
+        None => clipboard.clear(),
+    };
+    result
+}
+
+// Touch injection is Windows-only (InjectTouchInput, feature `touch`); it does not depend on
+// the pointer/keyboard backend.
+#[cfg(feature = "hands")]
+pub(crate) fn gesture(gesture: &crate::interceptor::Gesture, duration_ms: u32) -> Result<(), (StatusCode, String)> {
+    #[cfg(all(windows, feature = "touch"))]
+    return crate::touch::inject(gesture, Duration::from_millis(duration_ms.into())).map_err(internal);
+
+    #[cfg(not(all(windows, feature = "touch")))]
+    {
+        let _ = (gesture, duration_ms);
+        Err((StatusCode::NOT_IMPLEMENTED, "touch gestures need Windows and the `touch` feature".to_string()))
+    }
+}
+
+pub fn gestures_supported() -> bool {
+    cfg!(all(windows, feature = "touch"))
+}
+
+pub fn paste_combo() -> String {
+    std::env::var("SEEALLN_PASTE_COMBO")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| if cfg!(target_os = "macos") { "super+v" } else { "ctrl+v" }.to_string())
+}
+
+#[cfg(feature = "hands")]
+fn backend(name: &str) -> Option<Arc<dyn InputBackend>> {
+    Some(match name {
+        // A Wayland session gets native Wayland input when built in; enigo only reaches
+        // XWayland windows there.
+        #[cfg(feature = "wayland")]
+        "" if session_type() == Some("wayland") => Arc::new(crate::wayland::Wayland::from_env()),
+        #[cfg(feature = "wayland")]
+        "wayland" => Arc::new(crate::wayland::Wayland::from_env()),
+        "" | "enigo" => Arc::new(native::Enigo),
+        "xdotool" => Arc::new(command::Xdotool::from_env()),
+        "ydotool" => Arc::new(command::Ydotool::from_env()),
+        _ => return None,
+    })
+}
+
+#[cfg(not(feature = "hands"))]
+fn backend(_name: &str) -> Option<Arc<dyn InputBackend>> {
+    Some(Arc::new(Disabled))
+}
+
+// No injection at all (builds without `hands`). Combos are still validated so a typo is a
+// 400 rather than a 501.
+struct Disabled;
+
+impl InputBackend for Disabled {
+    fn name(&self) -> &'static str {
+        "none"
+    }
+
+    fn ready(&self) -> Result<(), String> {
+        Err("hands feature disabled".to_string())
+    }
+
+    fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+        if let Action::Key { combo } = action {
+            keys::parse_combo(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        }
+        Err((StatusCode::NOT_IMPLEMENTED, "hands feature disabled".to_string()))
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::default()
+    }
+}
+
+#[cfg(feature = "hands")]
+mod native {
+    use axum::http::StatusCode;
+    use enigo::{Axis, Button, Coordinate, Direction, Key, Keyboard, Mouse, Settings};
+
+    use super::{internal, Capabilities, InputBackend};
+    use crate::{interceptor::Action, keys};
+
+    pub struct Enigo;
+
+    // Connects to the default virtual display when the process has no DISPLAY.
+    fn settings() -> Settings {
+        Settings { x11_display: crate::vdisplay::default_display(), ..Settings::default() }
+    }
+
+    impl InputBackend for Enigo {
+        fn name(&self) -> &'static str {
+            "enigo"
+        }
+
+        fn ready(&self) -> Result<(), String> {
+            enigo::Enigo::new(&settings()).map(|_| ()).map_err(|e| e.to_string())
+        }
+
+        fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+            let mut enigo = enigo::Enigo::new(&settings()).map_err(internal)?;
+            match action {
+                Action::Move { x, y } => enigo.move_mouse(*x, *y, Coordinate::Abs).map_err(internal),
+                Action::Click { button } => {
+                    let b = match button.as_str() {
+                        "left" => Button::Left,
+                        "right" => Button::Right,
+                        "middle" => Button::Middle,
+                        _ => return Err(internal("invalid button")),
+                    };
+                    enigo.button(b, Direction::Click).map_err(internal)
+                }
+                Action::Type { text } => enigo.text(text).map_err(internal),
+                Action::Paste { text } => super::paste(self, text),
+                Action::Gesture { gesture, duration_ms } => super::gesture(gesture, *duration_ms),
+                Action::Browser { .. } => Err(super::not_input()),
+                Action::Key { combo } => {
+                    let keys = keys::parse_combo(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+                    let keys: Vec<Key> = keys.into_iter().map(enigo_key).collect();
+                    let (last, held) = keys.split_last().expect("combo has at least one key");
+                    for k in held {
+                        enigo.key(*k, Direction::Press).map_err(internal)?;
+                    }
+                    let result = enigo.key(*last, Direction::Click).map_err(internal);
+                    // Always release the modifiers, even if the final key failed.
+                    for k in held.iter().rev() {
+                        let _ = enigo.key(*k, Direction::Release);
+                    }
+                    result
+                }
+                Action::Scroll { dx, dy } => {
+                    if *dy != 0 {
+                        enigo.scroll(*dy, Axis::Vertical).map_err(internal)?;
+                    }
+                    if *dx != 0 {
+                        enigo.scroll(*dx, Axis::Horizontal).map_err(internal)?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                unicode_typing: true,
+                horizontal_scroll: true,
+                any_key_char: true,
+                x11_only: cfg!(target_os = "linux"),
+            }
+        }
+
+        fn location(&self) -> Result<(i32, i32), String> {
+            let enigo = enigo::Enigo::new(&settings()).map_err(|e| e.to_string())?;
+            enigo.location().map_err(|e| e.to_string())
+        }
+    }
+
+    fn enigo_key(key: keys::KeyName) -> Key {
+        use keys::KeyName as K;
+        match key {
+            K::Control => Key::Control,
+            K::Shift => Key::Shift,
+            K::Alt => Key::Alt,
+            K::Meta => Key::Meta,
+            K::Return => Key::Return,
+            K::Tab => Key::Tab,
+            K::Escape => Key::Escape,
+            K::Backspace => Key::Backspace,
+            K::Delete => Key::Delete,
+            K::Home => Key::Home,
+            K::End => Key::End,
+            K::PageUp => Key::PageUp,
+            K::PageDown => Key::PageDown,
+            K::Up => Key::UpArrow,
+            K::Down => Key::DownArrow,
+            K::Left => Key::LeftArrow,
+            K::Right => Key::RightArrow,
+            K::Space => Key::Space,
+            K::F(n) => [
+                Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10,
+                Key::F11, Key::F12, Key::F13, Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19, Key::F20,
+            ][(n.clamp(1, 20) - 1) as usize],
+            K::Char(c) => Key::Unicode(c),
+        }
+    }
+}
+
+// Backends that drive an external injector binary, one process per action.
+#[cfg(feature = "hands")]
+mod command {
+    use axum::http::StatusCode;
+    use std::process::{Command, Stdio};
+
+    use super::{internal, Capabilities, InputBackend};
+    use crate::{
+        interceptor::Action,
+        keys,
+        keys::KeyName as K,
+        layout::{self, Layout},
+    };
+
+    fn run(bin: &str, args: &[String]) -> Result<(), String> {
+        output(bin, args).map(|_| ())
+    }
+
+    // `run`, returning what the command printed.
+    fn output(bin: &str, args: &[String]) -> Result<String, String> {
+        let out = Command::new(bin)
+            .args(args)
+            .envs(crate::vdisplay::default_display().map(|name| ("DISPLAY", name)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| format!("{bin}: {e}"))?;
+        if !out.status.success() {
+            return Err(format!("{bin} exited with {}: {}", out.status, String::from_utf8_lossy(&out.stderr).trim()));
+        }
+        Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+    }
+
+    fn strings<const N: usize>(args: [&str; N]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    pub struct Xdotool {
+        bin: String,
+    }
+
+    impl Xdotool {
+        pub fn from_env() -> Self {
+            Self { bin: std::env::var("SEEALLN_XDOTOOL").unwrap_or_else(|_| "xdotool".to_string()) }
+        }
+    }
+
+    fn keysym(key: K) -> String {
+        match key {
+            K::Control => "ctrl".into(),
+            K::Shift => "shift".into(),
+            K::Alt => "alt".into(),
+            K::Meta => "super".into(),
+            K::Return => "Return".into(),
+            K::Tab => "Tab".into(),
+            K::Escape => "Escape".into(),
+            K::Backspace => "BackSpace".into(),
+            K::Delete => "Delete".into(),
+            K::Home => "Home".into(),
+            K::End => "End".into(),
+            K::PageUp => "Prior".into(),
+            K::PageDown => "Next".into(),
+            K::Up => "Up".into(),
+            K::Down => "Down".into(),
+            K::Left => "Left".into(),
+            K::Right => "Right".into(),
+            K::Space => "space".into(),
+            K::F(n) => format!("F{n}"),
+            // '+' would split the combo; punctuation goes by X keysym name.
+            K::Char('+') => "plus".into(),
+            K::Char('-') => "minus".into(),
+            K::Char('=') => "equal".into(),
+            K::Char(',') => "comma".into(),
+            K::Char('.') => "period".into(),
+            K::Char('/') => "slash".into(),
+            K::Char(c) => c.to_string(),
+        }
+    }
+
+    impl InputBackend for Xdotool {
+        fn name(&self) -> &'static str {
+            "xdotool"
+        }
+
+        fn ready(&self) -> Result<(), String> {
+            run(&self.bin, &strings(["getmouselocation"]))
+        }
+
+        fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+            let args = match action {
+                Action::Move { x, y } => vec!["mousemove".into(), "--".into(), x.to_string(), y.to_string()],
+                Action::Click { button } => {
+                    let b = match button.as_str() {
+                        "left" => "1",
+                        "middle" => "2",
+                        "right" => "3",
+                        _ => return Err(internal("invalid button")),
+                    };
+                    strings(["click", b])
+                }
+                Action::Type { text } => vec!["type".into(), "--delay".into(), "12".into(), "--".into(), text.clone()],
+                Action::Paste { text } => return super::paste(self, text),
+                Action::Gesture { gesture, duration_ms } => return super::gesture(gesture, *duration_ms),
+                Action::Browser { .. } => return Err(super::not_input()),
+                Action::Key { combo } => {
+                    let keys = keys::parse_combo(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+                    let combo = keys.into_iter().map(keysym).collect::<Vec<_>>().join("+");
+                    vec!["key".into(), "--clearmodifiers".into(), "--".into(), combo]
+                }
+                Action::Scroll { dx, dy } => {
+                    // Wheel buttons: 4 up, 5 down, 6 left, 7 right.
+                    for (n, neg, pos) in [(*dy, "4", "5"), (*dx, "6", "7")] {
+                        if n != 0 {
+                            let button = if n < 0 { neg } else { pos };
+                            let repeat = n.unsigned_abs().to_string();
+                            run(&self.bin, &strings(["click", "--repeat", repeat.as_str(), button])).map_err(internal)?;
+                        }
+                    }
+                    return Ok(());
+                }
+            };
+            run(&self.bin, &args).map_err(internal)
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                unicode_typing: true,
+                horizontal_scroll: true,
+                any_key_char: true,
+                x11_only: true,
+            }
+        }
+
+        // `getmouselocation --shell` prints X=.., Y=.., SCREEN=.., WINDOW=.. lines.
+        fn location(&self) -> Result<(i32, i32), String> {
+            let out = output(&self.bin, &strings(["getmouselocation", "--shell"]))?;
+            let field = |name: &str| {
+                out.lines()
+                    .find_map(|line| line.strip_prefix(name)?.strip_prefix('=')?.trim().parse::<i32>().ok())
+                    .ok_or_else(|| format!("unexpected getmouselocation output: {}", out.trim()))
+            };
+            Ok((field("X")?, field("Y")?))
+        }
+    }
+
+    pub struct Ydotool {
+        bin: String,
+    }
+
+    impl Ydotool {
+        pub fn from_env() -> Self {
+            Self { bin: std::env::var("SEEALLN_YDOTOOL").unwrap_or_else(|_| "ydotool".to_string()) }
+        }
+    }
+
+    const KEY_LEFTSHIFT: u16 = 42;
+
+    // `ydotool key` arguments pressing every code in order and releasing them in reverse.
+    fn press(codes: &[Option<u16>]) -> Vec<String> {
+        let codes: Vec<u16> = codes.iter().flatten().copied().collect();
+        let down = codes.iter().map(|c| format!("{c}:1"));
+        let up = codes.iter().rev().map(|c| format!("{c}:0"));
+        down.chain(up).collect()
+    }
+
+    // (shift needed, key code); None for characters `layout` has no key for.
+    fn evdev(key: K, layout: Layout) -> Option<(bool, u16)> {
+        let code = match key {
+            K::Control => 29,
+            K::Shift => KEY_LEFTSHIFT,
+            K::Alt => 56,
+            K::Meta => 125,
+            K::Return => 28,
+            K::Tab => 15,
+            K::Escape => 1,
+            K::Backspace => 14,
+            K::Delete => 111,
+            K::Home => 102,
+            K::End => 107,
+            K::PageUp => 104,
+            K::PageDown => 109,
+            K::Up => 103,
+            K::Down => 108,
+            K::Left => 105,
+            K::Right => 106,
+            K::Space | K::Char(' ') => 57,
+            K::F(n @ 1..=10) => 58 + u16::from(n),
+            K::F(11) => 87,
+            K::F(12) => 88,
+            K::F(n) => 170 + u16::from(n.clamp(13, 20)),
+            K::Char(c) => return layout.code(c),
+        };
+        Some((false, code))
+    }
+
+    impl InputBackend for Ydotool {
+        fn name(&self) -> &'static str {
+            "ydotool"
+        }
+
+        // A zero relative move: fails when ydotoold is not running, moves nothing otherwise.
+        fn ready(&self) -> Result<(), String> {
+            run(&self.bin, &strings(["mousemove", "-x", "0", "-y", "0"]))
+        }
+
+        fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+            let args = match action {
+                Action::Move { x, y } => {
+                    vec!["mousemove".into(), "--absolute".into(), "-x".into(), x.to_string(), "-y".into(), y.to_string()]
+                }
+                Action::Click { button } => {
+                    // 0xC0 = press + release of button 0 (left), 1 right, 2 middle.
+                    let b = match button.as_str() {
+                        "left" => "0xC0",
+                        "right" => "0xC1",
+                        "middle" => "0xC2",
+                        _ => return Err(internal("invalid button")),
+                    };
+                    strings(["click", b])
+                }
+                Action::Paste { text } => return super::paste(self, text),
+                Action::Gesture { gesture, duration_ms } => return super::gesture(gesture, *duration_ms),
+                Action::Browser { .. } => return Err(super::not_input()),
+                // `ydotool type` assumes a US keymap; other layouts get key codes of their own.
+                Action::Type { text } if layout::current() == Layout::Us => {
+                    if !text.is_ascii() {
+                        return Err((StatusCode::BAD_REQUEST, "ydotool backend types ASCII text only".to_string()));
+                    }
+                    vec!["type".into(), "--".into(), text.clone()]
+                }
+                Action::Type { text } => {
+                    let layout = layout::current();
+                    let mut args = vec!["key".to_string()];
+                    for c in text.chars() {
+                        let (shift, code) = evdev(K::Char(c), layout).ok_or_else(|| {
+                            (StatusCode::BAD_REQUEST, format!("'{c}' has no key on the {} layout", layout.name()))
+                        })?;
+                        args.extend(press(&[shift.then_some(KEY_LEFTSHIFT), Some(code)]));
+                    }
+                    args
+                }
+                Action::Key { combo } => {
+                    let layout = layout::current();
+                    let keys = keys::parse_combo(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+                    let mut codes = Vec::new();
+                    for key in keys {
+                        let (shift, code) = evdev(key, layout).ok_or_else(|| {
+                            (StatusCode::BAD_REQUEST, format!("key {key:?} has no code on the {} layout", layout.name()))
+                        })?;
+                        codes.extend([shift.then_some(KEY_LEFTSHIFT), Some(code)]);
+                    }
+                    let mut args = vec!["key".to_string()];
+                    args.extend(press(&codes));
+                    args
+                }
+                Action::Scroll { dx, dy } => {
+                    // Wheel events count up as positive; ours count down as positive.
+                    vec!["mousemove".into(), "--wheel".into(), "-x".into(), dx.to_string(), "-y".into(), (-dy).to_string()]
+                }
+            };
+            run(&self.bin, &args).map_err(internal)
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            Capabilities {
+                unicode_typing: false,
+                horizontal_scroll: true,
+                any_key_char: false,
+                x11_only: false,
+            }
+        }
+    }
+}
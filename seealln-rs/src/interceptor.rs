@@ -0,0 +1,343 @@
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::{desktop, hands::ScopeRect};
+
+// A resolved hands action as it flows through the interceptor pipeline.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Action {
+    Move { x: i32, y: i32 },
+    Click { button: String },
+    Type { text: String },
+    // Text delivered via the clipboard and the paste shortcut (see input.rs).
+    Paste { text: String },
+    // Key combo such as "ctrl+shift+t" or "Return" (see keys.rs).
+    Key { combo: String },
+    // Wheel notches at the pointer; positive dy scrolls down, positive dx right.
+    Scroll { dx: i32, dy: i32 },
+    // Touch gesture played over `duration_ms` (see /hands/gesture).
+    Gesture {
+        gesture: Gesture,
+        // Optional when naming the action for /hands/confirm.
+        #[serde(default)]
+        duration_ms: u32,
+    },
+    // DOM-level browser action over the Chrome DevTools Protocol (see cdp.rs).
+    Browser { op: BrowserOp },
+}
+
+// What a browser action does, in a page target (`target` id; the first page when omitted).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BrowserOp {
+    // http(s) only, limited by SEEALLN_CDP_ALLOW_HOSTS.
+    Navigate { url: String, target: Option<String> },
+    // The `index`th node matching `selector`, clicked at its centre.
+    Click {
+        selector: String,
+        #[serde(default)]
+        index: usize,
+        target: Option<String>,
+    },
+}
+
+// Touch gestures in screen coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Gesture {
+    Tap { x: i32, y: i32 },
+    Swipe { x: i32, y: i32, to_x: i32, to_y: i32 },
+    // Two fingers on a horizontal line centred on (x, y), `from` pixels apart, ending `to`
+    // pixels apart (to > from zooms in).
+    Pinch { x: i32, y: i32, from: i32, to: i32 },
+    // Two fingers side by side moving together by (dx, dy).
+    TwoFingerScroll { x: i32, y: i32, dx: i32, dy: i32 },
+}
+
+// Gap between the fingers of a two-finger scroll.
+const FINGER_GAP: i32 = 40;
+
+impl Gesture {
+    // (start, end) of each finger.
+    pub fn tracks(&self) -> Vec<((i32, i32), (i32, i32))> {
+        match *self {
+            Gesture::Tap { x, y } => vec![((x, y), (x, y))],
+            Gesture::Swipe { x, y, to_x, to_y } => vec![((x, y), (to_x, to_y))],
+            Gesture::Pinch { x, y, from, to } => vec![
+                ((x - from / 2, y), (x - to / 2, y)),
+                ((x + from / 2, y), (x + to / 2, y)),
+            ],
+            Gesture::TwoFingerScroll { x, y, dx, dy } => {
+                let half = FINGER_GAP / 2;
+                vec![
+                    ((x - half, y), (x - half + dx, y + dy)),
+                    ((x + half, y), (x + half + dx, y + dy)),
+                ]
+            }
+        }
+    }
+
+    // Applies `f` to the points the caller chose (tap point, swipe ends, pinch/scroll
+    // centre); finger spreads and scroll distances are kept.
+    pub fn map_anchors(&mut self, mut f: impl FnMut(i32, i32) -> (i32, i32)) {
+        match self {
+            Gesture::Tap { x, y } | Gesture::Pinch { x, y, .. } | Gesture::TwoFingerScroll { x, y, .. } => {
+                (*x, *y) = f(*x, *y);
+            }
+            Gesture::Swipe { x, y, to_x, to_y } => {
+                (*x, *y) = f(*x, *y);
+                (*to_x, *to_y) = f(*to_x, *to_y);
+            }
+        }
+    }
+}
+
+impl Action {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Action::Move { .. } => "move",
+            Action::Click { .. } => "click",
+            Action::Type { .. } => "type",
+            Action::Paste { .. } => "paste",
+            Action::Key { .. } => "key",
+            Action::Scroll { .. } => "scroll",
+            Action::Gesture { .. } => "gesture",
+            Action::Browser { .. } => "browser",
+        }
+    }
+
+    // Audit/log form: typed content is never included, only its size.
+    pub fn summary(&self) -> serde_json::Value {
+        match self {
+            Action::Move { x, y } => json!({"kind": "move", "x": x, "y": y}),
+            Action::Click { button } => json!({"kind": "click", "button": button}),
+            Action::Type { text } => json!({"kind": "type", "chars": text.chars().count()}),
+            Action::Paste { text } => json!({"kind": "paste", "chars": text.chars().count()}),
+            Action::Key { combo } => json!({"kind": "key", "combo": combo}),
+            Action::Scroll { dx, dy } => json!({"kind": "scroll", "dx": dx, "dy": dy}),
+            Action::Gesture { gesture, duration_ms } => json!({"kind": "gesture", "gesture": gesture, "duration_ms": duration_ms}),
+            Action::Browser { op } => json!({"kind": "browser", "op": op}),
+        }
+    }
+}
+
+// What interceptors can see about the world when an action is proposed.
+#[derive(Debug, Clone, Default)]
+pub struct ActionContext {
+    pub scope: Option<ScopeRect>,
+    // Out-of-scope moves are refused instead of clamped (see ScopeClamp).
+    pub strict_scope: bool,
+    // Name of the focused application, when the `focus` feature can tell.
+    pub focused_app: Option<String>,
+    // Summaries of the most recent executed actions, oldest first.
+    #[cfg_attr(not(feature = "lua-policy"), allow(dead_code))]
+    pub recent: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Denial {
+    pub status: StatusCode,
+    pub error: String,
+}
+
+impl Denial {
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        Self {
+            status,
+            error: error.into(),
+        }
+    }
+}
+
+// Hook into the hands pipeline. `before` runs in registration order and may rewrite the
+// action or deny it (short-circuiting the rest); `after` observes the execution result.
+pub trait ActionInterceptor: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn before(&self, _action: &mut Action, _ctx: &ActionContext) -> Result<(), Denial> {
+        Ok(())
+    }
+
+    fn after(&self, _action: &Action, _result: &Result<(), String>, _ctx: &ActionContext) {}
+}
+
+impl<T: ActionInterceptor + ?Sized> ActionInterceptor for Box<T> {
+    fn name(&self) -> &'static str {
+        (**self).name()
+    }
+
+    fn before(&self, action: &mut Action, ctx: &ActionContext) -> Result<(), Denial> {
+        (**self).before(action, ctx)
+    }
+
+    fn after(&self, action: &Action, result: &Result<(), String>, ctx: &ActionContext) {
+        (**self).after(action, result, ctx)
+    }
+}
+
+// Clamp onto the virtual desktop: a point between or beyond the monitors moves to the
+// nearest monitor, including ones left of or above the primary (negative coordinates).
+// Falls back to the main display when the layout is unknown (hands builds only).
+pub struct ScreenClamp;
+
+impl ActionInterceptor for ScreenClamp {
+    fn name(&self) -> &'static str {
+        "screen_clamp"
+    }
+
+    fn before(&self, action: &mut Action, _ctx: &ActionContext) -> Result<(), Denial> {
+        match action {
+            Action::Move { x, y } => {
+                (*x, *y) = clamp_to_screen(*x, *y);
+            }
+            Action::Gesture { gesture, .. } => gesture.map_anchors(clamp_to_screen),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn clamp_to_screen(x: i32, y: i32) -> (i32, i32) {
+    // Guardrail: clamp to a sane range to avoid overflow; actual screen bounds are OS-specific.
+    let (x, y) = (x.clamp(-100_000, 100_000), y.clamp(-100_000, 100_000));
+    if let Some(p) = desktop::clamp(&desktop::monitors(), x, y) {
+        return p;
+    }
+
+    #[cfg(feature = "hands")]
+    {
+        use enigo::{Enigo, Mouse, Settings};
+        if let Ok(enigo) = Enigo::new(&Settings::default()) {
+            if let Ok((w, h)) = enigo.main_display() {
+                return (x.clamp(0, w.saturating_sub(1)), y.clamp(0, h.saturating_sub(1)));
+            }
+        }
+    }
+    (x, y)
+}
+
+// Keep pointer moves and gestures inside the scope rect set via /scope/set. In strict mode a
+// point outside it is refused as a `scope_violation` instead: the nearest in-scope pixel may well be a
+// different UI element than the one aimed at.
+pub struct ScopeClamp;
+
+impl ActionInterceptor for ScopeClamp {
+    fn name(&self) -> &'static str {
+        "scope_clamp"
+    }
+
+    fn before(&self, action: &mut Action, ctx: &ActionContext) -> Result<(), Denial> {
+        let Some(scope) = ctx.scope else {
+            return Ok(());
+        };
+        let violation = |x: i32, y: i32| {
+            Denial::new(
+                StatusCode::FORBIDDEN,
+                format!("scope_violation: ({x}, {y}) is outside the scope {},{},{},{}", scope.x, scope.y, scope.w, scope.h),
+            )
+        };
+        match action {
+            Action::Move { x, y } if !scope.contains(*x, *y) => {
+                if ctx.strict_scope {
+                    return Err(violation(*x, *y));
+                }
+                (*x, *y) = scope.clamp_point(*x, *y);
+            }
+            // Every finger has to stay inside, not just the anchor points.
+            Action::Gesture { gesture, .. } => {
+                let outside = gesture.tracks().into_iter().flat_map(|(a, b)| [a, b]).find(|&(x, y)| !scope.contains(x, y));
+                if let Some((x, y)) = outside {
+                    if ctx.strict_scope {
+                        return Err(violation(x, y));
+                    }
+                    gesture.map_anchors(|x, y| scope.clamp_point(x, y));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// Length cap + refusal of anything that looks like credentials/MFA/CAPTCHA input.
+pub struct SensitiveText {
+    pub max_len: usize,
+}
+
+impl Default for SensitiveText {
+    fn default() -> Self {
+        Self { max_len: 200 }
+    }
+}
+
+fn looks_sensitive(text: &str) -> bool {
+    // Guardrail: if it looks like login/MFA/captcha, bail.
+    let t = text.to_lowercase();
+    ["password", "contrase", "otp", "2fa", "mfa", "captcha", "verification code", "c	digo"]
+        .iter()
+        .any(|k| t.contains(k))
+}
+
+impl ActionInterceptor for SensitiveText {
+    fn name(&self) -> &'static str {
+        "sensitive_text"
+    }
+
+    fn before(&self, action: &mut Action, _ctx: &ActionContext) -> Result<(), Denial> {
+        let (Action::Type { text } | Action::Paste { text }) = action else {
+            return Ok(());
+        };
+        if text.len() > self.max_len {
+            return Err(Denial::new(
+                StatusCode::BAD_REQUEST,
+                format!("text too long (max {})", self.max_len),
+            ));
+        }
+        if looks_sensitive(text) {
+            return Err(Denial::new(
+                StatusCode::FORBIDDEN,
+                "looks like login/MFA/CAPTCHA; refusing",
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Refuse every action while a denylisted application has focus.
+// SEEALLN_APP_DENYLIST: comma-separated, case-insensitive substrings of the app name.
+pub struct AppDenylist {
+    apps: Vec<String>,
+}
+
+impl AppDenylist {
+    pub fn from_env() -> Option<Self> {
+        let apps: Vec<String> = std::env::var("SEEALLN_APP_DENYLIST")
+            .ok()?
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect();
+        (!apps.is_empty()).then_some(Self { apps })
+    }
+}
+
+impl ActionInterceptor for AppDenylist {
+    fn name(&self) -> &'static str {
+        "app_denylist"
+    }
+
+    fn before(&self, _action: &mut Action, ctx: &ActionContext) -> Result<(), Denial> {
+        let Some(app) = ctx.focused_app.as_deref().map(str::to_lowercase) else {
+            // Unknown focus: nothing to match against.
+            return Ok(());
+        };
+        match self.apps.iter().find(|d| app.contains(d.as_str())) {
+            Some(d) => Err(Denial::new(
+                StatusCode::FORBIDDEN,
+                format!("focused app matches denylist entry '{d}'"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,536 @@
+use image::RgbImage;
+use serde::Deserialize;
+
+use crate::pool;
+
+// JPEG encoder for the options image's encoder lacks (it only writes baseline 4:4:4):
+// progressive scans, chroma subsampling and restart intervals. Standard Annex K tables and
+// the same libjpeg quality scaling as image, so `q` means the same thing either way.
+//   progressive: spectral selection (DC, then AC 1-5 and 6-63 per component), which lets
+//                viewers render a coarse frame early; no successive approximation.
+//   subsampling: 444 keeps full chroma (best for text), 422/420 halve it for fewer bytes.
+//   restart:     RSTn marker every N MCUs, so a corrupted chunk only damages one stripe.
+// Global defaults: SEEALLN_JPEG_PROGRESSIVE=1, SEEALLN_JPEG_SUBSAMPLING=444|422|420,
+// SEEALLN_JPEG_RESTART=N; snapshot/stream accept ?progressive=, ?subsampling=, ?restart=.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum Subsampling {
+    #[default]
+    #[serde(rename = "444", alias = "4:4:4")]
+    S444,
+    #[serde(rename = "422", alias = "4:2:2")]
+    S422,
+    #[serde(rename = "420", alias = "4:2:0")]
+    S420,
+}
+
+impl Subsampling {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "444" | "4:4:4" => Some(Self::S444),
+            "422" | "4:2:2" => Some(Self::S422),
+            "420" | "4:2:0" => Some(Self::S420),
+            _ => None,
+        }
+    }
+
+    // Luma sampling factors (h, v); chroma is always 1x1.
+    fn luma_factors(self) -> (usize, usize) {
+        match self {
+            Self::S444 => (1, 1),
+            Self::S422 => (2, 1),
+            Self::S420 => (2, 2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Options {
+    pub progressive: bool,
+    pub subsampling: Subsampling,
+    pub restart_interval: u16,
+}
+
+impl Options {
+    pub fn global() -> Self {
+        let env = |k: &str| std::env::var(k).ok();
+        Self {
+            progressive: env("SEEALLN_JPEG_PROGRESSIVE").is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true")),
+            subsampling: env("SEEALLN_JPEG_SUBSAMPLING")
+                .and_then(|v| Subsampling::parse(&v))
+                .unwrap_or_default(),
+            restart_interval: env("SEEALLN_JPEG_RESTART").and_then(|v| v.parse().ok()).unwrap_or(0),
+        }
+    }
+
+    // Global defaults with per-request overrides applied.
+    pub fn with(mut self, progressive: Option<bool>, subsampling: Option<Subsampling>, restart: Option<u16>) -> Self {
+        self.progressive = progressive.unwrap_or(self.progressive);
+        self.subsampling = subsampling.unwrap_or(self.subsampling);
+        self.restart_interval = restart.unwrap_or(self.restart_interval);
+        self
+    }
+
+    // True when image's baseline encoder produces the same kind of stream.
+    pub fn is_baseline(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[rustfmt::skip]
+const LUMA_QTABLE: [u8; 64] = [
+    16, 11, 10, 16,  24,  40,  51,  61,
+    12, 12, 14, 19,  26,  58,  60,  55,
+    14, 13, 16, 24,  40,  57,  69,  56,
+    14, 17, 22, 29,  51,  87,  80,  62,
+    18, 22, 37, 56,  68, 109, 103,  77,
+    24, 35, 55, 64,  81, 104, 113,  92,
+    49, 64, 78, 87, 103, 121, 120, 101,
+    72, 92, 95, 98, 112, 100, 103,  99,
+];
+
+#[rustfmt::skip]
+const CHROMA_QTABLE: [u8; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99,
+    18, 21, 26, 66, 99, 99, 99, 99,
+    24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+// Natural-order index of each zigzag position.
+#[rustfmt::skip]
+const ZIGZAG: [usize; 64] = [
+     0,  1,  8, 16,  9,  2,  3, 10,
+    17, 24, 32, 25, 18, 11,  4,  5,
+    12, 19, 26, 33, 40, 48, 41, 34,
+    27, 20, 13,  6,  7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36,
+    29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46,
+    53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+const DC_LUMA_BITS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const DC_CHROMA_BITS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const AC_LUMA_BITS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7d];
+#[rustfmt::skip]
+const AC_LUMA_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xa1, 0x08, 0x23, 0x42, 0xb1, 0xc1, 0x15, 0x52, 0xd1, 0xf0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0a, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2a, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5, 0xa6, 0xa7,
+    0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3, 0xc4, 0xc5,
+    0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda, 0xe1, 0xe2,
+    0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+const AC_CHROMA_BITS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+#[rustfmt::skip]
+const AC_CHROMA_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xa1, 0xb1, 0xc1, 0x09, 0x23, 0x33, 0x52, 0xf0,
+    0x15, 0x62, 0x72, 0xd1, 0x0a, 0x16, 0x24, 0x34, 0xe1, 0x25, 0xf1, 0x17, 0x18, 0x19, 0x1a, 0x26,
+    0x27, 0x28, 0x29, 0x2a, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3a, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4a, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5a, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6a, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7a, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8a, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9a, 0xa2, 0xa3, 0xa4, 0xa5,
+    0xa6, 0xa7, 0xa8, 0xa9, 0xaa, 0xb2, 0xb3, 0xb4, 0xb5, 0xb6, 0xb7, 0xb8, 0xb9, 0xba, 0xc2, 0xc3,
+    0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xd2, 0xd3, 0xd4, 0xd5, 0xd6, 0xd7, 0xd8, 0xd9, 0xda,
+    0xe2, 0xe3, 0xe4, 0xe5, 0xe6, 0xe7, 0xe8, 0xe9, 0xea, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8,
+    0xf9, 0xfa,
+];
+
+// Progressive AC bands (zigzag positions).
+const AC_BANDS: [(u8, u8); 2] = [(1, 5), (6, 63)];
+
+// (code, length) per symbol.
+struct Huffman([(u16, u8); 256]);
+
+impl Huffman {
+    fn new(bits: &[u8; 16], values: &[u8]) -> Self {
+        let mut table = [(0u16, 0u8); 256];
+        let mut code = 0u16;
+        let mut k = 0;
+        for (len, &count) in bits.iter().enumerate() {
+            for _ in 0..count {
+                table[values[k] as usize] = (code, len as u8 + 1);
+                code += 1;
+                k += 1;
+            }
+            code <<= 1;
+        }
+        Self(table)
+    }
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    acc: u32,
+    n: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, bits: u16, len: u8) {
+        if len == 0 {
+            return;
+        }
+        self.acc = (self.acc << len) | (bits as u32 & ((1 << len) - 1));
+        self.n += len as u32;
+        while self.n >= 8 {
+            self.n -= 8;
+            let byte = (self.acc >> self.n) as u8;
+            self.out.push(byte);
+            // Byte stuffing: 0xFF in entropy-coded data is followed by 0x00.
+            if byte == 0xff {
+                self.out.push(0);
+            }
+        }
+    }
+
+    fn code(&mut self, table: &Huffman, symbol: u8) {
+        let (code, len) = table.0[symbol as usize];
+        self.write(code, len);
+    }
+
+    // Pads the last byte with 1 bits.
+    fn flush(&mut self) {
+        if self.n > 0 {
+            let pad = 8 - self.n as u8;
+            self.write((1 << pad) - 1, pad);
+        }
+    }
+
+    fn marker(&mut self, marker: u8) {
+        self.out.extend_from_slice(&[0xff, marker]);
+    }
+
+    fn segment(&mut self, marker: u8, data: &[u8]) {
+        self.marker(marker);
+        self.out.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        self.out.extend_from_slice(data);
+    }
+}
+
+// Magnitude category and the low bits that encode `v`.
+fn category(v: i32) -> (u8, u16) {
+    let size = (32 - v.unsigned_abs().leading_zeros()) as u8;
+    let bits = if v < 0 { v - 1 } else { v };
+    (size, bits as u16)
+}
+
+fn scaled_qtable(base: &[u8; 64], quality: u8) -> [u8; 64] {
+    let quality = u32::from(quality.clamp(1, 100));
+    let scale = if quality < 50 { 5000 / quality } else { 200 - quality * 2 };
+    base.map(|v| ((u32::from(v) * scale + 50) / 100).clamp(1, 255) as u8)
+}
+
+// Separable float DCT-II of a level-shifted block, quantized, in zigzag order.
+fn fdct_quantize(block: &[f32; 64], qtable: &[u8; 64], cos: &[[f32; 8]; 8]) -> [i32; 64] {
+    let mut tmp = [0f32; 64];
+    for y in 0..8 {
+        for u in 0..8 {
+            tmp[y * 8 + u] = (0..8).map(|x| block[y * 8 + x] * cos[u][x]).sum();
+        }
+    }
+    let mut out = [0i32; 64];
+    for (k, &i) in ZIGZAG.iter().enumerate() {
+        let (v, u) = (i / 8, i % 8);
+        let coef: f32 = (0..8).map(|y| tmp[y * 8 + u] * cos[v][y]).sum();
+        // Clamped so DC differences and AC values stay within the standard tables' categories.
+        out[k] = ((coef / qtable[i] as f32).round() as i32).clamp(-1023, 1023);
+    }
+    out
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    table: usize,
+    // Blocks per row/column of the MCU-padded grid, and of the component itself.
+    grid_w: usize,
+    blocks_w: usize,
+    blocks_h: usize,
+    blocks: Vec<[i32; 64]>,
+}
+
+pub fn encode(img: &RgbImage, quality: u8, opts: &Options) -> Result<Vec<u8>, String> {
+    let (width, height) = (img.width() as usize, img.height() as usize);
+    if width == 0 || height == 0 || width > 65535 || height > 65535 {
+        return Err(format!("jpeg: unsupported size {width}x{height}"));
+    }
+    let (hmax, vmax) = opts.subsampling.luma_factors();
+    let (mcu_w, mcu_h) = (8 * hmax, 8 * vmax);
+    let (mcus_x, mcus_y) = (width.div_ceil(mcu_w), height.div_ceil(mcu_h));
+    let (pad_w, pad_h) = (mcus_x * mcu_w, mcus_y * mcu_h);
+
+    // JFIF YCbCr planes, edge-replicated to whole MCUs.
+    let mut planes = [(); 3].map(|_| pool::FLOATS.take(pad_w * pad_h));
+    for y in 0..pad_h {
+        for x in 0..pad_w {
+            let [r, g, b] = img.get_pixel(x.min(width - 1) as u32, y.min(height - 1) as u32).0.map(f32::from);
+            let i = y * pad_w + x;
+            planes[0][i] = 0.299 * r + 0.587 * g + 0.114 * b - 128.0;
+            planes[1][i] = -0.168_736 * r - 0.331_264 * g + 0.5 * b;
+            planes[2][i] = 0.5 * r - 0.418_688 * g - 0.081_312 * b;
+        }
+    }
+
+    let mut cos = [[0f32; 8]; 8];
+    for (u, row) in cos.iter_mut().enumerate() {
+        let c = if u == 0 { std::f32::consts::FRAC_1_SQRT_2 } else { 1.0 };
+        for (x, v) in row.iter_mut().enumerate() {
+            *v = 0.5 * c * (((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI) / 16.0).cos();
+        }
+    }
+    let qtables = [scaled_qtable(&LUMA_QTABLE, quality), scaled_qtable(&CHROMA_QTABLE, quality)];
+
+    let components: Vec<Component> = (0..3)
+        .map(|c| {
+            let (h, v) = if c == 0 { (hmax, vmax) } else { (1, 1) };
+            // Chroma is averaged over hmax x vmax pixels.
+            let (sx, sy) = (hmax / h, vmax / v);
+            let (grid_w, grid_h) = (mcus_x * h, mcus_y * v);
+            let mut blocks = Vec::with_capacity(grid_w * grid_h);
+            for by in 0..grid_h {
+                for bx in 0..grid_w {
+                    let mut block = [0f32; 64];
+                    for (k, px) in block.iter_mut().enumerate() {
+                        let (x0, y0) = ((bx * 8 + k % 8) * sx, (by * 8 + k / 8) * sy);
+                        let mut sum = 0.0;
+                        for dy in 0..sy {
+                            for dx in 0..sx {
+                                sum += planes[c][(y0 + dy) * pad_w + x0 + dx];
+                            }
+                        }
+                        *px = sum / (sx * sy) as f32;
+                    }
+                    blocks.push(fdct_quantize(&block, &qtables[c.min(1)], &cos));
+                }
+            }
+            Component {
+                id: c as u8 + 1,
+                h,
+                v,
+                table: c.min(1),
+                grid_w,
+                blocks_w: (width * h).div_ceil(hmax).div_ceil(8),
+                blocks_h: (height * v).div_ceil(vmax).div_ceil(8),
+                blocks,
+            }
+        })
+        .collect();
+    planes.into_iter().for_each(|p| pool::FLOATS.give(p));
+
+    let mut w = BitWriter {
+        out: pool::BYTES.with_capacity(width * height / 4),
+        acc: 0,
+        n: 0,
+    };
+    w.marker(0xd8); // SOI
+    w.segment(0xe0, &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0]);
+    let mut dqt = Vec::with_capacity(130);
+    for (i, table) in qtables.iter().enumerate() {
+        dqt.push(i as u8);
+        dqt.extend(ZIGZAG.iter().map(|&n| table[n]));
+    }
+    w.segment(0xdb, &dqt);
+
+    let mut sof = vec![8];
+    sof.extend_from_slice(&(height as u16).to_be_bytes());
+    sof.extend_from_slice(&(width as u16).to_be_bytes());
+    sof.push(3);
+    for c in &components {
+        sof.extend_from_slice(&[c.id, ((c.h as u8) << 4) | c.v as u8, c.table as u8]);
+    }
+    w.segment(if opts.progressive { 0xc2 } else { 0xc0 }, &sof);
+
+    let mut dht = Vec::new();
+    for (class_id, bits, values) in [
+        (0x00, &DC_LUMA_BITS, &DC_VALUES[..]),
+        (0x10, &AC_LUMA_BITS, &AC_LUMA_VALUES[..]),
+        (0x01, &DC_CHROMA_BITS, &DC_VALUES[..]),
+        (0x11, &AC_CHROMA_BITS, &AC_CHROMA_VALUES[..]),
+    ] {
+        dht.push(class_id);
+        dht.extend_from_slice(bits);
+        dht.extend_from_slice(values);
+    }
+    w.segment(0xc4, &dht);
+    if opts.restart_interval > 0 {
+        w.segment(0xdd, &opts.restart_interval.to_be_bytes());
+    }
+
+    let tables = [
+        (Huffman::new(&DC_LUMA_BITS, &DC_VALUES), Huffman::new(&AC_LUMA_BITS, &AC_LUMA_VALUES)),
+        (Huffman::new(&DC_CHROMA_BITS, &DC_VALUES), Huffman::new(&AC_CHROMA_BITS, &AC_CHROMA_VALUES)),
+    ];
+    let mcus = (mcus_x, mcus_y);
+    if opts.progressive {
+        scan(&mut w, &components, mcus, &[0, 1, 2], (0, 0), &tables, opts.restart_interval);
+        for c in 0..3 {
+            for band in AC_BANDS {
+                scan(&mut w, &components, mcus, &[c], band, &tables, opts.restart_interval);
+            }
+        }
+    } else {
+        scan(&mut w, &components, mcus, &[0, 1, 2], (0, 63), &tables, opts.restart_interval);
+    }
+    w.marker(0xd9); // EOI
+    Ok(w.out)
+}
+
+// One scan over zigzag positions `ss..=se` of the given components. Several components are
+// interleaved MCU by MCU; a single component is coded block by block over its own extent.
+fn scan(
+    w: &mut BitWriter,
+    components: &[Component],
+    (mcus_x, mcus_y): (usize, usize),
+    which: &[usize],
+    (ss, se): (u8, u8),
+    tables: &[(Huffman, Huffman); 2],
+    restart: u16,
+) {
+    let mut sos = vec![which.len() as u8];
+    for &c in which {
+        let t = components[c].table as u8;
+        sos.extend_from_slice(&[components[c].id, (t << 4) | t]);
+    }
+    sos.extend_from_slice(&[ss, se, 0]);
+    w.segment(0xda, &sos);
+
+    // Block indices making up each MCU of this scan.
+    let units: Vec<Vec<(usize, usize)>> = if which.len() == 1 {
+        let c = &components[which[0]];
+        (0..c.blocks_h)
+            .flat_map(|by| (0..c.blocks_w).map(move |bx| vec![(which[0], by * c.grid_w + bx)]))
+            .collect()
+    } else {
+        let mut units = Vec::with_capacity(mcus_x * mcus_y);
+        for my in 0..mcus_y {
+            for mx in 0..mcus_x {
+                let mut unit = Vec::new();
+                for &c in which {
+                    let comp = &components[c];
+                    for by in 0..comp.v {
+                        for bx in 0..comp.h {
+                            unit.push((c, (my * comp.v + by) * comp.grid_w + mx * comp.h + bx));
+                        }
+                    }
+                }
+                units.push(unit);
+            }
+        }
+        units
+    };
+
+    let mut pred = [0i32; 3];
+    let mut rst = 0u8;
+    for (n, unit) in units.iter().enumerate() {
+        if restart > 0 && n > 0 && n % restart as usize == 0 {
+            w.flush();
+            w.marker(0xd0 + rst);
+            rst = (rst + 1) % 8;
+            pred = [0; 3];
+        }
+        for &(c, i) in unit {
+            let block = &components[c].blocks[i];
+            let (dc, ac) = &tables[components[c].table];
+            if ss == 0 {
+                let (size, bits) = category(block[0] - pred[c]);
+                pred[c] = block[0];
+                w.code(dc, size);
+                w.write(bits, size);
+            }
+            if se == 0 {
+                continue;
+            }
+            let mut run = 0;
+            for &coef in &block[ss.max(1) as usize..=se as usize] {
+                if coef == 0 {
+                    run += 1;
+                    continue;
+                }
+                while run > 15 {
+                    w.code(ac, 0xf0); // ZRL
+                    run -= 16;
+                }
+                let (size, bits) = category(coef);
+                w.code(ac, (run << 4) | size);
+                w.write(bits, size);
+                run = 0;
+            }
+            if run > 0 {
+                w.code(ac, 0x00); // EOB
+            }
+        }
+    }
+    w.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Smooth gradients with a hard edge, so blocks, chroma and partial MCUs all matter.
+    fn picture(w: u32, h: u32) -> RgbImage {
+        RgbImage::from_fn(w, h, |x, y| {
+            let edge = if x * 3 > w { 60 } else { 0 };
+            image::Rgb([(x * 5).min(255) as u8, (y * 7).min(255) as u8, (((x + y) * 4) % 196) as u8 + edge])
+        })
+    }
+
+    fn mean_error(a: &RgbImage, b: &RgbImage) -> f64 {
+        let sum: u64 = a.as_raw().iter().zip(b.as_raw()).map(|(x, y)| x.abs_diff(*y) as u64).sum();
+        sum as f64 / a.as_raw().len() as f64
+    }
+
+    #[test]
+    fn decodes_back_for_every_option_combination() {
+        let sizes = [(1, 1), (7, 5), (16, 16), (17, 33), (33, 8), (101, 37)];
+        for progressive in [false, true] {
+            for subsampling in [Subsampling::S444, Subsampling::S422, Subsampling::S420] {
+                for restart_interval in [0, 1, 3] {
+                    let opts = Options { progressive, subsampling, restart_interval };
+                    for (w, h) in sizes {
+                        let img = picture(w, h);
+                        let jpeg = encode(&img, 90, &opts).unwrap();
+                        let decoded = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg)
+                            .unwrap_or_else(|e| panic!("{opts:?} {w}x{h}: {e}"))
+                            .to_rgb8();
+                        assert_eq!(decoded.dimensions(), (w, h), "{opts:?}");
+                        let err = mean_error(&img, &decoded);
+                        assert!(err < 6.0, "{opts:?} {w}x{h}: mean error {err}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quality_and_subsampling_trade_size() {
+        let img = picture(128, 96);
+        let size = |q, subsampling| encode(&img, q, &Options { subsampling, ..Options::default() }).unwrap().len();
+        assert!(size(30, Subsampling::S444) < size(90, Subsampling::S444));
+        assert!(size(90, Subsampling::S420) < size(90, Subsampling::S444));
+    }
+
+    #[test]
+    fn parses_subsampling() {
+        assert_eq!(Subsampling::parse(" 4:2:0 "), Some(Subsampling::S420));
+        assert_eq!(Subsampling::parse("422"), Some(Subsampling::S422));
+        assert_eq!(Subsampling::parse("411"), None);
+        assert!(Options::default().is_baseline());
+        assert!(!Options::default().with(Some(true), None, None).is_baseline());
+    }
+}
@@ -0,0 +1,91 @@
+// Key combos for Action::Key, in xdotool/X keysym style: "Return", "ctrl+shift+t", "super",
+// "F5", "a". Everything before the last `+` is held while the last key is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyName {
+    Control,
+    Shift,
+    Alt,
+    Meta,
+    Return,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+    Space,
+    F(u8),
+    Char(char),
+}
+
+pub const MAX_KEYS: usize = 5;
+
+// Canonical names accepted by `parse_combo`, besides F1-F20 and any single character.
+// Aliases (enter, esc, cmd, prior, ...) are accepted too but not listed.
+pub const NAMES: &[&str] = &[
+    "ctrl", "shift", "alt", "super", "return", "tab", "escape", "backspace", "delete", "home", "end", "page_up",
+    "page_down", "up", "down", "left", "right", "space", "minus", "plus", "equal", "comma", "period", "slash",
+];
+
+fn parse_key(name: &str) -> Option<KeyName> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return (!c.is_control()).then_some(KeyName::Char(c));
+    }
+    let lower = name.to_ascii_lowercase();
+    let key = match lower.as_str() {
+        "ctrl" | "control" | "control_l" | "control_r" => KeyName::Control,
+        "shift" | "shift_l" | "shift_r" => KeyName::Shift,
+        "alt" | "alt_l" | "alt_r" | "option" => KeyName::Alt,
+        "super" | "super_l" | "super_r" | "meta" | "cmd" | "command" | "win" => KeyName::Meta,
+        "return" | "enter" | "kp_enter" => KeyName::Return,
+        "tab" => KeyName::Tab,
+        "escape" | "esc" => KeyName::Escape,
+        "backspace" => KeyName::Backspace,
+        "delete" | "del" => KeyName::Delete,
+        "home" => KeyName::Home,
+        "end" => KeyName::End,
+        "prior" | "page_up" | "pageup" => KeyName::PageUp,
+        "next" | "page_down" | "pagedown" => KeyName::PageDown,
+        "up" => KeyName::Up,
+        "down" => KeyName::Down,
+        "left" => KeyName::Left,
+        "right" => KeyName::Right,
+        "space" => KeyName::Space,
+        // A few common punctuation keysyms.
+        "minus" => KeyName::Char('-'),
+        "plus" => KeyName::Char('+'),
+        "equal" => KeyName::Char('='),
+        "comma" => KeyName::Char(','),
+        "period" => KeyName::Char('.'),
+        "slash" => KeyName::Char('/'),
+        _ => {
+            let n: u8 = lower.strip_prefix('f')?.parse().ok()?;
+            return (1..=20).contains(&n).then_some(KeyName::F(n));
+        }
+    };
+    Some(key)
+}
+
+pub fn parse_combo(combo: &str) -> Result<Vec<KeyName>, String> {
+    let combo = combo.trim();
+    // "ctrl++" holds ctrl and presses '+'.
+    let (mods, last) = match combo.strip_suffix("++") {
+        Some(rest) => (rest, "+"),
+        None => combo.rsplit_once('+').unwrap_or(("", combo)),
+    };
+    let mut keys = Vec::new();
+    for name in mods.split('+').filter(|s| !s.is_empty()).chain([last]) {
+        keys.push(parse_key(name).ok_or_else(|| format!("unknown key '{name}'"))?);
+    }
+    if keys.len() > MAX_KEYS {
+        return Err(format!("too many keys in combo (max {MAX_KEYS})"));
+    }
+    Ok(keys)
+}
@@ -0,0 +1,302 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::sync::Semaphore;
+use tower_http::compression::{
+    predicate::{DefaultPredicate, NotForContentType, Predicate},
+    CompressionLayer,
+};
+use tracing::{debug, info};
+
+use crate::{
+    auth,
+    secrets::{self, Secret},
+};
+
+// Route classes with their own middleware stack. "eyes" are the read-only routes (frames,
+// OCR, events, listings); "hands" are everything that changes the machine (input, safety,
+// scripts, virtual displays). Each class reads, with CLASS = EYES or HANDS:
+//
+//   SEEALLN_<CLASS>_KEY               bearer key for the class (keychain `eyes_key` /
+//                                     `hands_key`); unset = no key, as before
+//   SEEALLN_<CLASS>_MAX_REQUESTS      requests per window for the whole class; 0/unset = off
+//   SEEALLN_<CLASS>_REQUEST_WINDOW_MS window length, default 1000
+//   SEEALLN_<CLASS>_LOG               off | info | debug (default: eyes debug, hands info)
+//
+// Health, /safety/kill and /admin/* sit outside both classes: the kill switch must never be
+// blocked by a key or a rate limit, and /admin has its own API key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Eyes,
+    Hands,
+}
+
+impl Class {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Class::Eyes => "eyes",
+            Class::Hands => "hands",
+        }
+    }
+
+    fn env(self, suffix: &str) -> Option<String> {
+        let prefix = match self {
+            Class::Eyes => "SEEALLN_EYES_",
+            Class::Hands => "SEEALLN_HANDS_",
+        };
+        std::env::var(format!("{prefix}{suffix}")).ok().map(|s| s.trim().to_string())
+    }
+
+    fn secret(self) -> Secret {
+        match self {
+            Class::Eyes => Secret::EyesKey,
+            Class::Hands => Secret::HandsKey,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Off,
+    Info,
+    Debug,
+}
+
+pub struct RoutePolicy {
+    class: Class,
+    max_requests: u32,
+    window: Duration,
+    log: Verbosity,
+    // (window start, requests in it)
+    window_state: Mutex<(Option<Instant>, u32)>,
+}
+
+impl RoutePolicy {
+    pub fn from_env(class: Class) -> Arc<Self> {
+        let log = match class.env("LOG").as_deref() {
+            Some("off") => Verbosity::Off,
+            Some("info") => Verbosity::Info,
+            Some("debug") => Verbosity::Debug,
+            _ if class == Class::Hands => Verbosity::Info,
+            _ => Verbosity::Debug,
+        };
+        Arc::new(Self {
+            class,
+            max_requests: class.env("MAX_REQUESTS").and_then(|s| s.parse().ok()).unwrap_or(0),
+            window: Duration::from_millis(
+                class.env("REQUEST_WINDOW_MS").and_then(|s| s.parse().ok()).filter(|ms| *ms > 0).unwrap_or(1000),
+            ),
+            log,
+            window_state: Mutex::new((None, 0)),
+        })
+    }
+
+    // Keys come from the keychain/env cache, so a key set at startup is the one enforced.
+    fn key(&self) -> Option<String> {
+        secrets::get(self.class.secret())
+    }
+
+    // Fixed window; on rejection, how long until it resets.
+    fn admit(&self) -> Result<(), Duration> {
+        if self.max_requests == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut state = self.window_state.lock().unwrap_or_else(|e| e.into_inner());
+        let start = match state.0 {
+            Some(t0) if now.duration_since(t0) < self.window => t0,
+            _ => {
+                *state = (Some(now), 0);
+                now
+            }
+        };
+        if state.1 >= self.max_requests {
+            return Err(self.window.saturating_sub(now.duration_since(start)));
+        }
+        state.1 += 1;
+        Ok(())
+    }
+}
+
+fn fail(code: StatusCode, err: &str) -> Response {
+    (code, Json(json!({"ok": false, "error": err}))).into_response()
+}
+
+// Middleware for one class: bearer key, rate limit, then the request, logged at the class's
+// verbosity.
+pub async fn enforce(State(policy): State<Arc<RoutePolicy>>, req: Request, next: Next) -> Response {
+    let (method, path) = (req.method().clone(), req.uri().path().to_string());
+    let class = policy.class.as_str();
+    if let Some(key) = policy.key() {
+        if let Err((code, msg)) = auth::require_bearer(req.headers(), &key) {
+            info!(class, %method, path, "rejected: {msg}");
+            return fail(code, msg);
+        }
+    }
+    if let Err(retry) = policy.admit() {
+        info!(class, %method, path, "rejected: rate limited");
+        let mut resp = fail(StatusCode::TOO_MANY_REQUESTS, "rate limited");
+        resp.headers_mut().insert("retry-after", (retry.as_secs_f64().ceil() as u64).max(1).into());
+        return resp;
+    }
+
+    let started = Instant::now();
+    let resp = next.run(req).await;
+    let (status, ms) = (resp.status().as_u16(), started.elapsed().as_millis() as u64);
+    match policy.log {
+        Verbosity::Off => {}
+        Verbosity::Info => info!(class, %method, path, status, ms, "request"),
+        Verbosity::Debug => debug!(class, %method, path, status, ms, "request"),
+    }
+    resp
+}
+
+// gzip/brotli for whatever the client accepts, on every route. JSON (OCR word lists and
+// layouts, admin exports) shrinks several times over; already-compressed or streamed bodies
+// are left alone: images (default predicate), SSE (default predicate), the MJPEG stream
+// (compressing it would buffer parts and gain nothing) and raw+zstd frames.
+pub fn compression() -> CompressionLayer<impl Predicate> {
+    CompressionLayer::new().compress_when(
+        DefaultPredicate::new()
+            .and(NotForContentType::const_new("multipart/x-mixed-replace"))
+            .and(NotForContentType::const_new("application/zstd")),
+    )
+}
+
+// Request limits, from env:
+//
+//   SEEALLN_MAX_BODY_BYTES           JSON request bodies, default 256 KiB
+//   SEEALLN_MAX_UPLOAD_BYTES         routes that take images (/frame/similar, /scripts/run),
+//                                    default 16 MiB
+//   SEEALLN_REQUEST_TIMEOUT_MS       whole request, default 30 s
+//   SEEALLN_LONG_REQUEST_TIMEOUT_MS  scripts, replays, xdotool and benchmarks, default 10 min
+//   SEEALLN_HEADER_TIMEOUT_MS        time for a client to send its request headers, default 10 s
+//
+// Streams (/stream, /events) have no request timeout; they end when the client leaves.
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub body: usize,
+    pub upload: usize,
+    pub timeout: Duration,
+    pub long_timeout: Duration,
+    pub header_timeout: Duration,
+}
+
+impl Limits {
+    pub fn from_env() -> Self {
+        let num = |var: &str, default: u64| {
+            std::env::var(var)
+                .ok()
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default)
+        };
+        Self {
+            body: num("SEEALLN_MAX_BODY_BYTES", 256 * 1024) as usize,
+            upload: num("SEEALLN_MAX_UPLOAD_BYTES", 16 * 1024 * 1024) as usize,
+            timeout: Duration::from_millis(num("SEEALLN_REQUEST_TIMEOUT_MS", 30_000)),
+            long_timeout: Duration::from_millis(num("SEEALLN_LONG_REQUEST_TIMEOUT_MS", 600_000)),
+            header_timeout: Duration::from_millis(num("SEEALLN_HEADER_TIMEOUT_MS", 10_000)),
+        }
+    }
+}
+
+// Overall request timeout (408). Dropping the handler future cancels it at its next await;
+// work already handed to spawn_blocking still runs to completion.
+pub async fn timeout(State(limit): State<Duration>, req: Request, next: Next) -> Response {
+    match tokio::time::timeout(limit, next.run(req)).await {
+        Ok(resp) => resp,
+        Err(_) => fail(StatusCode::REQUEST_TIMEOUT, "request timed out"),
+    }
+}
+
+// Load shedding: requests beyond a concurrency limit get 503 + Retry-After right away
+// instead of queueing behind the capture/encode pipeline, so a burst of snapshots cannot
+// starve the supervisor's MJPEG stream. Two limits apply, both must have room:
+//
+//   SEEALLN_MAX_CONCURRENT     all shed routes together, default 32
+//   SEEALLN_ROUTE_CONCURRENCY  per route, e.g. "/ocr=1,/snapshot.jpg=8" (0 = no per-route
+//                              limit); capture-heavy routes have defaults (see ROUTE_DEFAULTS)
+//
+// Streams, /health and /safety/kill are never shed.
+const ROUTE_DEFAULTS: &[(&str, usize)] = &[
+    ("/snapshot.jpg", 4),
+    ("/snapshot/next", 8),
+    ("/frame/phash", 4),
+    ("/frame/similar", 2),
+    ("/visual/baseline", 2),
+    ("/visual/compare", 2),
+    ("/ocr", 2),
+    ("/analyze/colors", 4),
+    ("/analyze/theme", 4),
+    ("/detect/elements", 1),
+    ("/detect/caret", 2),
+    ("/tools/invoke", 2),
+];
+const RETRY_AFTER_SECS: u64 = 1;
+
+pub struct Shed {
+    global: Arc<Semaphore>,
+    routes: HashMap<String, Arc<Semaphore>>,
+}
+
+impl Shed {
+    pub fn from_env() -> Arc<Self> {
+        let global = std::env::var("SEEALLN_MAX_CONCURRENT")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|n| *n > 0)
+            .unwrap_or(32);
+        let mut limits: HashMap<String, usize> = ROUTE_DEFAULTS.iter().map(|(p, n)| (p.to_string(), *n)).collect();
+        for item in std::env::var("SEEALLN_ROUTE_CONCURRENCY").unwrap_or_default().split(',') {
+            if let Some((path, n)) = item.trim().split_once('=') {
+                match n.trim().parse() {
+                    Ok(n) => {
+                        limits.insert(path.trim().to_string(), n);
+                    }
+                    Err(_) => tracing::warn!(item, "ignoring malformed SEEALLN_ROUTE_CONCURRENCY entry"),
+                }
+            }
+        }
+        Arc::new(Self {
+            global: Arc::new(Semaphore::new(global)),
+            routes: limits
+                .into_iter()
+                .filter(|(_, n)| *n > 0)
+                .map(|(path, n)| (path, Arc::new(Semaphore::new(n))))
+                .collect(),
+        })
+    }
+}
+
+pub async fn shed(State(shed): State<Arc<Shed>>, req: Request, next: Next) -> Response {
+    let route = req.extensions().get::<MatchedPath>().map(|p| p.as_str().to_string()).unwrap_or_default();
+    let _route_permit = match shed.routes.get(&route) {
+        Some(sem) => match sem.clone().try_acquire_owned() {
+            Ok(permit) => Some(permit),
+            Err(_) => return overloaded(&route),
+        },
+        None => None,
+    };
+    let Ok(_global_permit) = shed.global.clone().try_acquire_owned() else {
+        return overloaded(&route);
+    };
+    next.run(req).await
+}
+
+fn overloaded(route: &str) -> Response {
+    debug!(route, "shed: over concurrency limit");
+    let mut resp = fail(StatusCode::SERVICE_UNAVAILABLE, "overloaded, retry shortly");
+    resp.headers_mut().insert("retry-after", RETRY_AFTER_SECS.into());
+    resp
+}
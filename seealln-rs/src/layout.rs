@@ -0,0 +1,215 @@
+use std::{
+    process::{Command, Stdio},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+// Keyboard layouts for the keycode-based input path (the ydotool backend sends raw Linux key
+// codes, so "a" on an AZERTY machine is the key where US has "q"). The other backends send
+// keysyms or Unicode and follow the active layout by themselves.
+//
+// The layout is SEEALLN_KEYBOARD_LAYOUT (us, fr, de, dvorak) when set, else detected
+// (XKB_DEFAULT_LAYOUT, `setxkbmap -query`, `localectl status`), else us. A detected layout
+// without a table here is reported as unsupported and typed as us.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Us,
+    Fr,
+    De,
+    Dvorak,
+}
+
+// (first key code, unshifted, shifted): the n-th character of a row is produced by key code
+// first + n. '\0' marks a position with no character (never matched: `code` is only asked
+// for printable characters).
+type Row = (u16, &'static str, &'static str);
+
+const US: &[Row] = &[
+    (2, "1234567890-=", "!@#$%^&*()_+"),
+    (16, "qwertyuiop[]", "QWERTYUIOP{}"),
+    (30, "asdfghjkl;'`", "ASDFGHJKL:\"~"),
+    (43, "\\", "|"),
+    (44, "zxcvbnm,./", "ZXCVBNM<>?"),
+];
+
+const FR: &[Row] = &[
+    (2, "&é\"'(-è_çà)=", "1234567890°+"),
+    (16, "azertyuiop", "AZERTYUIOP"),
+    (27, "$", "£"),
+    (30, "qsdfghjklmù²", "QSDFGHJKLM%\0"),
+    (43, "*", "µ"),
+    (44, "wxcvbn,;:!", "WXCVBN?./§"),
+    (86, "<", ">"),
+];
+
+const DE: &[Row] = &[
+    (2, "1234567890ß", "!\"§$%&/()=?"),
+    (16, "qwertzuiopü+", "QWERTZUIOPÜ*"),
+    (30, "asdfghjklöä", "ASDFGHJKLÖÄ"),
+    (41, "\0", "°"),
+    (43, "#", "'"),
+    (44, "yxcvbnm,.-", "YXCVBNM;:_"),
+    (86, "<", ">"),
+];
+
+const DVORAK: &[Row] = &[
+    (2, "1234567890[]", "!@#$%^&*(){}"),
+    (16, "',.pyfgcrl/=", "\"<>PYFGCRL?+"),
+    (30, "aoeuidhtns-`", "AOEUIDHTNS_~"),
+    (43, "\\", "|"),
+    (44, ";qjkxbmwvz", ":QJKXBMWVZ"),
+];
+
+impl Layout {
+    // Variants other than dvorak ("fr(oss)", "de(nodeadkeys)") share their base layout's
+    // plain and shifted characters.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_ascii_lowercase();
+        match name.as_str() {
+            "us" | "en" | "en_us" => Some(Layout::Us),
+            "fr" | "azerty" => Some(Layout::Fr),
+            "de" | "qwertz" => Some(Layout::De),
+            "dvorak" | "us(dvorak)" => Some(Layout::Dvorak),
+            _ => match name.split_once('(') {
+                Some((base, _)) if base != "us" => Self::from_name(base),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Us => "us",
+            Layout::Fr => "fr",
+            Layout::De => "de",
+            Layout::Dvorak => "dvorak",
+        }
+    }
+
+    fn rows(self) -> &'static [Row] {
+        match self {
+            Layout::Us => US,
+            Layout::Fr => FR,
+            Layout::De => DE,
+            Layout::Dvorak => DVORAK,
+        }
+    }
+
+    // (shift needed, key code) producing `c`; None when the layout has no key for it (dead
+    // keys and AltGr characters included).
+    #[cfg_attr(not(feature = "hands"), allow(dead_code))]
+    pub fn code(self, c: char) -> Option<(bool, u16)> {
+        self.rows().iter().find_map(|(base, plain, shifted)| {
+            let at = |row: &str| row.chars().position(|r| r == c).map(|i| base + i as u16);
+            at(plain).map(|code| (false, code)).or_else(|| at(shifted).map(|code| (true, code)))
+        })
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Active {
+    // Layout the key codes are computed for.
+    pub layout: &'static str,
+    // What the OS reports ("fr", "us(dvorak)"), when it could be read.
+    pub detected: Option<String>,
+    // "configured" (SEEALLN_KEYBOARD_LAYOUT), "detected" or "default".
+    pub source: &'static str,
+    // False when the detected layout has no table and us is used instead.
+    pub supported: bool,
+}
+
+// Layout changes are rare; detection spawns processes, so results are reused briefly.
+const DETECT_TTL: Duration = Duration::from_secs(10);
+
+pub fn active() -> Active {
+    let configured = std::env::var("SEEALLN_KEYBOARD_LAYOUT").ok().filter(|s| !s.trim().is_empty());
+    if let Some(name) = configured {
+        match Layout::from_name(&name) {
+            Some(layout) => {
+                return Active {
+                    layout: layout.name(),
+                    detected: detected(),
+                    source: "configured",
+                    supported: true,
+                };
+            }
+            None => tracing::warn!(%name, "unknown SEEALLN_KEYBOARD_LAYOUT (us, fr, de, dvorak)"),
+        }
+    }
+    match detected() {
+        Some(name) => {
+            let layout = Layout::from_name(&name);
+            Active {
+                layout: layout.unwrap_or(Layout::Us).name(),
+                detected: Some(name),
+                source: "detected",
+                supported: layout.is_some(),
+            }
+        }
+        None => Active {
+            layout: Layout::Us.name(),
+            detected: None,
+            source: "default",
+            supported: true,
+        },
+    }
+}
+
+#[cfg_attr(not(feature = "hands"), allow(dead_code))]
+pub fn current() -> Layout {
+    Layout::from_name(active().layout).unwrap_or(Layout::Us)
+}
+
+fn detected() -> Option<String> {
+    static CACHE: Mutex<Option<(Instant, Option<String>)>> = Mutex::new(None);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, name)) = cache.as_ref().filter(|(at, _)| at.elapsed() < DETECT_TTL) {
+        return name.clone();
+    }
+    let name = detect();
+    *cache = Some((Instant::now(), name.clone()));
+    name
+}
+
+// First layout of the active XKB configuration, with its variant as "us(dvorak)".
+fn detect() -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    if let Ok(layout) = std::env::var("XKB_DEFAULT_LAYOUT") {
+        return join(&layout, std::env::var("XKB_DEFAULT_VARIANT").ok().as_deref());
+    }
+    let setxkbmap = || {
+        let out = output("setxkbmap", &["-query"])?;
+        join(field(&out, "layout:")?, field(&out, "variant:"))
+    };
+    let localectl = || {
+        let out = output("localectl", &["status"])?;
+        join(field(&out, "X11 Layout:")?, field(&out, "X11 Variant:"))
+    };
+    // Under Wayland, setxkbmap only sees XWayland's keymap, which is often just "us".
+    if crate::input::session_type() == Some("wayland") {
+        localectl().or_else(setxkbmap)
+    } else {
+        setxkbmap().or_else(localectl)
+    }
+}
+
+fn join(layout: &str, variant: Option<&str>) -> Option<String> {
+    let layout = layout.split(',').next()?.trim();
+    let variant = variant.and_then(|v| v.split(',').next()).map(str::trim).filter(|v| !v.is_empty());
+    match (layout, variant) {
+        ("", _) => None,
+        (l, Some(v)) => Some(format!("{l}({v})")),
+        (l, None) => Some(l.to_string()),
+    }
+}
+
+fn field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    text.lines().find_map(|line| line.trim().strip_prefix(key)).map(str::trim)
+}
+
+fn output(bin: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(bin).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
@@ -0,0 +1,274 @@
+use serde_json::json;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpStream, UdpSocket},
+    sync::Notify,
+};
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+use crate::store::civil_date;
+
+// Forwards server logs and audit events to a remote syslog collector (rsyslog, syslog-ng,
+// Vector's `syslog` source) as RFC 5424 messages whose MSG is one JSON object:
+//
+//   SEEALLN_SYSLOG_URL=tcp://logs.example:601    (octet-counted framing, RFC 6587)
+//   SEEALLN_SYSLOG_URL=udp://logs.example:514
+//
+// MSGID is `audit` (severity notice) or `log` (the tracing level). Messages queue in memory
+// while the collector is unreachable, up to SEEALLN_SYSLOG_BUFFER (default 10000; the oldest
+// are dropped first and the loss is reported once delivery resumes). SEEALLN_SYSLOG_AUDIT=0
+// or SEEALLN_SYSLOG_LOGS=0 leave one stream out. Facility local0; the hostname is
+// SEEALLN_SYSLOG_HOSTNAME, else $HOSTNAME / $COMPUTERNAME.
+struct Forwarder {
+    queue: Mutex<VecDeque<String>>,
+    ready: Notify,
+    capacity: usize,
+    dropped: AtomicU64,
+    audit: bool,
+    logs: bool,
+    hostname: String,
+}
+
+static FORWARDER: OnceLock<Forwarder> = OnceLock::new();
+
+const FACILITY_LOCAL0: u8 = 16;
+const SEVERITY_NOTICE: u8 = 5;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+enum Target {
+    Tcp(String),
+    Udp(String),
+}
+
+fn parse_target(url: &str) -> Result<Target, String> {
+    let url = url.trim();
+    let (scheme, addr) = url.split_once("://").ok_or("expected tcp://host:port or udp://host:port")?;
+    let with_port = |default: u16| {
+        if addr.rsplit_once(':').is_some_and(|(_, p)| p.parse::<u16>().is_ok()) {
+            addr.to_string()
+        } else {
+            format!("{addr}:{default}")
+        }
+    };
+    match scheme {
+        "tcp" => Ok(Target::Tcp(with_port(601))),
+        "udp" => Ok(Target::Udp(with_port(514))),
+        _ => Err(format!("unsupported scheme {scheme} (tcp, udp)")),
+    }
+}
+
+fn flag(name: &str) -> bool {
+    std::env::var(name).ok().as_deref() != Some("0")
+}
+
+// Sets up forwarding from the environment and starts the sender. Call before tracing is
+// initialised (so `layer` sees it) and inside the runtime.
+pub fn init() {
+    let Some(url) = std::env::var("SEEALLN_SYSLOG_URL").ok().filter(|u| !u.trim().is_empty()) else {
+        return;
+    };
+    let target = match parse_target(&url) {
+        Ok(target) => target,
+        Err(err) => {
+            eprintln!("SEEALLN_SYSLOG_URL ignored: {err}");
+            return;
+        }
+    };
+    let hostname = ["SEEALLN_SYSLOG_HOSTNAME", "HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|k| std::env::var(k).ok().filter(|h| !h.trim().is_empty()))
+        .map(|h| h.trim().replace(' ', "-"))
+        .unwrap_or_else(|| "-".to_string());
+    let forwarder = Forwarder {
+        queue: Mutex::new(VecDeque::new()),
+        ready: Notify::new(),
+        capacity: std::env::var("SEEALLN_SYSLOG_BUFFER")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(10_000usize)
+            .max(1),
+        dropped: AtomicU64::new(0),
+        audit: flag("SEEALLN_SYSLOG_AUDIT"),
+        logs: flag("SEEALLN_SYSLOG_LOGS"),
+        hostname,
+    };
+    if FORWARDER.set(forwarder).is_ok() {
+        tokio::spawn(run(target));
+    }
+}
+
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = now.as_secs();
+    let (h, m, s) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+    format!("{}T{h:02}:{m:02}:{s:02}.{:03}Z", civil_date(secs), now.subsec_millis())
+}
+
+impl Forwarder {
+    fn push(&self, severity: u8, msgid: &str, msg: &str) {
+        let pri = FACILITY_LOCAL0 * 8 + severity;
+        let line = format!(
+            "<{pri}>1 {} {} seealln {} {msgid} - {msg}",
+            timestamp(),
+            self.hostname,
+            std::process::id()
+        );
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.len() == self.capacity {
+            queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        queue.push_back(line);
+        drop(queue);
+        self.ready.notify_one();
+    }
+
+    fn front(&self) -> Option<String> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner()).front().cloned()
+    }
+
+    fn pop(&self) {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner()).pop_front();
+    }
+}
+
+// Store::audit calls this for every audit entry.
+pub fn audit(event: &serde_json::Value) {
+    if let Some(f) = FORWARDER.get().filter(|f| f.audit) {
+        f.push(SEVERITY_NOTICE, "audit", &event.to_string());
+    }
+}
+
+enum Conn {
+    Tcp(TcpStream),
+    Udp(UdpSocket),
+}
+
+async fn connect(target: &Target) -> std::io::Result<Conn> {
+    match target {
+        Target::Tcp(addr) => Ok(Conn::Tcp(TcpStream::connect(addr).await?)),
+        Target::Udp(addr) => {
+            let socket = UdpSocket::bind(if addr.starts_with('[') { "[::]:0" } else { "0.0.0.0:0" }).await?;
+            socket.connect(addr).await?;
+            Ok(Conn::Udp(socket))
+        }
+    }
+}
+
+async fn send(conn: &mut Conn, line: &str) -> std::io::Result<()> {
+    match conn {
+        Conn::Tcp(stream) => stream.write_all(format!("{} {line}", line.len()).as_bytes()).await,
+        Conn::Udp(socket) => socket.send(line.as_bytes()).await.map(|_| ()),
+    }
+}
+
+// Delivers queued lines in order; a line leaves the queue only once written. Failures close
+// the connection and retry with backoff. The sender's own warnings are not forwarded.
+async fn run(target: Target) {
+    let f = FORWARDER.get().expect("set before spawning");
+    let mut conn: Option<Conn> = None;
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let Some(line) = f.front() else {
+            f.ready.notified().await;
+            continue;
+        };
+        if conn.is_none() {
+            match connect(&target).await {
+                Ok(c) => conn = Some(c),
+                Err(err) => {
+                    tracing::warn!(%err, target = ?target, "syslog collector unreachable; buffering");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            }
+        }
+        let Some(c) = conn.as_mut() else { continue };
+        match send(c, &line).await {
+            Ok(()) => {
+                f.pop();
+                backoff = Duration::from_secs(1);
+                let dropped = f.dropped.swap(0, Ordering::Relaxed);
+                if dropped > 0 {
+                    let msg = json!({"message": "syslog buffer overflowed while the collector was unreachable", "dropped": dropped});
+                    f.push(4, "log", &msg.to_string());
+                }
+            }
+            Err(err) => {
+                tracing::warn!(%err, "syslog send failed; reconnecting");
+                conn = None;
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+// Tracing layer that queues every log event (after the global filter) as a JSON message:
+// {"level", "target", "message", ...fields}.
+pub struct SyslogLayer;
+
+pub fn layer() -> Option<SyslogLayer> {
+    FORWARDER.get().filter(|f| f.logs).map(|_| SyslogLayer)
+}
+
+struct Fields(serde_json::Map<String, serde_json::Value>);
+
+impl tracing::field::Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+}
+
+impl<S: Subscriber> Layer<S> for SyslogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let meta = event.metadata();
+        // Our own delivery warnings would feed back into the queue they are about.
+        if meta.target() == module_path!() {
+            return;
+        }
+        let Some(f) = FORWARDER.get() else { return };
+        let mut fields = Fields(serde_json::Map::new());
+        fields.0.insert("level".into(), json!(meta.level().as_str()));
+        fields.0.insert("target".into(), json!(meta.target()));
+        event.record(&mut fields);
+        let severity = match *meta.level() {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            Level::DEBUG | Level::TRACE => 7,
+        };
+        f.push(severity, "log", &serde_json::Value::Object(fields.0).to_string());
+    }
+}
@@ -1,10 +1,11 @@
 use axum::{
     body::Body,
-    extract::Query,
-    http::{header, HeaderName, HeaderValue, StatusCode},
+    extract::{ConnectInfo, DefaultBodyLimit, FromRef, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware,
     response::{IntoResponse, Response},
-    routing::{get, post},
-    Json, Router,
+    routing::{delete, get, patch, post},
+    Extension, Json, Router,
 };
 use bytes::Bytes;
 use futures::stream;
@@ -13,186 +14,588 @@ use serde_json::json;
 use std::{
     convert::Infallible,
     net::SocketAddr,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tracing::{error, info};
 
+use capture::{capture_frame, clamp, encode_jpeg_with, scale_image, Fallback};
+
+mod anomaly;
+mod arming;
+mod attest;
+mod auth;
+mod autotune;
+mod bandwidth;
+mod bench;
+mod capture;
+mod caret;
+#[cfg(feature = "cdp")]
+mod cdp;
+mod cluster;
+mod compat;
+mod confirm;
+mod crypto;
+mod desktop;
+mod detect;
+mod doctor;
+mod encoding;
+mod events;
+mod focus;
+mod format;
+mod frame;
+mod gpu;
 mod hands;
+mod input;
+mod interceptor;
+mod jpeg;
+mod keys;
+mod layers;
+mod layout;
+mod logfwd;
+mod mdns;
+mod ocr;
+mod origin;
+mod overrides;
+mod permissions;
+mod pip;
+mod policy;
+mod pool;
+mod privacy;
+mod ratelimit;
+mod redact;
+#[cfg(feature = "relay")]
+mod relay;
+#[cfg(feature = "s3")]
+mod s3;
+mod scopes;
+mod script;
+mod secrets;
+mod selftest;
+mod service;
+mod session;
+mod sim;
+mod signing;
+mod stats;
+mod store;
+mod systemd;
+mod theme;
+mod tools;
+mod trail;
+mod tunnel;
+#[cfg(all(windows, feature = "touch"))]
+mod touch;
+mod vdisplay;
+#[cfg(feature = "wayland")]
+mod wayland;
+#[cfg(feature = "webhook")]
+mod webhook;
+mod zstd;
+mod vision;
+mod visual;
+mod watch;
+
+#[derive(Clone, FromRef)]
+struct AppState {
+    hands: hands::HandsState,
+    store: store::Store,
+    events: events::Events,
+}
 
 #[derive(Debug, Deserialize)]
 struct StreamParams {
     fps: Option<f32>,
     q: Option<u8>,
+    display: Option<usize>,
+    fallback: Option<Fallback>,
+    target_ms: Option<u64>,
+    progressive: Option<bool>,
+    subsampling: Option<jpeg::Subsampling>,
+    restart: Option<u16>,
+    // Picture-in-picture: `scope` or x,y,w,h inset over a shrunk desktop (see pip.rs).
+    pip: Option<String>,
+    pip_scale: Option<f32>,
+    // Seconds of injected pointer path and clicks to draw into frames (see trail.rs).
+    trail: Option<f32>,
+    // Session id of a supervised arming (`/hands/arm?supervised=1`) this stream watches.
+    supervise: Option<String>,
 }
 
-fn clamp<T: PartialOrd>(v: T, lo: T, hi: T) -> T {
-    if v < lo {
-        lo
-    } else if v > hi {
-        hi
-    } else {
-        v
-    }
+#[derive(Debug, Deserialize)]
+struct SnapshotParams {
+    display: Option<usize>,
+    format: Option<format::Format>,
+    fallback: Option<Fallback>,
+    progressive: Option<bool>,
+    subsampling: Option<jpeg::Subsampling>,
+    restart: Option<u16>,
+}
+
+fn unknown_display(display: Option<usize>) -> Option<Response> {
+    let i = display?;
+    (i >= capture::displays().len())
+        .then(|| (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": format!("unknown display {i}")}))).into_response())
+}
+
+async fn displays() -> impl IntoResponse {
+    Json(json!({"ok": true, "displays": capture::displays(), "monitors": desktop::monitors()}))
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeParams {
+    client_ts: Option<f64>,
 }
 
-fn capture_jpeg(quality: u8) -> Result<Vec<u8>, String> {
-    // Real screen capture when enabled; otherwise placeholder.
-    // We intentionally keep endpoints stable even when capture is disabled/unavailable.
+// Clock reference for clients: wall clock (unix ms, same base as X-Frame-Timestamp) and a
+// monotonic clock (ms since server start), echoing ?client_ts= so an NTP-style exchange
+// gives offset = wall_ms - (client_ts + rtt / 2).
+async fn time(Query(params): Query<TimeParams>) -> impl IntoResponse {
+    let received = Instant::now();
+    let wall = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    Json(json!({
+        "ok": true,
+        "wall_ms": wall.as_secs_f64() * 1000.0,
+        "mono_ms": capture::mono_ms(received),
+        "client_ts": params.client_ts,
+        "server_processing_ms": received.elapsed().as_secs_f64() * 1000.0,
+    }))
+}
 
-    let quality = clamp(quality, 30, 90);
+// Build identity, and the public key frames and recordings are signed with (see attest.rs).
+async fn version() -> impl IntoResponse {
+    let signing = attest::public_key().map(|key| json!({"alg": "ed25519", "public_key": key, "header": attest::HEADER}));
+    Json(json!({
+        "ok": true,
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "frame_signing": signing,
+    }))
+}
 
+async fn health(State(hands_state): State<hands::HandsState>) -> impl IntoResponse {
     #[cfg(feature = "capture")]
-    {
-        match capture_jpeg_real(quality) {
-            Ok(buf) => return Ok(buf),
-            Err(err) => {
-                error!(%err, "capture failed; serving placeholder");
-            }
-        }
-    }
+    let capture = if scrap::Display::primary().is_ok() {
+        "ok"
+    } else {
+        "unavailable"
+    };
 
-    // Fallback placeholder (keeps endpoints stable)
-    let width = 640;
-    let height = 360;
-    let mut imgbuf = image::RgbImage::new(width, height);
-    for (i, p) in imgbuf.pixels_mut().enumerate() {
-        let x = (i as u32) % width;
-        let y = (i as u32) / width;
-        let v = (((x ^ y) & 0x3F) as u8).saturating_add(16);
-        *p = image::Rgb([v, v, v.saturating_add(8)]);
-    }
+    #[cfg(not(feature = "capture"))]
+    let capture = "disabled";
+    let capture = if sim::active() { "simulated" } else { capture };
+
+    // Probed, not just compiled in: a Wayland session without a working backend reports
+    // "unavailable" with the reason.
+    let input = hands_state.input().clone();
+    let hands_status = tokio::task::spawn_blocking(move || input.status()).await.unwrap_or_else(|e| Err(e.to_string()));
+    let hands = match &hands_status {
+        _ if !cfg!(feature = "hands") && !sim::active() => "disabled",
+        Ok(()) => "available",
+        Err(_) => "unavailable",
+    };
+    // Code and remediation hint, when the probe failed for a known reason.
+    let hands_failure = hands_status.as_ref().err().and_then(|e| input::Failure::classify(e)).map(input::Failure::json);
+    let cdp = if cfg!(feature = "cdp") { "available" } else { "disabled" };
+    let detect = if !cfg!(feature = "detect") {
+        "disabled"
+    } else if detect::available() {
+        "configured"
+    } else {
+        "unconfigured"
+    };
+
+    #[cfg(feature = "s3")]
+    let s3 = if s3::S3Config::from_env().is_some() { "configured" } else { "unconfigured" };
 
-    let mut out = Vec::new();
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
-    encoder
-        .encode_image(&image::DynamicImage::ImageRgb8(imgbuf))
-        .map_err(|e| e.to_string())?;
+    #[cfg(not(feature = "s3"))]
+    let s3 = "disabled";
 
-    Ok(out)
+    Json(json!({"ok": true, "bind": "127.0.0.1", "capture": capture, "hands": hands, "hands_backend": hands_state.input().name(), "hands_error": hands_status.err(), "hands_failure": hands_failure, "session": input::session_type(), "permissions": permissions::json(), "s3": s3, "cdp": cdp, "detect": detect, "capture_health": capture::health_json(), "hands_policy": {"arming": "required", "confirm": confirm::policy(), "rate_limit": {"kind": "token_bucket", "limit": hands_state.limit(), "weights": hands_state.weights()} } }))
 }
 
-#[cfg(feature = "capture")]
-fn capture_jpeg_real(quality: u8) -> Result<Vec<u8>, String> {
-    use std::{io::ErrorKind, thread, time::Duration};
+// Crops a frame to `region` (the hands scope when it confines capture) and returns the
+// screen rect actually shown, so clients can map image pixels back to screen coordinates.
+fn confine_frame(frame: capture::Frame, region: Option<hands::ScopeRect>) -> Result<(capture::Frame, Option<hands::ScopeRect>), String> {
+    let Some(region) = region else {
+        return Ok((frame, None));
+    };
+    let (image, x, y) = vision::crop(&frame.image, Some(region));
+    if image.width() == 0 || image.height() == 0 {
+        return Err("scope is outside the screen".to_string());
+    }
+    let shown = hands::ScopeRect { x, y, w: image.width() as i32, h: image.height() as i32 };
+    Ok((capture::Frame { image, source: frame.source }, Some(shown)))
+}
 
-    let display = scrap::Display::primary().map_err(|e| format!("display: {e}"))?;
-    let mut capturer = scrap::Capturer::new(display).map_err(|e| format!("capturer: {e}"))?;
+// 429 for a client over its bandwidth cap (see bandwidth.rs).
+fn over_cap(err: String, wait: Option<Duration>) -> Response {
+    let mut resp = (StatusCode::TOO_MANY_REQUESTS, Json(json!({"ok": false, "error": err}))).into_response();
+    if let Some(wait) = wait {
+        resp.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(wait.as_secs().max(1)));
+    }
+    resp
+}
 
-    let (w, h) = (capturer.width(), capturer.height());
+fn region_header(r: &hands::ScopeRect) -> HeaderValue {
+    HeaderValue::from_str(&format!("{},{},{},{}", r.x, r.y, r.w, r.h)).expect("digits are a valid header value")
+}
 
-    // scrap returns BGRA. We must copy the frame bytes because `frame()` borrows from `capturer`.
-    let mut frame_copy: Option<Vec<u8>> = None;
-    for _ in 0..50 {
-        match capturer.frame() {
-            Ok(buf) => {
-                frame_copy = Some(buf.to_vec());
-                break;
+async fn snapshot(
+    State(store): State<store::Store>,
+    State(hands): State<hands::HandsState>,
+    headers: HeaderMap,
+    origin: origin::Origin,
+    Query(params): Query<SnapshotParams>,
+) -> Response {
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let client = bandwidth::client_key(&origin);
+    if let Err((err, wait)) = bandwidth::admit(&client) {
+        return over_cap(err, wait);
+    }
+    let format = match format::negotiate(params.format, &headers) {
+        Ok(format) => format,
+        Err((code, err)) => return (code, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    // Real capture preferred; what happens when it fails depends on the fallback policy
+    // (x-seealln-capture tells which one was served, x-seealln-frame-age-ms how stale a
+    // repeated frame is). `fail` answers 503.
+    let fallback = params.fallback.unwrap_or_else(Fallback::global);
+    let (display, visible) = (params.display, hands.visible_region());
+    let grabbed = tokio::task::spawn_blocking(move || capture_frame(display, fallback).and_then(|f| confine_frame(f, visible)))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    let (frame, shown) = match grabbed {
+        Ok(confined) => confined,
+        Err(err) => {
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(json!({"ok": false, "error": err}))).into_response();
+        }
+    };
+    let captured = Instant::now();
+    let opts = jpeg::Options::global().with(params.progressive, params.subsampling, params.restart);
+    // AVIF encodes take long enough to stall the runtime.
+    let scale = capture::serve_scale(frame.image.dimensions());
+    let started = Instant::now();
+    let encoded = tokio::task::spawn_blocking(move || {
+        let image = scale_image(frame.image, scale);
+        let dims = image.dimensions();
+        let encoded = format::encode(&image, format, 75, &opts);
+        pool::BYTES.give(image.into_raw());
+        encoded.map(|buf| (buf, frame.source, dims))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match encoded {
+        Ok((buf, source, (width, height))) => {
+            stats::frame("snapshot", started.elapsed());
+            bandwidth::charge(&client, "snapshot", buf.len());
+            if source == capture::Source::Live {
+                store.record_frame(&buf, format.ext(), captured);
             }
-            Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                thread::sleep(Duration::from_millis(10));
-                continue;
+            let mut resp = image_response(buf, format, source, (width, height));
+            if let Some(r) = shown {
+                resp.headers_mut().insert(HeaderName::from_static("x-seealln-region"), region_header(&r));
             }
-            Err(e) => return Err(format!("frame: {e}")),
+            insert_scale(&mut resp, scale);
+            resp
+        }
+        Err(err) => {
+            error!(%err, "snapshot failed");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         }
     }
-    let frame = frame_copy.ok_or_else(|| "frame: timeout".to_string())?;
+}
 
-    // Convert BGRA -> RGB
-    let mut rgb = vec![0u8; w * h * 3];
-    for i in 0..(w * h) {
-        let b = frame[i * 4];
-        let g = frame[i * 4 + 1];
-        let r = frame[i * 4 + 2];
-        rgb[i * 3] = r;
-        rgb[i * 3 + 1] = g;
-        rgb[i * 3 + 2] = b;
+// x-seealln-scale when a served frame was shrunk to SEEALLN_CAPTURE_MAX_WIDTH/_HEIGHT; screen
+// coordinates are frame pixels divided by it.
+fn insert_scale(resp: &mut Response, scale: f32) {
+    if scale < 1.0 {
+        let value = HeaderValue::from_str(&format!("{scale:.3}")).expect("digits are a valid header value");
+        resp.headers_mut().insert(HeaderName::from_static("x-seealln-scale"), value);
     }
+}
 
-    let img = image::RgbImage::from_raw(w as u32, h as u32, rgb)
-        .ok_or_else(|| "rgb buffer: invalid".to_string())?;
+// An encoded frame with its content type and x-seealln-* metadata headers.
+fn image_response(buf: Vec<u8>, format: format::Format, source: capture::Source, (width, height): (u32, u32)) -> Response {
+    let signature = attest::header_value(&buf);
+    let mut resp = Response::new(Body::from(buf));
+    if let Some(sig) = signature.and_then(|s| HeaderValue::from_str(&s).ok()) {
+        resp.headers_mut().insert(HeaderName::from_static(attest::HEADER), sig);
+    }
+    resp.headers_mut()
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(format.mime()));
+    resp.headers_mut().insert(header::VARY, HeaderValue::from_static("accept"));
+    if format == format::Format::RawZstd {
+        let h = resp.headers_mut();
+        h.insert(HeaderName::from_static("x-seealln-width"), HeaderValue::from(width));
+        h.insert(HeaderName::from_static("x-seealln-height"), HeaderValue::from(height));
+        h.insert(HeaderName::from_static("x-seealln-pixel-format"), HeaderValue::from_static("bgra8"));
+    }
+    resp.headers_mut().insert(
+        HeaderName::from_static("x-seealln-capture"),
+        HeaderValue::from_static(source.as_str()),
+    );
+    if let Some(age) = source.age_ms() {
+        resp.headers_mut()
+            .insert(HeaderName::from_static("x-seealln-frame-age-ms"), HeaderValue::from(age));
+    }
+    resp
+}
 
-    let mut out = Vec::new();
-    let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, quality);
-    encoder
-        .encode_image(&image::DynamicImage::ImageRgb8(img))
-        .map_err(|e| e.to_string())?;
+#[derive(Debug, Deserialize)]
+struct NextParams {
+    display: Option<usize>,
+    format: Option<format::Format>,
+    // 16 hex digits from /frame/phash or an earlier x-seealln-phash header; without it the
+    // current frame is returned at once.
+    hash: Option<String>,
+    timeout_ms: Option<u64>,
+    // Hamming distance (bits) that counts as "new"; default 0, any change.
+    threshold: Option<u32>,
+}
 
-    Ok(out)
+fn phash_header(hash: u64) -> HeaderValue {
+    HeaderValue::from_str(&format!("{hash:016x}")).expect("hex is a valid header value")
 }
 
-async fn health() -> impl IntoResponse {
-    #[cfg(feature = "capture")]
-    let capture = if scrap::Display::primary().is_ok() {
-        "ok"
-    } else {
-        "unavailable"
+const NEXT_MAX_TIMEOUT: Duration = Duration::from_secs(25);
+
+// Long-poll for the next distinct frame: captures every SEEALLN_NEXT_POLL_MS (default 100)
+// until the live frame's perceptual hash is more than `threshold` bits from `hash`, then
+// returns it like /snapshot.jpg plus `x-seealln-phash` for the following request. 204 (same
+// phash header) when nothing changed within `timeout_ms` (default 5000, max 25000).
+// Fallback frames never count as new.
+async fn snapshot_next(
+    State(store): State<store::Store>,
+    State(hands): State<hands::HandsState>,
+    headers: HeaderMap,
+    origin: origin::Origin,
+    Query(params): Query<NextParams>,
+) -> Response {
+    if let Some(resp) = unknown_display(params.display) {
+        return resp;
+    }
+    let client = bandwidth::client_key(&origin);
+    if let Err((err, wait)) = bandwidth::admit(&client) {
+        return over_cap(err, wait);
+    }
+    let format = match format::negotiate(params.format, &headers) {
+        Ok(format) => format,
+        Err((code, err)) => return (code, Json(json!({"ok": false, "error": err}))).into_response(),
     };
-
-    #[cfg(not(feature = "capture"))]
-    let capture = "disabled";
-
-    let hands = if cfg!(feature = "hands") { "available" } else { "disabled" };
-    Json(json!({"ok": true, "bind": "127.0.0.1", "capture": capture, "hands": hands, "hands_policy": {"arming": "required", "confirm_header": "x-seealln-confirm: yes", "rate_limit": {"max_actions": std::env::var("SEEALLN_HANDS_MAX_ACTIONS").ok(), "window_ms": std::env::var("SEEALLN_HANDS_WINDOW_MS").ok()} } }))
-}
-
-async fn snapshot() -> Response {
-    // We always try to return a JPEG (real capture preferred; placeholder as fallback).
-    // Any hard failure returns 500.
-    match capture_jpeg(75) {
-        Ok(buf) => {
-            let mut resp = Response::new(Body::from(buf));
-            resp.headers_mut()
-                .insert(header::CONTENT_TYPE, HeaderValue::from_static("image/jpeg"));
-            // A hint for clients; real/placeholder is inferred from ability to open a Display.
-            #[cfg(feature = "capture")]
-            let mode = if scrap::Display::primary().is_ok() {
-                "real_or_placeholder"
-            } else {
-                "placeholder"
+    let known = match params.hash.as_deref().map(str::trim) {
+        None => None,
+        Some(h) => match u64::from_str_radix(h, 16) {
+            Ok(v) if h.len() == 16 => Some(v),
+            _ => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "hash must be 16 hex digits"})))
+                    .into_response()
+            }
+        },
+    };
+    let threshold = params.threshold.unwrap_or(0).min(64);
+    let timeout = Duration::from_millis(params.timeout_ms.unwrap_or(5000)).min(NEXT_MAX_TIMEOUT);
+    let poll = Duration::from_millis(
+        std::env::var("SEEALLN_NEXT_POLL_MS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(100u64)
+            .clamp(20, 2000),
+    );
+    let deadline = Instant::now() + timeout;
+    let display = params.display;
+
+    loop {
+        let visible = hands.visible_region();
+        let grabbed = tokio::task::spawn_blocking(move || {
+            let (frame, shown) = capture::capture_frame(display, Fallback::global()).and_then(|f| confine_frame(f, visible))?;
+            let captured = Instant::now();
+            Ok((vision::phash(&frame.image), (frame, shown, captured)))
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+        let fresh = grabbed.ok().filter(|(hash, (frame, _, _))| {
+            frame.source == capture::Source::Live && known.is_none_or(|k| (k ^ hash).count_ones() > threshold)
+        });
+        if let Some((hash, (frame, shown, captured))) = fresh {
+            let opts = jpeg::Options::global();
+            let scale = capture::serve_scale(frame.image.dimensions());
+            let started = Instant::now();
+            let encoded = tokio::task::spawn_blocking(move || {
+                let image = scale_image(frame.image, scale);
+                let dims = image.dimensions();
+                let encoded = format::encode(&image, format, 75, &opts);
+                pool::BYTES.give(image.into_raw());
+                encoded.map(|buf| (buf, dims))
+            })
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+            return match encoded {
+                Ok((buf, dims)) => {
+                    stats::frame("snapshot_next", started.elapsed());
+                    bandwidth::charge(&client, "snapshot_next", buf.len());
+                    store.record_frame(&buf, format.ext(), captured);
+                    let mut resp = image_response(buf, format, frame.source, dims);
+                    resp.headers_mut().insert(HeaderName::from_static("x-seealln-phash"), phash_header(hash));
+                    if let Some(r) = shown {
+                        resp.headers_mut().insert(HeaderName::from_static("x-seealln-region"), region_header(&r));
+                    }
+                    insert_scale(&mut resp, scale);
+                    resp
+                }
+                Err(err) => {
+                    error!(%err, "snapshot/next encode failed");
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                }
             };
-
-            #[cfg(not(feature = "capture"))]
-            let mode = "placeholder";
-            resp.headers_mut().insert(
-                HeaderName::from_static("x-seealln-capture"),
-                HeaderValue::from_static(mode),
-            );
-            resp
         }
-        Err(err) => {
-            error!(%err, "snapshot failed");
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        if Instant::now() + poll > deadline {
+            let mut resp = StatusCode::NO_CONTENT.into_response();
+            if let Some(k) = known {
+                resp.headers_mut().insert(HeaderName::from_static("x-seealln-phash"), phash_header(k));
+            }
+            return resp;
         }
+        tokio::time::sleep(poll).await;
     }
 }
 
-async fn stream_mjpeg(Query(params): Query<StreamParams>) -> Response {
-    let fps = clamp(params.fps.unwrap_or(10.0), 1.0, 15.0);
-    let q = clamp(params.q.unwrap_or(60), 30, 85);
+async fn stream_mjpeg(State(hands): State<hands::HandsState>, origin: origin::Origin, Query(params): Query<StreamParams>) -> Response {
+    let limits = capture::limits();
+    let fps = clamp(params.fps.unwrap_or(10.0), limits.fps.0, limits.fps.1);
+    let q = clamp(params.q.unwrap_or(60), limits.quality.0, limits.stream_quality_max);
+    let display = params.display;
+    let fallback = params.fallback.unwrap_or_else(Fallback::global);
+    if let Some(resp) = unknown_display(display) {
+        return resp;
+    }
+    let client = bandwidth::client_key(&origin);
+    if let Err((err, wait)) = bandwidth::admit(&client) {
+        return over_cap(err, wait);
+    }
+    let pip = match params.pip.as_deref().map(|s| pip::parse(s, params.pip_scale)).transpose() {
+        Ok(pip) => pip,
+        Err(err) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    let trail_window = params
+        .trail
+        .filter(|s| *s > 0.0)
+        .map(|s| Duration::from_secs_f32(s.min(trail::MAX_AGE.as_secs_f32())));
+
+    // With ?target_ms=, quality (capped at q) and scale adapt to keep each frame under the
+    // target; parts then report X-Seealln-Quality and X-Seealln-Scale (also reported whenever
+    // SEEALLN_CAPTURE_MAX_WIDTH/_HEIGHT shrink the frames).
+    let tune = params.target_ms.map(|ms| autotune::AutoTune::new(ms, q));
+    let opts = jpeg::Options::global().with(params.progressive, params.subsampling, params.restart);
 
     let boundary = "frame";
 
-    let body_stream = stream::unfold(Instant::now(), move |mut last| async move {
-        let frame_interval = Duration::from_secs_f32(1.0 / fps);
+    // Every tick takes the next X-Frame-Seq, so a gap means a skipped frame. X-Frame-Timestamp
+    // is the capture time (unix ms); for a repeated frame, when it was originally captured.
+    // The guard rides along in the stream state, so the stream counts as open until dropped;
+    // while it is paused nothing is captured. Under a bandwidth cap the next frame also waits
+    // out `pause`; past the daily quota the stream ends.
+    if let Some(session) = &params.supervise {
+        if let Err(msg) = hands.supervisable(session, &origin) {
+            return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": msg}))).into_response();
+        }
+    }
+    let open = stats::StreamGuard::open();
+    if let Some(session) = params.supervise {
+        open.supervise(session, origin.clone());
+    }
+    let stream_id = open.id();
+    let state = (Instant::now(), 0u64, tune, hands, open, client, Duration::ZERO);
+    let body_stream = stream::unfold(state, move |(mut last, seq, mut tune, hands, mut open, client, pause)| async move {
+        open.resumed().await;
+        let frame_interval = Duration::from_secs_f32(1.0 / fps).max(pause);
         let now = Instant::now();
         if now.duration_since(last) < frame_interval {
             tokio::time::sleep(frame_interval - now.duration_since(last)).await;
         }
+        if let Err((_, None)) = bandwidth::admit(&client) {
+            return None;
+        }
         last = Instant::now();
-
-        let jpeg = match capture_jpeg(q) {
-            Ok(b) => b,
-            Err(_) => Vec::new(),
+        let captured_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+
+        // Under `fail`, frames that cannot be captured are skipped rather than faked.
+        let (quality, tuned) = tune.as_ref().map_or((q, 1.0), |t| (t.quality, t.scale));
+        // The scope is re-read every tick: confinement applies to streams already open. Capture
+        // and encoding run on the blocking pool so a slow (4K) frame never holds up a runtime
+        // worker and the requests queued behind it.
+        let view = hands.clone();
+        let grabbed = tokio::task::spawn_blocking(move || {
+            let (mut f, shown) = capture_frame(display, fallback).and_then(|f| confine_frame(f, view.visible_region())).ok()?;
+            let started = Instant::now();
+            let origin = shown.map_or((0, 0), |r| (r.x, r.y));
+            if let Some(window) = trail_window {
+                trail::draw(&mut f.image, &view.trail(), origin, window);
+            }
+            let (image, placed) = match pip {
+                Some(spec) => {
+                    let region = match spec.inset {
+                        pip::Inset::Scope => view.get_scope(),
+                        pip::Inset::Fixed(r) => Some(r),
+                    };
+                    let region = region.and_then(|r| view.confine(Some(r)).ok().flatten());
+                    pip::compose(&f.image, origin, region, spec.backdrop)
+                }
+                None => (f.image, None),
+            };
+            let scale = tuned * capture::serve_scale(image.dimensions());
+            let image = scale_image(image, scale);
+            let jpeg = encode_jpeg_with(&image, quality, &opts);
+            pool::BYTES.give(image.into_raw());
+            let jpeg = jpeg.ok()?;
+            stats::frame("stream", started.elapsed());
+            Some((jpeg, f.source, shown, placed, scale))
+        })
+        .await
+        .ok()
+        .flatten();
+        let Some((jpeg, source, shown, placed, scale)) = grabbed else {
+            return Some((Ok::<Bytes, Infallible>(Bytes::new()), (last, seq + 1, tune, hands, open, client, Duration::ZERO)));
         };
+        if let Some(tune) = tune.as_mut() {
+            tune.observe(last.elapsed());
+        }
+        let timestamp = captured_at.saturating_sub(source.age_ms().unwrap_or(0));
 
-        let mut chunk = Vec::with_capacity(jpeg.len() + 128);
+        let mut chunk = Vec::with_capacity(jpeg.len() + 192);
         chunk.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
         chunk.extend_from_slice(b"Content-Type: image/jpeg\r\n");
+        chunk.extend_from_slice(format!("X-Frame-Seq: {seq}\r\n").as_bytes());
+        chunk.extend_from_slice(format!("X-Frame-Timestamp: {timestamp}\r\n").as_bytes());
+        chunk.extend_from_slice(format!("X-Seealln-Capture: {}\r\n", source.as_str()).as_bytes());
+        if let Some(age) = source.age_ms() {
+            chunk.extend_from_slice(format!("X-Seealln-Frame-Age-Ms: {age}\r\n").as_bytes());
+        }
+        if let Some(r) = shown {
+            chunk.extend_from_slice(format!("X-Seealln-Region: {},{},{},{}\r\n", r.x, r.y, r.w, r.h).as_bytes());
+        }
+        if let Some(p) = placed {
+            let (r, i) = (p.region, p.inset);
+            chunk.extend_from_slice(format!("X-Seealln-Pip-Region: {},{},{},{}\r\n", r.x, r.y, r.w, r.h).as_bytes());
+            chunk.extend_from_slice(format!("X-Seealln-Pip-Inset: {},{},{},{}\r\n", i.x, i.y, i.w, i.h).as_bytes());
+        }
+        if tune.is_some() || scale < 1.0 {
+            chunk.extend_from_slice(format!("X-Seealln-Scale: {scale:.3}\r\n").as_bytes());
+        }
+        if let Some(tune) = &tune {
+            chunk.extend_from_slice(format!("X-Seealln-Quality: {quality}\r\n").as_bytes());
+            chunk.extend_from_slice(format!("X-Seealln-Target-Ms: {}\r\n", tune.target_ms()).as_bytes());
+        }
+        if let Some(sig) = attest::header_value(&jpeg) {
+            chunk.extend_from_slice(format!("X-Seealln-Frame-Signature: {sig}\r\n").as_bytes());
+        }
         chunk.extend_from_slice(format!("Content-Length: {}\r\n\r\n", jpeg.len()).as_bytes());
         chunk.extend_from_slice(&jpeg);
         chunk.extend_from_slice(b"\r\n");
+        pool::BYTES.give(jpeg);
 
-        Some((Ok::<Bytes, Infallible>(Bytes::from(chunk)), last))
+        let pause = bandwidth::charge(&client, "stream", chunk.len());
+        Some((Ok::<Bytes, Infallible>(Bytes::from(chunk)), (last, seq + 1, tune, hands, open, client, pause)))
     });
 
     let mut resp = Response::new(Body::from_stream(body_stream));
@@ -205,51 +608,304 @@ async fn stream_mjpeg(Query(params): Query<StreamParams>) -> Response {
     );
     resp.headers_mut()
         .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    resp.headers_mut()
+        .insert(HeaderName::from_static("x-seealln-stream-id"), HeaderValue::from(stream_id));
     resp
 }
 
+// SEEALLN_BIND:SEEALLN_PORT (default 127.0.0.1:8765); also checked by `seealln doctor`.
+fn listen_addr() -> SocketAddr {
+    let bind_ip_raw = std::env::var("SEEALLN_BIND").unwrap_or_else(|_| "127.0.0.1".to_string());
+    let bind_ip = bind_ip_raw.trim();
+    let bind_ip = if bind_ip.is_empty() { "127.0.0.1" } else { bind_ip };
+
+    let port: u16 = std::env::var("SEEALLN_PORT")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(8765);
+
+    format!("{}:{}", bind_ip, port)
+        .parse()
+        .unwrap_or_else(|_| {
+            // Defensive fallback
+            "127.0.0.1:8765".parse().expect("valid fallback socket")
+        })
+}
+
+// Speaks HTTP/1.1 and cleartext HTTP/2 (h2c, prior knowledge) on the same port. Hand-rolled
+// instead of axum::serve so HTTP/1 clients that dribble their headers are cut off.
+async fn serve(listener: tokio::net::TcpListener, app: Router, header_timeout: Duration) {
+    use hyper_util::{
+        rt::{TokioExecutor, TokioIo, TokioTimer},
+        server::conn::auto,
+        service::TowerToHyperService,
+    };
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                // Typically out of file descriptors; back off instead of spinning.
+                error!(%err, "accept failed");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        // The peer address binds arming tokens to their client (see origin.rs).
+        let app = tower::Layer::layer(&Extension(ConnectInfo(peer)), app.clone());
+        let service = TowerToHyperService::new(app);
+        tokio::spawn(async move {
+            let mut builder = auto::Builder::new(TokioExecutor::new());
+            builder.http1().timer(TokioTimer::new()).header_read_timeout(header_timeout);
+            if let Err(err) = builder.serve_connection_with_upgrades(TokioIo::new(stream), service).await {
+                tracing::debug!(%err, "connection closed");
+            }
+        });
+    }
+}
+
+// Ctrl-C, or SIGTERM from systemd/launchd/docker stop.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                return;
+            }
+            Err(err) => error!(%err, "cannot listen for SIGTERM"),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
 #[tokio::main]
 async fn main() {
-    tracing_subscriber::fmt().with_env_filter("info").init();
-
-    let hands_state = hands::HandsState::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("secrets") {
+        std::process::exit(secrets::cli(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("service") {
+        std::process::exit(service::cli(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("override") {
+        std::process::exit(overrides::cli(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("doctor") {
+        std::process::exit(doctor::cli(&args[1..]));
+    }
+    if args.first().map(String::as_str) == Some("tunnel") {
+        std::process::exit(tunnel::cli(&args[1..]).await);
+    }
 
-    let app = Router::new()
-        .route("/", get(health))
-        .route("/health", get(health))
+    logfwd::init();
+    {
+        use tracing_subscriber::prelude::*;
+        tracing_subscriber::registry()
+            .with(tracing_subscriber::EnvFilter::new("info"))
+            .with(tracing_subscriber::fmt::layer())
+            .with(logfwd::layer())
+            .init();
+    }
+    stats::started();
+    capture::mono_epoch();
+    permissions::warn_missing();
+
+    let store = store::Store::from_env();
+    store.spawn_retention();
+    attest::init(store.root());
+    #[cfg(feature = "s3")]
+    match s3::S3Config::from_env() {
+        Some(cfg) => s3::Uploader::new(cfg, store.clone()).spawn(),
+        None => info!("s3 upload not configured (SEEALLN_S3_BUCKET + credentials)"),
+    }
+    let events = events::Events::default();
+    let hands_state = hands::HandsState::with_store(store.clone())
+        .with_events(events.clone())
+        .with_default_interceptors();
+    capture::spawn_display_monitor(events.clone());
+    capture::spawn_capture_watchdog(events.clone());
+    capture::spawn_idle_suspend(events.clone());
+    hands_state.spawn_supervision_watch();
+    #[cfg(feature = "webhook")]
+    webhook::spawn(&events);
+
+    // Read-only routes ("eyes") and control routes ("hands") get separate middleware stacks
+    // (key, rate limit, log verbosity); see layers.rs. Body limits and timeouts are per route.
+    let limits = layers::Limits::from_env();
+    let shed = layers::Shed::from_env();
+    let eyes = Router::new()
+        .route("/time", get(time))
+        .route("/version", get(version))
+        .route("/displays", get(displays))
         .route("/snapshot.jpg", get(snapshot))
+        .route("/frame/phash", get(frame::frame_phash))
+        .route("/frame/similar", post(frame::frame_similar).layer(DefaultBodyLimit::max(limits.upload)))
+        .route("/visual/compare", post(visual::visual_compare))
+        .route("/ocr", get(frame::frame_ocr))
+        .route("/detect/elements", get(frame::detect_elements))
+        .route("/detect/caret", get(frame::detect_caret))
+        .route("/analyze/colors", get(frame::analyze_colors))
+        .route("/analyze/theme", get(frame::analyze_theme))
+        .route("/safety/status", get(hands::safety_status))
+        .route("/hands/capabilities", get(hands::hands_capabilities))
+        .route("/scopes", get(scopes::scopes_list))
+        .route("/sessions", get(session::sessions_list))
+        .route("/sessions/:id/frames", get(session::session_frames))
+        .route("/sessions/:id/captions", get(session::session_captions))
+        .route("/sessions/:id/verify", get(session::session_verify))
+        .route("/tools.json", get(tools::tools_json))
+        .route("/stats", get(stats::stats_json))
+        .route("/cluster/status", get(cluster::cluster_status))
+        .route("/sim/state", get(sim::sim_state));
+    // Browser DOM reads over the Chrome DevTools Protocol (feature `cdp`)
+    #[cfg(feature = "cdp")]
+    let eyes = eyes.route("/cdp/targets", get(cdp::cdp_targets)).route("/cdp/query", post(cdp::cdp_query));
+    let eyes = eyes
+        .layer(middleware::from_fn_with_state(limits.timeout, layers::timeout))
+        .route("/snapshot/next", get(snapshot_next).layer(middleware::from_fn_with_state(limits.long_timeout, layers::timeout)))
+        .route("/bench", post(bench::bench).layer(middleware::from_fn_with_state(limits.long_timeout, layers::timeout)))
+        .layer(middleware::from_fn_with_state(shed.clone(), layers::shed))
+        // Long-lived streams: no request timeout, never shed
+        .route("/events", get(events::events_sse))
         .route("/stream", get(stream_mjpeg))
+        .route("/stream/:id/pause", post(stats::stream_pause))
+        .route("/stream/:id/resume", post(stats::stream_resume))
+        .layer(DefaultBodyLimit::max(limits.body))
+        .route_layer(middleware::from_fn_with_state(
+            layers::RoutePolicy::from_env(layers::Class::Eyes),
+            layers::enforce,
+        ));
+
+    let hands = Router::new()
+        .route("/displays/virtual", post(vdisplay::displays_virtual_create))
+        .route("/displays/virtual/:n", delete(vdisplay::displays_virtual_delete))
         // Hands (input control) - guarded, local-only
         .route("/hands/arm", post(hands::hands_arm))
         .route("/hands/disarm", post(hands::hands_disarm))
+        .route("/hands/extend", post(hands::hands_extend))
+        .route("/hands/token/introspect", get(hands::hands_token_introspect))
+        .route("/hands/token/revoke", post(hands::hands_token_revoke))
+        .route("/hands/confirm", post(confirm::hands_confirm))
+        .route("/hands/confirm/:id", get(confirm::hands_confirm_poll))
         .route("/hands/move", post(hands::hands_move))
         .route("/hands/click", post(hands::hands_click))
         .route("/hands/type", post(hands::hands_type))
+        .route("/hands/key", post(hands::hands_key))
+        .route("/hands/scroll", post(hands::hands_scroll))
+        .route("/hands/gesture", post(hands::hands_gesture))
+        .route("/hands/undo", post(hands::hands_undo))
+        .route("/selftest", post(selftest::selftest))
 
-        // Safety + scope
-        .route("/safety/kill", post(hands::safety_kill))
+        // Safety reset + scope
         .route("/safety/reset", post(hands::safety_reset))
-        .route("/safety/status", get(hands::safety_status))
         .route("/scope/set", post(hands::scope_set))
-        .with_state(hands_state);
+        .route("/scopes/:name", post(scopes::scopes_put).delete(scopes::scopes_delete))
+        // Writes the golden image /visual/compare (eyes) reads
+        .route("/visual/baseline", post(visual::visual_baseline))
+        // Synthetic desktop (feature `simulation`)
+        .route("/sim/reset", post(sim::sim_reset));
+    // Browser DOM actions over the Chrome DevTools Protocol (feature `cdp`)
+    #[cfg(feature = "cdp")]
+    let hands = hands.route("/cdp/navigate", post(cdp::cdp_navigate)).route("/cdp/click", post(cdp::cdp_click));
+    let hands = hands
+        // OpenAI function-calling adapter
+        .route("/tools/invoke", post(tools::tools_invoke))
+        .layer(middleware::from_fn_with_state(limits.timeout, layers::timeout))
+
+        // Multi-step runs (sleeps, waits, retries) get the long timeout
+        .route("/sessions/:id/replay", post(session::session_replay))
+        .route("/compat/xdotool", post(compat::compat_xdotool))
+        .route("/scripts/run", post(script::scripts_run).layer(DefaultBodyLimit::max(limits.upload)))
+        .layer(middleware::from_fn_with_state(limits.long_timeout, layers::timeout))
+        .layer(middleware::from_fn_with_state(shed.clone(), layers::shed))
+        .layer(DefaultBodyLimit::max(limits.body))
+        // Signed requests with replay protection when an HMAC secret is set; runs after the key check.
+        .route_layer(middleware::from_fn_with_state(limits.upload, signing::verify))
+        .route_layer(middleware::from_fn_with_state(
+            layers::RoutePolicy::from_env(layers::Class::Hands),
+            layers::enforce,
+        ));
 
-    let bind_ip_raw = std::env::var("SEEALLN_BIND").unwrap_or_else(|_| "127.0.0.1".to_string());
-    let bind_ip = bind_ip_raw.trim();
-    let bind_ip = if bind_ip.is_empty() { "127.0.0.1" } else { bind_ip };
-
-    let port: u16 = std::env::var("SEEALLN_PORT")
-        .ok()
-        .and_then(|s| s.trim().parse().ok())
-        .unwrap_or(8765);
-
-    let addr: SocketAddr = format!("{}:{}", bind_ip, port)
-        .parse()
-        .unwrap_or_else(|_| {
-            // Defensive fallback
-            "127.0.0.1:8765".parse().expect("valid fallback socket")
+    let app = Router::new()
+        // Data footprint (recordings/frames/audit retention); API key checked per handler
+        .route("/admin/retention", get(store::admin_retention))
+        .route("/admin/purge", post(store::admin_purge))
+        .route("/admin/export", get(store::admin_export))
+        .route("/sessions/:id/transcript", get(session::session_transcript))
+        // Hands rate limit, adjustable at runtime (also needs x-seealln-confirm: yes)
+        .route("/admin/limits", patch(hands::admin_limits))
+        // Confirmations awaiting a human (SEEALLN_CONFIRM_APPROVAL=1)
+        .route("/admin/confirmations", get(confirm::admin_confirmations))
+        .route("/admin/confirmations/undo", post(confirm::admin_confirm_undo))
+        .route("/admin/confirmations/:id/approve", post(confirm::admin_confirm_approve))
+        .route("/admin/confirmations/:id/deny", post(confirm::admin_confirm_deny))
+        .layer(middleware::from_fn_with_state(limits.long_timeout, layers::timeout))
+        .layer(middleware::from_fn_with_state(shed, layers::shed))
+        .layer(DefaultBodyLimit::max(limits.body))
+        .route("/", get(health))
+        .route("/health", get(health))
+        // Never behind a key, rate limit or load shedding.
+        .route("/safety/kill", post(hands::safety_kill))
+        .merge(eyes)
+        .merge(hands)
+        .layer(middleware::from_fn(encoding::negotiate))
+        .layer(layers::compression())
+        .with_state(AppState {
+            hands: hands_state.clone(),
+            store,
+            events: events.clone(),
         });
-    info!("SeeAlln Rust server listening on http://{}", addr);
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    // Reverse-connection mode: dial out to the relay instead of listening.
+    #[cfg(feature = "relay")]
+    if let Some(url) = relay::url() {
+        if let Err(err) = relay::check(&url) {
+            error!("{err}");
+            std::process::exit(2);
+        }
+        systemd::ready();
+        tokio::select! {
+            _ = relay::run(url, app) => {}
+            _ = shutdown_signal() => info!("shutting down"),
+        }
+        systemd::stopping();
+        vdisplay::stop_all();
+        return;
+    }
+    #[cfg(not(feature = "relay"))]
+    if std::env::var("SEEALLN_RELAY_URL").is_ok_and(|u| !u.trim().is_empty()) {
+        error!("SEEALLN_RELAY_URL is set but this build has no `relay` feature");
+        std::process::exit(2);
+    }
+
+    let addr = listen_addr();
+    let listener = match systemd::listener() {
+        Some(std_listener) => {
+            let listener = tokio::net::TcpListener::from_std(std_listener).unwrap();
+            info!("SeeAlln Rust server listening on http://{} (socket activated)", listener.local_addr().unwrap());
+            listener
+        }
+        None => {
+            info!("SeeAlln Rust server listening on http://{}", addr);
+            tokio::net::TcpListener::bind(addr).await.unwrap()
+        }
+    };
+    cluster::spawn(listener.local_addr().unwrap(), &events, hands_state);
+    let advertisement = if mdns::wanted(&args) { mdns::advertise(listener.local_addr().unwrap()) } else { None };
+    systemd::ready();
+    // Not a graceful shutdown: open MJPEG streams would hold it forever.
+    tokio::select! {
+        _ = serve(listener, app, limits.header_timeout) => {}
+        _ = shutdown_signal() => info!("shutting down"),
+    }
+    systemd::stopping();
+    if let Some(ad) = advertisement {
+        ad.withdraw();
+    }
+    cluster::leave();
+    vdisplay::stop_all();
 }
@@ -0,0 +1,123 @@
+use std::net::SocketAddr;
+#[cfg(feature = "mdns")]
+use tracing::info;
+use tracing::warn;
+
+#[cfg(feature = "mdns")]
+use crate::secrets::{self, Secret};
+
+// mDNS/DNS-SD advertisement (feature `mdns`) as `_seealln._tcp`, so LAN clients can discover
+// instances instead of keeping IP/port lists. Off by default (`--no-advertise`); turned on by
+// `--advertise` or SEEALLN_ADVERTISE=1, and only when bound to a non-loopback address, since a
+// loopback-only server is unreachable from the LAN anyway.
+//
+// TXT records: v (server version), path (/), auth (`none` or the keys required: api, eyes,
+// hands), hands / capture / cdp (available or disabled), id (SEEALLN_INSTANCE when set).
+// Nothing secret is published; instance name is SEEALLN_INSTANCE or "seealln on <host>".
+#[cfg(feature = "mdns")]
+const SERVICE_TYPE: &str = "_seealln._tcp.local.";
+
+// `--advertise` / `--no-advertise` win over the environment; the last flag given counts.
+pub fn wanted(args: &[String]) -> bool {
+    let flag = args.iter().rev().find_map(|a| match a.as_str() {
+        "--advertise" => Some(true),
+        "--no-advertise" => Some(false),
+        _ => None,
+    });
+    flag.unwrap_or_else(|| std::env::var("SEEALLN_ADVERTISE").ok().as_deref() == Some("1"))
+}
+
+#[cfg(feature = "mdns")]
+fn hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|k| std::env::var(k).ok().filter(|h| !h.trim().is_empty()))
+        .map(|h| h.trim().split('.').next().unwrap_or_default().to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "seealln".to_string())
+}
+
+#[cfg(feature = "mdns")]
+fn txt_records() -> Vec<(&'static str, String)> {
+    let keys: Vec<&str> = [(Secret::ApiKey, "api"), (Secret::EyesKey, "eyes"), (Secret::HandsKey, "hands")]
+        .into_iter()
+        .filter(|(secret, _)| secrets::get(*secret).is_some())
+        .map(|(_, name)| name)
+        .collect();
+    let state = |on: bool| if on { "available" } else { "disabled" }.to_string();
+    let mut txt = vec![
+        ("v", env!("CARGO_PKG_VERSION").to_string()),
+        ("path", "/".to_string()),
+        ("auth", if keys.is_empty() { "none".to_string() } else { keys.join(",") }),
+        ("hands", state(cfg!(feature = "hands"))),
+        ("capture", state(cfg!(feature = "capture"))),
+        ("cdp", state(cfg!(feature = "cdp"))),
+    ];
+    if let Some(id) = std::env::var("SEEALLN_INSTANCE").ok().filter(|s| !s.trim().is_empty()) {
+        txt.push(("id", id.trim().to_string()));
+    }
+    txt
+}
+
+// Keeps the registration alive until `withdraw` is called on the returned handle.
+pub struct Advertisement {
+    #[cfg(feature = "mdns")]
+    daemon: mdns_sd::ServiceDaemon,
+    #[cfg(feature = "mdns")]
+    fullname: String,
+}
+
+#[cfg(feature = "mdns")]
+pub fn advertise(local: SocketAddr) -> Option<Advertisement> {
+    if local.ip().is_loopback() {
+        warn!(%local, "not advertising over mDNS: bound to loopback (set SEEALLN_BIND)");
+        return None;
+    }
+    let host = hostname();
+    let instance = std::env::var("SEEALLN_INSTANCE")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| format!("seealln on {host}"));
+    let txt = txt_records();
+    let props: Vec<(&str, &str)> = txt.iter().map(|(k, v)| (*k, v.as_str())).collect();
+    let registered = mdns_sd::ServiceDaemon::new().and_then(|daemon| {
+        // An unspecified bind advertises every interface address.
+        let ip = if local.ip().is_unspecified() { String::new() } else { local.ip().to_string() };
+        let mut info = mdns_sd::ServiceInfo::new(SERVICE_TYPE, &instance, &format!("{host}.local."), ip, local.port(), &props[..])?;
+        if local.ip().is_unspecified() {
+            info = info.enable_addr_auto();
+        }
+        let fullname = info.get_fullname().to_string();
+        daemon.register(info)?;
+        Ok(Advertisement { daemon, fullname })
+    });
+    match registered {
+        Ok(ad) => {
+            info!(name = %ad.fullname, port = local.port(), "advertising over mDNS");
+            Some(ad)
+        }
+        Err(err) => {
+            warn!(%err, "mDNS advertisement failed");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "mdns"))]
+pub fn advertise(_local: SocketAddr) -> Option<Advertisement> {
+    warn!("--advertise ignored: built without the `mdns` feature");
+    None
+}
+
+impl Advertisement {
+    // Sends goodbye packets so browsers drop the entry right away.
+    pub fn withdraw(self) {
+        #[cfg(feature = "mdns")]
+        {
+            if let Ok(rx) = self.daemon.unregister(&self.fullname) {
+                let _ = rx.recv_timeout(std::time::Duration::from_secs(1));
+            }
+            let _ = self.daemon.shutdown();
+        }
+    }
+}
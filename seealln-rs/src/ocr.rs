@@ -0,0 +1,231 @@
+use image::RgbImage;
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use crate::vision::Match;
+
+// OCR through the Tesseract CLI (SEEALLN_TESSERACT, default `tesseract` on PATH); no
+// in-process engine is bundled. Word boxes come from its TSV output.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Word {
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub conf: f32,
+    pub block: u32,
+    pub par: u32,
+    pub line: u32,
+}
+
+pub fn words(img: &RgbImage) -> Result<Vec<Word>, String> {
+    // Sparse text: finds isolated labels anywhere on a screen.
+    run(img, "11")
+}
+
+fn run(img: &RgbImage, psm: &str) -> Result<Vec<Word>, String> {
+    let mut png = Vec::new();
+    img.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    let bin = std::env::var("SEEALLN_TESSERACT").unwrap_or_else(|_| "tesseract".to_string());
+    let mut child = Command::new(&bin)
+        .args(["stdin", "stdout", "--psm", psm, "tsv"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("ocr unavailable ({bin}: {e})"))?;
+    child
+        .stdin
+        .take()
+        .expect("piped stdin")
+        .write_all(&png)
+        .map_err(|e| format!("ocr: {e}"))?;
+    let out = child.wait_with_output().map_err(|e| format!("ocr: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("ocr: {bin} exited with {}", out.status));
+    }
+
+    // level page_num block_num par_num line_num word_num left top width height conf text
+    let tsv = String::from_utf8_lossy(&out.stdout);
+    Ok(tsv
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split('\t').collect();
+            if cols.len() < 12 || cols[0] != "5" || cols[11].trim().is_empty() {
+                return None;
+            }
+            let n = |i: usize| cols[i].parse::<i32>().unwrap_or(0);
+            Some(Word {
+                text: cols[11].to_string(),
+                x: n(6),
+                y: n(7),
+                w: n(8),
+                h: n(9),
+                conf: cols[10].parse().unwrap_or(0.0),
+                block: n(2) as u32,
+                par: n(3) as u32,
+                line: n(4) as u32,
+            })
+        })
+        .collect())
+}
+
+// Recognized text, one line per OCR line.
+pub fn text(img: &RgbImage) -> Result<String, String> {
+    let mut out = String::new();
+    let mut prev = None;
+    for w in words(img)? {
+        let key = (w.block, w.par, w.line);
+        match prev {
+            Some(p) if p == key => out.push(' '),
+            Some(_) => out.push('\n'),
+            None => {}
+        }
+        out.push_str(&w.text);
+        prev = Some(key);
+    }
+    Ok(out)
+}
+
+// Finds `needle` (case-insensitive, may span consecutive words of one line) and returns
+// its bounding box, offset by (ox, oy) to screen coordinates.
+pub fn find_text(img: &RgbImage, needle: &str, ox: i32, oy: i32) -> Result<Option<Match>, String> {
+    let needle: Vec<String> = needle.split_whitespace().map(str::to_lowercase).collect();
+    if needle.is_empty() {
+        return Ok(None);
+    }
+    let words = words(img)?;
+    for (i, _) in words.iter().enumerate() {
+        let run = &words[i..];
+        if run.len() < needle.len() {
+            break;
+        }
+        let same_line = run[..needle.len()]
+            .iter()
+            .all(|w| (w.block, w.par, w.line) == (run[0].block, run[0].par, run[0].line));
+        let matches = run[..needle.len()]
+            .iter()
+            .zip(&needle)
+            .all(|(w, n)| w.text.to_lowercase().contains(n.as_str()));
+        if same_line && matches {
+            let span = &run[..needle.len()];
+            let x0 = span.iter().map(|w| w.x).min().unwrap_or(0);
+            let y0 = span.iter().map(|w| w.y).min().unwrap_or(0);
+            let x1 = span.iter().map(|w| w.x + w.w).max().unwrap_or(0);
+            let y1 = span.iter().map(|w| w.y + w.h).max().unwrap_or(0);
+            let conf = span.iter().map(|w| w.conf).sum::<f32>() / span.len() as f32;
+            return Ok(Some(Match {
+                x: ox + x0,
+                y: oy + y0,
+                w: x1 - x0,
+                h: y1 - y0,
+                score: conf / 100.0,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+// Layout mode: Tesseract's automatic page segmentation (columns, paragraphs), kept in its
+// reading order. Boxes are offset by (ox, oy) to screen coordinates; `font_px` is the median
+// word height, a rough font-size estimate (cap height + descenders).
+#[derive(Debug, serde::Serialize)]
+pub struct Line {
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub font_px: i32,
+    pub words: Vec<Word>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct Paragraph {
+    pub order: usize,
+    pub block: u32,
+    #[serde(skip)]
+    par: u32,
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub font_px: i32,
+    pub lines: Vec<Line>,
+}
+
+fn median(mut v: Vec<i32>) -> i32 {
+    v.sort_unstable();
+    v.get(v.len() / 2).copied().unwrap_or(0)
+}
+
+fn bbox(boxes: impl Iterator<Item = (i32, i32, i32, i32)>) -> (i32, i32, i32, i32) {
+    let (mut x0, mut y0, mut x1, mut y1) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    for (x, y, w, h) in boxes {
+        x0 = x0.min(x);
+        y0 = y0.min(y);
+        x1 = x1.max(x + w);
+        y1 = y1.max(y + h);
+    }
+    if x0 > x1 {
+        return (0, 0, 0, 0);
+    }
+    (x0, y0, x1 - x0, y1 - y0)
+}
+
+pub fn layout(img: &RgbImage, ox: i32, oy: i32) -> Result<Vec<Paragraph>, String> {
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    for mut word in run(img, "3")? {
+        word.x += ox;
+        word.y += oy;
+        let starts_par = paragraphs
+            .last()
+            .is_none_or(|p| (p.block, p.par) != (word.block, word.par));
+        if starts_par {
+            paragraphs.push(Paragraph {
+                order: paragraphs.len(),
+                block: word.block,
+                par: word.par,
+                text: String::new(),
+                x: 0,
+                y: 0,
+                w: 0,
+                h: 0,
+                font_px: 0,
+                lines: Vec::new(),
+            });
+        }
+        let par = paragraphs.last_mut().expect("just pushed");
+        match par.lines.last_mut() {
+            Some(line) if line.words[0].line == word.line => line.words.push(word),
+            _ => par.lines.push(Line {
+                text: String::new(),
+                x: 0,
+                y: 0,
+                w: 0,
+                h: 0,
+                font_px: 0,
+                words: vec![word],
+            }),
+        }
+    }
+
+    for par in &mut paragraphs {
+        for line in &mut par.lines {
+            line.text = line.words.iter().map(|w| w.text.as_str()).collect::<Vec<_>>().join(" ");
+            (line.x, line.y, line.w, line.h) = bbox(line.words.iter().map(|w| (w.x, w.y, w.w, w.h)));
+            line.font_px = median(line.words.iter().map(|w| w.h).collect());
+        }
+        par.text = par.lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n");
+        (par.x, par.y, par.w, par.h) = bbox(par.lines.iter().map(|l| (l.x, l.y, l.w, l.h)));
+        par.font_px = median(par.lines.iter().map(|l| l.font_px).collect());
+    }
+    Ok(paragraphs)
+}
@@ -0,0 +1,58 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::request::Parts,
+};
+use std::{convert::Infallible, net::SocketAddr};
+
+// Who is calling, as far as the server can tell: the peer address of the connection and an
+// optional self-chosen client id (x-seealln-client-id). An arming token is bound to the
+// origin of the /hands/arm request and refused from any other (see HandsState::consume), so a
+// token lifted from logs or an environment dump is useless on another machine. Every loopback
+// address counts as the same peer, since `localhost` may resolve to 127.0.0.1 or ::1 from one
+// call to the next, so without a client id any local process can use the token; only the id
+// (which that process would also have to learn) tells local clients apart. Requests without a
+// socket (relay mode) have no peer; only the client id binds.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct Origin {
+    pub peer: Option<String>,
+    pub client_id: Option<String>,
+}
+
+impl Origin {
+    // How much the binding proves: `client_id` when an id was sent, `weak` for a peer address
+    // alone (every local process shares loopback), `none` for neither.
+    pub fn strength(&self) -> &'static str {
+        match (&self.peer, &self.client_id) {
+            (_, Some(_)) => "client_id",
+            (Some(_), None) => "weak",
+            (None, None) => "none",
+        }
+    }
+}
+
+pub const CLIENT_ID_HEADER: &str = "x-seealln-client-id";
+const MAX_CLIENT_ID: usize = 128;
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Origin {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts.extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| {
+            let ip = addr.ip().to_canonical();
+            if ip.is_loopback() {
+                "loopback".to_string()
+            } else {
+                ip.to_string()
+            }
+        });
+        let client_id = parts
+            .headers
+            .get(CLIENT_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.trim().chars().take(MAX_CLIENT_ID).collect::<String>())
+            .filter(|s| !s.is_empty());
+        Ok(Self { peer, client_id })
+    }
+}
@@ -0,0 +1,238 @@
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as B64, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    interceptor::{Action, ActionContext},
+    secrets::{self, Secret},
+};
+
+// Operator overrides: a narrowly scoped, short-lived exception to one guardrail, signed with the
+// `override_key` secret (SEEALLN_OVERRIDE_KEY) that only the operator holds. The agent sends
+// it along with its request:
+//
+//   x-seealln-override: <base64url(claims json)>.<hex hmac-sha256(override_key, base64url part)>
+//
+// and an interceptor denial it covers is skipped (audited as `override_used`). Claims:
+//
+//   rule     interceptor to lift: sensitive_text, app_denylist, scope_clamp, policy_script
+//   text     only for typing exactly this text
+//   app      only while the focused app name contains this (case-insensitive)
+//   kind     only for this action kind (click, type, ...)
+//   expires  unix seconds; at most SEEALLN_OVERRIDE_MAX_SECS (default 3600) ahead
+//   uses     optional cap on how many denials it lifts
+//
+// Every override names a non-empty `text` or `app`, so none can switch a guardrail off wholesale. Mint
+// them with `seealln override sign`. Without the secret the header is refused.
+pub const HEADER: &str = "x-seealln-override";
+pub const RULES: &[&str] = &["sensitive_text", "app_denylist", "scope_clamp", "policy_script"];
+const MAX_PER_REQUEST: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Override {
+    pub id: String,
+    pub rule: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    pub expires: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uses: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Override {
+    // Whether this override lifts a denial by `rule` for `action` in `ctx`.
+    pub fn covers(&self, rule: &str, action: &Action, ctx: &ActionContext) -> bool {
+        if self.rule != rule || self.kind.as_deref().is_some_and(|k| k != action.kind()) {
+            return false;
+        }
+        if let Some(allowed) = &self.text {
+            let (Action::Type { text } | Action::Paste { text }) = action else {
+                return false;
+            };
+            if text != allowed {
+                return false;
+            }
+        }
+        if let Some(app) = &self.app {
+            let focused = ctx.focused_app.as_deref().map(str::to_lowercase);
+            if !focused.is_some_and(|f| f.contains(&app.to_lowercase())) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+fn max_lifetime() -> u64 {
+    static MAX: OnceLock<u64> = OnceLock::new();
+    *MAX.get_or_init(|| {
+        std::env::var("SEEALLN_OVERRIDE_MAX_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(3600)
+    })
+}
+
+fn mac(key: &str, payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac accepts any key length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn check_claims(o: &Override) -> Result<(), String> {
+    if !RULES.contains(&o.rule.as_str()) {
+        return Err(format!("unknown rule {} ({})", o.rule, RULES.join(", ")));
+    }
+    if o.text.is_none() && o.app.is_none() {
+        return Err("an override must name text or app".to_string());
+    }
+    // An empty `app` is a substring of every focused app name, so it would cover them all.
+    if [&o.text, &o.app].into_iter().flatten().any(|v| v.trim().is_empty()) {
+        return Err("override text and app must not be empty".to_string());
+    }
+    Ok(())
+}
+
+fn sign(key: &str, o: &Override) -> String {
+    let payload = B64.encode(serde_json::to_vec(o).unwrap_or_default());
+    let sig = hex::encode(mac(key, &payload).finalize().into_bytes());
+    format!("{payload}.{sig}")
+}
+
+fn verify(key: &str, token: &str) -> Result<Override, String> {
+    let (payload, sig) = token.trim().split_once('.').ok_or("malformed override")?;
+    let sig = hex::decode(sig).map_err(|_| "malformed override signature")?;
+    mac(key, payload).verify_slice(&sig).map_err(|_| "override signature does not match")?;
+    let bytes = B64.decode(payload).map_err(|_| "malformed override")?;
+    let o: Override = serde_json::from_slice(&bytes).map_err(|e| format!("override claims: {e}"))?;
+    check_claims(&o)?;
+    let now = now();
+    if o.expires <= now {
+        return Err(format!("override {} expired", o.id));
+    }
+    if o.expires > now + max_lifetime() {
+        return Err(format!("override {} lives longer than SEEALLN_OVERRIDE_MAX_SECS", o.id));
+    }
+    Ok(o)
+}
+
+// Overrides a request carries (none without the header).
+pub fn from_headers(headers: &HeaderMap) -> Result<Vec<Override>, String> {
+    let Some(value) = headers.get(HEADER) else {
+        return Ok(Vec::new());
+    };
+    let value = value.to_str().map_err(|_| "malformed override")?;
+    let key = secrets::get(Secret::OverrideKey).ok_or("overrides are not enabled (no override_key)")?;
+    let tokens: Vec<&str> = value.split(',').filter(|t| !t.trim().is_empty()).collect();
+    if tokens.len() > MAX_PER_REQUEST {
+        return Err(format!("at most {MAX_PER_REQUEST} overrides per request"));
+    }
+    tokens.into_iter().map(|t| verify(&key, t)).collect()
+}
+
+// Uses so far per override id, kept until the override expires.
+fn used() -> std::sync::MutexGuard<'static, HashMap<String, (u32, u64)>> {
+    static USED: OnceLock<Mutex<HashMap<String, (u32, u64)>>> = OnceLock::new();
+    USED.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Counts one use; false once the override's `uses` are spent.
+pub fn take_use(o: &Override) -> bool {
+    let now = now();
+    let mut used = used();
+    used.retain(|_, (_, expires)| *expires > now);
+    let (count, _) = used.entry(o.id.clone()).or_insert((0, o.expires));
+    if o.uses.is_some_and(|max| *count >= max) {
+        return false;
+    }
+    *count += 1;
+    true
+}
+
+const USAGE: &str = "usage: seealln override sign --rule RULE [--text TEXT] [--app APP] [--kind KIND] [--ttl SECS] [--uses N] [--note NOTE]";
+
+// `seealln override sign ...`: prints a signed override; returns the process exit code.
+pub fn cli(args: &[String]) -> i32 {
+    if args.first().map(String::as_str) != Some("sign") {
+        eprintln!("{USAGE}");
+        eprintln!("rules: {}", RULES.join(", "));
+        return 2;
+    }
+    let mut o = Override {
+        id: String::new(),
+        rule: String::new(),
+        text: None,
+        app: None,
+        kind: None,
+        expires: 0,
+        uses: None,
+        note: None,
+    };
+    let mut ttl = 120u64;
+    let mut it = args[1..].iter();
+    while let Some(arg) = it.next() {
+        let Some(value) = it.next().cloned() else {
+            eprintln!("override: {arg} needs a value");
+            return 2;
+        };
+        match arg.as_str() {
+            "--rule" => o.rule = value,
+            "--text" => o.text = Some(value),
+            "--app" => o.app = Some(value),
+            "--kind" => o.kind = Some(value),
+            "--note" => o.note = Some(value),
+            "--ttl" | "--uses" => {
+                let Ok(n) = value.parse::<u64>() else {
+                    eprintln!("override: {arg} must be a number");
+                    return 2;
+                };
+                if arg == "--ttl" {
+                    ttl = n;
+                } else {
+                    o.uses = Some(n.min(u32::MAX as u64) as u32);
+                }
+            }
+            other => {
+                eprintln!("override: unknown option {other}");
+                eprintln!("{USAGE}");
+                return 2;
+            }
+        }
+    }
+    if let Err(err) = check_claims(&o) {
+        eprintln!("override: {err}");
+        return 2;
+    }
+    if ttl == 0 || ttl > max_lifetime() {
+        eprintln!("override: --ttl must be between 1 and {} seconds", max_lifetime());
+        return 2;
+    }
+    let Some(key) = secrets::get(Secret::OverrideKey) else {
+        eprintln!("override: no override_key secret (seealln secrets set override_key)");
+        return 1;
+    };
+    let mut id = [0u8; 8];
+    getrandom::getrandom(&mut id).expect("os rng");
+    o.id = hex::encode(id);
+    o.expires = now() + ttl;
+    println!("{}", sign(&key, &o));
+    0
+}
@@ -0,0 +1,200 @@
+use serde_json::json;
+use tracing::warn;
+
+// macOS privacy permissions, granted per binary (or per terminal app that starts it) under
+// System Settings > Privacy & Security:
+//
+//   accessibility     posting synthetic input; without it enigo's events are dropped or fail
+//   input_monitoring  reading the physical keyboard and mouse (physical-input kill helpers)
+//   screen_recording  capturing windows; without it frames show only wallpaper and menu bar
+//
+// `check` only reads the state, so it is safe at startup and for /health; `request` shows the
+// system prompt for each missing one (`seealln doctor`). Other platforms have nothing to grant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Grant {
+    Granted,
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Denied,
+    // The OS has not decided yet (never prompted) or cannot say.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Permissions {
+    pub accessibility: Grant,
+    pub input_monitoring: Grant,
+    pub screen_recording: Grant,
+}
+
+impl Permissions {
+    pub fn all(&self) -> [(&'static str, Grant); 3] {
+        [
+            ("accessibility", self.accessibility),
+            ("input_monitoring", self.input_monitoring),
+            ("screen_recording", self.screen_recording),
+        ]
+    }
+
+    pub fn missing(&self) -> Vec<&'static str> {
+        self.all().into_iter().filter(|(_, grant)| *grant != Grant::Granted).map(|(name, _)| name).collect()
+    }
+}
+
+// Where to grant a permission by hand.
+pub fn pane(name: &str) -> &'static str {
+    match name {
+        "accessibility" => "System Settings > Privacy & Security > Accessibility",
+        "input_monitoring" => "System Settings > Privacy & Security > Input Monitoring",
+        _ => "System Settings > Privacy & Security > Screen Recording",
+    }
+}
+
+// None off macOS.
+pub fn check() -> Option<Permissions> {
+    #[cfg(target_os = "macos")]
+    return Some(imp::check());
+    #[cfg(not(target_os = "macos"))]
+    None
+}
+
+// Prompts for every missing permission, then checks again. macOS prompts at most once per
+// binary; after a refusal only the Settings pane helps.
+pub fn request() -> Option<Permissions> {
+    #[cfg(target_os = "macos")]
+    return Some(imp::request());
+    #[cfg(not(target_os = "macos"))]
+    None
+}
+
+// For /health: the grants, with the missing ones listed; null off macOS.
+pub fn json() -> serde_json::Value {
+    match check() {
+        Some(p) => json!({"accessibility": p.accessibility, "input_monitoring": p.input_monitoring, "screen_recording": p.screen_recording, "missing": p.missing()}),
+        None => serde_json::Value::Null,
+    }
+}
+
+// Startup: first runs on macOS otherwise fail with nothing in the log but enigo's errors.
+pub fn warn_missing() {
+    let Some(permissions) = check() else {
+        return;
+    };
+    for name in permissions.missing() {
+        warn!(permission = name, "macOS permission not granted; run `seealln doctor` or use {}", pane(name));
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod imp {
+    use std::ffi::c_void;
+
+    use super::{Grant, Permissions};
+
+    type CFTypeRef = *const c_void;
+
+    // Only ever used by address.
+    #[repr(C)]
+    struct Opaque([u8; 0]);
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        static kCFBooleanTrue: CFTypeRef;
+        static kCFTypeDictionaryKeyCallBacks: Opaque;
+        static kCFTypeDictionaryValueCallBacks: Opaque;
+        fn CFDictionaryCreate(
+            allocator: CFTypeRef,
+            keys: *const CFTypeRef,
+            values: *const CFTypeRef,
+            count: isize,
+            key_callbacks: *const Opaque,
+            value_callbacks: *const Opaque,
+        ) -> CFTypeRef;
+        fn CFRelease(cf: CFTypeRef);
+    }
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        static kAXTrustedCheckOptionPrompt: CFTypeRef;
+        fn AXIsProcessTrusted() -> u8;
+        fn AXIsProcessTrustedWithOptions(options: CFTypeRef) -> u8;
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> u8;
+        fn CGRequestScreenCaptureAccess() -> u8;
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOHIDCheckAccess(request: u32) -> u32;
+        fn IOHIDRequestAccess(request: u32) -> u8;
+    }
+
+    // kIOHIDRequestTypeListenEvent; IOHIDAccessType granted / denied (else unknown).
+    const LISTEN_EVENT: u32 = 1;
+    const ACCESS_GRANTED: u32 = 0;
+    const ACCESS_DENIED: u32 = 1;
+
+    fn grant(granted: bool) -> Grant {
+        if granted {
+            Grant::Granted
+        } else {
+            Grant::Denied
+        }
+    }
+
+    fn input_monitoring() -> Grant {
+        match unsafe { IOHIDCheckAccess(LISTEN_EVENT) } {
+            ACCESS_GRANTED => Grant::Granted,
+            ACCESS_DENIED => Grant::Denied,
+            _ => Grant::Unknown,
+        }
+    }
+
+    pub fn check() -> Permissions {
+        unsafe {
+            Permissions {
+                accessibility: grant(AXIsProcessTrusted() != 0),
+                input_monitoring: input_monitoring(),
+                screen_recording: grant(CGPreflightScreenCaptureAccess() != 0),
+            }
+        }
+    }
+
+    // AXIsProcessTrustedWithOptions({kAXTrustedCheckOptionPrompt: true}) shows the prompt.
+    fn prompt_accessibility() {
+        unsafe {
+            let keys = [kAXTrustedCheckOptionPrompt];
+            let values = [kCFBooleanTrue];
+            let options = CFDictionaryCreate(
+                std::ptr::null(),
+                keys.as_ptr(),
+                values.as_ptr(),
+                1,
+                std::ptr::addr_of!(kCFTypeDictionaryKeyCallBacks),
+                std::ptr::addr_of!(kCFTypeDictionaryValueCallBacks),
+            );
+            AXIsProcessTrustedWithOptions(options);
+            if !options.is_null() {
+                CFRelease(options);
+            }
+        }
+    }
+
+    pub fn request() -> Permissions {
+        let before = check();
+        if before.accessibility != Grant::Granted {
+            prompt_accessibility();
+        }
+        if before.input_monitoring != Grant::Granted {
+            unsafe { IOHIDRequestAccess(LISTEN_EVENT) };
+        }
+        if before.screen_recording != Grant::Granted {
+            unsafe { CGRequestScreenCaptureAccess() };
+        }
+        check()
+    }
+}
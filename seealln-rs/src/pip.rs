@@ -0,0 +1,96 @@
+use image::{imageops, Rgb, RgbImage};
+
+use crate::{frame, hands::ScopeRect, vision};
+
+// Picture-in-picture frames for /stream?pip=: the whole desktop shrunk to `pip_scale` (default
+// 0.4) as context, with the region of interest pasted at full resolution into its bottom-right
+// corner as the detail. `pip=scope` follows the active hands scope (the inset disappears while
+// none is set); `pip=x,y,w,h` is a fixed region. The inset is shrunk only when it would cover
+// more than MAX_INSET of the backdrop either way. The region is also outlined on the backdrop.
+// Parts report X-Seealln-Pip-Region (screen) and X-Seealln-Pip-Inset (frame pixels).
+const DEFAULT_BACKDROP: f32 = 0.4;
+const MAX_INSET: f32 = 0.6;
+const MARGIN: u32 = 8;
+const BORDER: Rgb<u8> = Rgb([255, 200, 0]);
+
+#[derive(Debug, Clone, Copy)]
+pub enum Inset {
+    Scope,
+    Fixed(ScopeRect),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Spec {
+    pub inset: Inset,
+    pub backdrop: f32,
+}
+
+pub fn parse(spec: &str, backdrop: Option<f32>) -> Result<Spec, String> {
+    let inset = match spec.trim() {
+        "scope" => Inset::Scope,
+        rect => Inset::Fixed(frame::parse_region(rect).map_err(|e| format!("pip: {e} or `scope`"))?),
+    };
+    Ok(Spec { inset, backdrop: backdrop.unwrap_or(DEFAULT_BACKDROP).clamp(0.1, 1.0) })
+}
+
+// Where the inset went: its screen region and its place in the composited frame.
+pub struct Placed {
+    pub region: ScopeRect,
+    pub inset: ScopeRect,
+}
+
+fn outline(img: &mut RgbImage, r: ScopeRect, width: u32) {
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    for i in 0..width as i32 {
+        for x in r.x - i..r.x + r.w + i {
+            for y in [r.y - 1 - i, r.y + r.h + i] {
+                if (0..w).contains(&x) && (0..h).contains(&y) {
+                    img.put_pixel(x as u32, y as u32, BORDER);
+                }
+            }
+        }
+        for y in r.y - 1 - i..=r.y + r.h + i {
+            for x in [r.x - 1 - i, r.x + r.w + i] {
+                if (0..w).contains(&x) && (0..h).contains(&y) {
+                    img.put_pixel(x as u32, y as u32, BORDER);
+                }
+            }
+        }
+    }
+}
+
+// Composites `full` (whose top-left pixel is screen `origin`) with `region` (screen
+// coordinates) inset. Without a region, or one off the frame, the backdrop alone.
+pub fn compose(full: &RgbImage, origin: (i32, i32), region: Option<ScopeRect>, backdrop: f32) -> (RgbImage, Option<Placed>) {
+    let scale = |v: i32| (v as f32 * backdrop) as i32;
+    let bw = ((full.width() as f32 * backdrop) as u32).max(1);
+    let bh = ((full.height() as f32 * backdrop) as u32).max(1);
+    let mut out = imageops::resize(full, bw, bh, imageops::FilterType::Triangle);
+    let Some(region) = region else {
+        return (out, None);
+    };
+    let local = ScopeRect { x: region.x - origin.0, y: region.y - origin.1, ..region };
+    let (detail, lx, ly) = vision::crop(full, Some(local));
+    if detail.width() == 0 || detail.height() == 0 {
+        return (out, None);
+    }
+    let shown = ScopeRect { x: lx + origin.0, y: ly + origin.1, w: detail.width() as i32, h: detail.height() as i32 };
+    outline(&mut out, ScopeRect { x: scale(lx), y: scale(ly), w: scale(shown.w).max(1), h: scale(shown.h).max(1) }, 1);
+
+    let fit = ((bw as f32 * MAX_INSET) / detail.width() as f32)
+        .min((bh as f32 * MAX_INSET) / detail.height() as f32)
+        .min(1.0);
+    let detail = if fit < 1.0 {
+        let w = ((detail.width() as f32 * fit) as u32).max(1);
+        let h = ((detail.height() as f32 * fit) as u32).max(1);
+        imageops::resize(&detail, w, h, imageops::FilterType::Triangle)
+    } else {
+        detail
+    };
+    let x = bw.saturating_sub(detail.width() + MARGIN);
+    let y = bh.saturating_sub(detail.height() + MARGIN);
+    imageops::replace(&mut out, &detail, x as i64, y as i64);
+    let inset = ScopeRect { x: x as i32, y: y as i32, w: detail.width() as i32, h: detail.height() as i32 };
+    outline(&mut out, inset, 2);
+    (out, Some(Placed { region: shown, inset }))
+}
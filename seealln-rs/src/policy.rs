@@ -0,0 +1,189 @@
+use axum::http::StatusCode;
+use std::sync::Arc;
+use tracing::{error, info};
+
+use crate::interceptor::{Action, ActionContext, ActionInterceptor, Denial};
+
+// User-provided policy script (SEEALLN_POLICY_SCRIPT=/path/policy.lua), run for every
+// proposed action. The script defines a global `policy(action, ctx)`:
+//
+//   function policy(action, ctx)
+//     -- action: {kind="move", x=.., y=..} | {kind="click", button=..} | {kind="type", text=..}
+//                 | {kind="key", combo=..}
+//     -- ctx: {focused_app=.., scope={x,y,w,h}, recent={...last executed actions...}}
+//     if ctx.focused_app == "Terminal" then return {verdict="deny", reason="no terminals"} end
+//     return "allow"   -- or {verdict="modify", action={...}}
+//   end
+//
+// The interpreter only gets the table/string/math libraries (no io/os), and each call has
+// an instruction budget. Any script error denies the action: policies fail closed.
+//
+// A modified action goes through `guardrails` (the built-in interceptors) again, so a script
+// cannot move the pointer out of scope or type what SensitiveText refuses; a modification they
+// deny denies the action.
+pub fn from_env(guardrails: Arc<Vec<Arc<dyn ActionInterceptor>>>) -> Option<Box<dyn ActionInterceptor>> {
+    let path = std::env::var("SEEALLN_POLICY_SCRIPT")
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())?;
+    match load(&path) {
+        Ok(script) => {
+            info!(%path, "policy script loaded");
+            Some(Box::new(Checked { script, guardrails }))
+        }
+        Err(err) => {
+            error!(%path, %err, "policy script unavailable; denying all actions");
+            Some(Box::new(Unavailable(err)))
+        }
+    }
+}
+
+// The script, with the guardrails re-run on an action it rewrote.
+struct Checked {
+    script: Box<dyn ActionInterceptor>,
+    guardrails: Arc<Vec<Arc<dyn ActionInterceptor>>>,
+}
+
+impl ActionInterceptor for Checked {
+    fn name(&self) -> &'static str {
+        self.script.name()
+    }
+
+    fn before(&self, action: &mut Action, ctx: &ActionContext) -> Result<(), Denial> {
+        let proposed = action.clone();
+        self.script.before(action, ctx)?;
+        if *action == proposed {
+            return Ok(());
+        }
+        for guardrail in self.guardrails.iter() {
+            if let Err(denial) = guardrail.before(action, ctx) {
+                // Put back what the guardrails passed, in case an override lifts this denial.
+                *action = proposed;
+                return Err(Denial::new(denial.status, format!("policy: modified action refused by {}: {}", guardrail.name(), denial.error)));
+            }
+        }
+        Ok(())
+    }
+
+    fn after(&self, action: &Action, result: &Result<(), String>, ctx: &ActionContext) {
+        self.script.after(action, result, ctx)
+    }
+}
+
+// Stand-in when a configured script can't be loaded.
+struct Unavailable(String);
+
+impl ActionInterceptor for Unavailable {
+    fn name(&self) -> &'static str {
+        "policy_script"
+    }
+
+    fn before(&self, _action: &mut Action, _ctx: &ActionContext) -> Result<(), Denial> {
+        Err(Denial::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("policy script unavailable: {}", self.0),
+        ))
+    }
+}
+
+#[cfg(feature = "lua-policy")]
+mod lua {
+    use super::*;
+    use mlua::{HookTriggers, Lua, LuaOptions, LuaSerdeExt, StdLib};
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn context_json(ctx: &ActionContext) -> serde_json::Value {
+        json!({"focused_app": ctx.focused_app, "scope": ctx.scope, "recent": ctx.recent})
+    }
+
+    // Interprets what `policy()` returned.
+    fn apply_verdict(verdict: serde_json::Value, action: &mut Action) -> Result<(), Denial> {
+        let deny = |reason: &str| Err(Denial::new(StatusCode::FORBIDDEN, format!("policy: {reason}")));
+        let (kind, obj) = match &verdict {
+            serde_json::Value::Null => return Ok(()),
+            serde_json::Value::String(s) => (s.as_str(), None),
+            serde_json::Value::Object(o) => (o.get("verdict").and_then(|v| v.as_str()).unwrap_or(""), Some(o)),
+            _ => return deny("invalid verdict"),
+        };
+        let reason = obj
+            .and_then(|o| o.get("reason"))
+            .and_then(|r| r.as_str())
+            .unwrap_or("denied by policy script");
+        match kind {
+            "allow" => Ok(()),
+            "deny" => deny(reason),
+            "modify" => {
+                let Some(new) = obj.and_then(|o| o.get("action")) else {
+                    return deny("modify verdict without action");
+                };
+                match serde_json::from_value::<Action>(new.clone()) {
+                    Ok(new) => {
+                        *action = new;
+                        Ok(())
+                    }
+                    Err(_) => deny("modify verdict with invalid action"),
+                }
+            }
+            _ => deny("invalid verdict"),
+        }
+    }
+
+    // Per-call instruction budget; runaway scripts are aborted (and the action denied).
+    const INSTRUCTION_BUDGET: u32 = 1_000_000;
+
+    pub struct LuaPolicy {
+        lua: Mutex<Lua>,
+    }
+
+    pub fn load(path: &str) -> Result<LuaPolicy, String> {
+        let src = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let lua = Lua::new_with(StdLib::TABLE | StdLib::STRING | StdLib::MATH, LuaOptions::default())
+            .map_err(|e| e.to_string())?;
+        lua.load(&src).set_name(path).exec().map_err(|e| e.to_string())?;
+        lua.globals()
+            .get::<_, mlua::Function>("policy")
+            .map_err(|_| "script does not define policy(action, ctx)".to_string())?;
+        Ok(LuaPolicy { lua: Mutex::new(lua) })
+    }
+
+    impl LuaPolicy {
+        fn call(&self, action: &Action, ctx: &ActionContext) -> mlua::Result<serde_json::Value> {
+            // A panic inside an earlier call may have left the interpreter half-updated: fail
+            // closed and deny every action until the server is restarted.
+            let Ok(lua) = self.lua.lock() else {
+                return Err(mlua::Error::RuntimeError("interpreter poisoned by an earlier panic".to_string()));
+            };
+            // Re-arming the hook resets the instruction counter for this call.
+            lua.set_hook(HookTriggers::new().every_nth_instruction(INSTRUCTION_BUDGET), |_, _| {
+                Err(mlua::Error::RuntimeError("instruction budget exceeded".to_string()))
+            });
+            let policy: mlua::Function = lua.globals().get("policy")?;
+            let verdict: mlua::Value = policy.call((lua.to_value(action)?, lua.to_value(&context_json(ctx))?))?;
+            lua.from_value(verdict)
+        }
+    }
+
+    impl ActionInterceptor for LuaPolicy {
+        fn name(&self) -> &'static str {
+            "policy_script"
+        }
+
+        fn before(&self, action: &mut Action, ctx: &ActionContext) -> Result<(), Denial> {
+            match self.call(action, ctx) {
+                Ok(verdict) => apply_verdict(verdict, action),
+                Err(err) => Err(Denial::new(StatusCode::FORBIDDEN, format!("policy script error: {err}"))),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "lua-policy")]
+fn load(path: &str) -> Result<Box<dyn ActionInterceptor>, String> {
+    lua::load(path).map(|p| Box::new(p) as Box<dyn ActionInterceptor>)
+}
+
+#[cfg(not(feature = "lua-policy"))]
+fn load(_path: &str) -> Result<Box<dyn ActionInterceptor>, String> {
+    Err("built without the `lua-policy` feature".to_string())
+}
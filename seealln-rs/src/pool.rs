@@ -0,0 +1,89 @@
+use serde_json::json;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+// Reused frame-sized buffers: capture copies (BGRA), RGB frames, JPEG planes and encoded
+// output. At 15 fps on a 4K display each of these is tens of MB per frame, and allocating
+// them fresh every time shows up in profiles (page faults, allocator locks). Buffers come
+// back through `give` once a frame is done with them; anything not given back is simply
+// freed as before. Each pool keeps at most MAX_FREE buffers, largest first, and ignores
+// buffers under MIN_BYTES. Hit and miss counts are in /stats as `buffer_pool`.
+const MAX_FREE: usize = 6;
+const MIN_BYTES: usize = 64 * 1024;
+
+pub struct Pool<T> {
+    free: Mutex<Vec<Vec<T>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+// Raw and RGB frames, encoded output.
+pub static BYTES: Pool<u8> = Pool::new();
+// JPEG color planes.
+pub static FLOATS: Pool<f32> = Pool::new();
+
+impl<T: Copy + Default> Pool<T> {
+    const fn new() -> Self {
+        Self {
+            free: Mutex::new(Vec::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // An empty buffer with room for at least `cap` elements: the smallest pooled one that
+    // fits, else a new one.
+    pub fn with_capacity(&self, cap: usize) -> Vec<T> {
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        let best = (0..free.len()).filter(|&i| free[i].capacity() >= cap).min_by_key(|&i| free[i].capacity());
+        match best {
+            Some(i) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                let mut buf = free.swap_remove(i);
+                buf.clear();
+                buf
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Vec::with_capacity(cap)
+            }
+        }
+    }
+
+    // `len` default (zero) elements.
+    pub fn take(&self, len: usize) -> Vec<T> {
+        let mut buf = self.with_capacity(len);
+        buf.resize(len, T::default());
+        buf
+    }
+
+    pub fn give(&self, buf: Vec<T>) {
+        if buf.capacity() * std::mem::size_of::<T>() < MIN_BYTES {
+            return;
+        }
+        let mut free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        free.push(buf);
+        if free.len() > MAX_FREE {
+            if let Some(i) = (0..free.len()).min_by_key(|&i| free[i].capacity()) {
+                free.swap_remove(i);
+            }
+        }
+    }
+
+    fn json(&self) -> serde_json::Value {
+        let free = self.free.lock().unwrap_or_else(|e| e.into_inner());
+        let bytes: usize = free.iter().map(|b| b.capacity() * std::mem::size_of::<T>()).sum();
+        json!({
+            "hits": self.hits.load(Ordering::Relaxed),
+            "misses": self.misses.load(Ordering::Relaxed),
+            "pooled": free.len(),
+            "pooled_bytes": bytes,
+        })
+    }
+}
+
+pub fn json() -> serde_json::Value {
+    json!({"bytes": BYTES.json(), "floats": FLOATS.json()})
+}
@@ -0,0 +1,257 @@
+use image::{Rgb, RgbImage};
+use std::{
+    process::{Command, Stdio},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{desktop, hands::ScopeRect, sim, vision};
+
+// Privacy filters applied to every live frame right after capture, before anything else
+// (snapshots, streams, OCR, recordings, the last-good cache) sees it, so the raw pixels never
+// leave the process.
+//
+// Text-free mode (SEEALLN_TEXT_FREE=1) smudges everything that looks like text while leaving
+// layout, colors, icons and images alone. Detection is a cheap per-block heuristic rather
+// than OCR: a BLOCK x BLOCK block is text when it has plenty of sharp luma steps both across
+// and down (glyph strokes) and enough contrast. Straight rules and borders only step one way
+// and are kept. Each horizontal run of text blocks (one block row of a line of text) is
+// filled with its average color, so word shapes disappear but the lines of text still show
+// where they were. Expect false positives on fine textures and noisy photos.
+//
+// Window exclusion (SEEALLN_CAPTURE_EXCLUDE="signal,keepassxc,1password") blacks out every
+// window whose title or process name contains one of the entries (case-insensitive), wherever
+// it is on screen. Window geometry is looked up per frame (reused for WINDOW_TTL) and padded
+// by WINDOW_PAD for frames taken while a window moves. Where it comes from:
+//
+//   simulation   the synthetic desktop's windows
+//   Linux (X11)  `wmctrl -lpG`, process names from /proc
+//   otherwise    the focused window only (`focus` feature)
+//
+// The whole window rectangle is blacked out, including parts covered by other windows. A
+// window that cannot be listed (Wayland without `focus`, or a listed app in the background
+// on macOS/Windows) is not masked; check with a snapshot before relying on it.
+const WINDOW_TTL: Duration = Duration::from_millis(200);
+const WINDOW_PAD: i32 = 8;
+const BLACK: Rgb<u8> = Rgb([0, 0, 0]);
+
+const BLOCK: u32 = 8;
+const STEP: u8 = 40;
+const MIN_STEPS: u32 = 6;
+const MIN_CONTRAST: u8 = 60;
+
+pub fn text_free() -> bool {
+    static ON: OnceLock<bool> = OnceLock::new();
+    *ON.get_or_init(|| std::env::var("SEEALLN_TEXT_FREE").is_ok_and(|v| v.trim() == "1"))
+}
+
+fn exclusions() -> &'static [String] {
+    static LIST: OnceLock<Vec<String>> = OnceLock::new();
+    LIST.get_or_init(|| {
+        std::env::var("SEEALLN_CAPTURE_EXCLUDE")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty())
+            .collect()
+    })
+}
+
+// Applies the configured filters to a freshly captured frame of `display`.
+pub fn apply(display: Option<usize>, img: &mut RgbImage) {
+    if !exclusions().is_empty() {
+        black_out_windows(display, img);
+    }
+    if text_free() {
+        scrub_text(img);
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WindowInfo {
+    title: String,
+    app: String,
+    rect: ScopeRect,
+}
+
+fn output(bin: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(bin).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+// `wmctrl -lpG` lines: id, desktop, pid, x, y, w, h, host, title...
+fn wmctrl_windows() -> Vec<WindowInfo> {
+    let Some(out) = output("wmctrl", &["-lpG"]) else {
+        return Vec::new();
+    };
+    out.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let n = |i: usize| fields.get(i)?.parse::<i32>().ok();
+            let rect = ScopeRect { x: n(3)?, y: n(4)?, w: n(5)?, h: n(6)? };
+            let title = fields.get(8..).map(|t| t.join(" ")).unwrap_or_default();
+            let app = std::fs::read_to_string(format!("/proc/{}/comm", fields.get(2)?)).unwrap_or_default();
+            Some(WindowInfo { title, app: app.trim().to_string(), rect })
+        })
+        .collect()
+}
+
+#[cfg(feature = "focus")]
+fn focused_window() -> Vec<WindowInfo> {
+    let Ok(w) = active_win_pos_rs::get_active_window() else {
+        return Vec::new();
+    };
+    let p = w.position;
+    let rect = ScopeRect { x: p.x as i32, y: p.y as i32, w: p.width as i32, h: p.height as i32 };
+    vec![WindowInfo { title: w.title, app: w.app_name, rect }]
+}
+
+#[cfg(not(feature = "focus"))]
+fn focused_window() -> Vec<WindowInfo> {
+    Vec::new()
+}
+
+fn list_windows() -> Vec<WindowInfo> {
+    if sim::active() {
+        return sim::windows().into_iter().map(|(title, rect)| WindowInfo { app: title.clone(), title, rect }).collect();
+    }
+    let listed = if cfg!(target_os = "linux") { wmctrl_windows() } else { Vec::new() };
+    if listed.is_empty() {
+        focused_window()
+    } else {
+        listed
+    }
+}
+
+// Desktop rectangles of the excluded windows, padded.
+fn excluded_rects() -> Vec<ScopeRect> {
+    static CACHE: Mutex<Option<(Instant, Vec<ScopeRect>)>> = Mutex::new(None);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, rects)) = cache.as_ref().filter(|(at, _)| at.elapsed() < WINDOW_TTL) {
+        return rects.clone();
+    }
+    let patterns = exclusions();
+    let rects: Vec<ScopeRect> = list_windows()
+        .into_iter()
+        .filter(|w| {
+            let (title, app) = (w.title.to_lowercase(), w.app.to_lowercase());
+            patterns.iter().any(|p| title.contains(p) || app.contains(p))
+        })
+        .map(|w| ScopeRect {
+            x: w.rect.x - WINDOW_PAD,
+            y: w.rect.y - WINDOW_PAD,
+            w: w.rect.w + 2 * WINDOW_PAD,
+            h: w.rect.h + 2 * WINDOW_PAD,
+        })
+        .collect();
+    *cache = Some((Instant::now(), rects.clone()));
+    rects
+}
+
+// Excluded windows currently on screen, for /health; None without SEEALLN_CAPTURE_EXCLUDE.
+pub fn excluded_count() -> Option<usize> {
+    (!exclusions().is_empty()).then(|| excluded_rects().len())
+}
+
+// Desktop position of the frame's top-left pixel: the primary display is at (0, 0); others
+// are looked up in the monitor layout, matched by size.
+fn frame_origin(display: Option<usize>, img: &RgbImage) -> (i32, i32) {
+    let Some(i) = display else {
+        return (0, 0);
+    };
+    let size = (img.width() as i32, img.height() as i32);
+    desktop::monitors()
+        .get(i)
+        .filter(|m| (m.w, m.h) == size)
+        .map_or((0, 0), |m| (m.x, m.y))
+}
+
+fn black_out_windows(display: Option<usize>, img: &mut RgbImage) {
+    let rects = excluded_rects();
+    if rects.is_empty() {
+        return;
+    }
+    let (ox, oy) = frame_origin(display, img);
+    let (w, h) = (img.width() as i32, img.height() as i32);
+    for r in rects {
+        let (x0, y0) = ((r.x - ox).clamp(0, w), (r.y - oy).clamp(0, h));
+        let (x1, y1) = ((r.x - ox + r.w).clamp(0, w), (r.y - oy + r.h).clamp(0, h));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                img.put_pixel(x as u32, y as u32, BLACK);
+            }
+        }
+    }
+}
+
+fn is_text(luma: &[u8], width: u32, bx: u32, by: u32, bw: u32, bh: u32) -> bool {
+    let at = |x: u32, y: u32| luma[(y * width + x) as usize];
+    let (mut across, mut down, mut lo, mut hi) = (0, 0, u8::MAX, 0);
+    for y in by..by + bh {
+        for x in bx..bx + bw {
+            let v = at(x, y);
+            (lo, hi) = (lo.min(v), hi.max(v));
+            if x + 1 < bx + bw && v.abs_diff(at(x + 1, y)) > STEP {
+                across += 1;
+            }
+            if y + 1 < by + bh && v.abs_diff(at(x, y + 1)) > STEP {
+                down += 1;
+            }
+        }
+    }
+    across >= MIN_STEPS && down >= MIN_STEPS && hi - lo >= MIN_CONTRAST
+}
+
+fn scrub_text(img: &mut RgbImage) {
+    let (w, h) = img.dimensions();
+    if w < BLOCK || h < BLOCK {
+        return;
+    }
+    let luma: Vec<u8> = img.pixels().map(|p| vision::luma(p.0)).collect();
+    let (cols, rows) = (w.div_ceil(BLOCK), h.div_ceil(BLOCK));
+    let mut text = vec![false; (cols * rows) as usize];
+    for row in 0..rows {
+        for col in 0..cols {
+            let (bx, by) = (col * BLOCK, row * BLOCK);
+            text[(row * cols + col) as usize] = is_text(&luma, w, bx, by, BLOCK.min(w - bx), BLOCK.min(h - by));
+        }
+    }
+    // Bridge single-block gaps (word spacing) so a line is smudged as one run.
+    for row in 0..rows {
+        for col in 1..cols.saturating_sub(1) {
+            let i = (row * cols + col) as usize;
+            if !text[i] && text[i - 1] && text[i + 1] {
+                text[i] = true;
+            }
+        }
+    }
+    for row in 0..rows {
+        let mut col = 0;
+        while col < cols {
+            if !text[(row * cols + col) as usize] {
+                col += 1;
+                continue;
+            }
+            let start = col;
+            while col < cols && text[(row * cols + col) as usize] {
+                col += 1;
+            }
+            let (x0, x1) = (start * BLOCK, (col * BLOCK).min(w));
+            let (y0, y1) = (row * BLOCK, ((row + 1) * BLOCK).min(h));
+            let mut sum = [0u64; 3];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let p = img.get_pixel(x, y).0;
+                    (0..3).for_each(|c| sum[c] += p[c] as u64);
+                }
+            }
+            let n = ((x1 - x0) * (y1 - y0)) as u64;
+            let fill = Rgb([(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8]);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    img.put_pixel(x, y, fill);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::Instant;
+use tracing::warn;
+
+use crate::interceptor::Action;
+
+// Token-bucket rate limit for hands actions. The bucket holds up to `max_actions` tokens and
+// refills continuously, from empty to full in `window_ms`, so sustained throughput matches a
+// fixed window without its 2x burst at window boundaries. Each action takes tokens by weight:
+// a click or key is 1, a move much less, typing 2 plus a share per character.
+//
+// Both start from the environment (SEEALLN_HANDS_MAX_ACTIONS, default 20;
+// SEEALLN_HANDS_WINDOW_MS, default 10000;
+// SEEALLN_HANDS_WEIGHTS="move=0.2,scroll=0.5,click=1,key=1,gesture=1,type=2,char=0.05,cdp=1",
+// any subset) and live in HandsState afterwards; PATCH /admin/limits changes them at runtime.
+// A cost above the capacity is capped to it, so a long text waits for a full bucket instead of
+// never running.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Limit {
+    pub max_actions: u32,
+    pub window_ms: u64,
+}
+
+pub const MAX_ACTIONS_RANGE: (u32, u32) = (1, 10_000);
+pub const WINDOW_MS_RANGE: (u64, u64) = (100, 3_600_000);
+
+impl Default for Limit {
+    fn default() -> Self {
+        Self {
+            max_actions: 20,
+            window_ms: 10_000,
+        }
+    }
+}
+
+impl Limit {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let max_actions = std::env::var("SEEALLN_HANDS_MAX_ACTIONS")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(default.max_actions);
+        let window_ms = std::env::var("SEEALLN_HANDS_WINDOW_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(default.window_ms);
+        Self {
+            max_actions: max_actions.clamp(MAX_ACTIONS_RANGE.0, MAX_ACTIONS_RANGE.1),
+            window_ms: window_ms.clamp(WINDOW_MS_RANGE.0, WINDOW_MS_RANGE.1),
+        }
+    }
+
+    fn capacity(self) -> f64 {
+        f64::from(self.max_actions)
+    }
+
+    fn refill_per_sec(self) -> f64 {
+        self.capacity() * 1000.0 / self.window_ms as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Weights {
+    #[serde(rename = "move")]
+    pub move_: f64,
+    pub scroll: f64,
+    pub click: f64,
+    pub key: f64,
+    pub gesture: f64,
+    // Type and paste: `type` plus `char` per character.
+    #[serde(rename = "type")]
+    pub type_: f64,
+    pub char: f64,
+    // /cdp/* browser actions.
+    pub cdp: f64,
+}
+
+pub const MAX_WEIGHT: f64 = 1000.0;
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            move_: 0.2,
+            scroll: 0.5,
+            click: 1.0,
+            key: 1.0,
+            gesture: 1.0,
+            type_: 2.0,
+            char: 0.05,
+            cdp: 1.0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn from_env() -> Self {
+        match std::env::var("SEEALLN_HANDS_WEIGHTS") {
+            Ok(spec) => Self::parse(&spec),
+            Err(_) => Self::default(),
+        }
+    }
+
+    // Defaults overridden by a `name=weight,...` spec; bad entries are logged and skipped.
+    fn parse(spec: &str) -> Self {
+        let mut weights = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let parsed = part.split_once('=').and_then(|(k, v)| Some((k.trim(), v.trim().parse::<f64>().ok()?)));
+            if let Err(err) = parsed.ok_or_else(|| "expected name=weight".to_string()).and_then(|(k, v)| weights.set(k, v)) {
+                warn!(part, %err, "ignoring SEEALLN_HANDS_WEIGHTS entry");
+            }
+        }
+        weights
+    }
+
+    pub fn set(&mut self, name: &str, weight: f64) -> Result<(), String> {
+        if !(0.0..=MAX_WEIGHT).contains(&weight) {
+            return Err(format!("weight for {name} must be between 0 and {MAX_WEIGHT}"));
+        }
+        let slot = match name {
+            "move" => &mut self.move_,
+            "scroll" => &mut self.scroll,
+            "click" => &mut self.click,
+            "key" => &mut self.key,
+            "gesture" => &mut self.gesture,
+            "type" => &mut self.type_,
+            "char" => &mut self.char,
+            "cdp" => &mut self.cdp,
+            _ => return Err(format!("unknown weight {name} (move, scroll, click, key, gesture, type, char, cdp)")),
+        };
+        *slot = weight;
+        Ok(())
+    }
+
+    pub fn cost(&self, action: &Action) -> f64 {
+        match action {
+            Action::Move { .. } => self.move_,
+            Action::Click { .. } => self.click,
+            Action::Key { .. } => self.key,
+            Action::Scroll { .. } => self.scroll,
+            Action::Gesture { .. } => self.gesture,
+            Action::Browser { .. } => self.cdp,
+            Action::Type { text } | Action::Paste { text } => self.type_ + self.char * text.chars().count() as f64,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Bucket {
+    // Tokens left and when that was measured; None = full.
+    level: Option<(f64, Instant)>,
+}
+
+impl Bucket {
+    // Tokens held after refilling up to `now`.
+    pub fn tokens(&self, limit: Limit, now: Instant) -> f64 {
+        match self.level {
+            None => limit.capacity(),
+            Some((tokens, at)) => {
+                (tokens + now.saturating_duration_since(at).as_secs_f64() * limit.refill_per_sec()).min(limit.capacity())
+            }
+        }
+    }
+
+    // Takes `cost` tokens if the bucket holds them after refilling up to `now`.
+    pub fn take(&mut self, limit: Limit, cost: f64, now: Instant) -> bool {
+        let tokens = self.tokens(limit, now);
+        let cost = cost.min(limit.capacity());
+        if tokens < cost {
+            self.level = Some((tokens, now));
+            return false;
+        }
+        self.level = Some((tokens - cost, now));
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const LIMIT: Limit = Limit {
+        max_actions: 10,
+        window_ms: 10_000,
+    };
+
+    #[test]
+    fn refills_over_time() {
+        let t0 = Instant::now();
+        let mut bucket = Bucket::default();
+        assert_eq!(bucket.tokens(LIMIT, t0), 10.0);
+        assert!(bucket.take(LIMIT, 10.0, t0));
+        assert!(!bucket.take(LIMIT, 1.0, t0));
+        // One token per second, never past capacity.
+        assert!((bucket.tokens(LIMIT, t0 + Duration::from_secs(3)) - 3.0).abs() < 1e-9);
+        assert!(bucket.take(LIMIT, 1.0, t0 + Duration::from_secs(1)));
+        assert_eq!(bucket.tokens(LIMIT, t0 + Duration::from_secs(60)), 10.0);
+    }
+
+    #[test]
+    fn cost_above_capacity_waits_for_a_full_bucket() {
+        let t0 = Instant::now();
+        let mut bucket = Bucket::default();
+        assert!(bucket.take(LIMIT, 1.0, t0));
+        assert!(!bucket.take(LIMIT, 500.0, t0));
+        let full = t0 + Duration::from_secs(1);
+        assert!(bucket.take(LIMIT, 500.0, full));
+        assert!(bucket.tokens(LIMIT, full).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejection_leaves_level_unchanged() {
+        let t0 = Instant::now();
+        let mut bucket = Bucket::default();
+        assert!(bucket.take(LIMIT, 8.0, t0));
+        let later = t0 + Duration::from_millis(500);
+        let before = bucket.tokens(LIMIT, later);
+        assert!(!bucket.take(LIMIT, 5.0, later));
+        assert!((bucket.tokens(LIMIT, later) - before).abs() < 1e-9);
+        assert!((bucket.tokens(LIMIT, later + Duration::from_secs(1)) - (before + 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weights_parse_and_reject() {
+        let weights = Weights::parse(" move=0.5, char=0.1 ,cdp=3,bogus=1,click=x,key=-1,type=2000,scroll");
+        assert_eq!(weights.move_, 0.5);
+        assert_eq!(weights.char, 0.1);
+        assert_eq!(weights.cdp, 3.0);
+        // Bad entries keep the defaults.
+        let default = Weights::default();
+        assert_eq!(weights.click, default.click);
+        assert_eq!(weights.key, default.key);
+        assert_eq!(weights.type_, default.type_);
+        assert_eq!(weights.scroll, default.scroll);
+
+        let mut weights = Weights::default();
+        assert!(weights.set("bogus", 1.0).is_err());
+        assert!(weights.set("move", -0.1).is_err());
+        assert!(weights.set("move", MAX_WEIGHT + 1.0).is_err());
+        assert!(weights.set("move", f64::NAN).is_err());
+        assert!(weights.set("move", 0.0).is_ok());
+
+        let text = Action::Type { text: "héllo".to_string() };
+        assert!((Weights::default().cost(&text) - 2.25).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,103 @@
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{path::Path, sync::Arc};
+use tracing::warn;
+
+use crate::interceptor::Action;
+
+// What audit entries say about typed and pasted text. By default only its length, a salted
+// SHA-256 (equal texts hash equally, so repeats can be correlated without storing them) and
+// a shape preview ("Xxxxx, 9999!" for "Hello, 2024!"):
+//
+//   {"kind":"type","chars":12,"sha256":"..","preview":"Xxxxx, 9999!"}
+//
+// The salt is SEEALLN_AUDIT_SALT, else 32 random bytes kept in <data dir>/audit.salt so
+// hashes stay comparable across restarts. SEEALLN_AUDIT_TYPED_TEXT=1 also records the raw
+// text; only for trusted environments where the audit trail must show exactly what was typed.
+#[derive(Clone, Default)]
+pub struct Redactor {
+    salt: Arc<[u8]>,
+    full_text: bool,
+}
+
+// Characters of the preview; longer texts end with "…".
+const PREVIEW_CHARS: usize = 16;
+
+impl Redactor {
+    pub fn load(data_dir: &Path) -> Self {
+        let full_text = std::env::var("SEEALLN_AUDIT_TYPED_TEXT").ok().as_deref() == Some("1");
+        if full_text {
+            warn!("SEEALLN_AUDIT_TYPED_TEXT=1: typed text is written to the audit log verbatim");
+        }
+        Self {
+            salt: salt(data_dir).into(),
+            full_text,
+        }
+    }
+
+    // `Action::summary` plus the redacted (or, when enabled, raw) text of type/paste actions.
+    pub fn action(&self, action: &Action) -> serde_json::Value {
+        self.describe(action, self.full_text)
+    }
+
+    // The same, never with the raw text whatever SEEALLN_AUDIT_TYPED_TEXT says (session recordings).
+    pub fn redacted(&self, action: &Action) -> serde_json::Value {
+        self.describe(action, false)
+    }
+
+    fn describe(&self, action: &Action, full_text: bool) -> serde_json::Value {
+        let mut summary = action.summary();
+        if let (Action::Type { text } | Action::Paste { text }, Some(fields)) = (action, summary.as_object_mut()) {
+            fields.insert("sha256".into(), json!(self.hash(text)));
+            fields.insert("preview".into(), json!(preview(text)));
+            if full_text {
+                fields.insert("text".into(), json!(text));
+            }
+        }
+        summary
+    }
+
+    fn hash(&self, text: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(&self.salt);
+        hasher.update(text.as_bytes());
+        hex::encode(hasher.finalize())
+    }
+}
+
+// Letters become x/X and digits 9; whitespace and ASCII punctuation are kept, anything else is *.
+fn preview(text: &str) -> String {
+    let mut out: String = text
+        .chars()
+        .take(PREVIEW_CHARS)
+        .map(|c| match c {
+            c if c.is_lowercase() => 'x',
+            c if c.is_uppercase() => 'X',
+            c if c.is_numeric() => '9',
+            c if c.is_whitespace() => ' ',
+            c if c.is_ascii_punctuation() => c,
+            _ => '*',
+        })
+        .collect();
+    if text.chars().nth(PREVIEW_CHARS).is_some() {
+        out.push('…');
+    }
+    out
+}
+
+fn salt(data_dir: &Path) -> Vec<u8> {
+    if let Some(salt) = std::env::var("SEEALLN_AUDIT_SALT").ok().filter(|s| !s.trim().is_empty()) {
+        return salt.into_bytes();
+    }
+    let path = data_dir.join("audit.salt");
+    if let Some(salt) = std::fs::read_to_string(&path).ok().and_then(|s| hex::decode(s.trim()).ok()) {
+        return salt;
+    }
+    let mut salt = vec![0u8; 32];
+    getrandom::getrandom(&mut salt).expect("os rng");
+    let written = std::fs::create_dir_all(data_dir).and_then(|_| std::fs::write(&path, hex::encode(&salt)));
+    if let Err(err) = written {
+        warn!(%err, path = %path.display(), "audit salt not saved; text hashes change on restart");
+    }
+    salt
+}
@@ -0,0 +1,241 @@
+use axum::{
+    body::Body,
+    http::{HeaderName, HeaderValue, Method, Request},
+    Router,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::mpsc, task::AbortHandle};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use tower::ServiceExt;
+use tracing::{info, warn};
+
+use crate::secrets::{self, Secret};
+
+// Reverse-connection mode (feature `relay`) for machines that cannot accept inbound
+// connections: with SEEALLN_RELAY_URL=wss://relay.example/agent set, the server does not
+// listen; it dials the relay over a WebSocket (Authorization: Bearer <relay_token>) and
+// serves the full API over it, many requests at a time. Every frame is JSON text:
+//
+//   relay -> agent  {"id": 7, "method": "GET", "path": "/snapshot.jpg", "headers": {...}, "body": "<base64>"}
+//                   {"id": 7, "cancel": true}
+//   agent -> relay  {"id": 7, "status": 200, "headers": {...}}, then {"id": 7, "data": "<base64>"}
+//                   per body chunk and {"id": 7, "end": true} (or {"id": 7, "error": "..."})
+//
+// Streams (/stream, /events) keep sending chunks until cancelled. Requests go through the same
+// router and middleware as local ones, so the eyes and hands keys are required: the relay
+// makes the API reachable from outside, and relay mode refuses to start without them.
+// Dropped connections are retried with backoff; in-flight requests are abandoned.
+const PING_EVERY: Duration = Duration::from_secs(20);
+// No frame (not even a pong) from the relay for this long means the connection is dead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Headers that describe the relay hop, not the request.
+const SKIP_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "host",
+    "keep-alive",
+    "te",
+    "transfer-encoding",
+    "upgrade",
+    "x-forwarded-for",
+];
+
+#[derive(Debug, Deserialize)]
+struct Frame {
+    id: u64,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    cancel: bool,
+}
+
+pub fn url() -> Option<String> {
+    std::env::var("SEEALLN_RELAY_URL").ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty())
+}
+
+// Relay mode needs the relay credential and both route class keys.
+pub fn check(url: &str) -> Result<(), String> {
+    if !url.starts_with("wss://") && !url.starts_with("ws://") {
+        return Err("SEEALLN_RELAY_URL must be wss://host/path".to_string());
+    }
+    let missing: Vec<&str> = [Secret::RelayToken, Secret::EyesKey, Secret::HandsKey]
+        .into_iter()
+        .filter(|s| secrets::get(*s).is_none())
+        .map(Secret::name)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("relay mode requires secrets: {}", missing.join(", ")));
+    }
+    if url.starts_with("ws://") {
+        warn!("relay connection is not encrypted (ws://); use wss:// outside of testing");
+    }
+    Ok(())
+}
+
+// Keeps a connection to the relay up; never returns.
+pub async fn run(url: String, app: Router) {
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        match connect(&url).await {
+            Ok(ws) => {
+                info!(%url, "connected to relay");
+                backoff = Duration::from_secs(1);
+                if let Err(err) = session(ws, &app).await {
+                    warn!(%err, "relay connection lost");
+                } else {
+                    info!("relay closed the connection");
+                }
+            }
+            Err(err) => warn!(%err, %url, "relay unreachable"),
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+type Ws = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+async fn connect(url: &str) -> Result<Ws, String> {
+    let mut req = url.into_client_request().map_err(|e| e.to_string())?;
+    let token = secrets::get(Secret::RelayToken).unwrap_or_default();
+    let auth = HeaderValue::from_str(&format!("Bearer {token}")).map_err(|_| "relay_token is not a valid header value")?;
+    req.headers_mut().insert("authorization", auth);
+    req.headers_mut().insert("x-seealln-version", HeaderValue::from_static(env!("CARGO_PKG_VERSION")));
+    let fut = tokio_tungstenite::connect_async(req);
+    let (ws, _) = tokio::time::timeout(Duration::from_secs(15), fut)
+        .await
+        .map_err(|_| "connect timeout".to_string())?
+        .map_err(|e| e.to_string())?;
+    Ok(ws)
+}
+
+async fn session(mut ws: Ws, app: &Router) -> Result<(), String> {
+    // Request tasks send their frames here; bounded, so a slow relay slows the streams down.
+    let (tx, mut rx) = mpsc::channel::<String>(64);
+    let tasks: Arc<Mutex<HashMap<u64, AbortHandle>>> = Arc::default();
+    let mut ping = tokio::time::interval(PING_EVERY);
+    let mut last_seen = tokio::time::Instant::now();
+    let result = loop {
+        tokio::select! {
+            Some(text) = rx.recv() => {
+                if let Err(err) = ws.send(Message::Text(text)).await {
+                    break Err(err.to_string());
+                }
+            }
+            msg = ws.next() => {
+                last_seen = tokio::time::Instant::now();
+                match msg {
+                    Some(Ok(Message::Text(text))) => dispatch(&text, app, &tx, &tasks),
+                    Some(Ok(Message::Ping(payload))) => {
+                        if let Err(err) = ws.send(Message::Pong(payload)).await {
+                            break Err(err.to_string());
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => break Ok(()),
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => break Err(err.to_string()),
+                }
+            }
+            _ = ping.tick() => {
+                if last_seen.elapsed() > IDLE_TIMEOUT {
+                    break Err("relay stopped responding".to_string());
+                }
+                if let Err(err) = ws.send(Message::Ping(Vec::new())).await {
+                    break Err(err.to_string());
+                }
+            }
+        }
+    };
+    for (_, task) in tasks.lock().unwrap_or_else(|e| e.into_inner()).drain() {
+        task.abort();
+    }
+    result
+}
+
+fn dispatch(text: &str, app: &Router, tx: &mpsc::Sender<String>, tasks: &Arc<Mutex<HashMap<u64, AbortHandle>>>) {
+    let frame: Frame = match serde_json::from_str(text) {
+        Ok(frame) => frame,
+        Err(err) => {
+            warn!(%err, "ignoring malformed relay frame");
+            return;
+        }
+    };
+    let id = frame.id;
+    if frame.cancel {
+        if let Some(task) = tasks.lock().unwrap_or_else(|e| e.into_inner()).remove(&id) {
+            task.abort();
+        }
+        return;
+    }
+    let (app, tx, registry) = (app.clone(), tx.clone(), tasks.clone());
+    // Held across the spawn so the task cannot deregister before it is registered.
+    let mut running = tasks.lock().unwrap_or_else(|e| e.into_inner());
+    let handle = tokio::spawn(async move {
+        if let Err(err) = serve(frame, app, &tx).await {
+            let _ = tx.send(json!({"id": id, "error": err}).to_string()).await;
+        }
+        registry.lock().unwrap_or_else(|e| e.into_inner()).remove(&id);
+    });
+    // A reused id replaces (and stops) the older request.
+    if let Some(old) = running.insert(id, handle.abort_handle()) {
+        old.abort();
+    }
+}
+
+fn build_request(frame: Frame) -> Result<Request<Body>, String> {
+    let method = Method::from_bytes(frame.method.as_deref().unwrap_or("GET").as_bytes()).map_err(|e| e.to_string())?;
+    let path = frame.path.filter(|p| p.starts_with('/')).ok_or("path must start with /")?;
+    let body = match frame.body {
+        Some(b64) => B64.decode(b64).map_err(|e| format!("body: {e}"))?,
+        None => Vec::new(),
+    };
+    let mut req = Request::builder().method(method).uri(path).body(Body::from(body)).map_err(|e| e.to_string())?;
+    for (name, value) in frame.headers {
+        let name = HeaderName::from_bytes(name.trim().to_ascii_lowercase().as_bytes()).map_err(|e| e.to_string())?;
+        if SKIP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        let value = HeaderValue::from_str(&value).map_err(|e| e.to_string())?;
+        req.headers_mut().append(name, value);
+    }
+    Ok(req)
+}
+
+async fn serve(frame: Frame, app: Router, tx: &mpsc::Sender<String>) -> Result<(), String> {
+    let id = frame.id;
+    let req = build_request(frame)?;
+    let resp = match app.oneshot(req).await {
+        Ok(resp) => resp,
+        Err(never) => match never {},
+    };
+    let headers: HashMap<&str, &str> = resp
+        .headers()
+        .iter()
+        .filter_map(|(k, v)| Some((k.as_str(), v.to_str().ok()?)))
+        .collect();
+    let head = json!({"id": id, "status": resp.status().as_u16(), "headers": headers}).to_string();
+    tx.send(head).await.map_err(|_| "relay gone")?;
+    let mut body = resp.into_body().into_data_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        tx.send(json!({"id": id, "data": B64.encode(&chunk)}).to_string()).await.map_err(|_| "relay gone")?;
+    }
+    tx.send(json!({"id": id, "end": true}).to_string()).await.map_err(|_| "relay gone")?;
+    Ok(())
+}
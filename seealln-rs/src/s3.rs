@@ -0,0 +1,271 @@
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+use crate::store::{civil_date, Area, Store};
+
+// Optional push of completed recordings and daily audit bundles to an S3-compatible bucket
+// (AWS, MinIO, R2, ...). Enabled when SEEALLN_S3_BUCKET and credentials are set.
+#[derive(Clone)]
+pub struct S3Config {
+    // e.g. https://s3.eu-west-1.amazonaws.com or http://127.0.0.1:9000
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    // Prepended to every key, e.g. "fleet/host-17/"
+    prefix: String,
+    recordings_prefix: String,
+    audit_prefix: String,
+    // Path-style URLs (endpoint/bucket/key) work with most self-hosted stores.
+    path_style: bool,
+    // Recordings untouched for this long are considered complete.
+    settle: Duration,
+    interval: Duration,
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+impl S3Config {
+    pub fn from_env() -> Option<Self> {
+        let bucket = env("SEEALLN_S3_BUCKET")?;
+        let access_key_id = env("SEEALLN_S3_ACCESS_KEY_ID").or_else(|| env("AWS_ACCESS_KEY_ID"))?;
+        let secret_access_key = env("SEEALLN_S3_SECRET_ACCESS_KEY").or_else(|| env("AWS_SECRET_ACCESS_KEY"))?;
+        let region = env("SEEALLN_S3_REGION").unwrap_or_else(|| "us-east-1".to_string());
+        let endpoint = env("SEEALLN_S3_ENDPOINT")
+            .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"))
+            .trim_end_matches('/')
+            .to_string();
+        let secs = |name: &str, default: u64| {
+            env(name).and_then(|s| s.parse().ok()).map(Duration::from_secs).unwrap_or(Duration::from_secs(default))
+        };
+        Some(Self {
+            endpoint,
+            bucket,
+            region,
+            access_key_id,
+            secret_access_key,
+            prefix: env("SEEALLN_S3_PREFIX").unwrap_or_default(),
+            recordings_prefix: env("SEEALLN_S3_RECORDINGS_PREFIX").unwrap_or_else(|| "recordings/".to_string()),
+            audit_prefix: env("SEEALLN_S3_AUDIT_PREFIX").unwrap_or_else(|| "audit/".to_string()),
+            path_style: env("SEEALLN_S3_PATH_STYLE").as_deref() != Some("0"),
+            settle: secs("SEEALLN_S3_SETTLE_SECS", 120),
+            interval: secs("SEEALLN_S3_INTERVAL_SECS", 300).max(Duration::from_secs(10)),
+        })
+    }
+}
+
+pub struct Uploader {
+    cfg: S3Config,
+    client: reqwest::Client,
+    store: Store,
+}
+
+impl Uploader {
+    pub fn new(cfg: S3Config, store: Store) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(120))
+            .build()
+            .expect("reqwest client");
+        Self { cfg, client, store }
+    }
+
+    // Runs forever: every interval, upload whatever is complete and not yet in the ledger.
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(self.cfg.interval);
+            loop {
+                tick.tick().await;
+                let (uploaded, failed) = self.sync_once().await;
+                if uploaded > 0 || failed > 0 {
+                    info!(uploaded, failed, bucket = %self.cfg.bucket, "s3: sync pass");
+                }
+            }
+        });
+    }
+
+    fn ledger_path(&self) -> PathBuf {
+        self.store.root().join(".s3-uploaded")
+    }
+
+    // Files that are ready to ship, as (path relative to the data dir, object key).
+    fn pending(&self, ledger: &BTreeSet<String>) -> Vec<(String, String)> {
+        let now = SystemTime::now();
+        let today = civil_date(now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs());
+        let mut out = Vec::new();
+
+        for (area, area_prefix) in [
+            (Area::Recordings, &self.cfg.recordings_prefix),
+            (Area::Audit, &self.cfg.audit_prefix),
+        ] {
+            let dir = self.store.dir(area);
+            let mut files = Vec::new();
+            walk(&dir, &mut files);
+            for path in files {
+                let Ok(rel) = path.strip_prefix(&dir) else { continue };
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                let ledger_key = format!("{}/{rel}", area.dir_name());
+                if ledger.contains(&ledger_key) {
+                    continue;
+                }
+                let complete = match area {
+                    // Daily bundles are complete once the day has rolled over.
+                    Area::Audit => rel.strip_suffix(".jsonl").map(|d| d < today.as_str()).unwrap_or(true),
+                    _ => fs::metadata(&path)
+                        .and_then(|m| m.modified())
+                        .map(|t| now.duration_since(t).unwrap_or_default() >= self.cfg.settle)
+                        .unwrap_or(false),
+                };
+                if complete {
+                    out.push((ledger_key, format!("{}{area_prefix}{rel}", self.cfg.prefix)));
+                }
+            }
+        }
+        out
+    }
+
+    pub async fn sync_once(&self) -> (u32, u32) {
+        let ledger_path = self.ledger_path();
+        let mut ledger: BTreeSet<String> = fs::read_to_string(&ledger_path)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+
+        let (mut uploaded, mut failed) = (0, 0);
+        for (rel, key) in self.pending(&ledger) {
+            let body = match fs::read(self.store.root().join(&rel)) {
+                Ok(b) => b,
+                Err(err) => {
+                    warn!(%err, file = %rel, "s3: read failed");
+                    failed += 1;
+                    continue;
+                }
+            };
+            match self.put_object(&key, body).await {
+                Ok(()) => {
+                    uploaded += 1;
+                    ledger.insert(rel);
+                }
+                Err(err) => {
+                    warn!(%err, %key, "s3: upload failed");
+                    failed += 1;
+                }
+            }
+        }
+
+        // Forget files retention already removed so the ledger doesn't grow forever.
+        let root = self.store.root().to_path_buf();
+        ledger.retain(|rel| root.join(rel).exists());
+        let contents: String = ledger.iter().map(|l| format!("{l}\n")).collect();
+        if let Err(err) = fs::write(&ledger_path, contents) {
+            warn!(%err, "s3: ledger write failed");
+        }
+        (uploaded, failed)
+    }
+
+    async fn put_object(&self, key: &str, body: Vec<u8>) -> Result<(), String> {
+        let cfg = &self.cfg;
+        let (scheme, host_base) = cfg.endpoint.split_once("://").unwrap_or(("https", cfg.endpoint.as_str()));
+        let key_path = uri_encode(key, false);
+        let (host, path) = if cfg.path_style {
+            (host_base.to_string(), format!("/{}/{key_path}", uri_encode(&cfg.bucket, true)))
+        } else {
+            (format!("{}.{host_base}", cfg.bucket), format!("/{key_path}"))
+        };
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let amz_date = amz_datetime(now);
+        let date = &amz_date[..8];
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "PUT\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\nhost;x-amz-content-sha256;x-amz-date\n{payload_hash}"
+        );
+        let scope = format!("{date}/{}/s3/aws4_request", cfg.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let k_date = hmac(format!("AWS4{}", cfg.secret_access_key).as_bytes(), date.as_bytes());
+        let k_region = hmac(&k_date, cfg.region.as_bytes());
+        let k_service = hmac(&k_region, b"s3");
+        let k_signing = hmac(&k_service, b"aws4_request");
+        let signature = hex::encode(hmac(&k_signing, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}",
+            cfg.access_key_id
+        );
+
+        let resp = self
+            .client
+            .put(format!("{scheme}://{host}{path}"))
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-content-sha256", &payload_hash)
+            .header("authorization", authorization)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if resp.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("status {}", resp.status()))
+        }
+    }
+}
+
+fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => walk(&path, out),
+            Ok(t) if t.is_file() => out.push(path),
+            _ => {}
+        }
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+// RFC 3986 unreserved characters pass through; '/' too unless encoding a single segment.
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            b'/' if !encode_slash => out.push('/'),
+            _ => out.push_str(&format!("%{b:02X}")),
+        }
+    }
+    out
+}
+
+// YYYYMMDDTHHMMSSZ
+fn amz_datetime(unix_secs: u64) -> String {
+    let secs_of_day = unix_secs % 86_400;
+    format!(
+        "{}T{:02}{:02}{:02}Z",
+        civil_date(unix_secs).replace('-', ""),
+        secs_of_day / 3600,
+        (secs_of_day / 60) % 60,
+        secs_of_day % 60
+    )
+}
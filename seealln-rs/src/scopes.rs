@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::warn;
+
+use crate::hands::{confirmed, require_local_only, HandsState, ScopeRect};
+
+// Named scope presets ("browser-left-half", "approval-dialog-area"), selectable by name in
+// /scope/set and /hands/arm?scope=. Kept in SEEALLN_SCOPES_FILE (default
+// <data dir>/scopes.json, a plain JSON object name -> preset) so they survive restarts;
+// the file can also be written by hand before startup.
+//
+//   GET    /scopes            all presets
+//   POST   /scopes/:name      {scope, confine_capture?}   create or replace
+//   DELETE /scopes/:name
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Preset {
+    pub scope: ScopeRect,
+    // Overrides SEEALLN_SCOPE_CONFINE_CAPTURE when the preset is applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confine_capture: Option<bool>,
+}
+
+#[derive(Clone, Default)]
+pub struct Presets {
+    // None = in memory only.
+    path: Option<PathBuf>,
+    map: Arc<Mutex<BTreeMap<String, Preset>>>,
+}
+
+const MAX_NAME: usize = 64;
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+impl Presets {
+    pub fn load(data_dir: &std::path::Path) -> Self {
+        let path = std::env::var("SEEALLN_SCOPES_FILE")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir.join("scopes.json"));
+        let map = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice::<BTreeMap<String, Preset>>(&bytes).unwrap_or_else(|err| {
+                warn!(%err, path = %path.display(), "ignoring unreadable scope presets");
+                BTreeMap::new()
+            }),
+            Err(_) => BTreeMap::new(),
+        };
+        let map = map
+            .into_iter()
+            .filter(|(name, p)| valid_name(name) && p.scope.w > 0 && p.scope.h > 0)
+            .collect();
+        Self {
+            path: Some(path),
+            map: Arc::new(Mutex::new(map)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Preset> {
+        self.map.lock().unwrap_or_else(|e| e.into_inner()).get(name).copied()
+    }
+
+    pub fn all(&self) -> BTreeMap<String, Preset> {
+        self.map.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Write-then-rename so a crash never leaves a truncated file.
+    fn save(&self, map: &BTreeMap<String, Preset>) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let body = serde_json::to_vec_pretty(map).map_err(|e| e.to_string())?;
+        std::fs::write(&tmp, body).and_then(|_| std::fs::rename(&tmp, path)).map_err(|e| e.to_string())
+    }
+
+    pub fn put(&self, name: &str, preset: Preset) -> Result<(), String> {
+        let mut map = self.map.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = map.insert(name.to_string(), preset);
+        if let Err(err) = self.save(&map) {
+            match previous {
+                Some(p) => {
+                    map.insert(name.to_string(), p);
+                }
+                None => {
+                    map.remove(name);
+                }
+            }
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    // Ok(false) when there was no such preset.
+    pub fn remove(&self, name: &str) -> Result<bool, String> {
+        let mut map = self.map.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(previous) = map.remove(name) else {
+            return Ok(false);
+        };
+        if let Err(err) = self.save(&map) {
+            map.insert(name.to_string(), previous);
+            return Err(err);
+        }
+        Ok(true)
+    }
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+pub async fn scopes_list(State(state): State<HandsState>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    (StatusCode::OK, Json(json!({"ok": true, "scopes": state.presets().all()}))).into_response()
+}
+
+pub async fn scopes_put(
+    State(state): State<HandsState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Json(preset): Json<Preset>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    if !valid_name(&name) {
+        return fail(StatusCode::BAD_REQUEST, format!("scope names are 1-{MAX_NAME} of [A-Za-z0-9_-]"));
+    }
+    if preset.scope.w <= 0 || preset.scope.h <= 0 {
+        return fail(StatusCode::BAD_REQUEST, "invalid scope");
+    }
+    if let Err(err) = state.presets().put(&name, preset) {
+        return fail(StatusCode::INTERNAL_SERVER_ERROR, format!("saving scope presets: {err}"));
+    }
+    state.audit(json!({"event": "scope_preset_saved", "name": name, "preset": preset}));
+    (StatusCode::OK, Json(json!({"ok": true, "name": name, "preset": preset}))).into_response()
+}
+
+pub async fn scopes_delete(State(state): State<HandsState>, Path(name): Path<String>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    match state.presets().remove(&name) {
+        Ok(true) => {
+            state.audit(json!({"event": "scope_preset_deleted", "name": name}));
+            (StatusCode::OK, Json(json!({"ok": true}))).into_response()
+        }
+        Ok(false) => fail(StatusCode::NOT_FOUND, format!("unknown scope preset {name}")),
+        Err(err) => fail(StatusCode::INTERNAL_SERVER_ERROR, format!("saving scope presets: {err}")),
+    }
+}
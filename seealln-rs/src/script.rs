@@ -0,0 +1,392 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use image::RgbImage;
+use serde::Deserialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+
+use crate::{
+    capture::{capture_rgb, encode_jpeg},
+    confirm::Intent,
+    hands::{require_local_only, HandsState, ScopeRect},
+    interceptor::Action,
+    ocr,
+    origin::Origin,
+    session,
+    store::{Area, Store},
+    vision::{self, Match},
+};
+
+// Declarative automation scripts, YAML or JSON:
+//
+//   name: save-report
+//   defaults: {retries: 2, retry_delay_ms: 500}
+//   artifacts: true              # keep a frame after every step
+//   steps:
+//     - wait_for: {target: {text: "Report ready"}, timeout_ms: 10000}
+//     - click: {target: {template: "save.png", threshold: 0.9}}
+//     - type: {text: "q3-summary"}
+//     - wait: {ms: 300}
+//     - move: {x: 10, y: 10}
+//     - assert: {region: {x: 0, y: 0, w: 400, h: 80}, contains: "Saved"}
+//
+// Targets are located on a fresh capture by template (file under SEEALLN_TEMPLATE_DIR or
+// "base64:...") or by OCR text, optionally restricted to a region. Every action goes through
+// the same arming/rate-limit/interceptor pipeline as the /hands endpoints. Assertions check
+// the screen without acting and fail the run with a crop of the region as evidence.
+#[derive(Debug, Deserialize)]
+pub struct Script {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub defaults: Defaults,
+    #[serde(default)]
+    pub artifacts: bool,
+    pub steps: Vec<Step>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct Defaults {
+    pub retries: Option<u32>,
+    pub retry_delay_ms: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Step {
+    pub name: Option<String>,
+    pub retries: Option<u32>,
+    #[serde(flatten)]
+    pub op: Op,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Op {
+    Move {
+        x: Option<i32>,
+        y: Option<i32>,
+        target: Option<Target>,
+    },
+    Click {
+        button: Option<String>,
+        // Moves to the target's center first.
+        target: Option<Target>,
+    },
+    Type {
+        text: String,
+    },
+    Wait {
+        ms: u64,
+    },
+    WaitFor {
+        target: Target,
+        timeout_ms: Option<u64>,
+    },
+    Assert(Check),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Target {
+    pub template: Option<String>,
+    pub text: Option<String>,
+    pub region: Option<ScopeRect>,
+    // Minimum template match score (0..1), default 0.8.
+    pub threshold: Option<f32>,
+}
+
+// All given conditions must hold on a single capture of `region` (whole screen when None).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Check {
+    pub region: Option<ScopeRect>,
+    // The region contains this template with a score of at least `threshold` (default 0.9).
+    pub template: Option<String>,
+    pub threshold: Option<f32>,
+    // OCR text of the region contains this (case-insensitive).
+    pub contains: Option<String>,
+}
+
+impl Check {
+    pub fn evaluate(&self, screen: &RgbImage) -> Result<serde_json::Value, String> {
+        if self.template.is_none() && self.contains.is_none() {
+            return Err("assert needs `template` or `contains`".to_string());
+        }
+        let mut detail = json!({});
+        if let Some(tpl) = &self.template {
+            let template = vision::load_template(tpl)?;
+            let threshold = self.threshold.unwrap_or(0.9);
+            let score = vision::find_template(screen, &template, self.region).map(|m| m.score).unwrap_or(0.0);
+            detail["score"] = json!(score);
+            if score < threshold {
+                return Err(format!("template scored {score:.2} < {threshold:.2}"));
+            }
+        }
+        if let Some(needle) = &self.contains {
+            let (img, _, _) = vision::crop(screen, self.region);
+            let seen = ocr::text(&img)?;
+            if !seen.to_lowercase().contains(&needle.to_lowercase()) {
+                let snippet: String = seen.chars().take(200).collect();
+                return Err(format!("text {needle:?} not found (saw {snippet:?})"));
+            }
+            detail["text"] = json!(needle);
+        }
+        Ok(detail)
+    }
+}
+
+// A failed attempt; assertions attach the evidence crop they stored.
+pub struct Failure {
+    pub error: String,
+    pub evidence: Option<String>,
+}
+
+impl From<String> for Failure {
+    fn from(error: String) -> Self {
+        Self { error, evidence: None }
+    }
+}
+
+const MAX_STEPS: usize = 500;
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+impl Target {
+    // Locates the target on a fresh capture (blocking: capture + matching/OCR).
+    pub fn locate_blocking(&self) -> Result<Option<Match>, String> {
+        let screen = capture_rgb()?;
+        self.locate_in(&screen)
+    }
+
+    pub fn locate_in(&self, screen: &RgbImage) -> Result<Option<Match>, String> {
+        match (&self.template, &self.text) {
+            (Some(tpl), _) => {
+                let template = vision::load_template(tpl)?;
+                let threshold = self.threshold.unwrap_or(0.8);
+                Ok(vision::find_template(screen, &template, self.region).filter(|m| m.score >= threshold))
+            }
+            (None, Some(text)) => {
+                let (img, ox, oy) = vision::crop(screen, self.region);
+                ocr::find_text(&img, text, ox, oy)
+            }
+            (None, None) => Err("target needs `template` or `text`".to_string()),
+        }
+    }
+
+    pub async fn locate(&self) -> Result<Option<Match>, String> {
+        let target = self.clone();
+        tokio::task::spawn_blocking(move || target.locate_blocking())
+            .await
+            .map_err(|e| e.to_string())?
+    }
+}
+
+pub struct Runner<'a> {
+    pub state: &'a HandsState,
+    pub store: &'a Store,
+    pub token: &'a str,
+    pub origin: &'a Origin,
+    pub run_id: String,
+}
+
+impl Runner<'_> {
+    async fn act(&self, action: Action) -> Result<serde_json::Value, String> {
+        self.state.consume_action(self.token, self.origin, &action).map_err(str::to_string)?;
+        self.state
+            .perform(self.token, action)
+            .await
+            .map(|a| a.summary())
+            .map_err(|(_, e)| e)
+    }
+
+    async fn resolve(&self, target: &Target) -> Result<Match, String> {
+        target.locate().await?.ok_or_else(|| "target not found".to_string())
+    }
+
+    // One attempt at one step; Ok carries step details for the report.
+    pub async fn exec(&self, index: usize, op: &Op) -> Result<serde_json::Value, Failure> {
+        match op {
+            Op::Move { x, y, target } => {
+                let (x, y, found) = match (target, x, y) {
+                    (Some(t), _, _) => {
+                        let m = self.resolve(t).await?;
+                        let (cx, cy) = m.center();
+                        (cx, cy, Some(m))
+                    }
+                    (None, Some(x), Some(y)) => (*x, *y, None),
+                    _ => return Err("move needs x/y or target".to_string().into()),
+                };
+                let action = self.act(Action::Move { x, y }).await?;
+                Ok(json!({"action": action, "target": found}))
+            }
+            Op::Click { button, target } => {
+                let mut found = None;
+                if let Some(t) = target {
+                    let m = self.resolve(t).await?;
+                    let (x, y) = m.center();
+                    self.act(Action::Move { x, y }).await?;
+                    found = Some(m);
+                }
+                let button = button.clone().unwrap_or_else(|| "left".to_string());
+                let action = self.act(Action::Click { button }).await?;
+                Ok(json!({"action": action, "target": found}))
+            }
+            Op::Type { text } => {
+                let action = self.act(Action::Type { text: text.clone() }).await?;
+                Ok(json!({"action": action}))
+            }
+            Op::Wait { ms } => {
+                tokio::time::sleep(Duration::from_millis(*ms).min(MAX_WAIT)).await;
+                Ok(json!({"waited_ms": ms}))
+            }
+            Op::WaitFor { target, timeout_ms } => {
+                let timeout = Duration::from_millis(timeout_ms.unwrap_or(10_000)).min(MAX_WAIT);
+                let started = Instant::now();
+                loop {
+                    if let Some(m) = target.locate().await? {
+                        return Ok(json!({"target": m, "waited_ms": started.elapsed().as_millis() as u64}));
+                    }
+                    if started.elapsed() >= timeout {
+                        return Err(format!("target not found within {}ms", timeout.as_millis()).into());
+                    }
+                    tokio::time::sleep(Duration::from_millis(250)).await;
+                }
+            }
+            Op::Assert(check) => {
+                let check = check.clone();
+                let store = self.store.clone();
+                let rel = format!("scripts/{}/step-{index:03}-evidence.jpg", self.run_id);
+                tokio::task::spawn_blocking(move || {
+                    let screen = capture_rgb()?;
+                    check.evaluate(&screen).map_err(|error| {
+                        let (crop, _, _) = vision::crop(&screen, check.region);
+                        let evidence = encode_jpeg(&crop, 80)
+                            .ok()
+                            .and_then(|jpeg| store.write(Area::Recordings, &rel, &jpeg).ok())
+                            .map(|_| format!("recordings/{rel}"));
+                        Failure { error, evidence }
+                    })
+                })
+                .await
+                .map_err(|e| Failure::from(e.to_string()))?
+            }
+        }
+    }
+
+    // Frame after a step, stored under recordings/scripts/<run>/ (exportable via /admin/export).
+    async fn artifact(&self, index: usize) -> Option<String> {
+        let rel = format!("scripts/{}/step-{index:03}.jpg", self.run_id);
+        let store = self.store.clone();
+        let path = rel.clone();
+        tokio::task::spawn_blocking(move || {
+            let jpeg = encode_jpeg(&capture_rgb().ok()?, 70).ok()?;
+            store.write(Area::Recordings, &path, &jpeg).ok()
+        })
+        .await
+        .ok()
+        .flatten()
+        .map(|_| format!("recordings/{rel}"))
+    }
+
+    pub async fn run(&self, script: &Script) -> (bool, Vec<serde_json::Value>) {
+        let delay = Duration::from_millis(script.defaults.retry_delay_ms.unwrap_or(500));
+        let mut results = Vec::with_capacity(script.steps.len());
+        let mut ok = true;
+
+        for (index, step) in script.steps.iter().enumerate() {
+            if !ok {
+                results.push(json!({"index": index, "name": step.name, "skipped": true}));
+                continue;
+            }
+            let attempts = step.retries.or(script.defaults.retries).unwrap_or(0).min(10) + 1;
+            let mut outcome = Err(Failure::from(String::new()));
+            let mut used = 0;
+            for attempt in 1..=attempts {
+                used = attempt;
+                outcome = self.exec(index, &step.op).await;
+                // Never retry past the kill switch.
+                if outcome.is_ok() || self.state.is_killed() {
+                    break;
+                }
+                if attempt < attempts {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+
+            let artifact = if script.artifacts { self.artifact(index).await } else { None };
+            let mut result = json!({"index": index, "name": step.name, "ok": outcome.is_ok(), "attempts": used, "artifact": artifact});
+            match outcome {
+                Ok(detail) => result["detail"] = detail,
+                Err(failure) => {
+                    ok = false;
+                    result["error"] = json!(failure.error);
+                    if failure.evidence.is_some() {
+                        result["evidence"] = json!(failure.evidence);
+                    }
+                }
+            }
+            results.push(result);
+        }
+        (ok, results)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunReq {
+    token: String,
+    // A script object, or a YAML/JSON document as a string.
+    script: serde_json::Value,
+}
+
+fn parse_script(v: serde_json::Value) -> Result<Script, String> {
+    match v {
+        serde_json::Value::String(doc) => serde_yaml::from_str(&doc).map_err(|e| e.to_string()),
+        other => serde_json::from_value(other).map_err(|e| e.to_string()),
+    }
+}
+
+pub async fn scripts_run(
+    State(state): State<HandsState>,
+    State(store): State<Store>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<RunReq>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err(msg) = state.check_confirmation(&headers, &req.token, Intent::Route("/scripts/run")) {
+        return (StatusCode::PRECONDITION_REQUIRED, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let script = match parse_script(req.script) {
+        Ok(s) if s.steps.len() <= MAX_STEPS => s,
+        Ok(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": format!("too many steps (max {MAX_STEPS})")})))
+                .into_response()
+        }
+        Err(err) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": format!("invalid script: {err}")})))
+                .into_response()
+        }
+    };
+    if !state.is_armed(&req.token) {
+        return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": "not armed"}))).into_response();
+    }
+
+    let runner = Runner {
+        state: &state,
+        store: &store,
+        token: &req.token,
+        origin: &origin,
+        run_id: session::new_id(),
+    };
+    state.audit(json!({"event": "script_start", "run": runner.run_id, "name": script.name, "steps": script.steps.len()}));
+    let (ok, steps) = runner.run(&script).await;
+    state.audit(json!({"event": "script_end", "run": runner.run_id, "ok": ok}));
+
+    let report = json!({"ok": ok, "run": runner.run_id, "name": script.name, "steps": steps});
+    if script.artifacts {
+        let _ = store.write(Area::Recordings, &format!("scripts/{}/report.json", runner.run_id), report.to_string().as_bytes());
+    }
+    (StatusCode::OK, Json(report)).into_response()
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::HashMap,
+    io::BufRead,
+    sync::{Mutex, OnceLock},
+};
+
+// Secrets live in the platform keychain / credential manager (feature `keychain`):
+// macOS Keychain, Windows Credential Manager, Linux kernel keyutils. The matching
+// SEEALLN_* env var is still honored as a fallback so existing setups keep working,
+// but it leaks via `ps e`/crash dumps and should be migrated with `seealln secrets set`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Secret {
+    ApiKey,
+    Hmac,
+    WebhookKey,
+    StoreKey,
+    EyesKey,
+    HandsKey,
+    RelayToken,
+    OverrideKey,
+    SigningKey,
+}
+
+#[cfg(feature = "keychain")]
+const SERVICE: &str = "seealln";
+
+impl Secret {
+    pub const ALL: [Secret; 9] = [
+        Secret::ApiKey,
+        Secret::Hmac,
+        Secret::WebhookKey,
+        Secret::StoreKey,
+        Secret::EyesKey,
+        Secret::HandsKey,
+        Secret::RelayToken,
+        Secret::OverrideKey,
+        Secret::SigningKey,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Secret::ApiKey => "api_key",
+            Secret::Hmac => "hmac_secret",
+            Secret::WebhookKey => "webhook_key",
+            Secret::StoreKey => "store_key",
+            Secret::EyesKey => "eyes_key",
+            Secret::HandsKey => "hands_key",
+            Secret::RelayToken => "relay_token",
+            Secret::OverrideKey => "override_key",
+            Secret::SigningKey => "signing_key",
+        }
+    }
+
+    pub fn env_var(self) -> &'static str {
+        match self {
+            Secret::ApiKey => "SEEALLN_API_KEY",
+            Secret::Hmac => "SEEALLN_HMAC_SECRET",
+            Secret::WebhookKey => "SEEALLN_WEBHOOK_KEY",
+            Secret::StoreKey => "SEEALLN_STORE_KEY",
+            Secret::EyesKey => "SEEALLN_EYES_KEY",
+            Secret::HandsKey => "SEEALLN_HANDS_KEY",
+            Secret::RelayToken => "SEEALLN_RELAY_TOKEN",
+            Secret::OverrideKey => "SEEALLN_OVERRIDE_KEY",
+            Secret::SigningKey => "SEEALLN_SIGNING_KEY",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Secret> {
+        Secret::ALL.into_iter().find(|k| k.name() == s)
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<Secret, Option<String>>> {
+    static CACHE: OnceLock<Mutex<HashMap<Secret, Option<String>>>> = OnceLock::new();
+    CACHE.get_or_init(Mutex::default)
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_get(secret: Secret) -> Option<String> {
+    let entry = keyring::Entry::new(SERVICE, secret.name()).ok()?;
+    match entry.get_password() {
+        Ok(v) => Some(v),
+        Err(keyring::Error::NoEntry) => None,
+        Err(err) => {
+            tracing::warn!(%err, secret = secret.name(), "keychain lookup failed");
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_get(_secret: Secret) -> Option<String> {
+    None
+}
+
+// Keychain first, then env. Resolved once per process: keychain lookups can be slow
+// (and on macOS may prompt), so they must not happen per request.
+pub fn get(secret: Secret) -> Option<String> {
+    let mut cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(secret)
+        .or_insert_with(|| {
+            keychain_get(secret).or_else(|| {
+                std::env::var(secret.env_var())
+                    .ok()
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+            })
+        })
+        .clone()
+}
+
+fn usage() -> i32 {
+    eprintln!("usage: seealln secrets <set|delete|status> [name]");
+    eprintln!("names: {}", Secret::ALL.map(Secret::name).join(", "));
+    eprintln!("`set` reads the value from stdin so it never appears in argv or shell history.");
+    2
+}
+
+// `seealln secrets ...`; returns the process exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let (cmd, name) = (args.first().map(String::as_str), args.get(1).map(String::as_str));
+    match (cmd, name.map(Secret::parse)) {
+        (Some("status"), _) => {
+            for s in Secret::ALL {
+                let source = if keychain_get(s).is_some() {
+                    "keychain"
+                } else if std::env::var(s.env_var()).is_ok() {
+                    "env (migrate with `seealln secrets set`)"
+                } else {
+                    "unset"
+                };
+                println!("{:<12} {source}", s.name());
+            }
+            0
+        }
+        (Some("set"), Some(Some(secret))) => {
+            let mut value = String::new();
+            if std::io::stdin().lock().read_line(&mut value).is_err() || value.trim().is_empty() {
+                eprintln!("no value on stdin");
+                return 1;
+            }
+            store(secret, Some(value.trim()))
+        }
+        (Some("delete"), Some(Some(secret))) => store(secret, None),
+        _ => usage(),
+    }
+}
+
+#[cfg(feature = "keychain")]
+fn store(secret: Secret, value: Option<&str>) -> i32 {
+    let res = keyring::Entry::new(SERVICE, secret.name()).and_then(|entry| match value {
+        Some(v) => entry.set_password(v),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        },
+    });
+    match res {
+        Ok(()) => {
+            println!("{}: {}", secret.name(), if value.is_some() { "stored in keychain" } else { "deleted" });
+            0
+        }
+        Err(err) => {
+            eprintln!("{}: keychain error: {err}", secret.name());
+            1
+        }
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+fn store(secret: Secret, _value: Option<&str>) -> i32 {
+    eprintln!("{}: built without the `keychain` feature", secret.name());
+    1
+}
@@ -0,0 +1,185 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use image::RgbImage;
+use serde_json::{json, Value};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    capture::{self, Fallback},
+    hands::{confirmed, require_local_only, HandsState},
+    interceptor::Action,
+};
+
+// POST /selftest: an end-to-end check of eyes and hands for an orchestrator to run before it
+// dispatches a task, beyond what /health probes. It captures a frame, moves the pointer DELTA
+// px and back through the normal action path (interceptors, audit), and reports:
+//
+//   capture  a frame of the primary display came back, and how long it took
+//   pointer  the backend reports the pointer where it was moved (skipped when the backend
+//            cannot read it, e.g. ydotool)
+//   frame    the pixels around the pointer changed after the move; inconclusive rather than
+//            failed when unchanged, since most capture paths leave the cursor out
+//   restore  the pointer is back where it started
+//
+// Local-only, needs `x-seealln-confirm: yes`, one run at a time, and refused while killed or
+// armed: an armed agent owns the pointer.
+const DELTA: i32 = 8;
+// Time the OS gets to apply a move before it is read back.
+const SETTLE: Duration = Duration::from_millis(50);
+// Pixels around the pointer compared between frames.
+const PATCH: i32 = 24;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+struct Running;
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+async fn grab() -> Result<(RgbImage, Duration), String> {
+    tokio::task::spawn_blocking(|| {
+        let started = Instant::now();
+        capture::capture_frame(None, Fallback::Fail).map(|f| (f.image, started.elapsed()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+}
+
+async fn location(state: &HandsState) -> Result<(i32, i32), String> {
+    let input = state.input().clone();
+    tokio::task::spawn_blocking(move || input.location()).await.unwrap_or_else(|e| Err(e.to_string()))
+}
+
+// Pixels that differ between the two frames in the squares around `points`, None when a point
+// is off the (primary display's) frame or the frames differ in size.
+fn changed_near(before: &RgbImage, after: &RgbImage, points: [(i32, i32); 2]) -> Option<u64> {
+    if before.dimensions() != after.dimensions() {
+        return None;
+    }
+    let (w, h) = (before.width() as i32, before.height() as i32);
+    let mut changed = 0;
+    for (px, py) in points {
+        if px < 0 || py < 0 || px >= w || py >= h {
+            return None;
+        }
+        for y in (py - PATCH).max(0)..(py + PATCH).min(h) {
+            for x in (px - PATCH).max(0)..(px + PATCH).min(w) {
+                if before.get_pixel(x as u32, y as u32) != after.get_pixel(x as u32, y as u32) {
+                    changed += 1;
+                }
+            }
+        }
+    }
+    Some(changed)
+}
+
+fn step(ok: Option<bool>, detail: Value) -> Value {
+    let status = match ok {
+        Some(true) => "ok",
+        Some(false) => "fail",
+        None => "skipped",
+    };
+    let mut step = json!({"status": status});
+    if let (Some(step), Some(detail)) = (step.as_object_mut(), detail.as_object()) {
+        step.extend(detail.clone());
+    }
+    step
+}
+
+pub async fn selftest(State(state): State<HandsState>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    if state.is_killed() {
+        return fail(StatusCode::CONFLICT, "killed; POST /safety/reset first");
+    }
+    if state.arming_status()["state"] == "armed" {
+        return fail(StatusCode::CONFLICT, "hands are armed; run the self-test before arming or after disarming");
+    }
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return fail(StatusCode::CONFLICT, "a self-test is already running");
+    }
+    let _running = Running;
+    let started = Instant::now();
+
+    let (before, capture_step) = match grab().await {
+        Ok((image, took)) => {
+            let detail = json!({"width": image.width(), "height": image.height(), "ms": took.as_millis() as u64});
+            (Some(image), step(Some(true), detail))
+        }
+        Err(err) => (None, step(Some(false), json!({"error": err}))),
+    };
+
+    // Start from where the OS has the pointer, else from where the last action left it, else
+    // the middle of the primary display.
+    let read = location(&state).await;
+    let readable = read.is_ok();
+    let start = read.ok().or_else(|| state.pointer()).unwrap_or_else(|| {
+        let d = capture::displays().first().map_or((0, 0), |d| (d.width as i32, d.height as i32));
+        (d.0 / 2, d.1 / 2)
+    });
+    let width = before.as_ref().map_or(i32::MAX, |img| img.width() as i32);
+    let target = if start.0 + DELTA < width { (start.0 + DELTA, start.1) } else { (start.0 - DELTA, start.1) };
+
+    let moved = state.perform_untracked(Action::Move { x: target.0, y: target.1 }).await;
+    tokio::time::sleep(SETTLE).await;
+    let pointer_step = match (&moved, readable) {
+        (Err((_, err)), _) => step(Some(false), json!({"error": err, "target": target})),
+        (Ok(_), false) => step(None, json!({"reason": "the input backend cannot read the pointer position", "target": target})),
+        (Ok(_), true) => match location(&state).await {
+            Ok(at) => step(Some(near(at, target)), json!({"target": target, "observed": at})),
+            Err(err) => step(Some(false), json!({"error": err, "target": target})),
+        },
+    };
+
+    let after = if moved.is_ok() { grab().await.ok().map(|(image, _)| image) } else { None };
+    let frame_step = match (&before, &after) {
+        (Some(before), Some(after)) => match changed_near(before, after, [start, target]) {
+            Some(n) if n > 0 => step(Some(true), json!({"changed_pixels": n})),
+            Some(_) => step(None, json!({"reason": "no change around the pointer; the capture likely leaves the cursor out", "changed_pixels": 0})),
+            None => step(None, json!({"reason": "pointer is off the primary display's frame"})),
+        },
+        _ => step(None, json!({"reason": "no frame to compare"})),
+    };
+
+    let restore_step = match state.perform_untracked(Action::Move { x: start.0, y: start.1 }).await {
+        Err((_, err)) => step(Some(false), json!({"error": err, "target": start})),
+        Ok(_) if !readable => step(None, json!({"reason": "the input backend cannot read the pointer position", "target": start})),
+        Ok(_) => {
+            tokio::time::sleep(SETTLE).await;
+            match location(&state).await {
+                Ok(at) => step(Some(near(at, start)), json!({"target": start, "observed": at})),
+                Err(err) => step(Some(false), json!({"error": err, "target": start})),
+            }
+        }
+    };
+
+    let steps = json!({"capture": capture_step, "pointer": pointer_step, "frame": frame_step, "restore": restore_step});
+    let ok = steps.as_object().is_some_and(|s| s.values().all(|v| v["status"] != "fail"));
+    let body = json!({"ok": ok, "steps": steps, "duration_ms": started.elapsed().as_millis() as u64});
+    state.audit(json!({"event": "selftest", "ok": ok}));
+    let code = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (code, Json(body)).into_response()
+}
+
+// Within a pixel: scaled (HiDPI) desktops may round a move.
+fn near(a: (i32, i32), b: (i32, i32)) -> bool {
+    (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1
+}
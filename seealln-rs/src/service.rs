@@ -0,0 +1,385 @@
+#[cfg(target_os = "macos")]
+use std::path::Path;
+#[cfg(any(windows, target_os = "macos"))]
+use std::{path::PathBuf, process::Command};
+
+#[cfg(target_os = "macos")]
+use crate::secrets::Secret;
+
+// `seealln service install|uninstall|status`: start the server at login and keep it running.
+//
+// macOS: a per-user launchd agent (~/Library/LaunchAgents/dev.seealln.agent.plist), restarted
+// by launchd whenever it exits abnormally, logging to ~/Library/Logs/seealln/seealln.log.
+//
+// Windows: a logon task in the Task Scheduler rather than a Windows service. Services run in
+// session 0, which has no access to the user's desktop, so capture and input would fail. The
+// task runs `seealln service run`, a small supervisor that restarts the server when it exits
+// with an error and appends its output to <working dir>\logs\seealln.log.
+//
+// Both use a per-user working directory (~/Library/Application Support/seealln,
+// %LOCALAPPDATA%\seealln), so the default data dir ends up there. On macOS the non-secret
+// SEEALLN_* variables set at install time are copied into the agent; secrets are left out and
+// belong in the keychain (`seealln secrets set`). The Windows task inherits the user's
+// environment instead.
+#[cfg(target_os = "macos")]
+const LABEL: &str = "dev.seealln.agent";
+#[cfg(windows)]
+const TASK_NAME: &str = "seealln";
+
+fn usage() -> i32 {
+    eprintln!("usage: seealln service <install|uninstall|status>");
+    2
+}
+
+// `seealln service ...`; returns the process exit code.
+pub fn cli(args: &[String]) -> i32 {
+    let result = match args.first().map(String::as_str) {
+        Some("install") => install(),
+        Some("uninstall") => uninstall(),
+        Some("status") => status(),
+        #[cfg(windows)]
+        Some("run") => return supervise(),
+        _ => return usage(),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("service: {err}");
+            1
+        }
+    }
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn home() -> Result<PathBuf, String> {
+    ["HOME", "USERPROFILE"]
+        .iter()
+        .find_map(|k| std::env::var_os(k).filter(|v| !v.is_empty()))
+        .map(PathBuf::from)
+        .ok_or_else(|| "cannot find the home directory".to_string())
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn working_dir() -> Result<PathBuf, String> {
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA").filter(|v| !v.is_empty()) {
+            return Ok(PathBuf::from(local).join("seealln"));
+        }
+        return Ok(home()?.join("AppData").join("Local").join("seealln"));
+    }
+    Ok(home()?.join("Library").join("Application Support").join("seealln"))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("cannot locate the seealln binary: {e}"))
+}
+
+// SEEALLN_* variables to carry into the service, minus secrets. A relative data dir is made
+// absolute so it still points where it did when installing.
+#[cfg(target_os = "macos")]
+fn service_env() -> Vec<(String, String)> {
+    let secret_vars: Vec<&str> = Secret::ALL.iter().map(|s| s.env_var()).collect();
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(k, _)| k.starts_with("SEEALLN_") && !secret_vars.contains(&k.as_str()))
+        .map(|(k, v)| {
+            if k != "SEEALLN_DATA_DIR" || Path::new(v.trim()).is_absolute() {
+                return (k, v);
+            }
+            let abs = std::env::current_dir().map(|d| d.join(v.trim())).unwrap_or_else(|_| PathBuf::from(&v));
+            (k, abs.display().to_string())
+        })
+        .collect();
+    vars.sort();
+    vars
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn run(cmd: &str, args: &[&str]) -> Result<std::process::Output, String> {
+    Command::new(cmd).args(args).output().map_err(|e| format!("{cmd}: {e}"))
+}
+
+#[cfg(any(windows, target_os = "macos"))]
+fn check(cmd: &str, args: &[&str]) -> Result<(), String> {
+    let out = run(cmd, args)?;
+    if out.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    Err(format!("{cmd} {} failed: {}", args.join(" "), if stderr.trim().is_empty() { stdout.trim() } else { stderr.trim() }))
+}
+
+#[cfg(target_os = "macos")]
+fn plist_path() -> Result<PathBuf, String> {
+    Ok(home()?.join("Library").join("LaunchAgents").join(format!("{LABEL}.plist")))
+}
+
+#[cfg(target_os = "macos")]
+fn gui_domain() -> Result<String, String> {
+    let out = run("id", &["-u"])?;
+    Ok(format!("gui/{}", String::from_utf8_lossy(&out.stdout).trim()))
+}
+
+#[cfg(target_os = "macos")]
+fn install() -> Result<(), String> {
+    let dir = working_dir()?;
+    let logs = home()?.join("Library").join("Logs").join("seealln");
+    for d in [&dir, &logs] {
+        std::fs::create_dir_all(d).map_err(|e| format!("{}: {e}", d.display()))?;
+    }
+    let log = xml_escape(&logs.join("seealln.log").display().to_string());
+    let env: String = service_env()
+        .iter()
+        .map(|(k, v)| format!("\t\t<key>{}</key>\n\t\t<string>{}</string>\n", xml_escape(k), xml_escape(v)))
+        .collect();
+    // KeepAlive/SuccessfulExit=false: restart after a crash, not after a clean shutdown.
+    // ThrottleInterval spaces restarts so a crash loop does not spin.
+    let plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>{LABEL}</string>
+	<key>ProgramArguments</key>
+	<array>
+		<string>{exe}</string>
+	</array>
+	<key>WorkingDirectory</key>
+	<string>{dir}</string>
+	<key>EnvironmentVariables</key>
+	<dict>
+{env}	</dict>
+	<key>RunAtLoad</key>
+	<true/>
+	<key>KeepAlive</key>
+	<dict>
+		<key>SuccessfulExit</key>
+		<false/>
+	</dict>
+	<key>ThrottleInterval</key>
+	<integer>10</integer>
+	<key>ProcessType</key>
+	<string>Interactive</string>
+	<key>StandardOutPath</key>
+	<string>{log}</string>
+	<key>StandardErrorPath</key>
+	<string>{log}</string>
+</dict>
+</plist>
+"#,
+        exe = xml_escape(&exe()?.display().to_string()),
+        dir = xml_escape(&dir.display().to_string()),
+    );
+    let path = plist_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+    }
+    let domain = gui_domain()?;
+    // Reinstalling replaces the running agent.
+    let _ = run("launchctl", &["bootout", &format!("{domain}/{LABEL}")]);
+    std::fs::write(&path, plist).map_err(|e| format!("{}: {e}", path.display()))?;
+    check("launchctl", &["bootstrap", &domain, &path.display().to_string()])?;
+    println!("installed {} (logs: {})", path.display(), logs.join("seealln.log").display());
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn uninstall() -> Result<(), String> {
+    let path = plist_path()?;
+    let _ = run("launchctl", &["bootout", &format!("{}/{LABEL}", gui_domain()?)]);
+    match std::fs::remove_file(&path) {
+        Ok(()) => println!("removed {}", path.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => println!("not installed"),
+        Err(e) => return Err(format!("{}: {e}", path.display())),
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn status() -> Result<(), String> {
+    let path = plist_path()?;
+    if !path.exists() {
+        println!("not installed");
+        return Ok(());
+    }
+    println!("installed   {}", path.display());
+    let out = run("launchctl", &["print", &format!("{}/{LABEL}", gui_domain()?)])?;
+    if !out.status.success() {
+        println!("state       not loaded");
+        return Ok(());
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    for key in ["state", "pid", "last exit code", "runs"] {
+        let prefix = format!("{key} = ");
+        if let Some(value) = text.lines().map(str::trim).find_map(|l| l.strip_prefix(prefix.as_str())) {
+            println!("{key:<11} {value}");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn install() -> Result<(), String> {
+    let dir = working_dir()?;
+    let logs = dir.join("logs");
+    std::fs::create_dir_all(&logs).map_err(|e| format!("{}: {e}", logs.display()))?;
+    let user = match (std::env::var("USERDOMAIN"), std::env::var("USERNAME")) {
+        (Ok(domain), Ok(name)) => format!("{domain}\\{name}"),
+        (_, Ok(name)) => name,
+        _ => return Err("cannot determine the current user (USERNAME)".to_string()),
+    };
+    // The supervisor restarts the server; the task itself only has to start it at logon.
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <RegistrationInfo>
+    <Description>SeeAlln local eyes + hands server</Description>
+  </RegistrationInfo>
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+      <UserId>{user}</UserId>
+    </LogonTrigger>
+  </Triggers>
+  <Principals>
+    <Principal id="Author">
+      <UserId>{user}</UserId>
+      <LogonType>InteractiveToken</LogonType>
+      <RunLevel>LeastPrivilege</RunLevel>
+    </Principal>
+  </Principals>
+  <Settings>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+    <DisallowStartIfOnBatteries>false</DisallowStartIfOnBatteries>
+    <StopIfGoingOnBatteries>false</StopIfGoingOnBatteries>
+    <ExecutionTimeLimit>PT0S</ExecutionTimeLimit>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>3</Count>
+    </RestartOnFailure>
+    <Enabled>true</Enabled>
+  </Settings>
+  <Actions Context="Author">
+    <Exec>
+      <Command>{exe}</Command>
+      <Arguments>service run</Arguments>
+      <WorkingDirectory>{dir}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+        user = xml_escape(&user),
+        exe = xml_escape(&exe()?.display().to_string()),
+        dir = xml_escape(&dir.display().to_string()),
+    );
+    // schtasks only reliably accepts UTF-16 task definitions.
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(xml.encode_utf16().flat_map(u16::to_le_bytes));
+    let def = dir.join("task.xml");
+    std::fs::write(&def, bytes).map_err(|e| format!("{}: {e}", def.display()))?;
+    let created = check("schtasks", &["/Create", "/F", "/TN", TASK_NAME, "/XML", &def.display().to_string()]);
+    let _ = std::fs::remove_file(&def);
+    created?;
+    // Start now instead of at the next logon; a running instance is left alone.
+    let _ = run("schtasks", &["/Run", "/TN", TASK_NAME]);
+    println!("installed task {TASK_NAME} (logs: {})", logs.join("seealln.log").display());
+    Ok(())
+}
+
+#[cfg(windows)]
+fn uninstall() -> Result<(), String> {
+    let _ = run("schtasks", &["/End", "/TN", TASK_NAME]);
+    check("schtasks", &["/Delete", "/F", "/TN", TASK_NAME])?;
+    println!("removed task {TASK_NAME}");
+    Ok(())
+}
+
+#[cfg(windows)]
+fn status() -> Result<(), String> {
+    let out = run("schtasks", &["/Query", "/TN", TASK_NAME, "/FO", "LIST", "/V"])?;
+    if !out.status.success() {
+        println!("not installed");
+        return Ok(());
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    for key in ["Status", "Last Run Time", "Last Result", "Task To Run", "Start In"] {
+        let prefix = format!("{key}:");
+        if let Some(value) = text.lines().find_map(|l| l.strip_prefix(prefix.as_str())) {
+            println!("{:<14} {}", key.to_lowercase(), value.trim());
+        }
+    }
+    println!("{:<14} {}", "logs", working_dir()?.join("logs").join("seealln.log").display());
+    Ok(())
+}
+
+// Runs the server as a child, appending its output to logs\seealln.log, and restarts it after a
+// failure: 5 s, doubling up to 5 min while it keeps dying within a minute of starting. A clean
+// exit (status 0) ends the supervisor too.
+#[cfg(windows)]
+fn supervise() -> i32 {
+    use std::time::{Duration, Instant};
+
+    let (exe, log) = match (exe(), working_dir()) {
+        (Ok(exe), Ok(dir)) => (exe, dir.join("logs").join("seealln.log")),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("service: {err}");
+            return 1;
+        }
+    };
+    let mut backoff = Duration::from_secs(5);
+    loop {
+        let out = match std::fs::OpenOptions::new().create(true).append(true).open(&log) {
+            Ok(f) => f,
+            Err(err) => {
+                eprintln!("service: {}: {err}", log.display());
+                return 1;
+            }
+        };
+        let err = match out.try_clone() {
+            Ok(f) => f,
+            Err(err) => {
+                eprintln!("service: {}: {err}", log.display());
+                return 1;
+            }
+        };
+        let started = Instant::now();
+        let status = Command::new(&exe).stdout(out).stderr(err).status();
+        match status {
+            Ok(s) if s.success() => return 0,
+            Ok(s) => eprintln!("service: server exited with {s}; restarting in {}s", backoff.as_secs()),
+            Err(err) => eprintln!("service: cannot start server: {err}; retrying in {}s", backoff.as_secs()),
+        }
+        if started.elapsed() > Duration::from_secs(60) {
+            backoff = Duration::from_secs(5);
+        }
+        std::thread::sleep(backoff);
+        backoff = (backoff * 2).min(Duration::from_secs(300));
+    }
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn unsupported() -> Result<(), String> {
+    Err("only macOS (launchd) and Windows (Task Scheduler) are supported; on Linux use a systemd user unit (see README)".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn install() -> Result<(), String> {
+    unsupported()
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn uninstall() -> Result<(), String> {
+    unsupported()
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn status() -> Result<(), String> {
+    unsupported()
+}
@@ -0,0 +1,873 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+use crate::{
+    attest,
+    auth::require_api_key,
+    capture::{self, capture_rgb, encode_jpeg},
+    confirm::Intent,
+    hands::{require_local_only, HandsState},
+    interceptor::{Action, BrowserOp, Gesture},
+    origin::Origin,
+    overrides,
+    redact::Redactor,
+    store::{Area, Store},
+};
+
+// Armed sessions are recorded to recordings/<id>.jsonl as a timeline of resolved
+// actions interleaved with hashes of the frame captured right after each action:
+//
+//   {"type":"start","id":..,"unix_ms":..}
+//   {"type":"action","seq":1,"t_ms":120,"action":{"kind":"move","x":10,"y":20}}
+//   {"type":"action","seq":2,"t_ms":410,"action":{"kind":"type","chars":5,"sha256":"..","preview":"xxxxx"},"redacted":true}
+//   {"type":"frame","seq":1,"t_ms":180,"mono_ms":5321.4,"grab_ms":14,"sha256":"..","file":"<id>/1.jpg",
+//    "prev":"..","chain":".."}
+//   {"type":"end","t_ms":..,"reason":"disarm"}
+//
+// The action payload is kept verbatim so it can be replayed, except typed and pasted text:
+// that is written in the audit log's redacted form (see redact.rs) and marked `redacted`, and
+// replay refuses such steps. SEEALLN_RECORD_TYPED_TEXT=1 keeps the text for replayable
+// sessions. Recordings are covered by retention and encryption at rest like any other area.
+// With SEEALLN_RECORD_FRAME_IMAGES=1 the frames themselves are kept next to the timeline
+// (recordings/<id>/<seq>.jpg, seq 0 being the screen at arming time) for transcripts.
+// A frame's `t_ms` is when it was captured (not when it was written), `mono_ms` the same
+// instant on the server's monotonic clock (as /time) and `grab_ms` how long the grab took;
+// capture latency varies, so frames are not evenly spaced and GET /sessions/:id/frames gives
+// their real durations for variable-frame-rate playback or muxing. Frame entries form a hash
+// chain in timeline order (attest::link over `<seq>:<t_ms>:<sha256>`, `prev` null for the
+// first), checked by GET /sessions/:id/verify together with the kept images.
+#[derive(Clone)]
+pub struct Recorder {
+    store: Store,
+    // Last chain link per active session.
+    chains: Arc<Mutex<HashMap<String, String>>>,
+    // Hash a fresh frame after every action (SEEALLN_RECORD_FRAMES, default on).
+    frames: bool,
+    // Keep the frame images too (SEEALLN_RECORD_FRAME_IMAGES, default off).
+    images: bool,
+    // Typed/pasted text verbatim instead of redacted (SEEALLN_RECORD_TYPED_TEXT, default off).
+    typed_text: bool,
+    redactor: Redactor,
+}
+
+#[derive(Debug, Clone)]
+pub struct ActiveSession {
+    pub id: String,
+    started: Instant,
+    seq: u64,
+}
+
+impl ActiveSession {
+    // A session that records nothing, for tests of the arming lifecycle.
+    #[cfg(test)]
+    pub fn detached(id: &str) -> ActiveSession {
+        ActiveSession {
+            id: id.to_string(),
+            started: Instant::now(),
+            seq: 0,
+        }
+    }
+
+    pub fn next(&mut self) -> ActiveSession {
+        self.seq += 1;
+        self.clone()
+    }
+
+    fn t_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn t_ms_at(&self, at: Instant) -> u64 {
+        at.saturating_duration_since(self.started).as_millis() as u64
+    }
+}
+
+fn flag(name: &str, default: bool) -> bool {
+    match std::env::var(name).ok().as_deref().map(str::trim) {
+        Some("1") | Some("true") => true,
+        Some("0") | Some("false") => false,
+        _ => default,
+    }
+}
+
+pub fn new_id() -> String {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    let ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+    format!("{ms}-{:04x}", COUNTER.fetch_add(1, Ordering::Relaxed) & 0xffff)
+}
+
+// Sessions started and not yet ended; capture is not suspended while any is recording.
+static RECORDING: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "capture")]
+pub fn recording() -> bool {
+    RECORDING.load(Ordering::Relaxed) > 0
+}
+
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 64 && id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+pub fn frame_hash(jpeg: &[u8]) -> String {
+    hex::encode(Sha256::digest(jpeg))
+}
+
+impl Recorder {
+    // None when disabled via SEEALLN_RECORD_SESSIONS=0.
+    pub fn from_env(store: Store, redactor: Redactor) -> Option<Self> {
+        flag("SEEALLN_RECORD_SESSIONS", true).then(|| {
+            let frames = flag("SEEALLN_RECORD_FRAMES", true);
+            let typed_text = flag("SEEALLN_RECORD_TYPED_TEXT", false);
+            if typed_text {
+                warn!("SEEALLN_RECORD_TYPED_TEXT=1: typed text is written to session recordings verbatim");
+            }
+            Self {
+                store,
+                chains: Arc::default(),
+                frames,
+                images: frames && flag("SEEALLN_RECORD_FRAME_IMAGES", false),
+                typed_text,
+                redactor,
+            }
+        })
+    }
+
+    fn write(&self, id: &str, entry: serde_json::Value) {
+        if let Err(err) = self.store.append_line(Area::Recordings, &format!("{id}.jsonl"), &entry.to_string()) {
+            warn!(%err, session = id, "session recording write failed");
+        }
+    }
+
+    pub fn start(&self) -> ActiveSession {
+        let session = ActiveSession {
+            id: new_id(),
+            started: Instant::now(),
+            seq: 0,
+        };
+        RECORDING.fetch_add(1, Ordering::Relaxed);
+        let unix_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        self.write(&session.id, json!({"type": "start", "id": session.id, "unix_ms": unix_ms}));
+        // What the screen looked like before the first action.
+        if self.images {
+            let (recorder, snapshot) = (self.clone(), session.clone());
+            tokio::spawn(async move {
+                if let Some(shot) = capture_frame().await {
+                    recorder.frame(&snapshot, 0, &shot);
+                }
+            });
+        }
+        session
+    }
+
+    pub fn end(&self, session: &ActiveSession, reason: &str) {
+        self.write(&session.id, json!({"type": "end", "t_ms": session.t_ms(), "reason": reason}));
+        self.chains.lock().unwrap_or_else(|e| e.into_inner()).remove(&session.id);
+        let _ = RECORDING.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        // The caption sidecar, so the recordings dir plays back self-explained.
+        let vtt = captions(&self.store, &session.id, false).and_then(|vtt| {
+            self.store.write(Area::Recordings, &format!("{}.vtt", session.id), vtt.as_bytes()).map_err(|e| e.to_string())
+        });
+        if let Err(err) = vtt {
+            warn!(%err, session = %session.id, "writing captions failed");
+        }
+        let timeline = format!("{}.jsonl", session.id);
+        if let Ok(data) = self.store.read(Area::Recordings, &timeline) {
+            self.sign(&timeline, &data);
+        }
+    }
+
+    // Writes the detached signature of `rel` when frame signing is on (see attest.rs).
+    fn sign(&self, rel: &str, data: &[u8]) {
+        let Some(sig) = attest::sidecar(data) else {
+            return;
+        };
+        if let Err(err) = self.store.write(Area::Recordings, &format!("{rel}.sig"), &sig) {
+            warn!(%err, file = rel, "writing signature failed");
+        }
+    }
+
+    // `session` is a snapshot taken right after bumping its sequence number. Returns the
+    // sha256 of the frame recorded after the action, if any.
+    pub async fn action(&self, session: &ActiveSession, action: &Action) -> Option<String> {
+        let seq = session.seq;
+        let entry = match action {
+            Action::Type { .. } | Action::Paste { .. } if !self.typed_text => {
+                json!({"type": "action", "seq": seq, "t_ms": session.t_ms(), "action": self.redactor.redacted(action), "redacted": true})
+            }
+            _ => json!({"type": "action", "seq": seq, "t_ms": session.t_ms(), "action": action}),
+        };
+        self.write(&session.id, entry);
+
+        if !self.frames {
+            return None;
+        }
+        let shot = capture_frame().await?;
+        Some(self.frame(session, seq, &shot))
+    }
+
+    fn frame(&self, session: &ActiveSession, seq: u64, shot: &Shot) -> String {
+        let (t_ms, digest) = (session.t_ms_at(shot.at), frame_hash(&shot.jpeg));
+        let mut entry = json!({
+            "type": "frame",
+            "seq": seq,
+            "t_ms": t_ms,
+            "mono_ms": capture::mono_ms(shot.at),
+            "grab_ms": shot.grab.as_millis() as u64,
+            "sha256": digest,
+        });
+        if self.images {
+            let rel = format!("{}/{seq}.jpg", session.id);
+            match self.store.write(Area::Recordings, &rel, &shot.jpeg) {
+                Ok(_) => {
+                    entry["file"] = json!(rel);
+                    self.sign(&rel, &shot.jpeg);
+                }
+                Err(err) => warn!(%err, session = %session.id, "session frame write failed"),
+            }
+        }
+        // Held until the line is written, so the timeline order is the chain order.
+        let mut chains = self.chains.lock().unwrap_or_else(|e| e.into_inner());
+        let prev = chains.get(&session.id).cloned();
+        let link = attest::link(prev.as_deref(), &format!("{seq}:{t_ms}:{digest}"));
+        entry["prev"] = json!(prev);
+        entry["chain"] = json!(link);
+        self.write(&session.id, entry);
+        chains.insert(session.id.clone(), link);
+        digest
+    }
+}
+
+// A recorded frame and when it was taken: `at` is when the grab returned, before encoding.
+struct Shot {
+    jpeg: Vec<u8>,
+    at: Instant,
+    grab: Duration,
+}
+
+async fn capture_frame() -> Option<Shot> {
+    tokio::task::spawn_blocking(|| {
+        let started = Instant::now();
+        let image = capture_rgb().ok()?;
+        let at = Instant::now();
+        let jpeg = encode_jpeg(&image, 60).ok()?;
+        Some(Shot { jpeg, at, grab: at - started })
+    })
+    .await
+    .ok()
+    .flatten()
+}
+
+async fn capture_hash() -> Option<String> {
+    capture_frame().await.map(|shot| frame_hash(&shot.jpeg))
+}
+
+struct Step {
+    seq: u64,
+    t_ms: u64,
+    // None when the step's text was recorded redacted; it cannot be replayed.
+    action: Option<Action>,
+    // The action entry as written.
+    recorded: serde_json::Value,
+    frame: Option<String>,
+}
+
+const REDACTED_STEP: &str = "typed text was recorded redacted (SEEALLN_RECORD_TYPED_TEXT=1 keeps it for replay)";
+
+fn load_timeline(store: &Store, id: &str) -> Result<Vec<Step>, String> {
+    let data = store.read(Area::Recordings, &format!("{id}.jsonl"))?;
+    let mut steps: Vec<Step> = Vec::new();
+    for line in String::from_utf8_lossy(&data).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let seq = entry["seq"].as_u64().unwrap_or(0);
+        match entry["type"].as_str() {
+            Some("action") => {
+                let action = match entry["redacted"].as_bool() {
+                    Some(true) => None,
+                    _ => match serde_json::from_value(entry["action"].clone()) {
+                        Ok(action) => Some(action),
+                        Err(_) => continue,
+                    },
+                };
+                steps.push(Step {
+                    seq,
+                    t_ms: entry["t_ms"].as_u64().unwrap_or(0),
+                    action,
+                    recorded: entry["action"].clone(),
+                    frame: None,
+                });
+            }
+            Some("frame") => {
+                if let Some(step) = steps.iter_mut().rev().find(|s| s.seq == seq) {
+                    step.frame = entry["sha256"].as_str().map(str::to_string);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(steps)
+}
+
+pub async fn sessions_list(State(store): State<Store>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let ids: Vec<String> = store
+        .list(Area::Recordings)
+        .into_iter()
+        .filter_map(|f| f.strip_suffix(".jsonl").map(str::to_string))
+        .collect();
+    (StatusCode::OK, Json(json!({"ok": true, "sessions": ids}))).into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayParams {
+    // Playback speed multiplier (2.0 = twice as fast); gaps are capped at 5s either way.
+    speed: Option<f32>,
+    // Only run the interceptors and report what would be executed.
+    dry_run: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplayReq {
+    token: String,
+}
+
+// Re-executes a recorded session through the normal guarded pipeline: every step consumes
+// the arming token/rate limit and passes the interceptors, and the kill switch stops it.
+// Operator overrides (x-seealln-override, see overrides.rs) apply as they do to live actions.
+pub async fn session_replay(
+    State(state): State<HandsState>,
+    State(store): State<Store>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    origin: Origin,
+    Query(params): Query<ReplayParams>,
+    Json(req): Json<ReplayReq>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    // Checked before the confirmation, which a refused request would otherwise use up.
+    if !valid_id(&id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid session id"}))).into_response();
+    }
+    let overrides = match overrides::from_headers(&headers) {
+        Ok(overrides) => overrides,
+        Err(err) => return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    let route = format!("/sessions/{id}/replay");
+    if let Err(msg) = state.check_confirmation(&headers, &req.token, Intent::Route(&route)) {
+        return (StatusCode::PRECONDITION_REQUIRED, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+
+    let dry_run = params.dry_run.unwrap_or(false);
+    let speed = params.speed.unwrap_or(1.0).clamp(0.25, 20.0);
+    let steps = match load_timeline(&store, &id) {
+        Ok(steps) => steps,
+        Err(err) => return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    // Refused up front rather than at the step, so a replay never runs half a session.
+    let redacted: Vec<u64> = steps.iter().filter(|s| s.action.is_none()).map(|s| s.seq).collect();
+    if !dry_run && !redacted.is_empty() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({"ok": false, "error": REDACTED_STEP, "redacted_steps": redacted})),
+        )
+            .into_response();
+    }
+
+    state.audit(json!({"event": "replay_start", "session": id, "steps": steps.len(), "dry_run": dry_run, "speed": speed}));
+
+    let mut results = Vec::with_capacity(steps.len());
+    let mut all_ok = true;
+    let mut prev_t = steps.first().map(|s| s.t_ms).unwrap_or(0);
+    for step in steps {
+        if !dry_run {
+            let gap = Duration::from_millis(step.t_ms.saturating_sub(prev_t)).min(Duration::from_secs(5));
+            tokio::time::sleep(gap.div_f32(speed)).await;
+        }
+        prev_t = step.t_ms;
+
+        let Some(action) = step.action else {
+            all_ok = false;
+            results.push(json!({"seq": step.seq, "ok": false, "action": step.recorded, "error": REDACTED_STEP}));
+            continue;
+        };
+        if dry_run {
+            match state.preview(action.clone()) {
+                Ok(resolved) => results.push(json!({"seq": step.seq, "ok": true, "action": resolved.summary()})),
+                Err(denial) => {
+                    all_ok = false;
+                    results.push(json!({"seq": step.seq, "ok": false, "action": action.summary(), "error": denial.error}));
+                }
+            }
+            continue;
+        }
+
+        if let Err(msg) = state.consume_action(&req.token, &origin, &action) {
+            all_ok = false;
+            results.push(json!({"seq": step.seq, "ok": false, "error": msg}));
+            break;
+        }
+        match state.perform_with(&req.token, action.clone(), &overrides).await {
+            Ok(resolved) => {
+                let frame_match = match &step.frame {
+                    Some(expected) => capture_hash().await.map(|h| &h == expected),
+                    None => None,
+                };
+                results.push(json!({"seq": step.seq, "ok": true, "action": resolved.summary(), "frame_match": frame_match}));
+            }
+            Err((_, err)) => {
+                all_ok = false;
+                results.push(json!({"seq": step.seq, "ok": false, "action": action.summary(), "error": err}));
+                break;
+            }
+        }
+    }
+
+    (StatusCode::OK, Json(json!({"ok": all_ok, "session": id, "dry_run": dry_run, "steps": results}))).into_response()
+}
+
+// Training transcript of a recorded session, schema `seealln.transcript/v1`:
+//
+//   {"schema": "seealln.transcript/v1",
+//    "session": {"id", "started_unix_ms", "ended": {"t_ms", "reason"} | null},
+//    "steps": [{"seq", "t_ms", "action": <resolved action, as executed>,
+//               "before": <frame> | null, "after": <frame> | null}]}
+//
+// where a frame is {"seq", "t_ms", "sha256", "mime": "image/jpeg"} plus, when the image was
+// kept, "file" (for /admin/export?area=recordings) or "data" (base64, with ?frames=inline).
+// `before` is the latest frame taken before the action (seq 0 at arming time), `after` the one
+// taken right after it. Typed text is included verbatim when it was recorded (see Recorder),
+// hence the API key.
+#[derive(Debug, Deserialize)]
+pub struct TranscriptParams {
+    // ref (default): file references; inline: base64 images; none: hashes only.
+    frames: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FrameMode {
+    Ref,
+    Inline,
+    None,
+}
+
+fn transcript(store: &Store, id: &str, mode: FrameMode) -> Result<serde_json::Value, String> {
+    let data = store.read(Area::Recordings, &format!("{id}.jsonl"))?;
+    let (mut started, mut ended) = (None, None);
+    let mut actions = Vec::new();
+    let mut frames = BTreeMap::new();
+    for line in String::from_utf8_lossy(&data).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let seq = entry["seq"].as_u64().unwrap_or(0);
+        match entry["type"].as_str() {
+            Some("start") => started = entry["unix_ms"].as_u64(),
+            Some("end") => ended = Some(json!({"t_ms": entry["t_ms"], "reason": entry["reason"]})),
+            Some("action") => actions.push((seq, entry["t_ms"].clone(), entry["action"].clone())),
+            Some("frame") => {
+                let mut frame = json!({"seq": seq, "t_ms": entry["t_ms"], "sha256": entry["sha256"], "mime": "image/jpeg"});
+                if let Some(file) = entry["file"].as_str().filter(|_| mode != FrameMode::None) {
+                    if mode == FrameMode::Inline {
+                        frame["data"] = match store.read(Area::Recordings, file) {
+                            Ok(jpeg) => json!(B64.encode(jpeg)),
+                            Err(_) => serde_json::Value::Null,
+                        };
+                    } else {
+                        frame["file"] = json!(file);
+                    }
+                }
+                frames.insert(seq, frame);
+            }
+            _ => {}
+        }
+    }
+    let steps: Vec<_> = actions
+        .into_iter()
+        .map(|(seq, t_ms, action)| {
+            let before = frames.range(..seq).next_back().map(|(_, f)| f.clone());
+            json!({"seq": seq, "t_ms": t_ms, "action": action, "before": before, "after": frames.get(&seq)})
+        })
+        .collect();
+    Ok(json!({
+        "schema": "seealln.transcript/v1",
+        "session": {"id": id, "started_unix_ms": started, "ended": ended},
+        "steps": steps,
+    }))
+}
+
+pub async fn session_transcript(
+    State(store): State<Store>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<TranscriptParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err((code, msg)) = require_api_key(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !valid_id(&id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid session id"}))).into_response();
+    }
+    let mode = match params.frames.as_deref().unwrap_or("ref") {
+        "ref" => FrameMode::Ref,
+        "inline" => FrameMode::Inline,
+        "none" => FrameMode::None,
+        other => {
+            let error = format!("unknown frames mode {other} (ref, inline, none)");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+    };
+
+    let (read_store, read_id) = (store.clone(), id.clone());
+    let doc = tokio::task::spawn_blocking(move || transcript(&read_store, &read_id, mode))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match doc {
+        Ok(doc) => {
+            store.audit(json!({"event": "export", "area": "recordings", "file": format!("{id}.jsonl"), "transcript": true}));
+            (StatusCode::OK, Json(doc)).into_response()
+        }
+        Err(err) => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    }
+}
+
+// Variable-frame-rate timeline of a session's frames: each frame shown from its capture time
+// until the next one's (the last until the session ended, else DEFAULT_LAST_FRAME).
+//
+//   ?format=json (default)  {"session", "frames": [{"seq", "t_ms", "duration_ms", "sha256", "file"}]}
+//   ?format=ffconcat        ffmpeg concat script, paths relative to the recordings dir:
+//                           ffmpeg -f concat -i timeline.txt -vsync vfr out.mp4
+//
+// The ffconcat form needs the frame images (SEEALLN_RECORD_FRAME_IMAGES=1) stored in the clear.
+const DEFAULT_LAST_FRAME: u64 = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct FramesParams {
+    format: Option<String>,
+}
+
+struct TimedFrame {
+    seq: u64,
+    t_ms: u64,
+    sha256: serde_json::Value,
+    file: Option<String>,
+}
+
+// Frames in playback order with how long each is shown, and when the session ended.
+struct Timeline {
+    frames: Vec<(TimedFrame, u64)>,
+    ended: Option<u64>,
+}
+
+fn frame_timeline(store: &Store, id: &str) -> Result<Timeline, String> {
+    let data = store.read(Area::Recordings, &format!("{id}.jsonl"))?;
+    let mut frames = Vec::new();
+    let mut ended = None;
+    for line in String::from_utf8_lossy(&data).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        match entry["type"].as_str() {
+            Some("frame") => frames.push(TimedFrame {
+                seq: entry["seq"].as_u64().unwrap_or(0),
+                t_ms: entry["t_ms"].as_u64().unwrap_or(0),
+                sha256: entry["sha256"].clone(),
+                file: entry["file"].as_str().map(str::to_string),
+            }),
+            Some("end") => ended = entry["t_ms"].as_u64(),
+            _ => {}
+        }
+    }
+    // Frames are written as their captures finish, which is not always capture order.
+    frames.sort_by_key(|f| (f.t_ms, f.seq));
+    let ends: Vec<u64> = frames
+        .iter()
+        .skip(1)
+        .map(|f| f.t_ms)
+        .chain(std::iter::once(frames.last().map_or(0, |f| match ended {
+            Some(end) if end > f.t_ms => end,
+            _ => f.t_ms + DEFAULT_LAST_FRAME,
+        })))
+        .collect();
+    let timed = frames
+        .into_iter()
+        .zip(ends)
+        .map(|(f, end)| {
+            let duration = end.saturating_sub(f.t_ms);
+            (f, duration)
+        })
+        .collect();
+    Ok(Timeline { frames: timed, ended })
+}
+
+pub async fn session_frames(
+    State(store): State<Store>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<FramesParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !valid_id(&id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid session id"}))).into_response();
+    }
+    let ffconcat = match params.format.as_deref().unwrap_or("json") {
+        "json" => false,
+        "ffconcat" => true,
+        other => {
+            let error = format!("unknown format {other} (json, ffconcat)");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+    };
+    let (read_store, read_id) = (store.clone(), id.clone());
+    let timeline = tokio::task::spawn_blocking(move || frame_timeline(&read_store, &read_id))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    let Timeline { frames, ended } = match timeline {
+        Ok(v) => v,
+        Err(err) => return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+
+    if !ffconcat {
+        let list: Vec<_> = frames
+            .iter()
+            .map(|(f, duration)| json!({"seq": f.seq, "t_ms": f.t_ms, "duration_ms": duration, "sha256": f.sha256, "file": f.file}))
+            .collect();
+        return (StatusCode::OK, Json(json!({"ok": true, "session": id, "ended_t_ms": ended, "frames": list}))).into_response();
+    }
+    if store.encrypted() {
+        let error = "frames are encrypted at rest; ffmpeg cannot read them (use /sessions/:id/transcript?frames=inline)";
+        return (StatusCode::CONFLICT, Json(json!({"ok": false, "error": error}))).into_response();
+    }
+    let with_files: Vec<_> = frames.iter().filter_map(|(f, d)| f.file.as_ref().map(|file| (file, d))).collect();
+    if with_files.is_empty() {
+        let error = "session has no frame images (SEEALLN_RECORD_FRAME_IMAGES=1)";
+        return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": error}))).into_response();
+    }
+    let mut script = String::from("ffconcat version 1.0\n");
+    for (file, duration) in &with_files {
+        script.push_str(&format!("file '{file}'\nduration {:.3}\n", **duration as f64 / 1000.0));
+    }
+    // The concat demuxer ignores the last duration unless the last file is repeated.
+    if let Some((file, _)) = with_files.last() {
+        script.push_str(&format!("file '{file}'\n"));
+    }
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], script).into_response()
+}
+
+// Subtitle track of a session's actions, one cue per action ("click(512,334) left"), timed to
+// play over the /sessions/:id/frames video: cue times count from its first frame. A cue lasts
+// until the next action, at most CUE_MAX. Typed text is described by length only. Written as
+// recordings/<id>.vtt when the session ends; GET /sessions/:id/captions?format=vtt|srt
+// renders either format on demand.
+const CUE_MIN: u64 = 500;
+const CUE_MAX: u64 = 2000;
+
+#[derive(Debug, Deserialize)]
+pub struct CaptionParams {
+    format: Option<String>,
+}
+
+fn cue_time(ms: u64, srt: bool) -> String {
+    let (h, m, s, ms) = (ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000);
+    let sep = if srt { ',' } else { '.' };
+    format!("{h:02}:{m:02}:{s:02}{sep}{ms:03}")
+}
+
+// `pointer` follows the moves so clicks can say where they landed.
+fn caption(action: &Action, pointer: &mut Option<(i32, i32)>) -> String {
+    match action {
+        Action::Move { x, y } => {
+            *pointer = Some((*x, *y));
+            format!("move({x},{y})")
+        }
+        Action::Click { button } => match pointer {
+            Some((x, y)) => format!("click({x},{y}) {button}"),
+            None => format!("click {button}"),
+        },
+        Action::Type { text } => format!("type {} chars", text.chars().count()),
+        Action::Paste { text } => format!("paste {} chars", text.chars().count()),
+        Action::Key { combo } => format!("key {combo}"),
+        Action::Scroll { dx, dy } => format!("scroll({dx},{dy})"),
+        Action::Gesture { gesture, .. } => match *gesture {
+            Gesture::Tap { x, y } => {
+                *pointer = Some((x, y));
+                format!("tap({x},{y})")
+            }
+            Gesture::Swipe { x, y, to_x, to_y } => format!("swipe({x},{y} -> {to_x},{to_y})"),
+            Gesture::Pinch { x, y, from, to } => format!("pinch({x},{y}) {from} -> {to}"),
+            Gesture::TwoFingerScroll { x, y, dx, dy } => format!("two-finger scroll({x},{y}) by ({dx},{dy})"),
+        },
+        Action::Browser { op } => match op {
+            BrowserOp::Navigate { url, .. } => format!("navigate {url}"),
+            BrowserOp::Click { selector, index, .. } => format!("click {selector} #{index}"),
+        },
+    }
+}
+
+fn captions(store: &Store, id: &str, srt: bool) -> Result<String, String> {
+    let data = store.read(Area::Recordings, &format!("{id}.jsonl"))?;
+    let mut actions = Vec::new();
+    let mut first_frame: Option<u64> = None;
+    let mut ended = None;
+    let mut pointer = None;
+    for line in String::from_utf8_lossy(&data).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        let t_ms = entry["t_ms"].as_u64().unwrap_or(0);
+        match entry["type"].as_str() {
+            // Redacted text keeps its kind and length, all a caption shows anyway.
+            Some("action") if entry["redacted"].as_bool() == Some(true) => {
+                let recorded = &entry["action"];
+                actions.push((t_ms, format!("{} {} chars", recorded["kind"].as_str().unwrap_or("type"), recorded["chars"])));
+            }
+            Some("action") => {
+                if let Ok(action) = serde_json::from_value::<Action>(entry["action"].clone()) {
+                    actions.push((t_ms, caption(&action, &mut pointer)));
+                }
+            }
+            Some("frame") => first_frame = Some(first_frame.map_or(t_ms, |f| f.min(t_ms))),
+            Some("end") => ended = Some(t_ms),
+            _ => {}
+        }
+    }
+    let offset = first_frame.unwrap_or(0);
+    let mut out = String::new();
+    if !srt {
+        out.push_str("WEBVTT\n\n");
+    }
+    for (i, (t_ms, text)) in actions.iter().enumerate() {
+        let next = actions.get(i + 1).map(|(t, _)| *t).or(ended).unwrap_or(t_ms + CUE_MAX);
+        let end = next.clamp(t_ms + CUE_MIN, t_ms + CUE_MAX);
+        let (start, end) = (t_ms.saturating_sub(offset), end.saturating_sub(offset));
+        if srt {
+            out.push_str(&format!("{}\n", i + 1));
+        }
+        out.push_str(&format!("{} --> {}\n{}\n\n", cue_time(start, srt), cue_time(end, srt), text));
+    }
+    Ok(out)
+}
+
+pub async fn session_captions(
+    State(store): State<Store>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Query(params): Query<CaptionParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !valid_id(&id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid session id"}))).into_response();
+    }
+    let (srt, mime) = match params.format.as_deref().unwrap_or("vtt") {
+        "vtt" => (false, "text/vtt; charset=utf-8"),
+        "srt" => (true, "application/x-subrip; charset=utf-8"),
+        other => {
+            let error = format!("unknown format {other} (vtt, srt)");
+            return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": error}))).into_response();
+        }
+    };
+    let read_store = store.clone();
+    let text = tokio::task::spawn_blocking(move || captions(&read_store, &id, srt))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match text {
+        Ok(text) => ([(header::CONTENT_TYPE, mime)], text).into_response(),
+        Err(err) => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    }
+}
+
+// Integrity check of a recording: walks the frame hash chain in timeline order and re-hashes
+// the kept frame images. `chain_break` is the seq of the first frame whose link does not
+// follow from the one before it (a frame deleted, inserted or moved there); `files` lists
+// images that are missing or no longer match their recorded hash. Signatures (attest.rs)
+// are left to the verifier, which needs the public key from /version anyway.
+fn verify(store: &Store, id: &str) -> Result<serde_json::Value, String> {
+    let data = store.read(Area::Recordings, &format!("{id}.jsonl"))?;
+    let (mut frames, mut unchained) = (0u64, 0u64);
+    let mut prev: Option<String> = None;
+    let mut chain_break = None;
+    let mut files = Vec::new();
+    for line in String::from_utf8_lossy(&data).lines() {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if entry["type"].as_str() != Some("frame") {
+            continue;
+        }
+        frames += 1;
+        let (seq, t_ms, digest) = (entry["seq"].as_u64().unwrap_or(0), entry["t_ms"].as_u64().unwrap_or(0), entry["sha256"].as_str().unwrap_or(""));
+        match entry["chain"].as_str() {
+            // Recorded before frames were chained.
+            None => unchained += 1,
+            Some(chain) => {
+                let expected = attest::link(prev.as_deref(), &format!("{seq}:{t_ms}:{digest}"));
+                if chain_break.is_none() && (entry["prev"].as_str() != prev.as_deref() || chain != expected) {
+                    chain_break = Some(seq);
+                }
+                prev = Some(chain.to_string());
+            }
+        }
+        if let Some(file) = entry["file"].as_str() {
+            match store.read(Area::Recordings, file) {
+                Ok(jpeg) if frame_hash(&jpeg) == digest => {}
+                Ok(_) => files.push(json!({"seq": seq, "file": file, "problem": "hash mismatch"})),
+                Err(_) => files.push(json!({"seq": seq, "file": file, "problem": "missing"})),
+            }
+        }
+    }
+    let signed = store.resolve(Area::Recordings, &format!("{id}.jsonl.sig")).is_some_and(|p| p.exists());
+    Ok(json!({
+        "ok": chain_break.is_none() && files.is_empty(),
+        "session": id,
+        "frames": frames,
+        "unchained_frames": unchained,
+        "chain_break": chain_break,
+        "files": files,
+        "timeline_signed": signed,
+    }))
+}
+
+pub async fn session_verify(State(store): State<Store>, Path(id): Path<String>, headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !valid_id(&id) {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid session id"}))).into_response();
+    }
+    let read_store = store.clone();
+    let report = tokio::task::spawn_blocking(move || verify(&read_store, &id))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match report {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(err) => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    }
+}
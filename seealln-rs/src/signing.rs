@@ -0,0 +1,215 @@
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::warn;
+
+use crate::{
+    secrets::{self, Secret},
+    stats,
+};
+
+// Request signing for the hands routes, on when a secret `hmac_secret` (SEEALLN_HMAC_SECRET) is
+// configured. Every hands request then carries:
+//
+//   x-seealln-timestamp  unix seconds; within SEEALLN_HMAC_WINDOW_SECS (default 30) of ours
+//   x-seealln-nonce      integer, strictly greater than the last one accepted for the same
+//                        arming token (a counter or unix milliseconds both work)
+//   x-seealln-signature  sha256=<hex hmac-sha256(secret, METHOD \n PATH?QUERY \n timestamp \n
+//                        nonce \n hex sha256(body))>
+//
+// A captured request cannot be replayed: its nonce is used up, and once the window has passed
+// its timestamp is too old. Requests without a token in the body (/hands/arm, /safety/reset)
+// share one nonce sequence. /safety/kill is never signed or checked.
+pub const TIMESTAMP_HEADER: &str = "x-seealln-timestamp";
+pub const NONCE_HEADER: &str = "x-seealln-nonce";
+pub const SIGNATURE_HEADER: &str = "x-seealln-signature";
+
+// Nonce sequences of tokens not seen for this long are forgotten; tokens live 5 min at most.
+const FORGET_AFTER: Duration = Duration::from_secs(600);
+
+fn window() -> u64 {
+    static WINDOW: OnceLock<u64> = OnceLock::new();
+    *WINDOW.get_or_init(|| {
+        std::env::var("SEEALLN_HMAC_WINDOW_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(30)
+    })
+}
+
+// Last accepted nonce per arming token.
+fn nonces() -> std::sync::MutexGuard<'static, HashMap<String, (u64, Instant)>> {
+    static NONCES: OnceLock<Mutex<HashMap<String, (u64, Instant)>>> = OnceLock::new();
+    NONCES.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn reject(code: StatusCode, err: &str) -> Response {
+    stats::rejection(None, "bad signature");
+    (code, Json(json!({"ok": false, "error": err}))).into_response()
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim)
+}
+
+// Middleware for the hands router; `max_body` bounds the body buffered for hashing.
+pub async fn verify(State(max_body): State<usize>, req: Request, next: Next) -> Response {
+    let Some(secret) = secrets::get(Secret::Hmac) else {
+        return next.run(req).await;
+    };
+    let (parts, body) = req.into_parts();
+    let bytes = match axum::body::to_bytes(body, max_body).await {
+        Ok(bytes) => bytes,
+        Err(_) => return reject(StatusCode::PAYLOAD_TOO_LARGE, "request body too large"),
+    };
+    let path = parts.uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    if let Err((code, err)) = check(&secret, parts.method.as_str(), path, &parts.headers, &bytes, now) {
+        return reject(code, err);
+    }
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+// Signature, time window and nonce of one request; `now` in unix seconds. A request that
+// passes uses up its nonce.
+fn check(secret: &str, method: &str, path: &str, headers: &HeaderMap, body: &[u8], now: u64) -> Result<(), (StatusCode, &'static str)> {
+    let (Some(ts), Some(nonce), Some(sig)) = (
+        header(headers, TIMESTAMP_HEADER),
+        header(headers, NONCE_HEADER),
+        header(headers, SIGNATURE_HEADER),
+    ) else {
+        return Err((StatusCode::UNAUTHORIZED, "signed request required: x-seealln-timestamp, x-seealln-nonce, x-seealln-signature"));
+    };
+    let (Ok(ts), Ok(nonce)) = (ts.parse::<u64>(), nonce.parse::<u64>()) else {
+        return Err((StatusCode::BAD_REQUEST, "x-seealln-timestamp and x-seealln-nonce must be integers"));
+    };
+    let Some(sig) = sig.strip_prefix("sha256=").and_then(|h| hex::decode(h).ok()) else {
+        return Err((StatusCode::BAD_REQUEST, "x-seealln-signature must be sha256=<hex>"));
+    };
+
+    let message = format!("{method}\n{path}\n{ts}\n{nonce}\n{}", hex::encode(Sha256::digest(body)));
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts any key length");
+    mac.update(message.as_bytes());
+    if mac.verify_slice(&sig).is_err() {
+        warn!(method, path, "rejected: bad request signature");
+        return Err((StatusCode::UNAUTHORIZED, "invalid signature"));
+    }
+
+    if now.abs_diff(ts) > window() {
+        warn!(method, path, ts, now, "rejected: signed request outside the time window");
+        return Err((StatusCode::UNAUTHORIZED, "timestamp outside the allowed window (check the clock)"));
+    }
+
+    // Checked after the signature, so only a holder of the secret can advance a sequence.
+    let token = serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["token"].as_str().map(str::to_string))
+        .unwrap_or_default();
+    let mut seen = nonces();
+    seen.retain(|_, (_, at)| at.elapsed() < FORGET_AFTER);
+    match seen.get(&token) {
+        Some((last, _)) if nonce <= *last => {
+            warn!(method, path, nonce, last, "rejected: replayed or stale nonce");
+            Err((StatusCode::UNAUTHORIZED, "nonce already used; nonces must increase"))
+        }
+        _ => {
+            seen.insert(token, (nonce, Instant::now()));
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    fn signed(method: &str, path: &str, body: &[u8], ts: u64, nonce: u64, secret: &str) -> HeaderMap {
+        let message = format!("{method}\n{path}\n{ts}\n{nonce}\n{}", hex::encode(Sha256::digest(body)));
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(message.as_bytes());
+        let mut headers = HeaderMap::new();
+        headers.insert(TIMESTAMP_HEADER, ts.to_string().parse().unwrap());
+        headers.insert(NONCE_HEADER, nonce.to_string().parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, format!("sha256={}", hex::encode(mac.finalize().into_bytes())).parse().unwrap());
+        headers
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[test]
+    fn accepts_a_good_signature() {
+        let body = br#"{"token":"tok-good","x":1}"#;
+        let headers = signed("POST", "/hands/move", body, now(), 1, SECRET);
+        assert!(check(SECRET, "POST", "/hands/move", &headers, body, now()).is_ok());
+    }
+
+    #[test]
+    fn rejects_tampering_and_missing_headers() {
+        let body = br#"{"token":"tok-tamper","x":1}"#;
+        let headers = signed("POST", "/hands/move", body, now(), 1, SECRET);
+        let tampered = br#"{"token":"tok-tamper","x":2}"#;
+        assert_eq!(check(SECRET, "POST", "/hands/move", &headers, tampered, now()).unwrap_err().1, "invalid signature");
+        assert!(check(SECRET, "POST", "/hands/click", &headers, body, now()).is_err());
+        assert!(check("other-secret", "POST", "/hands/move", &headers, body, now()).is_err());
+        let unsigned = check(SECRET, "POST", "/hands/move", &HeaderMap::new(), body, now()).unwrap_err();
+        assert_eq!(unsigned.0, StatusCode::UNAUTHORIZED);
+        // None of the rejections used up the nonce.
+        assert!(check(SECRET, "POST", "/hands/move", &headers, body, now()).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_stale_timestamp() {
+        let body = br#"{"token":"tok-stale"}"#;
+        let old = now() - window() - 1;
+        let headers = signed("POST", "/hands/key", body, old, 1, SECRET);
+        let err = check(SECRET, "POST", "/hands/key", &headers, body, now()).unwrap_err();
+        assert!(err.1.starts_with("timestamp outside"));
+        let ahead = signed("POST", "/hands/key", body, now() + window() + 1, 2, SECRET);
+        assert!(check(SECRET, "POST", "/hands/key", &ahead, body, now()).is_err());
+    }
+
+    #[test]
+    fn rejects_replayed_and_decreasing_nonces() {
+        let body = br#"{"token":"tok-replay"}"#;
+        let first = signed("POST", "/hands/click", body, now(), 10, SECRET);
+        assert!(check(SECRET, "POST", "/hands/click", &first, body, now()).is_ok());
+        let err = check(SECRET, "POST", "/hands/click", &first, body, now()).unwrap_err();
+        assert_eq!(err.1, "nonce already used; nonces must increase");
+        let lower = signed("POST", "/hands/click", body, now(), 9, SECRET);
+        assert!(check(SECRET, "POST", "/hands/click", &lower, body, now()).is_err());
+        let higher = signed("POST", "/hands/click", body, now(), 11, SECRET);
+        assert!(check(SECRET, "POST", "/hands/click", &higher, body, now()).is_ok());
+    }
+
+    #[test]
+    fn tokenless_requests_share_their_own_sequence() {
+        // Another token's counter does not hold back requests without one, and vice versa.
+        let with_token = br#"{"token":"tok-seq"}"#;
+        let high = signed("POST", "/hands/move", with_token, now(), 1_000_000, SECRET);
+        assert!(check(SECRET, "POST", "/hands/move", &high, with_token, now()).is_ok());
+        let arm = signed("POST", "/hands/arm", b"", now(), 500, SECRET);
+        assert!(check(SECRET, "POST", "/hands/arm", &arm, b"", now()).is_ok());
+        let reset = signed("POST", "/safety/reset", b"{}", now(), 500, SECRET);
+        assert!(check(SECRET, "POST", "/safety/reset", &reset, b"{}", now()).is_err(), "/hands/arm used nonce 500");
+        let reset = signed("POST", "/safety/reset", b"{}", now(), 501, SECRET);
+        assert!(check(SECRET, "POST", "/safety/reset", &reset, b"{}", now()).is_ok());
+    }
+}
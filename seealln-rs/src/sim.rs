@@ -0,0 +1,766 @@
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use image::{Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::VecDeque,
+    sync::{Mutex, MutexGuard, OnceLock},
+};
+use tracing::{error, info};
+
+use crate::{
+    hands::{require_local_only, ScopeRect},
+    input::{Capabilities, InputBackend},
+    interceptor::{Action, Gesture},
+    keys::{self, KeyName},
+};
+
+// Synthetic desktop (feature `simulation`, on with SEEALLN_SIMULATION=1): capture returns a
+// rendered scene instead of the screen and hands actions drive that scene instead of the OS,
+// so agents can be developed and CI-tested with no desktop at all. Rendering is
+// deterministic: the same scene and the same actions always give the same pixels.
+//
+// The scene comes from SEEALLN_SIM_SCENE (a JSON file) or a built-in default:
+//
+//   {"width": 1280, "height": 800, "background": "#2b5797",
+//    "windows": [{"id": "notes", "title": "Notes", "x": 80, "y": 60, "w": 640, "h": 420,
+//                 "widgets": [{"kind": "button", "id": "save", "label": "Save", "x": 20, "y": 20, "w": 100, "h": 32, "on_click": "submit"},
+//                             {"kind": "label", "text": "Name", "x": 20, "y": 76},
+//                             {"kind": "input", "id": "name", "x": 90, "y": 70, "w": 300, "h": 28, "secret": false}]}]}
+//
+// Widget coordinates are relative to the window's content area (below the title bar). Windows
+// are listed bottom to top; a click raises the window under the cursor, presses a button or
+// focuses an input. A pressed button counts in `clicks`, is drawn depressed until the next
+// action and runs its optional `on_click`: "submit" logs the window's input values (secret
+// ones masked), "clear" empties them, "close" removes the window. Typing and pasting go into
+// the focused input; Backspace deletes, Tab moves to the next input of the window, Return
+// submits it.
+// The scroll wheel scrolls the window under the cursor and a tap gesture clicks. The focused
+// window's title is reported as the focused app. GET /sim/state shows the scene with its
+// input values and an event log, and POST /sim/reset restores it, or loads a scene sent as
+// the body.
+const TITLE_H: i32 = 24;
+const SCALE: i32 = 2;
+const ADVANCE: i32 = 6 * SCALE;
+const TEXT_H: i32 = 7 * SCALE;
+const SCROLL_STEP: i32 = 20;
+const LOG_LEN: usize = 100;
+const MAX_WINDOWS: usize = 32;
+const MAX_WIDGETS: usize = 256;
+
+pub fn active() -> bool {
+    static ACTIVE: OnceLock<bool> = OnceLock::new();
+    *ACTIVE.get_or_init(|| {
+        let on = cfg!(feature = "simulation") && std::env::var("SEEALLN_SIMULATION").ok().as_deref() == Some("1");
+        if on {
+            info!("simulation mode: capture and hands use the synthetic desktop");
+        }
+        on
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    #[serde(default = "default_width")]
+    width: u32,
+    #[serde(default = "default_height")]
+    height: u32,
+    #[serde(default = "default_background")]
+    background: String,
+    #[serde(default)]
+    windows: Vec<Window>,
+}
+
+fn default_width() -> u32 {
+    1280
+}
+
+fn default_height() -> u32 {
+    800
+}
+
+fn default_background() -> String {
+    "#2b5797".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Window {
+    id: String,
+    title: String,
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+    #[serde(default)]
+    widgets: Vec<Widget>,
+    // Content scroll offset in pixels.
+    #[serde(default)]
+    scroll: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Widget {
+    Button {
+        id: String,
+        label: String,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        #[serde(default)]
+        clicks: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        on_click: Option<Effect>,
+    },
+    Label {
+        text: String,
+        x: i32,
+        y: i32,
+    },
+    Input {
+        id: String,
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        #[serde(default)]
+        value: String,
+        // Shown as asterisks.
+        #[serde(default)]
+        secret: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Effect {
+    Submit,
+    Clear,
+    Close,
+}
+
+impl Widget {
+    fn rect(&self) -> Option<(i32, i32, i32, i32)> {
+        match self {
+            Widget::Button { x, y, w, h, .. } | Widget::Input { x, y, w, h, .. } => Some((*x, *y, *w, *h)),
+            Widget::Label { .. } => None,
+        }
+    }
+
+    fn id(&self) -> Option<&str> {
+        match self {
+            Widget::Button { id, .. } | Widget::Input { id, .. } => Some(id.as_str()),
+            Widget::Label { .. } => None,
+        }
+    }
+}
+
+impl Window {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.w && y < self.y + self.h
+    }
+
+    // Top-left of the content area on screen, scroll applied.
+    fn origin(&self) -> (i32, i32) {
+        (self.x, self.y + TITLE_H - self.scroll)
+    }
+
+    fn max_scroll(&self) -> i32 {
+        let bottom = self.widgets.iter().map(|w| match w {
+            Widget::Label { y, .. } => y + TEXT_H,
+            Widget::Button { y, h, .. } | Widget::Input { y, h, .. } => y + h,
+        });
+        (bottom.max().unwrap_or(0) + 8 - (self.h - TITLE_H)).max(0)
+    }
+}
+
+impl Scene {
+    fn validate(&self) -> Result<(), String> {
+        if !(64..=7680).contains(&self.width) || !(64..=4320).contains(&self.height) {
+            return Err("scene size must be within 64x64 and 7680x4320".to_string());
+        }
+        if self.windows.len() > MAX_WINDOWS || self.windows.iter().any(|w| w.widgets.len() > MAX_WIDGETS) {
+            return Err(format!("at most {MAX_WINDOWS} windows of {MAX_WIDGETS} widgets"));
+        }
+        if self.windows.iter().any(|w| w.w <= 0 || w.h <= TITLE_H) {
+            return Err(format!("windows need a positive width and a height above {TITLE_H}"));
+        }
+        color(&self.background).map(|_| ())
+    }
+}
+
+fn default_scene() -> Scene {
+    let scene = json!({
+        "windows": [
+            {"id": "notes", "title": "Notes", "x": 80, "y": 60, "w": 640, "h": 420, "widgets": [
+                {"kind": "label", "text": "Name", "x": 20, "y": 27},
+                {"kind": "input", "id": "name", "x": 100, "y": 20, "w": 400, "h": 28},
+                {"kind": "label", "text": "Note", "x": 20, "y": 77},
+                {"kind": "input", "id": "note", "x": 100, "y": 70, "w": 400, "h": 28},
+                {"kind": "button", "id": "save", "label": "Save", "x": 100, "y": 130, "w": 100, "h": 32, "on_click": "submit"},
+                {"kind": "button", "id": "clear", "label": "Clear", "x": 220, "y": 130, "w": 100, "h": 32, "on_click": "clear"}
+            ]},
+            {"id": "login", "title": "Sign in", "x": 780, "y": 200, "w": 420, "h": 260, "widgets": [
+                {"kind": "label", "text": "User", "x": 20, "y": 27},
+                {"kind": "input", "id": "user", "x": 130, "y": 20, "w": 260, "h": 28},
+                {"kind": "label", "text": "Password", "x": 20, "y": 77},
+                {"kind": "input", "id": "password", "x": 130, "y": 70, "w": 260, "h": 28, "secret": true},
+                {"kind": "button", "id": "sign_in", "label": "Sign in", "x": 130, "y": 130, "w": 120, "h": 32, "on_click": "submit"}
+            ]}
+        ]
+    });
+    serde_json::from_value(scene).expect("built-in scene is valid")
+}
+
+fn load_scene() -> Scene {
+    let Some(path) = std::env::var("SEEALLN_SIM_SCENE").ok().filter(|s| !s.trim().is_empty()) else {
+        return default_scene();
+    };
+    let loaded = std::fs::read(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| serde_json::from_slice::<Scene>(&data).map_err(|e| e.to_string()))
+        .and_then(|scene| scene.validate().map(|_| scene));
+    loaded.unwrap_or_else(|err| {
+        error!(%err, %path, "cannot load SEEALLN_SIM_SCENE; using the built-in scene");
+        default_scene()
+    })
+}
+
+struct Sim {
+    scene: Scene,
+    cursor: (i32, i32),
+    // (window id, input id)
+    focused_input: Option<(String, String)>,
+    // (window id, button id) pressed by the last action.
+    pressed: Option<(String, String)>,
+    log: VecDeque<serde_json::Value>,
+}
+
+impl Sim {
+    fn new(scene: Scene) -> Self {
+        let cursor = (scene.width as i32 / 2, scene.height as i32 / 2);
+        Self {
+            scene,
+            cursor,
+            focused_input: None,
+            pressed: None,
+            log: VecDeque::new(),
+        }
+    }
+
+    fn record(&mut self, event: serde_json::Value) {
+        if self.log.len() == LOG_LEN {
+            self.log.pop_front();
+        }
+        self.log.push_back(event);
+    }
+
+    fn window_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.scene.windows.iter().rposition(|w| w.contains(x, y))
+    }
+
+    fn focused_window(&self) -> Option<&Window> {
+        self.scene.windows.last()
+    }
+
+    fn focused_value(&mut self) -> Option<&mut String> {
+        let (window, input) = self.focused_input.clone()?;
+        self.scene.windows.iter_mut().find(|w| w.id == window)?.widgets.iter_mut().find_map(|w| match w {
+            Widget::Input { id, value, .. } if *id == input => Some(value),
+            _ => None,
+        })
+    }
+
+    fn click(&mut self, button: &str) {
+        let (x, y) = self.cursor;
+        let Some(i) = self.window_at(x, y) else {
+            self.focused_input = None;
+            self.record(json!({"event": "click", "button": button, "x": x, "y": y, "target": null}));
+            return;
+        };
+        // Raise it.
+        let window = self.scene.windows.remove(i);
+        self.scene.windows.push(window);
+        let window = self.scene.windows.last_mut().expect("just pushed");
+        let (ox, oy) = window.origin();
+        let hit = if y >= window.y + TITLE_H {
+            window.widgets.iter_mut().rev().find(|w| {
+                w.rect().is_some_and(|(wx, wy, ww, wh)| x >= ox + wx && y >= oy + wy && x < ox + wx + ww && y < oy + wy + wh)
+            })
+        } else {
+            None
+        };
+        let window_id = window.id.clone();
+        let target = hit.as_ref().and_then(|w| w.id()).map(str::to_string);
+        let mut effect = None;
+        match hit {
+            Some(Widget::Button { id, clicks, on_click, .. }) if button == "left" => {
+                *clicks += 1;
+                effect = *on_click;
+                self.pressed = Some((window_id.clone(), id.clone()));
+            }
+            Some(Widget::Input { id, .. }) if button == "left" => self.focused_input = Some((window_id.clone(), id.clone())),
+            Some(_) => {}
+            None if button == "left" => self.focused_input = None,
+            None => {}
+        }
+        self.record(json!({"event": "click", "button": button, "x": x, "y": y, "window": window_id, "target": target}));
+        if let Some(effect) = effect {
+            self.run(effect, target);
+        }
+    }
+
+    // A button's `on_click` (or Return's submit) in the window on top, the one just clicked
+    // or typed into.
+    fn run(&mut self, effect: Effect, button: Option<String>) {
+        let Some(window) = self.scene.windows.last_mut() else {
+            return;
+        };
+        let window_id = window.id.clone();
+        match effect {
+            Effect::Submit => {
+                let values: serde_json::Map<String, serde_json::Value> = window
+                    .widgets
+                    .iter()
+                    .filter_map(|w| match w {
+                        Widget::Input { id, value, secret, .. } => {
+                            let shown = if *secret { "*".repeat(value.chars().count()) } else { value.clone() };
+                            Some((id.clone(), json!(shown)))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                self.record(json!({"event": "submit", "window": window_id, "button": button, "values": values}));
+            }
+            Effect::Clear => {
+                for widget in &mut window.widgets {
+                    if let Widget::Input { value, .. } = widget {
+                        value.clear();
+                    }
+                }
+                self.record(json!({"event": "clear", "window": window_id, "button": button}));
+            }
+            Effect::Close => {
+                self.scene.windows.pop();
+                if self.focused_input.as_ref().is_some_and(|(w, _)| *w == window_id) {
+                    self.focused_input = None;
+                }
+                self.pressed = None;
+                self.record(json!({"event": "close", "window": window_id, "button": button}));
+            }
+        }
+    }
+
+    fn type_text(&mut self, text: &str) {
+        let typed: String = text.chars().filter(|c| !c.is_control()).collect();
+        let target = self.focused_input.clone();
+        match self.focused_value() {
+            Some(value) => value.push_str(&typed),
+            None => return self.record(json!({"event": "type", "target": null, "chars": typed.chars().count()})),
+        }
+        let (window, input) = target.expect("focused");
+        self.record(json!({"event": "type", "window": window, "target": input, "chars": typed.chars().count()}));
+    }
+
+    fn key(&mut self, combo: &str) -> Result<(), (StatusCode, String)> {
+        let keys = keys::parse_combo(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        let (last, held) = keys.split_last().expect("combo has at least one key");
+        let shift = held.contains(&KeyName::Shift);
+        let plain = held.iter().all(|k| *k == KeyName::Shift);
+        match last {
+            KeyName::Char(c) if plain => {
+                let c = if shift { c.to_uppercase().next().unwrap_or(*c) } else { *c };
+                self.type_text(&c.to_string());
+                return Ok(());
+            }
+            KeyName::Space if plain => {
+                self.type_text(" ");
+                return Ok(());
+            }
+            KeyName::Backspace if plain => {
+                if let Some(value) = self.focused_value() {
+                    value.pop();
+                }
+            }
+            KeyName::Tab if plain => self.focus_next(),
+            KeyName::Return if plain && self.focused_input.is_some() => self.run(Effect::Submit, None),
+            _ => {}
+        }
+        self.record(json!({"event": "key", "combo": combo}));
+        Ok(())
+    }
+
+    fn focus_next(&mut self) {
+        let Some(window) = self.focused_window() else {
+            return;
+        };
+        let inputs: Vec<&str> = window
+            .widgets
+            .iter()
+            .filter_map(|w| match w {
+                Widget::Input { id, .. } => Some(id.as_str()),
+                _ => None,
+            })
+            .collect();
+        if inputs.is_empty() {
+            return;
+        }
+        let current = self.focused_input.as_ref().filter(|(w, _)| *w == window.id).map(|(_, i)| i.as_str());
+        let next = match current.and_then(|c| inputs.iter().position(|i| *i == c)) {
+            Some(pos) => inputs[(pos + 1) % inputs.len()],
+            None => inputs[0],
+        };
+        let next = (window.id.clone(), next.to_string());
+        self.focused_input = Some(next);
+    }
+
+    fn apply(&mut self, action: &Action) -> Result<(), (StatusCode, String)> {
+        let (w, h) = (self.scene.width as i32, self.scene.height as i32);
+        // A button stays down for one frame's worth: until whatever the agent does next.
+        self.pressed = None;
+        match action {
+            Action::Move { x, y } => self.cursor = ((*x).clamp(0, w - 1), (*y).clamp(0, h - 1)),
+            Action::Click { button } => match button.as_str() {
+                "left" | "right" | "middle" => self.click(button),
+                _ => return Err((StatusCode::BAD_REQUEST, "invalid button".to_string())),
+            },
+            Action::Type { text } | Action::Paste { text } => self.type_text(text),
+            Action::Key { combo } => self.key(combo)?,
+            Action::Scroll { dy, .. } => {
+                let (x, y) = self.cursor;
+                if let Some(i) = self.window_at(x, y) {
+                    let window = &mut self.scene.windows[i];
+                    window.scroll = (window.scroll + dy * SCROLL_STEP).clamp(0, window.max_scroll());
+                    let event = json!({"event": "scroll", "window": window.id, "scroll": window.scroll});
+                    self.record(event);
+                }
+            }
+            Action::Gesture { gesture: Gesture::Tap { x, y }, .. } => {
+                self.cursor = ((*x).clamp(0, w - 1), (*y).clamp(0, h - 1));
+                self.click("left");
+            }
+            Action::Gesture { .. } => {
+                return Err((StatusCode::NOT_IMPLEMENTED, "only tap gestures are simulated".to_string()));
+            }
+            Action::Browser { .. } => return Err(crate::input::not_input()),
+        }
+        Ok(())
+    }
+
+    fn state(&self) -> serde_json::Value {
+        let focused = self.focused_input.as_ref().map(|(w, i)| json!({"window": w, "input": i}));
+        json!({
+            "cursor": {"x": self.cursor.0, "y": self.cursor.1},
+            "focused_window": self.focused_window().map(|w| &w.id),
+            "focused_input": focused,
+            "pressed": self.pressed.as_ref().map(|(w, b)| json!({"window": w, "button": b})),
+            "scene": self.scene,
+            "log": self.log,
+        })
+    }
+}
+
+fn sim() -> MutexGuard<'static, Sim> {
+    static SIM: OnceLock<Mutex<Sim>> = OnceLock::new();
+    SIM.get_or_init(|| Mutex::new(Sim::new(load_scene()))).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Scene bounds, as the one and only monitor.
+pub fn monitor() -> ScopeRect {
+    let sim = sim();
+    ScopeRect { x: 0, y: 0, w: sim.scene.width as i32, h: sim.scene.height as i32 }
+}
+
+// Whether a text input has keyboard focus.
+pub fn input_focused() -> bool {
+    sim().focused_input.is_some()
+}
+
+// Titles and bounds of the open windows, bottom to top.
+pub fn windows() -> Vec<(String, ScopeRect)> {
+    sim().scene.windows.iter().map(|w| (w.title.clone(), ScopeRect { x: w.x, y: w.y, w: w.w, h: w.h })).collect()
+}
+
+// Title of the window on top; None outside simulation mode.
+pub fn focused_app() -> Option<String> {
+    active().then(|| sim().focused_window().map(|w| w.title.clone())).flatten()
+}
+
+fn color(hex: &str) -> Result<Rgb<u8>, String> {
+    let digits = hex.trim().trim_start_matches('#');
+    let v = u32::from_str_radix(digits, 16).ok().filter(|_| digits.len() == 6).ok_or_else(|| format!("invalid color {hex}"))?;
+    Ok(Rgb([(v >> 16) as u8, (v >> 8) as u8, v as u8]))
+}
+
+const INK: Rgb<u8> = Rgb([20, 20, 20]);
+const WHITE: Rgb<u8> = Rgb([255, 255, 255]);
+const BORDER: Rgb<u8> = Rgb([90, 90, 90]);
+const TITLE_ACTIVE: Rgb<u8> = Rgb([0, 90, 158]);
+const TITLE_INACTIVE: Rgb<u8> = Rgb([150, 150, 150]);
+const CONTENT: Rgb<u8> = Rgb([240, 240, 240]);
+const BUTTON: Rgb<u8> = Rgb([220, 220, 220]);
+const BUTTON_PRESSED: Rgb<u8> = Rgb([180, 200, 230]);
+const FOCUS: Rgb<u8> = Rgb([0, 120, 215]);
+
+// Drawing area: x0..x1, y0..y1.
+#[derive(Clone, Copy)]
+struct Clip(i32, i32, i32, i32);
+
+fn fill(img: &mut RgbImage, clip: Clip, x: i32, y: i32, w: i32, h: i32, c: Rgb<u8>) {
+    let (x0, y0) = (x.max(clip.0).max(0), y.max(clip.1).max(0));
+    let x1 = (x + w).min(clip.2).min(img.width() as i32);
+    let y1 = (y + h).min(clip.3).min(img.height() as i32);
+    for py in y0..y1 {
+        for px in x0..x1 {
+            img.put_pixel(px as u32, py as u32, c);
+        }
+    }
+}
+
+fn outline(img: &mut RgbImage, clip: Clip, x: i32, y: i32, w: i32, h: i32, c: Rgb<u8>) {
+    fill(img, clip, x, y, w, 1, c);
+    fill(img, clip, x, y + h - 1, w, 1, c);
+    fill(img, clip, x, y, 1, h, c);
+    fill(img, clip, x + w - 1, y, 1, h, c);
+}
+
+fn text(img: &mut RgbImage, clip: Clip, x: i32, y: i32, s: &str, c: Rgb<u8>) {
+    for (i, ch) in s.chars().enumerate() {
+        let left = x + i as i32 * ADVANCE;
+        for (row, bits) in glyph(ch).iter().enumerate() {
+            for col in 0..5 {
+                if (bits >> (4 - col)) & 1 == 1 {
+                    fill(img, clip, left + col * SCALE, y + row as i32 * SCALE, SCALE, SCALE, c);
+                }
+            }
+        }
+    }
+}
+
+fn text_width(s: &str) -> i32 {
+    s.chars().count() as i32 * ADVANCE - SCALE
+}
+
+fn cursor(img: &mut RgbImage, x: i32, y: i32) {
+    let clip = Clip(0, 0, img.width() as i32, img.height() as i32);
+    for r in 0..16 {
+        let w = r * 2 / 3 + 1;
+        fill(img, clip, x, y + r, w, 1, INK);
+        if w > 2 && r < 15 {
+            fill(img, clip, x + 1, y + r, w - 2, 1, WHITE);
+        }
+    }
+}
+
+fn render_window(img: &mut RgbImage, window: &Window, focused: bool, focused_input: Option<&str>, pressed: Option<&str>) {
+    let screen = Clip(0, 0, img.width() as i32, img.height() as i32);
+    let (x, y, w, h) = (window.x, window.y, window.w, window.h);
+    fill(img, screen, x, y, w, h, CONTENT);
+    fill(img, screen, x, y, w, TITLE_H, if focused { TITLE_ACTIVE } else { TITLE_INACTIVE });
+    let title_clip = Clip(x, y, x + w - 4, y + TITLE_H);
+    text(img, title_clip, x + 8, y + (TITLE_H - TEXT_H) / 2, &window.title, WHITE);
+    outline(img, screen, x, y, w, h, BORDER);
+
+    let content = Clip(x + 1, y + TITLE_H, x + w - 1, y + h - 1);
+    let (ox, oy) = window.origin();
+    for widget in &window.widgets {
+        match widget {
+            Widget::Label { text: s, x: wx, y: wy } => text(img, content, ox + wx, oy + wy, s, INK),
+            Widget::Button { id, label, x: wx, y: wy, w: ww, h: wh, .. } => {
+                let (bx, by) = (ox + wx, oy + wy);
+                let down = pressed == Some(id.as_str());
+                fill(img, content, bx, by, *ww, *wh, if down { BUTTON_PRESSED } else { BUTTON });
+                outline(img, content, bx, by, *ww, *wh, BORDER);
+                // Sunken: a dark top-left edge and the label nudged down-right.
+                let shift = if down {
+                    fill(img, content, bx + 1, by + 1, ww - 2, 1, INK);
+                    fill(img, content, bx + 1, by + 1, 1, wh - 2, INK);
+                    1
+                } else {
+                    0
+                };
+                let inner = Clip(bx.max(content.0), by.max(content.1), (bx + ww).min(content.2), (by + wh).min(content.3));
+                text(img, inner, bx + (ww - text_width(label)) / 2 + shift, by + (wh - TEXT_H) / 2 + shift, label, INK);
+            }
+            Widget::Input { id, x: wx, y: wy, w: ww, h: wh, value, secret } => {
+                let (bx, by) = (ox + wx, oy + wy);
+                let has_focus = focused && focused_input == Some(id.as_str());
+                fill(img, content, bx, by, *ww, *wh, WHITE);
+                outline(img, content, bx, by, *ww, *wh, if has_focus { FOCUS } else { BORDER });
+                let shown: String = if *secret { "*".repeat(value.chars().count()) } else { value.clone() };
+                // The end of the value stays visible, like a real text field.
+                let fit = ((ww - 12) / ADVANCE).max(0) as usize;
+                let count = shown.chars().count();
+                let shown: String = shown.chars().skip(count.saturating_sub(fit)).collect();
+                let inner = Clip(bx.max(content.0), by.max(content.1), (bx + ww).min(content.2), (by + wh).min(content.3));
+                let ty = by + (wh - TEXT_H) / 2;
+                text(img, inner, bx + 6, ty, &shown, INK);
+                if has_focus {
+                    let caret = bx + 6 + shown.chars().count() as i32 * ADVANCE;
+                    fill(img, inner, caret, ty - 2, SCALE, TEXT_H + 4, INK);
+                }
+            }
+        }
+    }
+}
+
+// The current scene as a frame.
+pub fn render() -> RgbImage {
+    let sim = sim();
+    let scene = &sim.scene;
+    let background = color(&scene.background).unwrap_or(Rgb([43, 87, 151]));
+    let mut img = RgbImage::from_pixel(scene.width, scene.height, background);
+    let top = scene.windows.len().saturating_sub(1);
+    let focused_input = sim.focused_input.as_ref().map(|(_, i)| i.as_str());
+    for (i, window) in scene.windows.iter().enumerate() {
+        let focused = i == top;
+        let input = focused_input.filter(|_| focused && sim.focused_input.as_ref().is_some_and(|(w, _)| *w == window.id));
+        let pressed = sim.pressed.as_ref().filter(|(w, _)| *w == window.id).map(|(_, b)| b.as_str());
+        render_window(&mut img, window, focused, input, pressed);
+    }
+    cursor(&mut img, sim.cursor.0, sim.cursor.1);
+    img
+}
+
+// Hands backend of simulation mode (SEEALLN_INPUT_BACKEND is ignored); needs no `hands`.
+pub struct SimInput;
+
+impl InputBackend for SimInput {
+    fn name(&self) -> &'static str {
+        "sim"
+    }
+
+    fn ready(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+        sim().apply(action)
+    }
+
+    fn location(&self) -> Result<(i32, i32), String> {
+        Ok(sim().cursor)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            unicode_typing: true,
+            horizontal_scroll: false,
+            any_key_char: true,
+            x11_only: false,
+        }
+    }
+}
+
+fn disabled() -> Option<Response> {
+    (!active()).then(|| {
+        let error = "simulation is off (build with --features simulation, set SEEALLN_SIMULATION=1)";
+        (StatusCode::NOT_IMPLEMENTED, Json(json!({"ok": false, "error": error}))).into_response()
+    })
+}
+
+pub async fn sim_state(headers: HeaderMap) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Some(resp) = disabled() {
+        return resp;
+    }
+    let mut state = sim().state();
+    state["ok"] = json!(true);
+    (StatusCode::OK, Json(state)).into_response()
+}
+
+// Back to the configured scene, or to the one in the body.
+pub async fn sim_reset(headers: HeaderMap, body: Option<Json<Scene>>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Some(resp) = disabled() {
+        return resp;
+    }
+    let scene = match body {
+        Some(Json(scene)) => match scene.validate() {
+            Ok(()) => scene,
+            Err(err) => return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": err}))).into_response(),
+        },
+        None => load_scene(),
+    };
+    let mut sim = sim();
+    *sim = Sim::new(scene);
+    let mut state = sim.state();
+    state["ok"] = json!(true);
+    (StatusCode::OK, Json(state)).into_response()
+}
+
+// 5x7 bitmap font, one byte per row (low 5 bits, MSB left). Lowercase draws as uppercase;
+// anything else as a hollow box.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0A, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        ';' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08],
+        '!' => [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04],
+        '?' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '\'' => [0x04, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '"' => [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '@' => [0x0E, 0x11, 0x01, 0x0D, 0x15, 0x15, 0x0E],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        '*' => [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '<' => [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02],
+        '>' => [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08],
+        _ => [0x1F, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1F],
+    }
+}
@@ -0,0 +1,240 @@
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::watch;
+
+use crate::{bandwidth, hands::require_local_only, origin::Origin, pool};
+
+// In-memory usage counters behind GET /stats, for users who do not run Prometheus. Lifetime
+// totals reset on restart; per-session counters are kept for the last SESSIONS recorded
+// sessions (see session.rs; nothing per session when recording is off). Per-client bandwidth
+// comes from bandwidth.rs.
+#[derive(Default)]
+struct Counters {
+    // Executed actions by kind (move, click, ...).
+    actions: BTreeMap<&'static str, u64>,
+    // Refused actions by reason: "rate limited", "not armed", "killed" or the interceptor.
+    rejections: BTreeMap<String, u64>,
+}
+
+impl Counters {
+    fn json(&self) -> serde_json::Value {
+        json!({
+            "actions": self.actions,
+            "actions_total": self.actions.values().sum::<u64>(),
+            "rejections": self.rejections,
+            "rejections_total": self.rejections.values().sum::<u64>(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    lifetime: Counters,
+    sessions: VecDeque<(String, u64, Counters)>,
+    // Frames served by route.
+    frames: BTreeMap<&'static str, u64>,
+    encodes: u64,
+    encode_time: Duration,
+    // Finished streams, plus the start and pause switch of every open one.
+    stream_time: Duration,
+    open_streams: HashMap<u64, (Instant, watch::Sender<bool>)>,
+    // Open streams supervising an arming (`/stream?supervise=<session id>`): the session and
+    // the client watching.
+    supervising: HashMap<u64, (String, Origin)>,
+}
+
+const SESSIONS: usize = 20;
+
+fn stats() -> MutexGuard<'static, Stats> {
+    static STATS: OnceLock<Mutex<Stats>> = OnceLock::new();
+    STATS.get_or_init(Mutex::default).lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn unix_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+// Process start as seen by /stats; called from main so uptime does not start at the first frame.
+pub fn started() -> (Instant, u64) {
+    static STARTED: OnceLock<(Instant, u64)> = OnceLock::new();
+    *STARTED.get_or_init(|| (Instant::now(), unix_ms()))
+}
+
+fn session_counters<'a>(s: &'a mut Stats, id: &str) -> &'a mut Counters {
+    if let Some(i) = s.sessions.iter().position(|(sid, _, _)| sid == id) {
+        return &mut s.sessions[i].2;
+    }
+    if s.sessions.len() == SESSIONS {
+        s.sessions.pop_front();
+    }
+    s.sessions.push_back((id.to_string(), unix_ms(), Counters::default()));
+    &mut s.sessions.back_mut().expect("just pushed").2
+}
+
+pub fn action(session: Option<&str>, kind: &'static str) {
+    let mut s = stats();
+    *s.lifetime.actions.entry(kind).or_default() += 1;
+    if let Some(id) = session {
+        *session_counters(&mut s, id).actions.entry(kind).or_default() += 1;
+    }
+}
+
+pub fn rejection(session: Option<&str>, reason: &str) {
+    let mut s = stats();
+    *s.lifetime.rejections.entry(reason.to_string()).or_default() += 1;
+    if let Some(id) = session {
+        *session_counters(&mut s, id).rejections.entry(reason.to_string()).or_default() += 1;
+    }
+}
+
+pub fn frame(route: &'static str, encode: Duration) {
+    let mut s = stats();
+    *s.frames.entry(route).or_default() += 1;
+    s.encodes += 1;
+    s.encode_time += encode;
+}
+
+// Counts an open /stream until dropped (the client disconnecting drops the body stream).
+// Its id (x-seealln-stream-id) lets a supervisor pause the stream with POST
+// /stream/:id/pause and pick it up again with /resume: the connection stays open, no frames
+// are captured or sent in between, and X-Frame-Seq carries on where it stopped.
+pub struct StreamGuard {
+    id: u64,
+    paused: watch::Receiver<bool>,
+}
+
+impl StreamGuard {
+    pub fn open() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT.fetch_add(1, Ordering::Relaxed);
+        let (tx, paused) = watch::channel(false);
+        stats().open_streams.insert(id, (Instant::now(), tx));
+        Self { id, paused }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    // Marks the stream as `origin` watching the arming recorded as `session`, for armings that
+    // require supervision (see hands.rs); they disarm once no such stream is left.
+    pub fn supervise(&self, session: String, origin: Origin) {
+        stats().supervising.insert(self.id, (session, origin));
+    }
+
+    // Returns at once unless paused, else once resumed.
+    pub async fn resumed(&mut self) {
+        // The sender lives as long as the guard's entry, so this cannot fail early.
+        let _ = self.paused.wait_for(|paused| !paused).await;
+    }
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        let mut s = stats();
+        if let Some((at, _)) = s.open_streams.remove(&self.id) {
+            s.stream_time += at.elapsed();
+        }
+        s.supervising.remove(&self.id);
+    }
+}
+
+// Open streams, paused or not.
+#[cfg(feature = "capture")]
+pub fn open_streams() -> usize {
+    stats().open_streams.len()
+}
+
+// Unpaused streams supervising `session` from any client but `agent`, the one the arming is
+// bound to: an agent does not supervise itself.
+pub fn supervisors(session: &str, agent: &Origin) -> usize {
+    let s = stats();
+    s.supervising
+        .iter()
+        .filter(|(_, (watched, origin))| watched == session && origin != agent)
+        .filter(|(id, _)| s.open_streams.get(id).is_some_and(|(_, paused)| !*paused.borrow()))
+        .count()
+}
+
+fn set_paused(id: u64, paused: bool) -> impl IntoResponse {
+    let s = stats();
+    let Some((_, tx)) = s.open_streams.get(&id) else {
+        return (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": format!("no open stream {id}")})));
+    };
+    tx.send_replace(paused);
+    (StatusCode::OK, Json(json!({"ok": true, "stream": id, "paused": paused})))
+}
+
+pub async fn stream_pause(headers: HeaderMap, Path(id): Path<u64>) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    set_paused(id, true).into_response()
+}
+
+pub async fn stream_resume(headers: HeaderMap, Path(id): Path<u64>) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    set_paused(id, false).into_response()
+}
+
+pub async fn stats_json(headers: HeaderMap) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let (since, since_unix_ms) = started();
+    let s = stats();
+    let streaming: Duration = s.open_streams.values().map(|(at, _)| at.elapsed()).sum();
+    let mut paused: Vec<u64> = s.open_streams.iter().filter(|(_, (_, tx))| *tx.borrow()).map(|(id, _)| *id).collect();
+    paused.sort_unstable();
+    let avg_encode_ms = (s.encodes > 0).then(|| s.encode_time.as_secs_f64() * 1000.0 / s.encodes as f64);
+    let sessions: Vec<_> = s
+        .sessions
+        .iter()
+        .rev()
+        .map(|(id, unix_ms, c)| {
+            let mut entry = c.json();
+            entry["id"] = json!(id);
+            entry["started_unix_ms"] = json!(unix_ms);
+            entry
+        })
+        .collect();
+    Json(json!({
+        "ok": true,
+        "since_unix_ms": since_unix_ms,
+        "uptime_secs": since.elapsed().as_secs(),
+        "lifetime": s.lifetime.json(),
+        "frames": {
+            "served": s.frames,
+            "served_total": s.frames.values().sum::<u64>(),
+            "avg_encode_ms": avg_encode_ms,
+        },
+        "streams": {
+            "open": s.open_streams.len(),
+            "paused": paused,
+            "supervising": s.supervising.len(),
+            "minutes": (s.stream_time + streaming).as_secs_f64() / 60.0,
+        },
+        // Bytes served per client, with the caps in force.
+        "bandwidth": bandwidth::json(),
+        // Reused frame buffers (see pool.rs).
+        "buffer_pool": pool::json(),
+        // Newest first.
+        "sessions": sessions,
+    }))
+    .into_response()
+}
@@ -0,0 +1,556 @@
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+use crate::{
+    attest,
+    auth::require_api_key,
+    crypto::Cipher,
+    hands::{confirmed, require_local_only},
+};
+
+// On-disk footprint of the server: recordings, frame history, audit entries and visual
+// baselines all live under one data dir so retention can manage them together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Area {
+    Recordings,
+    Frames,
+    Audit,
+    Baselines,
+}
+
+impl Area {
+    pub const ALL: [Area; 4] = [Area::Recordings, Area::Frames, Area::Audit, Area::Baselines];
+
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Area::Recordings => "recordings",
+            Area::Frames => "frames",
+            Area::Audit => "audit",
+            Area::Baselines => "baselines",
+        }
+    }
+
+    fn env_prefix(self) -> &'static str {
+        match self {
+            Area::Recordings => "SEEALLN_RECORDINGS",
+            Area::Frames => "SEEALLN_FRAMES",
+            Area::Audit => "SEEALLN_AUDIT",
+            Area::Baselines => "SEEALLN_BASELINES",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Area> {
+        Area::ALL.into_iter().find(|a| a.dir_name() == s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Retention {
+    // 0 means unlimited
+    pub max_age_secs: u64,
+    pub max_bytes: u64,
+}
+
+impl Retention {
+    fn from_env(area: Area) -> Self {
+        let (default_age, default_bytes) = match area {
+            Area::Recordings => (7 * 86_400, 2 << 30),
+            Area::Frames => (86_400, 512 << 20),
+            Area::Audit => (90 * 86_400, 256 << 20),
+            // Golden images are kept until replaced or purged.
+            Area::Baselines => (0, 0),
+        };
+        let prefix = area.env_prefix();
+        let max_age_secs = std::env::var(format!("{prefix}_MAX_AGE_SECS"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(default_age);
+        let max_bytes = std::env::var(format!("{prefix}_MAX_BYTES"))
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(default_bytes);
+        Self { max_age_secs, max_bytes }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, serde::Serialize)]
+pub struct PurgeReport {
+    pub files: u64,
+    pub bytes: u64,
+    pub remaining_bytes: u64,
+}
+
+#[derive(Clone)]
+pub struct Store {
+    root: PathBuf,
+    retention: [Retention; 4],
+    // Serializes line appends so concurrent handlers never interleave lines.
+    append_lock: Arc<Mutex<()>>,
+    // Encryption at rest (None = plaintext).
+    cipher: Option<Cipher>,
+    // Keep served snapshots under frames/ (SEEALLN_FRAME_HISTORY=1).
+    frame_history: bool,
+    // Last link of the frame history hash chain; None until the first frame (or restart).
+    frame_chain: Arc<Mutex<Option<String>>>,
+}
+
+impl Store {
+    pub fn from_env() -> Self {
+        let root = std::env::var("SEEALLN_DATA_DIR")
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| "seealln-data".to_string());
+        Self {
+            root: PathBuf::from(root),
+            retention: Area::ALL.map(Retention::from_env),
+            append_lock: Arc::default(),
+            cipher: Cipher::from_env(),
+            frame_history: std::env::var("SEEALLN_FRAME_HISTORY").ok().as_deref() == Some("1"),
+            frame_chain: Arc::default(),
+        }
+    }
+
+    pub fn encrypted(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dir(&self, area: Area) -> PathBuf {
+        self.root.join(area.dir_name())
+    }
+
+    pub fn retention(&self, area: Area) -> Retention {
+        self.retention[area as usize]
+    }
+
+    // Appends one JSON line to today's audit file (audit/YYYY-MM-DD.jsonl).
+    pub fn audit(&self, mut event: serde_json::Value) {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        if let Some(obj) = event.as_object_mut() {
+            obj.insert("ts_ms".to_string(), json!(now.as_millis() as u64));
+        }
+        crate::logfwd::audit(&event);
+        let rel = format!("{}.jsonl", civil_date(now.as_secs()));
+        if let Err(err) = self.append_line(Area::Audit, &rel, &event.to_string()) {
+            warn!(%err, file = %rel, "audit append failed");
+        }
+    }
+
+    // Appends one line to an area file (sealed per line when a key is set).
+    pub fn append_line(&self, area: Area, rel: &str, line: &str) -> std::io::Result<()> {
+        let path = self
+            .resolve(area, rel)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid path"))?;
+        let line = match &self.cipher {
+            Some(c) => c.seal_line(line),
+            None => line.to_string(),
+        };
+
+        let _guard = self.append_lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut f = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        writeln!(f, "{line}")
+    }
+
+    // Resolves a path relative to an area dir, refusing anything that could escape it.
+    pub fn resolve(&self, area: Area, rel: &str) -> Option<PathBuf> {
+        let rel = Path::new(rel);
+        let safe = !rel.as_os_str().is_empty()
+            && rel.components().all(|c| matches!(c, std::path::Component::Normal(_)));
+        safe.then(|| self.dir(area).join(rel))
+    }
+
+    // Writes a whole file (frame, recording, ...) into an area, encrypted when a key is set.
+    pub fn write(&self, area: Area, rel: &str, data: &[u8]) -> std::io::Result<PathBuf> {
+        let path = self
+            .resolve(area, rel)
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "invalid path"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match &self.cipher {
+            Some(c) => fs::write(&path, c.seal_blob(data))?,
+            None => fs::write(&path, data)?,
+        }
+        Ok(path)
+    }
+
+    // Frame history: frames/YYYY-MM-DD/<unix ms>.<ext> (jpg unless another format was served),
+    // when enabled, named by capture time (`captured`, not the later write). Each day also gets
+    // index.jsonl with the capture time on both clocks, so gaps between frames can be replayed
+    // as they happened (variable frame rate) even across wall-clock jumps. Entries are hash
+    // chained (attest::link over `<file>:<unix_ms>:<sha256>`), continuing from the last entry of
+    // today's index after a restart, so a deleted or reordered frame is detectable:
+    //
+    //   {"file":"2024-05-01/1714550400123.jpg","unix_ms":1714550400123,"mono_ms":5321.4,
+    //    "sha256":"..","prev":"..","chain":".."}
+    pub fn record_frame(&self, data: &[u8], ext: &str, captured: Instant) {
+        if !self.frame_history {
+            return;
+        }
+        let wall = SystemTime::now().checked_sub(captured.elapsed()).unwrap_or_else(SystemTime::now);
+        let at = wall.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let day = civil_date(at.as_secs());
+        let rel = format!("{day}/{}.{ext}", at.as_millis());
+        if let Err(err) = self.write(Area::Frames, &rel, data) {
+            warn!(%err, "frame history write failed");
+            return;
+        }
+        let index = format!("{day}/index.jsonl");
+        let unix_ms = at.as_millis() as u64;
+        let digest = hex::encode(Sha256::digest(data));
+        // Held until the line is written, so the index order is the chain order.
+        let mut chain = self.frame_chain.lock().unwrap_or_else(|e| e.into_inner());
+        if chain.is_none() {
+            *chain = self.last_chain_link(&index);
+        }
+        let link = attest::link(chain.as_deref(), &format!("{rel}:{unix_ms}:{digest}"));
+        let entry = json!({
+            "file": rel,
+            "unix_ms": unix_ms,
+            "mono_ms": crate::capture::mono_ms(captured),
+            "sha256": digest,
+            "prev": *chain,
+            "chain": link,
+        });
+        match self.append_line(Area::Frames, &index, &entry.to_string()) {
+            Ok(()) => *chain = Some(link),
+            Err(err) => warn!(%err, "frame history index write failed"),
+        }
+    }
+
+    fn last_chain_link(&self, index: &str) -> Option<String> {
+        let data = self.read(Area::Frames, index).ok()?;
+        let last = String::from_utf8_lossy(&data).lines().rev().find_map(|l| serde_json::from_str::<serde_json::Value>(l).ok())?;
+        last["chain"].as_str().map(str::to_string)
+    }
+
+    // Reads a file back as plaintext (decrypting blobs and sealed audit lines).
+    pub fn read(&self, area: Area, rel: &str) -> Result<Vec<u8>, String> {
+        let path = self.resolve(area, rel).ok_or_else(|| "invalid path".to_string())?;
+        let data = fs::read(&path).map_err(|e| e.to_string())?;
+        match &self.cipher {
+            Some(c) => c.open_file(&data),
+            None if data.starts_with(crate::crypto::BLOB_MAGIC) => {
+                Err("file is encrypted but no store key is configured".to_string())
+            }
+            None => Ok(data),
+        }
+    }
+
+    pub fn list(&self, area: Area) -> Vec<String> {
+        let dir = self.dir(area);
+        let mut files = Vec::new();
+        collect_files(&dir, &mut files);
+        let mut names: Vec<String> = files
+            .iter()
+            .filter_map(|f| f.path.strip_prefix(&dir).ok())
+            .map(|p| p.to_string_lossy().replace('\\', "/"))
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Applies the configured retention of one area: first drop files past max age,
+    // then the oldest files until the area fits in max bytes.
+    pub fn enforce(&self, area: Area) -> PurgeReport {
+        let r = self.retention(area);
+        let max_age = (r.max_age_secs > 0).then(|| Duration::from_secs(r.max_age_secs));
+        let max_bytes = (r.max_bytes > 0).then_some(r.max_bytes);
+        self.prune(area, max_age, max_bytes)
+    }
+
+    // Removes files of `area` older than `older_than` (all files when None and no size cap).
+    pub fn purge(&self, area: Area, older_than: Option<Duration>) -> PurgeReport {
+        self.prune(area, Some(older_than.unwrap_or_default()), None)
+    }
+
+    fn prune(&self, area: Area, max_age: Option<Duration>, max_bytes: Option<u64>) -> PurgeReport {
+        let dir = self.dir(area);
+        let mut files = Vec::new();
+        collect_files(&dir, &mut files);
+        // Oldest first
+        files.sort_by_key(|f| f.modified);
+
+        let now = SystemTime::now();
+        let mut report = PurgeReport {
+            remaining_bytes: files.iter().map(|f| f.len).sum(),
+            ..Default::default()
+        };
+
+        for f in &files {
+            let expired = max_age
+                .map(|age| now.duration_since(f.modified).unwrap_or_default() >= age)
+                .unwrap_or(false);
+            let over_budget = max_bytes.map(|b| report.remaining_bytes > b).unwrap_or(false);
+            if !expired && !over_budget {
+                continue;
+            }
+            match fs::remove_file(&f.path) {
+                Ok(()) => {
+                    report.files += 1;
+                    report.bytes += f.len;
+                    report.remaining_bytes -= f.len;
+                }
+                Err(err) => warn!(%err, path = %f.path.display(), "retention: remove failed"),
+            }
+        }
+
+        remove_empty_dirs(&dir, false);
+        report
+    }
+
+    // Periodic background sweep; interval from SEEALLN_RETENTION_INTERVAL_SECS (0 disables).
+    pub fn spawn_retention(&self) {
+        let interval = std::env::var("SEEALLN_RETENTION_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or(300);
+        if interval == 0 {
+            return;
+        }
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(interval));
+            loop {
+                tick.tick().await;
+                let store = store.clone();
+                let _ = tokio::task::spawn_blocking(move || {
+                    for area in Area::ALL {
+                        let report = store.enforce(area);
+                        if report.files > 0 {
+                            info!(area = area.dir_name(), files = report.files, bytes = report.bytes, "retention: pruned");
+                        }
+                    }
+                })
+                .await;
+            }
+        });
+    }
+}
+
+struct FileEntry {
+    path: PathBuf,
+    len: u64,
+    modified: SystemTime,
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<FileEntry>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect_files(&entry.path(), out);
+        } else if meta.is_file() {
+            out.push(FileEntry {
+                path: entry.path(),
+                len: meta.len(),
+                modified: meta.modified().unwrap_or(UNIX_EPOCH),
+            });
+        }
+    }
+}
+
+fn remove_empty_dirs(dir: &Path, remove_self: bool) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                remove_empty_dirs(&entry.path(), true);
+            }
+        }
+    }
+    if remove_self {
+        // Fails (and is ignored) when the directory still has content.
+        let _ = fs::remove_dir(dir);
+    }
+}
+
+// Days since the unix epoch -> YYYY-MM-DD (proleptic Gregorian, UTC).
+pub fn civil_date(unix_secs: u64) -> String {
+    let z = (unix_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PurgeParams {
+    // recordings | frames | audit | baselines | all; required so a bare POST never wipes everything.
+    area: Option<String>,
+    // Only remove files at least this old; omit to remove everything in the area.
+    older_than_secs: Option<u64>,
+}
+
+pub async fn admin_purge(
+    State(store): State<Store>,
+    headers: HeaderMap,
+    Query(params): Query<PurgeParams>,
+) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err((code, msg)) = require_api_key(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if !confirmed(&headers) {
+        return (
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({"ok": false, "error": "missing x-seealln-confirm: yes"})),
+        )
+            .into_response();
+    }
+
+    let areas: Vec<Area> = match params.area.as_deref() {
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"ok": false, "error": "area is required (recordings, frames, audit, baselines or all)"})),
+            )
+                .into_response()
+        }
+        Some("all") => Area::ALL.to_vec(),
+        Some(s) => match Area::parse(s) {
+            Some(a) => vec![a],
+            None => {
+                return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid area"})))
+                    .into_response()
+            }
+        },
+    };
+    let older_than = params.older_than_secs.map(Duration::from_secs);
+
+    // Recorded before anything is removed, so the request is on file even if the purge fails
+    // halfway; the result goes in a second entry.
+    store.audit(json!({"event": "admin_purge", "areas": areas, "older_than_secs": params.older_than_secs}));
+
+    let purge_store = store.clone();
+    let reports = tokio::task::spawn_blocking(move || {
+        areas
+            .into_iter()
+            .map(|a| (a.dir_name().to_string(), json!(purge_store.purge(a, older_than))))
+            .collect::<serde_json::Map<_, _>>()
+    })
+    .await;
+
+    match reports {
+        Ok(reports) => {
+            store.audit(json!({"event": "admin_purge_done", "result": reports}));
+            (StatusCode::OK, Json(json!({"ok": true, "purged": reports}))).into_response()
+        }
+        Err(err) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"ok": false, "error": err.to_string()})),
+        )
+            .into_response(),
+    }
+}
+
+// Requires the API key: the response reveals where the data dir lives.
+pub async fn admin_retention(State(store): State<Store>, headers: HeaderMap) -> impl IntoResponse {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err((code, msg)) = require_api_key(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let policy: serde_json::Map<_, _> = Area::ALL
+        .into_iter()
+        .map(|a| (a.dir_name().to_string(), json!(store.retention(a))))
+        .collect();
+    (
+        StatusCode::OK,
+        Json(json!({"ok": true, "data_dir": store.root().display().to_string(), "encrypted": store.encrypted(), "retention": policy})),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportParams {
+    area: String,
+    // Relative to the area dir; omit to list the area.
+    file: Option<String>,
+}
+
+// Plaintext export of stored data. Always requires the API key: this and session transcripts
+// (session.rs) are the only paths through which encrypted data leaves the store decrypted.
+pub async fn admin_export(
+    State(store): State<Store>,
+    headers: HeaderMap,
+    Query(params): Query<ExportParams>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    if let Err((code, msg)) = require_api_key(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let Some(area) = Area::parse(&params.area) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({"ok": false, "error": "invalid area"}))).into_response();
+    };
+
+    let Some(file) = params.file else {
+        return (StatusCode::OK, Json(json!({"ok": true, "area": area, "files": store.list(area)}))).into_response();
+    };
+
+    let read_store = store.clone();
+    let read_file = file.clone();
+    let data = tokio::task::spawn_blocking(move || read_store.read(area, &read_file))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match data {
+        Ok(bytes) => {
+            store.audit(json!({"event": "export", "area": area, "file": file}));
+            let content_type = if file.ends_with(".jsonl") {
+                "application/x-ndjson"
+            } else if file.ends_with(".jpg") {
+                "image/jpeg"
+            } else if file.ends_with(".png") {
+                "image/png"
+            } else if file.ends_with(".avif") {
+                "image/avif"
+            } else if file.ends_with(".zst") {
+                "application/zstd"
+            } else {
+                "application/octet-stream"
+            };
+            let mut resp = Response::new(Body::from(bytes));
+            resp.headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            resp
+        }
+        Err(err) => (StatusCode::NOT_FOUND, Json(json!({"ok": false, "error": err}))).into_response(),
+    }
+}
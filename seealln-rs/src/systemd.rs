@@ -0,0 +1,124 @@
+use std::time::Duration;
+use tracing::{info, warn};
+
+// systemd integration, a no-op when not started by systemd:
+//
+// - Socket activation: with LISTEN_PID/LISTEN_FDS set for this process, the first passed fd (3)
+//   is used as the HTTP listener instead of binding SEEALLN_BIND:SEEALLN_PORT. The .socket unit
+//   then decides the address, so keep ListenStream on 127.0.0.1.
+// - sd_notify: READY=1 once listening, STOPPING=1 on shutdown, and WATCHDOG=1 at half of
+//   WatchdogSec when the unit sets it. The pings come from a runtime task, so a wedged runtime
+//   stops them and systemd restarts the service.
+//
+// See README ("systemd") for matching unit files.
+
+// The listener passed by systemd, if any. The LISTEN_* variables are removed so child processes
+// (Xvfb, helpers) do not take the socket for theirs.
+#[cfg(unix)]
+pub fn listener() -> Option<std::net::TcpListener> {
+    use std::os::fd::FromRawFd;
+
+    const LISTEN_FDS_START: i32 = 3;
+
+    let pid = std::env::var("LISTEN_PID").ok()?;
+    let fds = std::env::var("LISTEN_FDS").ok()?;
+    for var in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+        std::env::remove_var(var);
+    }
+    if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+        return None;
+    }
+    let n: i32 = fds.trim().parse().unwrap_or(0);
+    if n < 1 {
+        return None;
+    }
+    if n > 1 {
+        warn!(fds = n, "socket activation passed several sockets; using the first");
+    }
+    // SAFETY: systemd passes fds from LISTEN_FDS_START for this pid (checked above); nothing
+    // else in the process owns fd 3.
+    let listener = unsafe { std::net::TcpListener::from_raw_fd(LISTEN_FDS_START) };
+    match listener.local_addr() {
+        Ok(addr) => {
+            if !addr.ip().is_loopback() {
+                warn!(%addr, "socket-activated listener is not on loopback");
+            }
+        }
+        Err(err) => {
+            warn!(%err, "LISTEN_FDS socket is not a TCP listener; binding normally");
+            std::mem::forget(listener);
+            return None;
+        }
+    }
+    if let Err(err) = listener.set_nonblocking(true) {
+        warn!(%err, "cannot make the socket-activated listener non-blocking; binding normally");
+        std::mem::forget(listener);
+        return None;
+    }
+    Some(listener)
+}
+
+#[cfg(not(unix))]
+pub fn listener() -> Option<std::net::TcpListener> {
+    None
+}
+
+// Sends one sd_notify message; false when not under systemd or the send failed.
+#[cfg(target_os = "linux")]
+pub fn notify(state: &str) -> bool {
+    use std::os::{linux::net::SocketAddrExt, unix::net::UnixDatagram};
+
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET").filter(|p| !p.is_empty()) else {
+        return false;
+    };
+    let sent = UnixDatagram::unbound().and_then(|socket| {
+        let path = path.to_string_lossy();
+        match path.strip_prefix('@') {
+            // Abstract namespace socket.
+            Some(name) => {
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes())?;
+                socket.send_to_addr(state.as_bytes(), &addr)
+            }
+            None => socket.send_to(state.as_bytes(), path.as_ref()),
+        }
+    });
+    if let Err(err) = &sent {
+        warn!(%err, state, "sd_notify failed");
+    }
+    sent.is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn notify(_state: &str) -> bool {
+    false
+}
+
+// Reports readiness and, when the unit has WatchdogSec, keeps pinging the watchdog.
+pub fn ready() {
+    if !notify("READY=1") {
+        return;
+    }
+    info!("notified systemd: ready");
+    let Some(interval) = watchdog_interval() else { return };
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(interval);
+        loop {
+            tick.tick().await;
+            notify("WATCHDOG=1");
+        }
+    });
+}
+
+fn watchdog_interval() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+        if pid.trim().parse::<u32>().ok() != Some(std::process::id()) {
+            return None;
+        }
+    }
+    let usec: u64 = std::env::var("WATCHDOG_USEC").ok()?.trim().parse().ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec / 2))
+}
+
+pub fn stopping() {
+    notify("STOPPING=1");
+}
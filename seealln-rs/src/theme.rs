@@ -0,0 +1,129 @@
+use image::RgbImage;
+use serde::Serialize;
+use std::{
+    process::{Command, Stdio},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::vision;
+
+// Dark/light theme estimate for /analyze/theme. The frame decides: what is on screen under the
+// scope is what templates and OCR preprocessing have to cope with, whatever the OS says. The
+// OS preference is reported next to it where it can be read:
+//
+//   macOS    `defaults read -g AppleInterfaceStyle` ("Dark" when dark)
+//   Windows  HKCU\...\Themes\Personalize AppsUseLightTheme (0 when dark)
+//   Linux    gsettings color-scheme ('prefer-dark'), else a GTK theme name containing "dark"
+//
+// The OS lookup runs a command, so its result is reused for OS_TTL.
+const DARK_LUMA: u8 = 96;
+const LIGHT_LUMA: u8 = 160;
+const OS_TTL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Luminance {
+    pub mean: f64,
+    pub median: u8,
+    // Fractions of pixels with luma below DARK_LUMA / above LIGHT_LUMA.
+    pub dark_share: f64,
+    pub light_share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Estimate {
+    pub theme: Theme,
+    // 0..1: how lopsided dark vs light pixels are.
+    pub confidence: f64,
+    pub luminance: Luminance,
+}
+
+pub fn estimate(img: &RgbImage) -> Estimate {
+    let mut hist = [0u64; 256];
+    for p in img.pixels() {
+        hist[vision::luma(p.0) as usize] += 1;
+    }
+    let pixels = hist.iter().sum::<u64>().max(1);
+    let mean = hist.iter().enumerate().map(|(v, n)| v as u64 * n).sum::<u64>() as f64 / pixels as f64;
+    let mut seen = 0;
+    let median = hist
+        .iter()
+        .position(|n| {
+            seen += n;
+            seen * 2 >= pixels
+        })
+        .unwrap_or(0) as u8;
+    let share = |range: std::ops::Range<usize>| hist[range].iter().sum::<u64>() as f64 / pixels as f64;
+    let (dark_share, light_share) = (share(0..DARK_LUMA as usize), share(LIGHT_LUMA as usize + 1..256));
+    // The background dominates the median; text and icons barely move it.
+    let theme = if median < 128 { Theme::Dark } else { Theme::Light };
+    Estimate {
+        theme,
+        confidence: (dark_share - light_share).abs().min(1.0),
+        luminance: Luminance {
+            mean,
+            median,
+            dark_share,
+            light_share,
+        },
+    }
+}
+
+fn output(bin: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(bin).args(args).stdin(Stdio::null()).stderr(Stdio::null()).output().ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+fn query_os() -> Option<(Theme, &'static str)> {
+    if cfg!(target_os = "macos") {
+        // Unset (the command fails) in light mode.
+        let dark = output("defaults", &["read", "-g", "AppleInterfaceStyle"]).is_some_and(|s| s.eq_ignore_ascii_case("dark"));
+        return Some((if dark { Theme::Dark } else { Theme::Light }, "defaults"));
+    }
+    if cfg!(windows) {
+        let out = output(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+        )?;
+        let value = out.lines().find(|l| l.contains("AppsUseLightTheme"))?.split_whitespace().last()?.to_string();
+        return Some((if value == "0x0" { Theme::Dark } else { Theme::Light }, "registry"));
+    }
+    let schema = "org.gnome.desktop.interface";
+    if let Some(scheme) = output("gsettings", &["get", schema, "color-scheme"]) {
+        match scheme.trim_matches('\'') {
+            "prefer-dark" => return Some((Theme::Dark, "gsettings")),
+            "prefer-light" => return Some((Theme::Light, "gsettings")),
+            _ => {}
+        }
+    }
+    let gtk = output("gsettings", &["get", schema, "gtk-theme"])?;
+    let dark = gtk.to_ascii_lowercase().contains("dark");
+    Some((if dark { Theme::Dark } else { Theme::Light }, "gtk-theme"))
+}
+
+// Last OS query: when it ran and what it found.
+type Cached = Option<(Instant, Option<(Theme, &'static str)>)>;
+
+// OS preference and where it came from; None when it cannot be read. Blocking.
+pub fn os_preference() -> Option<(Theme, &'static str)> {
+    static CACHE: Mutex<Cached> = Mutex::new(None);
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, value)) = cache.as_ref().filter(|(at, _)| at.elapsed() < OS_TTL) {
+        return *value;
+    }
+    let value = query_os();
+    *cache = Some((Instant::now(), value));
+    value
+}
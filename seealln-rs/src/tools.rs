@@ -0,0 +1,300 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    capture::{self, Fallback},
+    confirm::Intent,
+    hands::{self, require_local_only, HandsState, MAX_SCROLL},
+    input::Input,
+    interceptor::Action,
+    jpeg,
+    origin::Origin,
+    overrides,
+    vision,
+};
+
+// OpenAI function-calling adapter: GET /tools.json lists the tools in the `tools` format of
+// the Chat Completions API; POST /tools/invoke takes the model's `tool_calls` as-is and
+// answers with the matching `role: "tool"` messages, ready to append to the conversation.
+//
+//   screenshot {display?}            JPEG as a data URL (read-only, no token needed)
+//   click      {x, y, button?}       move + click
+//   type       {text, mode?}         mode: keys | paste | auto, as /hands/type
+//   key        {combo}
+//   scroll     {dy, dx?, x?, y?}     optional move first; wheel notches
+//
+// Input tools run through the same arming/rate-limit/interceptor pipeline as /hands and
+// need a confirmation for /tools/invoke (see confirm.rs). A failed call stops the batch; the remaining calls are answered
+// as skipped so every tool_call_id still gets a reply.
+const SCREENSHOT_QUALITY: u8 = 70;
+
+fn tool(name: &str, description: &str, properties: Value, required: &[&str]) -> Value {
+    json!({
+        "type": "function",
+        "function": {
+            "name": name,
+            "description": description,
+            "parameters": {
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false,
+            },
+        },
+    })
+}
+
+fn schema() -> Value {
+    let int = |d: &str| json!({"type": "integer", "description": d});
+    json!([
+        tool(
+            "screenshot",
+            "Capture the screen as a JPEG image. Screen coordinates for the other tools are image pixels plus `origin`.",
+            json!({"display": int("Display index (see /displays); primary display when omitted.")}),
+            &[],
+        ),
+        tool(
+            "click",
+            "Move the pointer to (x, y) and click.",
+            json!({
+                "x": int("Horizontal screen coordinate in pixels."),
+                "y": int("Vertical screen coordinate in pixels."),
+                "button": {"type": "string", "enum": ["left", "right", "middle"], "description": "Mouse button, default left."},
+            }),
+            &["x", "y"],
+        ),
+        tool(
+            "type",
+            "Type text into the focused element.",
+            json!({
+                "text": {"type": "string", "description": "Text to type."},
+                "mode": {"type": "string", "enum": ["keys", "paste", "auto"], "description": "keys: key by key; paste: via the clipboard (CJK, emoji); auto: paste only when needed."},
+            }),
+            &["text"],
+        ),
+        tool(
+            "key",
+            "Press a key or key combination, e.g. \"Return\", \"Tab\" or \"ctrl+l\".",
+            json!({"combo": {"type": "string", "description": "Key names joined by '+'."}}),
+            &["combo"],
+        ),
+        tool(
+            "scroll",
+            "Scroll with the mouse wheel, optionally after moving the pointer to (x, y).",
+            json!({
+                "dy": int("Wheel notches; positive scrolls down, negative up."),
+                "dx": int("Horizontal wheel notches; positive scrolls right."),
+                "x": int("Pointer x before scrolling."),
+                "y": int("Pointer y before scrolling."),
+            }),
+            &["dy"],
+        ),
+    ])
+}
+
+pub async fn tools_json() -> impl IntoResponse {
+    Json(json!({"ok": true, "tools": schema()}))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ToolCall {
+    #[serde(default)]
+    id: String,
+    function: FunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FunctionCall {
+    name: String,
+    // OpenAI sends a JSON-encoded string; an object is accepted too.
+    #[serde(default)]
+    arguments: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InvokeReq {
+    // Arming token from /hands/arm; only needed for input tools.
+    #[serde(default)]
+    token: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScreenshotArgs {
+    display: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClickArgs {
+    x: i32,
+    y: i32,
+    button: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TypeArgs {
+    text: String,
+    mode: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyArgs {
+    combo: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScrollArgs {
+    dy: i32,
+    #[serde(default)]
+    dx: i32,
+    x: Option<i32>,
+    y: Option<i32>,
+}
+
+enum Call {
+    Screenshot(ScreenshotArgs),
+    Act(Vec<Action>),
+}
+
+fn args<T: serde::de::DeserializeOwned>(name: &str, raw: &Value) -> Result<T, String> {
+    let parsed = match raw {
+        Value::String(s) => serde_json::from_str(s),
+        Value::Null => serde_json::from_value(json!({})),
+        v => serde_json::from_value(v.clone()),
+    };
+    parsed.map_err(|e| format!("{name}: invalid arguments: {e}"))
+}
+
+fn parse(call: &FunctionCall, input: &Input) -> Result<Call, String> {
+    let name = call.name.as_str();
+    Ok(match name {
+        "screenshot" => Call::Screenshot(args(name, &call.arguments)?),
+        "click" => {
+            let a: ClickArgs = args(name, &call.arguments)?;
+            let button = a.button.unwrap_or_else(|| "left".to_string());
+            if !matches!(button.as_str(), "left" | "right" | "middle") {
+                return Err(format!("click: unsupported button '{button}'"));
+            }
+            Call::Act(vec![Action::Move { x: a.x, y: a.y }, Action::Click { button }])
+        }
+        "type" => {
+            let a: TypeArgs = args(name, &call.arguments)?;
+            Call::Act(vec![hands::type_action(input, a.text, a.mode.as_deref()).map_err(|e| format!("type: {e}"))?])
+        }
+        "key" => Call::Act(vec![Action::Key { combo: args::<KeyArgs>(name, &call.arguments)?.combo }]),
+        "scroll" => {
+            let a: ScrollArgs = args(name, &call.arguments)?;
+            let mut actions = Vec::new();
+            match (a.x, a.y) {
+                (Some(x), Some(y)) => actions.push(Action::Move { x, y }),
+                (None, None) => {}
+                _ => return Err("scroll: give both x and y, or neither".to_string()),
+            }
+            let (dx, dy) = (a.dx.clamp(-MAX_SCROLL, MAX_SCROLL), a.dy.clamp(-MAX_SCROLL, MAX_SCROLL));
+            actions.push(Action::Scroll { dx, dy });
+            Call::Act(actions)
+        }
+        other => return Err(format!("unknown tool '{other}'")),
+    })
+}
+
+fn message(id: &str, content: Value) -> Value {
+    json!({"role": "tool", "tool_call_id": id, "content": content.to_string()})
+}
+
+// Cropped to the hands scope when it confines capture; `origin` maps image pixels back to
+// screen coordinates.
+async fn screenshot(state: &HandsState, args: ScreenshotArgs) -> Result<Value, String> {
+    if let Some(i) = args.display.filter(|i| *i >= capture::displays().len()) {
+        return Err(format!("unknown display {i}"));
+    }
+    let visible = state.visible_region();
+    tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(args.display, Fallback::global())?;
+        let (image, ox, oy) = vision::crop(&frame.image, visible);
+        if image.width() == 0 || image.height() == 0 {
+            return Err("scope is outside the screen".to_string());
+        }
+        let buf = capture::encode_jpeg_with(&image, SCREENSHOT_QUALITY, &jpeg::Options::global())?;
+        Ok(json!({
+            "ok": true,
+            "width": image.width(),
+            "height": image.height(),
+            "origin": {"x": ox, "y": oy},
+            "capture": frame.source.as_str(),
+            "image": format!("data:image/jpeg;base64,{}", B64.encode(buf)),
+        }))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+}
+
+pub async fn tools_invoke(
+    State(state): State<HandsState>,
+    headers: HeaderMap,
+    origin: Origin,
+    Json(req): Json<InvokeReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return (code, Json(json!({"ok": false, "error": msg}))).into_response();
+    }
+    let overrides = match overrides::from_headers(&headers) {
+        Ok(overrides) => overrides,
+        Err(err) => return (StatusCode::FORBIDDEN, Json(json!({"ok": false, "error": err}))).into_response(),
+    };
+    let calls: Vec<(String, Result<Call, String>)> =
+        req.tool_calls.iter().map(|c| (c.id.clone(), parse(&c.function, state.input()))).collect();
+    // Same rule as /hands: anything beyond pointer moves needs a confirmation (for this route).
+    let needs_confirm = calls.iter().any(|(_, c)| matches!(c, Ok(Call::Act(_))));
+    if needs_confirm {
+        if let Err(msg) = state.check_confirmation(&headers, &req.token, Intent::Route("/tools/invoke")) {
+            return (StatusCode::PRECONDITION_REQUIRED, Json(json!({"ok": false, "error": msg}))).into_response();
+        }
+    }
+
+    let mut messages = Vec::new();
+    let mut failed = false;
+    for (id, call) in calls {
+        if failed {
+            messages.push(message(&id, json!({"ok": false, "error": "skipped after an earlier failure"})));
+            continue;
+        }
+        let result = match call {
+            Err(err) => Err(err),
+            Ok(Call::Screenshot(args)) => screenshot(&state, args).await,
+            Ok(Call::Act(actions)) => {
+                let mut done = Vec::new();
+                let mut result = Ok(());
+                for action in actions {
+                    if let Err(msg) = state.consume_action(&req.token, &origin, &action) {
+                        result = Err(msg.to_string());
+                        break;
+                    }
+                    match state.perform_with(&req.token, action, &overrides).await {
+                        Ok(resolved) => done.push(resolved.summary()),
+                        Err((_, err)) => {
+                            result = Err(err);
+                            break;
+                        }
+                    }
+                }
+                result.map(|()| json!({"ok": true, "actions": done}))
+            }
+        };
+        messages.push(match result {
+            Ok(content) => message(&id, content),
+            Err(err) => {
+                failed = true;
+                message(&id, json!({"ok": false, "error": err}))
+            }
+        });
+    }
+    (StatusCode::OK, Json(json!({"ok": !failed, "messages": messages}))).into_response()
+}
@@ -0,0 +1,117 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+use windows_sys::Win32::{
+    Foundation::{POINT, RECT},
+    UI::{
+        Input::Pointer::{
+            InitializeTouchInjection, InjectTouchInput, POINTER_FLAGS, POINTER_FLAG_DOWN,
+            POINTER_FLAG_INCONTACT, POINTER_FLAG_INRANGE, POINTER_FLAG_UP, POINTER_FLAG_UPDATE,
+            POINTER_TOUCH_INFO, TOUCH_FEEDBACK_DEFAULT,
+        },
+        WindowsAndMessaging::{
+            PT_TOUCH, TOUCH_MASK_CONTACTAREA, TOUCH_MASK_ORIENTATION, TOUCH_MASK_PRESSURE,
+        },
+    },
+};
+
+use crate::interceptor::Gesture;
+
+// Synthetic touch input on Windows 8+ (feature `touch`): each gesture is played as a finger
+// down, a stream of contact updates along the finger's track, and a finger up. Windows
+// delivers these as real touch to the app under the fingers (touch keyboard, pinch-zoom and
+// edge swipes included).
+
+// Update rate while fingers move.
+const FRAME: Duration = Duration::from_millis(16);
+// Half the side of each finger's contact rectangle, in pixels.
+const CONTACT: i32 = 2;
+const MAX_CONTACTS: u32 = 2;
+
+fn init() -> Result<(), String> {
+    static INIT: OnceLock<Result<(), String>> = OnceLock::new();
+    INIT.get_or_init(|| {
+        // SAFETY: plain Win32 call; no pointers.
+        let ok = unsafe { InitializeTouchInjection(MAX_CONTACTS, TOUCH_FEEDBACK_DEFAULT) };
+        if ok == 0 {
+            Err(format!(
+                "InitializeTouchInjection failed: {}",
+                std::io::Error::last_os_error()
+            ))
+        } else {
+            Ok(())
+        }
+    })
+    .clone()
+}
+
+fn contact(id: u32, (x, y): (i32, i32), flags: POINTER_FLAGS) -> POINTER_TOUCH_INFO {
+    // SAFETY: POINTER_TOUCH_INFO is a plain C struct; all-zero is a valid value.
+    let mut info: POINTER_TOUCH_INFO = unsafe { std::mem::zeroed() };
+    info.pointerInfo.pointerType = PT_TOUCH;
+    info.pointerInfo.pointerId = id;
+    info.pointerInfo.pointerFlags = flags;
+    info.pointerInfo.ptPixelLocation = POINT { x, y };
+    info.touchMask = TOUCH_MASK_CONTACTAREA | TOUCH_MASK_ORIENTATION | TOUCH_MASK_PRESSURE;
+    info.rcContact = RECT {
+        left: x - CONTACT,
+        top: y - CONTACT,
+        right: x + CONTACT,
+        bottom: y + CONTACT,
+    };
+    info.orientation = 90;
+    info.pressure = 32000;
+    info
+}
+
+fn send(frame: &[POINTER_TOUCH_INFO]) -> Result<(), String> {
+    // SAFETY: `frame` is a live slice of initialised POINTER_TOUCH_INFO.
+    let ok = unsafe { InjectTouchInput(frame.len() as u32, frame.as_ptr()) };
+    if ok == 0 {
+        return Err(format!(
+            "InjectTouchInput failed: {}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+fn lerp((x0, y0): (i32, i32), (x1, y1): (i32, i32), t: f64) -> (i32, i32) {
+    (
+        x0 + ((x1 - x0) as f64 * t).round() as i32,
+        y0 + ((y1 - y0) as f64 * t).round() as i32,
+    )
+}
+
+pub fn inject(gesture: &Gesture, duration: Duration) -> Result<(), String> {
+    init()?;
+    let tracks = gesture.tracks();
+    let at = |t: f64, flags: POINTER_FLAGS| -> Vec<POINTER_TOUCH_INFO> {
+        tracks
+            .iter()
+            .enumerate()
+            .map(|(i, (a, b))| contact(i as u32, lerp(*a, *b, t), flags))
+            .collect()
+    };
+    let held = POINTER_FLAG_INRANGE | POINTER_FLAG_INCONTACT;
+
+    send(&at(0.0, POINTER_FLAG_DOWN | held))?;
+    // Fingers are lifted even if an update fails, so none stays stuck on the screen.
+    let result = (|| {
+        let start = Instant::now();
+        loop {
+            std::thread::sleep(FRAME);
+            let t = if duration.is_zero() {
+                1.0
+            } else {
+                (start.elapsed().as_secs_f64() / duration.as_secs_f64()).min(1.0)
+            };
+            send(&at(t, POINTER_FLAG_UPDATE | held))?;
+            if t >= 1.0 {
+                return Ok(());
+            }
+        }
+    })();
+    send(&at(1.0, POINTER_FLAG_UP)).and(result)
+}
@@ -0,0 +1,86 @@
+use image::{Rgb, RgbImage};
+use std::time::{Duration, Instant};
+
+// Pointer trail overlay for /stream?trail=<seconds>: the path injected moves took and where
+// clicks and taps landed in the last few seconds, drawn into the frames since the injected
+// cursor is usually invisible in captures. Marks fade out as they age. Only actions executed
+// through this server are known; a human moving the mouse leaves no trail.
+pub const MAX_AGE: Duration = Duration::from_secs(30);
+const MAX_MARKS: usize = 512;
+const PATH: Rgb<u8> = Rgb([0, 200, 255]);
+const CLICK: Rgb<u8> = Rgb([255, 40, 40]);
+const CLICK_RADIUS: i32 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    // The pointer passed through here.
+    Point,
+    // A click (at the pointer) or a tap.
+    Click,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Mark {
+    pub at: Instant,
+    pub x: i32,
+    pub y: i32,
+    pub kind: Kind,
+}
+
+// Appends a mark, dropping the oldest past MAX_MARKS or MAX_AGE.
+pub fn push(trail: &mut std::collections::VecDeque<Mark>, x: i32, y: i32, kind: Kind) {
+    while trail.len() >= MAX_MARKS || trail.front().is_some_and(|m| m.at.elapsed() > MAX_AGE) {
+        trail.pop_front();
+    }
+    trail.push_back(Mark { at: Instant::now(), x, y, kind });
+}
+
+fn blend(img: &mut RgbImage, x: i32, y: i32, color: Rgb<u8>, alpha: f32) {
+    if x < 0 || y < 0 || x >= img.width() as i32 || y >= img.height() as i32 {
+        return;
+    }
+    let p = img.get_pixel_mut(x as u32, y as u32);
+    for c in 0..3 {
+        p.0[c] = (p.0[c] as f32 * (1.0 - alpha) + color.0[c] as f32 * alpha) as u8;
+    }
+}
+
+fn dot(img: &mut RgbImage, x: i32, y: i32, color: Rgb<u8>, alpha: f32) {
+    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        blend(img, x + dx, y + dy, color, alpha);
+    }
+}
+
+fn line(img: &mut RgbImage, (x0, y0): (i32, i32), (x1, y1): (i32, i32), color: Rgb<u8>, alpha: f32) {
+    let steps = (x1 - x0).abs().max((y1 - y0).abs()).max(1);
+    for i in 0..=steps {
+        let x = x0 + (x1 - x0) * i / steps;
+        let y = y0 + (y1 - y0) * i / steps;
+        dot(img, x, y, color, alpha);
+    }
+}
+
+fn ring(img: &mut RgbImage, x: i32, y: i32, radius: i32, color: Rgb<u8>, alpha: f32) {
+    let steps = radius * 8;
+    for i in 0..steps {
+        let a = i as f32 / steps as f32 * std::f32::consts::TAU;
+        dot(img, x + (a.cos() * radius as f32) as i32, y + (a.sin() * radius as f32) as i32, color, alpha);
+    }
+}
+
+// Draws the marks younger than `window` onto `img`, whose top-left pixel is screen `origin`.
+pub fn draw(img: &mut RgbImage, marks: &[Mark], origin: (i32, i32), window: Duration) {
+    let now = Instant::now();
+    let alpha = |m: &Mark| 1.0 - now.duration_since(m.at).as_secs_f32() / window.as_secs_f32().max(0.001);
+    let local = |m: &Mark| (m.x - origin.0, m.y - origin.1);
+    let recent: Vec<&Mark> = marks.iter().filter(|m| now.duration_since(m.at) < window).collect();
+    for pair in recent.windows(2) {
+        line(img, local(pair[0]), local(pair[1]), PATH, alpha(pair[1]).clamp(0.15, 0.9));
+    }
+    for m in recent.iter().filter(|m| m.kind == Kind::Click) {
+        let (x, y) = local(m);
+        let a = alpha(m).clamp(0.2, 1.0);
+        ring(img, x, y, CLICK_RADIUS, CLICK, a);
+        ring(img, x, y, CLICK_RADIUS / 3, CLICK, a);
+    }
+}
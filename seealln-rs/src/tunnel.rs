@@ -0,0 +1,116 @@
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+// `seealln tunnel [options] user@host`: keeps a reverse SSH tunnel open so the local server is
+// reachable on the remote host's loopback (127.0.0.1:<remote port> there), without opening any
+// port here. Runs the system `ssh` with key-based auth only (BatchMode: no password or host-key
+// prompts; connect once by hand to accept the host key) and restarts it whenever it drops:
+// after 2 s, doubling to 60 s while it keeps failing within a minute. Ctrl-C stops it.
+const USAGE: &str = "usage: seealln tunnel [--port N] [--remote-port N] [--ssh-port N] [-i identity_file] user@host";
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+struct Options {
+    destination: String,
+    local_port: u16,
+    remote_port: u16,
+    ssh_port: Option<u16>,
+    identity: Option<String>,
+}
+
+fn parse(args: &[String]) -> Result<Options, String> {
+    let local_default = std::env::var("SEEALLN_PORT")
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(8765u16);
+    let (mut local_port, mut remote_port, mut ssh_port, mut identity, mut destination) = (None, None, None, None, None);
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        let mut value = |name: &str| it.next().cloned().ok_or(format!("{name} needs a value"));
+        let port = |s: String| s.parse::<u16>().ok().filter(|p| *p > 0).ok_or(format!("invalid port {s}"));
+        match arg.as_str() {
+            "--port" => local_port = Some(port(value("--port")?)?),
+            "--remote-port" => remote_port = Some(port(value("--remote-port")?)?),
+            "--ssh-port" => ssh_port = Some(port(value("--ssh-port")?)?),
+            "-i" => identity = Some(value("-i")?),
+            a if a.starts_with('-') => return Err(format!("unknown option {a}")),
+            a if destination.is_none() => destination = Some(a.to_string()),
+            a => return Err(format!("unexpected argument {a}")),
+        }
+    }
+    let destination = destination.ok_or("missing user@host")?;
+    let local_port = local_port.unwrap_or(local_default);
+    Ok(Options {
+        destination,
+        local_port,
+        remote_port: remote_port.unwrap_or(local_port),
+        ssh_port,
+        identity,
+    })
+}
+
+fn ssh_command(o: &Options) -> Command {
+    let mut cmd = Command::new("ssh");
+    cmd.arg("-N")
+        .args(["-R", &format!("127.0.0.1:{}:127.0.0.1:{}", o.remote_port, o.local_port)])
+        .args(["-o", "BatchMode=yes"])
+        .args(["-o", "ExitOnForwardFailure=yes"])
+        .args(["-o", "ServerAliveInterval=15"])
+        .args(["-o", "ServerAliveCountMax=3"]);
+    if let Some(port) = o.ssh_port {
+        cmd.args(["-p", &port.to_string()]);
+    }
+    if let Some(identity) = &o.identity {
+        cmd.args(["-i", identity, "-o", "IdentitiesOnly=yes"]);
+    }
+    cmd.arg(&o.destination).kill_on_drop(true);
+    cmd
+}
+
+// `seealln tunnel ...`; returns the process exit code.
+pub async fn cli(args: &[String]) -> i32 {
+    let opts = match parse(args) {
+        Ok(opts) => opts,
+        Err(err) => {
+            eprintln!("tunnel: {err}");
+            eprintln!("{USAGE}");
+            return 2;
+        }
+    };
+    eprintln!(
+        "tunnel: exposing 127.0.0.1:{} as 127.0.0.1:{} on {} (Ctrl-C to stop)",
+        opts.local_port, opts.remote_port, opts.destination
+    );
+    let mut backoff = Duration::from_secs(2);
+    loop {
+        let started = Instant::now();
+        let mut child = match ssh_command(&opts).spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                eprintln!("tunnel: cannot run ssh: {err}");
+                return 1;
+            }
+        };
+        tokio::select! {
+            status = child.wait() => match status {
+                Ok(status) => eprintln!("tunnel: ssh exited ({status}); reconnecting in {}s", backoff.as_secs()),
+                Err(err) => eprintln!("tunnel: ssh failed: {err}; reconnecting in {}s", backoff.as_secs()),
+            },
+            _ = tokio::signal::ctrl_c() => {
+                let _ = child.kill().await;
+                eprintln!("tunnel: stopped");
+                return 0;
+            }
+        }
+        if started.elapsed() > Duration::from_secs(60) {
+            backoff = Duration::from_secs(2);
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = tokio::signal::ctrl_c() => {
+                eprintln!("tunnel: stopped");
+                return 0;
+            }
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
@@ -0,0 +1,264 @@
+use axum::{
+    extract::Path,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use image::RgbImage;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    process::{Child, Command, Stdio},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tracing::{info, warn};
+
+use crate::hands::{confirmed, require_local_only};
+
+// Virtual X displays (Linux): `POST /displays/virtual` starts an Xvfb server and registers it
+// as an extra display. Virtual displays are listed after the physical ones in /displays, so
+// `?display=N` works for snapshot/stream; frames are read with `xwd` (SEEALLN_XWD), which
+// needs no capture feature. If DISPLAY is unset (typical CI), the oldest running virtual
+// display is the default one (`default_display`): hands backends connect to it and primary
+// capture reads it. The process environment is never changed.
+struct Virtual {
+    name: String,
+    width: u32,
+    height: u32,
+    child: Child,
+}
+
+fn registry() -> &'static Mutex<Vec<Virtual>> {
+    static VIRTUAL: OnceLock<Mutex<Vec<Virtual>>> = OnceLock::new();
+    VIRTUAL.get_or_init(Default::default)
+}
+
+// (name, width, height) of every running virtual display, in index order.
+pub fn list() -> Vec<(String, u32, u32)> {
+    let mut displays = registry().lock().unwrap_or_else(|e| e.into_inner());
+    // Drop servers that exited on their own.
+    displays.retain_mut(|d| !matches!(d.child.try_wait(), Ok(Some(_))));
+    displays.iter().map(|d| (d.name.clone(), d.width, d.height)).collect()
+}
+
+// The X display hands input and primary capture use when the process has no DISPLAY.
+pub fn default_display() -> Option<String> {
+    if std::env::var_os("DISPLAY").is_some() {
+        return None;
+    }
+    list().into_iter().next().map(|(name, _, _)| name)
+}
+
+fn socket_path(n: u32) -> std::path::PathBuf {
+    std::path::PathBuf::from(format!("/tmp/.X11-unix/X{n}"))
+}
+
+fn start(width: u32, height: u32) -> Result<(String, bool), String> {
+    if !cfg!(target_os = "linux") {
+        return Err("virtual displays need Linux + Xvfb".to_string());
+    }
+    let base: u32 = std::env::var("SEEALLN_XVFB_BASE").ok().and_then(|v| v.parse().ok()).unwrap_or(99);
+    let n = (base..base + 100)
+        .find(|n| !socket_path(*n).exists() && !std::path::Path::new(&format!("/tmp/.X{n}-lock")).exists())
+        .ok_or("no free X display number")?;
+    let name = format!(":{n}");
+
+    let bin = std::env::var("SEEALLN_XVFB").unwrap_or_else(|_| "Xvfb".to_string());
+    let mut child = Command::new(&bin)
+        .args([name.as_str(), "-screen", "0", &format!("{width}x{height}x24"), "-nolisten", "tcp"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{bin}: {e}"))?;
+
+    // Ready once the server socket shows up.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !socket_path(n).exists() {
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("{bin} exited with {status}"));
+        }
+        if Instant::now() > deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{bin} did not come up on {name}"));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    let mut displays = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let became_default = std::env::var_os("DISPLAY").is_none() && displays.is_empty();
+    displays.push(Virtual {
+        name: name.clone(),
+        width,
+        height,
+        child,
+    });
+    info!(display = %name, width, height, "virtual display started");
+    Ok((name, became_default))
+}
+
+// SIGTERM first so Xvfb removes its lock file and socket; SIGKILL if it lingers.
+fn terminate(child: &mut Child) {
+    let _ = Command::new("kill").args(["-TERM", &child.id().to_string()]).status();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+// Blocking (up to 2 s); call from spawn_blocking. The registry is released before waiting.
+fn stop(name: &str) -> bool {
+    let mut displays = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let Some(pos) = displays.iter().position(|d| d.name == name) else {
+        return false;
+    };
+    let mut d = displays.remove(pos);
+    drop(displays);
+    terminate(&mut d.child);
+    info!(display = %name, "virtual display stopped");
+    true
+}
+
+// Stops every virtual display (server shutdown).
+pub fn stop_all() {
+    let mut displays = registry().lock().unwrap_or_else(|e| e.into_inner());
+    for d in displays.iter_mut() {
+        terminate(&mut d.child);
+    }
+    displays.clear();
+}
+
+pub fn grab(name: &str) -> Result<RgbImage, String> {
+    let bin = std::env::var("SEEALLN_XWD").unwrap_or_else(|_| "xwd".to_string());
+    let out = Command::new(&bin)
+        .args(["-root", "-silent", "-display", name])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("{bin}: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("{bin} exited with {}", out.status));
+    }
+    parse_xwd(&out.stdout)
+}
+
+// XWD (X Window Dump) with a TrueColor ZPixmap of 24/32 bits per pixel, which is what Xvfb
+// produces at depth 24.
+fn parse_xwd(data: &[u8]) -> Result<RgbImage, String> {
+    let field = |i: usize| -> Result<u32, String> {
+        data.get(i * 4..i * 4 + 4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| "xwd: truncated header".to_string())
+    };
+    let (header_size, version, format) = (field(0)? as usize, field(1)?, field(2)?);
+    if version != 7 || format != 2 {
+        return Err(format!("xwd: unsupported version {version} / format {format}"));
+    }
+    let (width, height) = (field(4)?, field(5)?);
+    let byte_order = field(7)?;
+    let bpp = field(11)?;
+    let bytes_per_line = field(12)? as usize;
+    let masks = [field(15)?, field(16)?, field(17)?];
+    let ncolors = field(19)? as usize;
+    if bpp != 24 && bpp != 32 {
+        return Err(format!("xwd: unsupported {bpp} bits per pixel"));
+    }
+
+    let start = header_size + ncolors * 12;
+    let px = (bpp / 8) as usize;
+    let needed = start + bytes_per_line * height as usize;
+    if data.len() < needed || bytes_per_line < width as usize * px {
+        return Err("xwd: truncated pixel data".to_string());
+    }
+    let channel = |v: u32, mask: u32| -> u8 {
+        if mask == 0 {
+            return 0;
+        }
+        let v = (v & mask) >> mask.trailing_zeros();
+        let bits = (mask >> mask.trailing_zeros()).count_ones();
+        ((v as u64 * 255) / ((1u64 << bits) - 1)) as u8
+    };
+
+    let mut img = RgbImage::new(width, height);
+    for y in 0..height as usize {
+        let row = &data[start + y * bytes_per_line..];
+        for x in 0..width as usize {
+            let b = &row[x * px..x * px + px];
+            let mut bytes = [0u8; 4];
+            bytes[..px].copy_from_slice(b);
+            // byte_order 0 = LSBFirst.
+            let v = if byte_order == 0 {
+                u32::from_le_bytes(bytes)
+            } else {
+                u32::from_be_bytes(bytes) >> (8 * (4 - px))
+            };
+            img.put_pixel(
+                x as u32,
+                y as u32,
+                image::Rgb([channel(v, masks[0]), channel(v, masks[1]), channel(v, masks[2])]),
+            );
+        }
+    }
+    Ok(img)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReq {
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+pub async fn displays_virtual_create(headers: HeaderMap, Json(req): Json<CreateReq>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    let width = req.width.unwrap_or(1280).clamp(320, 7680);
+    let height = req.height.unwrap_or(800).clamp(240, 4320);
+    match tokio::task::spawn_blocking(move || start(width, height)).await {
+        Ok(Ok((name, became_default))) => {
+            let index = crate::capture::displays()
+                .into_iter()
+                .find(|d| d.virtual_display.as_deref() == Some(name.as_str()))
+                .map(|d| d.index);
+            (
+                StatusCode::OK,
+                Json(json!({"ok": true, "display": name, "index": index, "width": width, "height": height, "default": became_default})),
+            )
+                .into_response()
+        }
+        Ok(Err(err)) => {
+            warn!(%err, "virtual display start failed");
+            fail(StatusCode::SERVICE_UNAVAILABLE, err)
+        }
+        Err(err) => fail(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
+
+// DELETE /displays/virtual/:n stops the server for display `:n`.
+pub async fn displays_virtual_delete(headers: HeaderMap, Path(n): Path<u32>) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !confirmed(&headers) {
+        return fail(StatusCode::PRECONDITION_REQUIRED, "missing x-seealln-confirm: yes");
+    }
+    match tokio::task::spawn_blocking(move || stop(&format!(":{n}"))).await {
+        Ok(true) => (StatusCode::OK, Json(json!({"ok": true}))).into_response(),
+        Ok(false) => fail(StatusCode::NOT_FOUND, format!("no virtual display :{n}")),
+        Err(err) => fail(StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
+    }
+}
@@ -0,0 +1,275 @@
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use image::{imageops, GrayImage, RgbImage};
+
+use crate::hands::ScopeRect;
+
+// A located target, in screen pixel coordinates.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct Match {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32,
+    pub score: f32,
+}
+
+impl Match {
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+}
+
+// Crops `img` to `region` (clipped to the image); the whole image when None.
+pub fn crop(img: &RgbImage, region: Option<ScopeRect>) -> (RgbImage, i32, i32) {
+    let Some(r) = region else {
+        return (img.clone(), 0, 0);
+    };
+    let x0 = r.x.clamp(0, img.width() as i32);
+    let y0 = r.y.clamp(0, img.height() as i32);
+    let x1 = r.x.saturating_add(r.w).clamp(x0, img.width() as i32);
+    let y1 = r.y.saturating_add(r.h).clamp(y0, img.height() as i32);
+    let view = imageops::crop_imm(img, x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32);
+    (view.to_image(), x0, y0)
+}
+
+// Templates are PNG/JPEG files under SEEALLN_TEMPLATE_DIR (default ./templates), or inline
+// as "base64:<data>".
+pub fn load_template(spec: &str) -> Result<RgbImage, String> {
+    let bytes = if let Some(b64) = spec.strip_prefix("base64:") {
+        B64.decode(b64.trim()).map_err(|e| format!("template base64: {e}"))?
+    } else {
+        let rel = std::path::Path::new(spec);
+        if !rel.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+            return Err("template path must be relative to SEEALLN_TEMPLATE_DIR".to_string());
+        }
+        let dir = std::env::var("SEEALLN_TEMPLATE_DIR").unwrap_or_else(|_| "templates".to_string());
+        std::fs::read(std::path::Path::new(&dir).join(rel)).map_err(|e| format!("template {spec}: {e}"))?
+    };
+    image::load_from_memory(&bytes)
+        .map(|i| i.to_rgb8())
+        .map_err(|e| format!("template decode: {e}"))
+}
+
+// Best normalized cross-correlation match of `template` inside `region` of `screen`.
+// Both are downscaled (haystack to ~480px wide / template to ~24px on its short side, but
+// never below 6px) and matched by brute force; good enough for locating UI widgets, not
+// for sub-pixel work.
+pub fn find_template(screen: &RgbImage, template: &RgbImage, region: Option<ScopeRect>) -> Option<Match> {
+    let (hay, ox, oy) = crop(screen, region);
+    let (tw, th) = template.dimensions();
+    if tw == 0 || th == 0 || tw > hay.width() || th > hay.height() {
+        return None;
+    }
+
+    let tmin = tw.min(th);
+    let scale = (hay.width() / 480).max(tmin / 24).max(1).min((tmin / 6).max(1));
+    let to_gray = |img: &RgbImage| -> GrayImage {
+        let gray = imageops::grayscale(img);
+        if scale == 1 {
+            gray
+        } else {
+            imageops::resize(&gray, (img.width() / scale).max(1), (img.height() / scale).max(1), imageops::FilterType::Triangle)
+        }
+    };
+    let hay = to_gray(&hay);
+    let tpl = to_gray(template);
+    let (tw_s, th_s) = tpl.dimensions();
+    if tw_s > hay.width() || th_s > hay.height() {
+        return None;
+    }
+
+    let n = (tw_s * th_s) as f32;
+    let tvals: Vec<f32> = tpl.pixels().map(|p| p[0] as f32).collect();
+    let tmean = tvals.iter().sum::<f32>() / n;
+    let tdev: Vec<f32> = tvals.iter().map(|v| v - tmean).collect();
+    let tnorm = tdev.iter().map(|d| d * d).sum::<f32>().sqrt();
+
+    let mut best: Option<(f32, u32, u32)> = None;
+    for y in 0..=(hay.height() - th_s) {
+        for x in 0..=(hay.width() - tw_s) {
+            let mut sum = 0.0;
+            for ty in 0..th_s {
+                for tx in 0..tw_s {
+                    sum += hay.get_pixel(x + tx, y + ty)[0] as f32;
+                }
+            }
+            let mean = sum / n;
+            let (mut num, mut den) = (0.0, 0.0);
+            for ty in 0..th_s {
+                for tx in 0..tw_s {
+                    let d = hay.get_pixel(x + tx, y + ty)[0] as f32 - mean;
+                    num += d * tdev[(ty * tw_s + tx) as usize];
+                    den += d * d;
+                }
+            }
+            let denom = den.sqrt() * tnorm;
+            // Flat patches: identical if both are flat, otherwise no information.
+            let score = if denom < 1e-3 {
+                if tnorm < 1e-3 && den < 1e-3 && (mean - tmean).abs() < 8.0 { 1.0 } else { 0.0 }
+            } else {
+                num / denom
+            };
+            if best.map(|(s, _, _)| score > s).unwrap_or(true) {
+                best = Some((score, x, y));
+            }
+        }
+    }
+
+    best.map(|(score, x, y)| Match {
+        x: ox + (x * scale) as i32,
+        y: oy + (y * scale) as i32,
+        w: tw as i32,
+        h: th as i32,
+        score,
+    })
+}
+
+// 64-bit perceptual hash (DCT pHash): grayscale, 32x32, 2-D DCT, then one bit per
+// low-frequency coefficient of the top-left 8x8 above their median. Small edits, scaling and
+// recompression flip few bits; a different screen flips about half.
+pub fn phash(img: &RgbImage) -> u64 {
+    const N: usize = 32;
+    let small = imageops::resize(&imageops::grayscale(img), N as u32, N as u32, imageops::FilterType::Triangle);
+    let px: Vec<f32> = small.pixels().map(|p| p[0] as f32).collect();
+    let cos: Vec<f32> = (0..8 * N)
+        .map(|i| {
+            let (u, x) = (i / N, i % N);
+            (std::f32::consts::PI * u as f32 * (2 * x + 1) as f32 / (2 * N) as f32).cos()
+        })
+        .collect();
+    // Rows first, keeping only the 8 lowest frequencies, then columns.
+    let mut rows = [[0f32; 8]; N];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, v) in row.iter_mut().enumerate() {
+            *v = (0..N).map(|x| px[y * N + x] * cos[u * N + x]).sum();
+        }
+    }
+    let mut low = [0f32; 64];
+    for (i, v) in low.iter_mut().enumerate() {
+        let (fy, fx) = (i / 8, i % 8);
+        *v = (0..N).map(|y| rows[y][fx] * cos[fy * N + y]).sum();
+    }
+    let mut sorted = low;
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = (sorted[31] + sorted[32]) / 2.0;
+    low.iter().enumerate().fold(0u64, |h, (i, v)| if *v > median { h | (1 << (63 - i)) } else { h })
+}
+
+// One dominant color: the mean of the pixels in its quantization cell.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Dominant {
+    pub hex: String,
+    pub rgb: [u8; 3],
+    pub name: &'static str,
+    // Fraction of the pixels.
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Colors {
+    pub pixels: u64,
+    pub histogram: Histogram,
+    pub mean: [u8; 3],
+    pub dominant: Vec<Dominant>,
+}
+
+// Per-channel histograms with `bins` buckets each; luma is Rec. 601.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Histogram {
+    pub bins: usize,
+    pub r: Vec<u64>,
+    pub g: Vec<u64>,
+    pub b: Vec<u64>,
+    pub luma: Vec<u64>,
+}
+
+pub fn luma([r, g, b]: [u8; 3]) -> u8 {
+    ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8
+}
+
+// Coarse color name from hue/saturation/value, for checks like "is the banner red".
+pub fn color_name([r, g, b]: [u8; 3]) -> &'static str {
+    let (max, min) = (r.max(g).max(b) as f32, r.min(g).min(b) as f32);
+    let (v, s) = (max / 255.0, if max > 0.0 { (max - min) / max } else { 0.0 });
+    if s < 0.2 || v < 0.15 {
+        return match v {
+            v if v < 0.2 => "black",
+            v if v > 0.85 => "white",
+            _ => "gray",
+        };
+    }
+    let (r, g, b) = (r as f32, g as f32, b as f32);
+    let d = max - min;
+    let hue = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+    match hue {
+        h if h < 15.0 => "red",
+        h if h < 45.0 => "orange",
+        h if h < 70.0 => "yellow",
+        h if h < 170.0 => "green",
+        h if h < 200.0 => "cyan",
+        h if h < 260.0 => "blue",
+        h if h < 300.0 => "purple",
+        h if h < 345.0 => "pink",
+        _ => "red",
+    }
+}
+
+// Histograms and the `top` dominant colors of `img`. Dominant colors come from a 3-bit per
+// channel quantization (512 cells), so near-identical shades (anti-aliasing, gradients)
+// count together.
+pub fn colors(img: &RgbImage, bins: usize, top: usize) -> Colors {
+    let bins = bins.clamp(2, 256);
+    let bin = |v: u8| v as usize * bins / 256;
+    let mut hist = Histogram {
+        bins,
+        r: vec![0; bins],
+        g: vec![0; bins],
+        b: vec![0; bins],
+        luma: vec![0; bins],
+    };
+    // Per cell: pixel count and channel sums.
+    let mut cells = vec![(0u64, [0u64; 3]); 512];
+    let mut sum = [0u64; 3];
+    for p in img.pixels() {
+        let [r, g, b] = p.0;
+        hist.r[bin(r)] += 1;
+        hist.g[bin(g)] += 1;
+        hist.b[bin(b)] += 1;
+        hist.luma[bin(luma(p.0))] += 1;
+        let cell = &mut cells[((r as usize >> 5) << 6) | ((g as usize >> 5) << 3) | (b as usize >> 5)];
+        cell.0 += 1;
+        for (c, v) in p.0.iter().enumerate() {
+            cell.1[c] += *v as u64;
+            sum[c] += *v as u64;
+        }
+    }
+    let pixels = img.width() as u64 * img.height() as u64;
+    let avg = |sums: [u64; 3], n: u64| sums.map(|s| (s / n.max(1)) as u8);
+    cells.sort_by_key(|c| std::cmp::Reverse(c.0));
+    let dominant = cells
+        .into_iter()
+        .take_while(|(n, _)| *n > 0)
+        .take(top)
+        .map(|(n, sums)| {
+            let rgb = avg(sums, n);
+            Dominant {
+                hex: format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2]),
+                rgb,
+                name: color_name(rgb),
+                share: n as f64 / pixels.max(1) as f64,
+            }
+        })
+        .collect();
+    Colors {
+        pixels,
+        histogram: hist,
+        mean: avg(sum, pixels),
+        dominant,
+    }
+}
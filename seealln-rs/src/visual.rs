@@ -0,0 +1,296 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as B64, Engine as _};
+use image::{Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    capture::{self, Fallback},
+    format::{self, Format},
+    hands::{require_local_only, HandsState, ScopeRect},
+    jpeg,
+    store::{Area, Store},
+    vision,
+};
+
+// Golden-image visual regression: POST /visual/baseline stores the current frame (or a region
+// of it) under a name, POST /visual/compare captures the same display and region again and
+// diffs it against the baseline pixel by pixel.
+//
+//   baseline  {name, display?, region?: {x, y, w, h}}
+//   compare   {name, tolerance?, max_changed_ratio?, diff_image?}
+//
+// A pixel counts as changed when any channel differs by more than `tolerance` (default 8);
+// the comparison passes while changed / total <= `max_changed_ratio` (default 0.001). The diff
+// image (PNG data URL, on by default) is the baseline faded to grey with changed pixels in red
+// and their bounding box outlined. Baselines live in the `baselines` area of the data dir
+// (encrypted at rest like the rest of the store, never pruned by default).
+const DEFAULT_TOLERANCE: u8 = 8;
+const DEFAULT_MAX_CHANGED_RATIO: f64 = 0.001;
+const MAX_NAME: usize = 64;
+const CHANGED: Rgb<u8> = Rgb([255, 0, 0]);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Meta {
+    name: String,
+    display: Option<usize>,
+    // Screen area the image covers (after clipping and scope confinement).
+    region: ScopeRect,
+    created_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BaselineReq {
+    name: String,
+    display: Option<usize>,
+    region: Option<ScopeRect>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareReq {
+    name: String,
+    tolerance: Option<u8>,
+    max_changed_ratio: Option<f64>,
+    #[serde(default = "yes")]
+    diff_image: bool,
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn fail(code: StatusCode, err: impl Into<String>) -> Response {
+    (code, Json(json!({"ok": false, "error": err.into()}))).into_response()
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// The frame of `display` cropped to `region`, with the screen area it covers.
+async fn grab(display: Option<usize>, region: Option<ScopeRect>) -> Result<(RgbImage, ScopeRect, &'static str), String> {
+    tokio::task::spawn_blocking(move || {
+        let frame = capture::capture_frame(display, Fallback::global())?;
+        let (img, x, y) = vision::crop(&frame.image, region);
+        if img.width() == 0 || img.height() == 0 {
+            return Err("region is outside the screen".to_string());
+        }
+        let covered = ScopeRect { x, y, w: img.width() as i32, h: img.height() as i32 };
+        Ok((img, covered, frame.source.as_str()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+}
+
+pub async fn visual_baseline(
+    State(hands): State<HandsState>,
+    State(store): State<Store>,
+    headers: HeaderMap,
+    Json(req): Json<BaselineReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !valid_name(&req.name) {
+        return fail(StatusCode::BAD_REQUEST, format!("name must be 1-{MAX_NAME} of [A-Za-z0-9_-]"));
+    }
+    if let Some(i) = req.display.filter(|i| *i >= capture::displays().len()) {
+        return fail(StatusCode::NOT_FOUND, format!("unknown display {i}"));
+    }
+    if req.region.is_some_and(|r| r.w <= 0 || r.h <= 0) {
+        return fail(StatusCode::BAD_REQUEST, "region needs a positive size");
+    }
+    let region = match hands.confine(req.region) {
+        Ok(region) => region,
+        Err(err) => return fail(StatusCode::FORBIDDEN, err),
+    };
+    let (img, covered, source) = match grab(req.display, region).await {
+        Ok(v) => v,
+        Err(err) => return fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    };
+    let meta = Meta {
+        name: req.name.clone(),
+        display: req.display,
+        region: covered,
+        created_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+    };
+    let write_store = store.clone();
+    let name = req.name.clone();
+    let written = tokio::task::spawn_blocking(move || {
+        let png = format::encode(&img, Format::Png, 0, &jpeg::Options::global())?;
+        write_store.write(Area::Baselines, &format!("{name}.png"), &png).map_err(|e| e.to_string())?;
+        let meta = serde_json::to_vec(&meta).map_err(|e| e.to_string())?;
+        write_store.write(Area::Baselines, &format!("{name}.json"), &meta).map_err(|e| e.to_string())?;
+        Ok::<_, String>(())
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    if let Err(err) = written {
+        return fail(StatusCode::INTERNAL_SERVER_ERROR, err);
+    }
+    store.audit(json!({"event": "visual_baseline", "name": req.name, "display": req.display, "region": covered}));
+    (
+        StatusCode::OK,
+        Json(json!({"ok": true, "name": req.name, "display": req.display, "region": covered, "capture": source})),
+    )
+        .into_response()
+}
+
+struct Diff {
+    changed: u64,
+    max_delta: u8,
+    mean_delta: f64,
+    // x0, y0, x1, y1 (exclusive) of the changed pixels, image coordinates.
+    bbox: Option<(u32, u32, u32, u32)>,
+    image: Option<RgbImage>,
+}
+
+fn diff(baseline: &RgbImage, current: &RgbImage, tolerance: u8, with_image: bool) -> Diff {
+    let mut out = with_image.then(|| {
+        RgbImage::from_fn(baseline.width(), baseline.height(), |x, y| {
+            let [r, g, b] = baseline.get_pixel(x, y).0;
+            let luma = ((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8;
+            let faded = 160 + luma / 4;
+            Rgb([faded, faded, faded])
+        })
+    });
+    let (mut changed, mut max_delta, mut total) = (0u64, 0u8, 0u64);
+    let mut bbox: Option<(u32, u32, u32, u32)> = None;
+    for (x, y, a) in baseline.enumerate_pixels() {
+        let b = current.get_pixel(x, y);
+        let delta = (0..3).map(|c| a.0[c].abs_diff(b.0[c])).max().unwrap_or(0);
+        total += delta as u64;
+        max_delta = max_delta.max(delta);
+        if delta <= tolerance {
+            continue;
+        }
+        changed += 1;
+        bbox = Some(match bbox {
+            None => (x, y, x + 1, y + 1),
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)),
+        });
+        if let Some(img) = out.as_mut() {
+            img.put_pixel(x, y, CHANGED);
+        }
+    }
+    if let (Some(img), Some((x0, y0, x1, y1))) = (out.as_mut(), bbox) {
+        for x in x0..x1 {
+            img.put_pixel(x, y0, CHANGED);
+            img.put_pixel(x, y1 - 1, CHANGED);
+        }
+        for y in y0..y1 {
+            img.put_pixel(x0, y, CHANGED);
+            img.put_pixel(x1 - 1, y, CHANGED);
+        }
+    }
+    let pixels = (baseline.width() as u64 * baseline.height() as u64).max(1);
+    Diff {
+        changed,
+        max_delta,
+        mean_delta: total as f64 / pixels as f64,
+        bbox,
+        image: out,
+    }
+}
+
+pub async fn visual_compare(
+    State(hands): State<HandsState>,
+    State(store): State<Store>,
+    headers: HeaderMap,
+    Json(req): Json<CompareReq>,
+) -> Response {
+    if let Err((code, msg)) = require_local_only(&headers) {
+        return fail(code, msg);
+    }
+    if !valid_name(&req.name) {
+        return fail(StatusCode::BAD_REQUEST, format!("name must be 1-{MAX_NAME} of [A-Za-z0-9_-]"));
+    }
+    let read_store = store.clone();
+    let name = req.name.clone();
+    let loaded = tokio::task::spawn_blocking(move || {
+        let meta = read_store.read(Area::Baselines, &format!("{name}.json"))?;
+        let meta: Meta = serde_json::from_slice(&meta).map_err(|e| format!("baseline metadata: {e}"))?;
+        let png = read_store.read(Area::Baselines, &format!("{name}.png"))?;
+        let img = image::load_from_memory(&png).map_err(|e| format!("baseline image: {e}"))?.to_rgb8();
+        Ok::<_, String>((meta, img))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    let (meta, baseline) = match loaded {
+        Ok(v) => v,
+        Err(err) => return fail(StatusCode::NOT_FOUND, format!("baseline {}: {err}", req.name)),
+    };
+    if let Some(i) = meta.display.filter(|i| *i >= capture::displays().len()) {
+        return fail(StatusCode::CONFLICT, format!("display {i} of the baseline is gone"));
+    }
+    let region = match hands.confine(Some(meta.region)) {
+        Ok(region) => region,
+        Err(err) => return fail(StatusCode::FORBIDDEN, err),
+    };
+    let (current, covered, source) = match grab(meta.display, region).await {
+        Ok(v) => v,
+        Err(err) => return fail(StatusCode::SERVICE_UNAVAILABLE, err),
+    };
+    if current.dimensions() != baseline.dimensions() {
+        return (
+            StatusCode::OK,
+            Json(json!({
+                "ok": true,
+                "name": req.name,
+                "pass": false,
+                "size_mismatch": {"baseline": baseline.dimensions(), "current": current.dimensions()},
+                "region": covered,
+                "capture": source,
+            })),
+        )
+            .into_response();
+    }
+
+    let tolerance = req.tolerance.unwrap_or(DEFAULT_TOLERANCE);
+    let max_ratio = req.max_changed_ratio.unwrap_or(DEFAULT_MAX_CHANGED_RATIO).clamp(0.0, 1.0);
+    let with_image = req.diff_image;
+    let result = tokio::task::spawn_blocking(move || {
+        let d = diff(&baseline, &current, tolerance, with_image);
+        let png = d.image.as_ref().map(|img| format::encode(img, Format::Png, 0, &jpeg::Options::global())).transpose()?;
+        let pixels = baseline.width() as u64 * baseline.height() as u64;
+        Ok::<_, String>((d, png, pixels))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    let (d, png, pixels) = match result {
+        Ok(v) => v,
+        Err(err) => return fail(StatusCode::INTERNAL_SERVER_ERROR, err),
+    };
+    let ratio = d.changed as f64 / pixels.max(1) as f64;
+    // Screen coordinates, like every other box the server returns.
+    let changed_box = d.bbox.map(|(x0, y0, x1, y1)| {
+        json!({"x": covered.x + x0 as i32, "y": covered.y + y0 as i32, "w": x1 - x0, "h": y1 - y0})
+    });
+    let mut body = json!({
+        "ok": true,
+        "name": req.name,
+        "pass": ratio <= max_ratio,
+        "changed_pixels": d.changed,
+        "total_pixels": pixels,
+        "changed_ratio": ratio,
+        "max_changed_ratio": max_ratio,
+        "tolerance": tolerance,
+        "max_delta": d.max_delta,
+        "mean_delta": d.mean_delta,
+        "changed_box": changed_box,
+        "region": covered,
+        "capture": source,
+    });
+    if let Some(png) = png {
+        body["diff_image"] = json!(format!("data:image/png;base64,{}", B64.encode(png)));
+    }
+    (StatusCode::OK, Json(body)).into_response()
+}
@@ -0,0 +1,123 @@
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::{
+    capture::{self, Fallback},
+    events, frame,
+    hands::{HandsState, ScopeRect},
+    vision,
+};
+
+// Region watches for /events subscribers: `?watch=status:10,10,200,40:6;toast:900,20,300,80`
+// samples those regions every `interval_ms` (default 500, 100..=10000) and sends that
+// subscriber a `region_changed` event when a region's perceptual hash moved more than its
+// threshold (bits, default 6) from the last reported one. Only the subscriber that asked
+// gets them, so changes elsewhere on screen (toolbar animations) never reach it. Regions are
+// narrowed to what the hands scope lets capture show.
+const MAX_REGIONS: usize = 16;
+const DEFAULT_THRESHOLD: u32 = 6;
+const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+pub struct Region {
+    name: String,
+    rect: ScopeRect,
+    threshold: u32,
+}
+
+// Parses `name:x,y,w,h[:threshold]` entries separated by `;`.
+pub fn parse(spec: &str) -> Result<Vec<Region>, String> {
+    let mut regions = Vec::new();
+    for entry in spec.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let mut parts = entry.split(':');
+        let (Some(name), Some(rect)) = (parts.next(), parts.next()) else {
+            return Err(format!("watch entry `{entry}` must be name:x,y,w,h[:threshold]"));
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("invalid watch name `{name}`"));
+        }
+        let rect = frame::parse_region(rect)?;
+        let threshold = match parts.next() {
+            Some(t) => t.trim().parse::<u32>().map_err(|_| format!("invalid threshold in `{entry}`"))?.min(64),
+            None => DEFAULT_THRESHOLD,
+        };
+        if parts.next().is_some() {
+            return Err(format!("watch entry `{entry}` has too many fields"));
+        }
+        if regions.iter().any(|r: &Region| r.name == name) {
+            return Err(format!("duplicate watch name `{name}`"));
+        }
+        regions.push(Region { name: name.to_string(), rect, threshold });
+    }
+    if regions.is_empty() || regions.len() > MAX_REGIONS {
+        return Err(format!("watch 1-{MAX_REGIONS} regions"));
+    }
+    Ok(regions)
+}
+
+pub fn interval(ms: Option<u64>) -> Duration {
+    ms.map(|ms| Duration::from_millis(ms.clamp(100, 10_000))).unwrap_or(DEFAULT_INTERVAL)
+}
+
+// Starts sampling for one subscriber; stops once the receiver is dropped (client gone).
+pub fn spawn(
+    hands: HandsState,
+    display: Option<usize>,
+    regions: Vec<Region>,
+    every: Duration,
+) -> mpsc::Receiver<serde_json::Value> {
+    let (tx, rx) = mpsc::channel(64);
+    tokio::spawn(async move {
+        let mut last: Vec<Option<u64>> = vec![None; regions.len()];
+        let mut tick = tokio::time::interval(every);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        loop {
+            tick.tick().await;
+            if tx.is_closed() {
+                return;
+            }
+            // Re-confined every round: the scope may change while the stream is open.
+            let rects: Vec<Option<ScopeRect>> = regions.iter().map(|r| hands.confine(Some(r.rect)).ok().flatten()).collect();
+            let hashes = tokio::task::spawn_blocking(move || {
+                let frame = capture::capture_frame(display, Fallback::Fail).ok()?;
+                let hashes = rects
+                    .into_iter()
+                    .map(|rect| {
+                        let rect = rect?;
+                        let (img, _, _) = vision::crop(&frame.image, Some(rect));
+                        (img.width() > 0 && img.height() > 0).then(|| (vision::phash(&img), rect))
+                    })
+                    .collect::<Vec<_>>();
+                Some(hashes)
+            })
+            .await
+            .ok()
+            .flatten();
+            let Some(hashes) = hashes else {
+                continue;
+            };
+            for ((region, last), current) in regions.iter().zip(last.iter_mut()).zip(hashes) {
+                let Some((hash, rect)) = current else {
+                    continue;
+                };
+                let Some(previous) = *last else {
+                    *last = Some(hash);
+                    continue;
+                };
+                let distance = (hash ^ previous).count_ones();
+                if distance <= region.threshold {
+                    continue;
+                }
+                *last = Some(hash);
+                let event = events::stamp(
+                    "region_changed",
+                    json!({"region": region.name, "rect": rect, "distance": distance, "phash": format!("{hash:016x}")}),
+                );
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
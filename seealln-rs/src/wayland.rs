@@ -0,0 +1,253 @@
+use axum::http::StatusCode;
+use std::{
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use wayland_client::{
+    delegate_noop,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_pointer, wl_registry, wl_seat},
+    Connection, Dispatch, QueueHandle,
+};
+use wayland_protocols_wlr::virtual_pointer::v1::client::{
+    zwlr_virtual_pointer_manager_v1::ZwlrVirtualPointerManagerV1, zwlr_virtual_pointer_v1::ZwlrVirtualPointerV1,
+};
+
+use crate::{
+    desktop,
+    hands::ScopeRect,
+    input::{Capabilities, InputBackend},
+    interceptor::Action,
+    keys::{self, KeyName as K},
+};
+
+// Native Wayland input (feature `wayland`): the pointer goes through the wlroots
+// virtual-pointer protocol (zwlr_virtual_pointer_v1: Sway, Hyprland, river, Wayfire, KWin),
+// the keyboard through `wtype` (SEEALLN_WTYPE), which speaks zwp_virtual_keyboard_v1 and
+// builds its own keymap, so any Unicode text types. GNOME implements neither protocol; use
+// SEEALLN_INPUT_BACKEND=ydotool there.
+
+// Linux input event codes of the mouse buttons.
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+// Axis distance per wheel notch, as compositors expect for a discrete wheel step.
+const NOTCH: f64 = 15.0;
+
+pub struct Wayland {
+    wtype: String,
+}
+
+impl Wayland {
+    pub fn from_env() -> Self {
+        Self { wtype: std::env::var("SEEALLN_WTYPE").unwrap_or_else(|_| "wtype".to_string()) }
+    }
+}
+
+struct Noop;
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for Noop {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(Noop: ignore wl_seat::WlSeat);
+delegate_noop!(Noop: ZwlrVirtualPointerManagerV1);
+delegate_noop!(Noop: ZwlrVirtualPointerV1);
+
+// Event timestamps only need to increase; wrapping milliseconds are what wl_pointer uses.
+fn now_ms() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u32
+}
+
+// A short-lived virtual pointer, like the other backends' per-action connections; the final
+// roundtrip makes sure the compositor has processed every request before we return.
+fn with_pointer(f: impl FnOnce(&ZwlrVirtualPointerV1, u32)) -> Result<(), String> {
+    let conn = Connection::connect_to_env().map_err(|e| format!("wayland: {e}"))?;
+    let (globals, mut queue) = registry_queue_init::<Noop>(&conn).map_err(|e| format!("wayland: {e}"))?;
+    let qh = queue.handle();
+    let seat: wl_seat::WlSeat = globals.bind(&qh, 1..=1, ()).map_err(|e| format!("wayland: no seat ({e})"))?;
+    let manager: ZwlrVirtualPointerManagerV1 = globals
+        .bind(&qh, 1..=1, ())
+        .map_err(|_| "compositor does not offer zwlr_virtual_pointer_manager_v1".to_string())?;
+    let pointer = manager.create_virtual_pointer(Some(&seat), &qh, ());
+    f(&pointer, now_ms());
+    pointer.destroy();
+    queue.roundtrip(&mut Noop).map_err(|e| format!("wayland: {e}"))?;
+    Ok(())
+}
+
+// Absolute motion is relative to the bounding box of all outputs.
+fn layout() -> Result<ScopeRect, String> {
+    let monitors = desktop::monitors();
+    let first = monitors.first().ok_or("monitor layout unknown; set SEEALLN_MONITORS")?;
+    Ok(monitors.iter().skip(1).fold(*first, |b, m| {
+        let (x0, y0) = (b.x.min(m.x), b.y.min(m.y));
+        let (x1, y1) = ((b.x + b.w).max(m.x + m.w), (b.y + b.h).max(m.y + m.h));
+        ScopeRect { x: x0, y: y0, w: x1 - x0, h: y1 - y0 }
+    }))
+}
+
+// XKB keysym names, as `wtype -k` expects them.
+fn keysym(key: K) -> String {
+    match key {
+        K::Control => "Control_L".into(),
+        K::Shift => "Shift_L".into(),
+        K::Alt => "Alt_L".into(),
+        K::Meta => "Super_L".into(),
+        K::Return => "Return".into(),
+        K::Tab => "Tab".into(),
+        K::Escape => "Escape".into(),
+        K::Backspace => "BackSpace".into(),
+        K::Delete => "Delete".into(),
+        K::Home => "Home".into(),
+        K::End => "End".into(),
+        K::PageUp => "Prior".into(),
+        K::PageDown => "Next".into(),
+        K::Up => "Up".into(),
+        K::Down => "Down".into(),
+        K::Left => "Left".into(),
+        K::Right => "Right".into(),
+        K::Space | K::Char(' ') => "space".into(),
+        K::F(n) => format!("F{n}"),
+        K::Char(c) if c.is_ascii_alphanumeric() => c.to_string(),
+        // Everything else by code point ("U002B" is '+').
+        K::Char(c) => format!("U{:04X}", c as u32),
+    }
+}
+
+// wtype's own modifier names (-M/-m set the modifier state, not just the key).
+fn modifier(key: K) -> Option<&'static str> {
+    match key {
+        K::Control => Some("ctrl"),
+        K::Shift => Some("shift"),
+        K::Alt => Some("alt"),
+        K::Meta => Some("logo"),
+        _ => None,
+    }
+}
+
+impl Wayland {
+    fn wtype(&self, args: &[String]) -> Result<(), String> {
+        let out = Command::new(&self.wtype)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|e| format!("{}: {e}", self.wtype))?;
+        if !out.status.success() {
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.wtype,
+                out.status,
+                String::from_utf8_lossy(&out.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    // Held keys pressed in order, the last key tapped, held keys released in reverse.
+    fn combo_args(combo: &str) -> Result<Vec<String>, String> {
+        let keys = keys::parse_combo(combo)?;
+        let (last, held) = keys.split_last().expect("combo has at least one key");
+        let mut args = Vec::new();
+        for k in held {
+            match modifier(*k) {
+                Some(m) => args.extend(["-M".to_string(), m.to_string()]),
+                None => args.extend(["-P".to_string(), keysym(*k)]),
+            }
+        }
+        args.extend(["-k".to_string(), keysym(*last)]);
+        for k in held.iter().rev() {
+            match modifier(*k) {
+                Some(m) => args.extend(["-m".to_string(), m.to_string()]),
+                None => args.extend(["-p".to_string(), keysym(*k)]),
+            }
+        }
+        Ok(args)
+    }
+}
+
+impl InputBackend for Wayland {
+    fn name(&self) -> &'static str {
+        "wayland"
+    }
+
+    // Creating and dropping a virtual pointer sends no events; wtype only has to start.
+    fn ready(&self) -> Result<(), String> {
+        with_pointer(|_, _| {})?;
+        Command::new(&self.wtype)
+            .arg("-h")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|_| ())
+            .map_err(|e| format!("{}: {e}", self.wtype))
+    }
+
+    fn execute(&self, action: &Action) -> Result<(), (StatusCode, String)> {
+        let internal = |e: String| (StatusCode::INTERNAL_SERVER_ERROR, e);
+        match action {
+            Action::Move { x, y } => {
+                let b = layout().map_err(internal)?;
+                let px = (x - b.x).clamp(0, b.w - 1) as u32;
+                let py = (y - b.y).clamp(0, b.h - 1) as u32;
+                with_pointer(|p, t| {
+                    p.motion_absolute(t, px, py, b.w as u32, b.h as u32);
+                    p.frame();
+                })
+                .map_err(internal)
+            }
+            Action::Click { button } => {
+                let code = match button.as_str() {
+                    "left" => BTN_LEFT,
+                    "right" => BTN_RIGHT,
+                    "middle" => BTN_MIDDLE,
+                    _ => return Err(internal("invalid button".to_string())),
+                };
+                with_pointer(|p, t| {
+                    p.button(t, code, wl_pointer::ButtonState::Pressed);
+                    p.frame();
+                    p.button(t, code, wl_pointer::ButtonState::Released);
+                    p.frame();
+                })
+                .map_err(internal)
+            }
+            Action::Scroll { dx, dy } => with_pointer(|p, t| {
+                p.axis_source(wl_pointer::AxisSource::Wheel);
+                for (n, axis) in [(*dy, wl_pointer::Axis::VerticalScroll), (*dx, wl_pointer::Axis::HorizontalScroll)] {
+                    if n != 0 {
+                        p.axis_discrete(t, axis, f64::from(n) * NOTCH, n);
+                    }
+                }
+                p.frame();
+            })
+            .map_err(internal),
+            Action::Type { text } => self.wtype(&["--".to_string(), text.clone()]).map_err(internal),
+            Action::Paste { text } => crate::input::paste(self, text),
+            Action::Gesture { gesture, duration_ms } => crate::input::gesture(gesture, *duration_ms),
+            Action::Browser { .. } => Err(crate::input::not_input()),
+            Action::Key { combo } => {
+                let args = Self::combo_args(combo).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+                self.wtype(&args).map_err(internal)
+            }
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            unicode_typing: true,
+            horizontal_scroll: true,
+            any_key_char: true,
+            x11_only: false,
+        }
+    }
+}
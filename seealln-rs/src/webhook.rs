@@ -0,0 +1,64 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use crate::{
+    events::Events,
+    secrets::{self, Secret},
+};
+
+// Forwards selected events to SEEALLN_WEBHOOK_URL as JSON POSTs. SEEALLN_WEBHOOK_EVENTS picks
+// the event types (comma-separated, `*` for all; default: escalations only). When a webhook
+// key is configured (secret `webhook_key`), the body is signed:
+//   x-seealln-signature: sha256=<hex hmac-sha256(key, body)>
+const DEFAULT_EVENTS: &str = "capture_recovery_failed";
+
+pub fn spawn(events: &Events) {
+    let Some(url) = std::env::var("SEEALLN_WEBHOOK_URL").ok().filter(|u| !u.trim().is_empty()) else {
+        return;
+    };
+    let types: Vec<String> = std::env::var("SEEALLN_WEBHOOK_EVENTS")
+        .unwrap_or_else(|_| DEFAULT_EVENTS.to_string())
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let client = match reqwest::Client::builder().timeout(Duration::from_secs(10)).build() {
+        Ok(c) => c,
+        Err(err) => {
+            warn!(%err, "webhook client init failed");
+            return;
+        }
+    };
+    let key = secrets::get(Secret::WebhookKey);
+    info!(%url, events = %types.join(","), signed = key.is_some(), "webhook enabled");
+
+    let mut rx = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let kind = event["type"].as_str().unwrap_or_default();
+            if !types.iter().any(|t| t == "*" || t == kind) {
+                continue;
+            }
+            let body = event.to_string();
+            let mut req = client.post(&url).header("content-type", "application/json");
+            if let Some(key) = &key {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("hmac accepts any key length");
+                mac.update(body.as_bytes());
+                req = req.header("x-seealln-signature", format!("sha256={}", hex::encode(mac.finalize().into_bytes())));
+            }
+            match req.body(body).send().await {
+                Ok(resp) if resp.status().is_success() => {}
+                Ok(resp) => warn!(status = %resp.status(), event = kind, "webhook rejected"),
+                Err(err) => warn!(%err, event = kind, "webhook delivery failed"),
+            }
+        }
+    });
+}
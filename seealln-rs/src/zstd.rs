@@ -0,0 +1,579 @@
+// Minimal Zstandard (RFC 8878) compressor for raw frame transport. Greedy LZ77 over a 4 MiB
+// window, literals stored raw, sequences coded with the predefined FSE tables; no Huffman
+// literals, dictionaries or checksums. Screen content is dominated by runs and repeated rows,
+// which matches alone capture well. Any zstd decoder reads the output.
+
+const MAGIC: u32 = 0xFD2F_B528;
+const WINDOW_LOG: u32 = 22;
+const MAX_BLOCK: usize = 128 * 1024;
+const MIN_MATCH: usize = 4;
+const HASH_LOG: u32 = 17;
+
+// Predefined distributions (RFC 8878 3.1.1.3.2.2); -1 is a "less than 1" probability.
+#[rustfmt::skip]
+const LL_DIST: [i16; 36] = [
+    4, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1, 1, 1,
+    2, 2, 2, 2, 2, 2, 2, 2, 2, 3, 2, 1, 1, 1, 1, 1,
+    -1, -1, -1, -1,
+];
+#[rustfmt::skip]
+const ML_DIST: [i16; 53] = [
+    1, 4, 3, 2, 2, 2, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, -1, -1,
+    -1, -1, -1, -1, -1,
+];
+#[rustfmt::skip]
+const OF_DIST: [i16; 29] = [
+    1, 1, 1, 1, 1, 1, 2, 2, 2, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, -1, -1, -1, -1, -1,
+];
+
+// (baseline, extra bits) for literal length codes 16.. and match length codes 32..
+#[rustfmt::skip]
+const LL_CODES: [(u32, u8); 20] = [
+    (16, 1), (18, 1), (20, 1), (22, 1), (24, 2), (28, 2), (32, 3), (40, 3), (48, 4), (64, 6),
+    (128, 7), (256, 8), (512, 9), (1024, 10), (2048, 11), (4096, 12), (8192, 13), (16384, 14),
+    (32768, 15), (65536, 16),
+];
+#[rustfmt::skip]
+const ML_CODES: [(u32, u8); 21] = [
+    (35, 1), (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3), (67, 4), (83, 4),
+    (99, 5), (131, 7), (259, 8), (515, 9), (1027, 10), (2051, 11), (4099, 12), (8195, 13),
+    (16387, 14), (32771, 15), (65539, 16),
+];
+
+// Symbol code, extra bits and their width for a literal / match length.
+fn length_code(value: u32, direct: u32, table: &[(u32, u8)], min: u32) -> (u8, u32, u8) {
+    if value - min < direct {
+        return ((value - min) as u8, 0, 0);
+    }
+    let i = table.iter().rposition(|(base, _)| *base <= value).unwrap_or(0);
+    let (base, bits) = table[i];
+    (direct as u8 + i as u8, value - base, bits)
+}
+
+// FSE table for one predefined distribution: per state its symbol, bit count and baseline,
+// plus, per symbol, which state to be in so that the next state is reachable.
+struct Fse {
+    log: u8,
+    states: Vec<(u8, u8, u16)>,
+    // encode[symbol][next_state] = state
+    encode: Vec<Vec<u16>>,
+}
+
+impl Fse {
+    fn new(dist: &[i16], log: u8) -> Self {
+        let size = 1usize << log;
+        let mut symbols = vec![0u8; size];
+        let mut high = size - 1;
+        for (s, &p) in dist.iter().enumerate() {
+            if p == -1 {
+                symbols[high] = s as u8;
+                high -= 1;
+            }
+        }
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut pos = 0;
+        for (s, &p) in dist.iter().enumerate() {
+            for _ in 0..p.max(0) {
+                symbols[pos] = s as u8;
+                pos = (pos + step) & (size - 1);
+                while pos > high {
+                    pos = (pos + step) & (size - 1);
+                }
+            }
+        }
+
+        let mut next: Vec<u32> = dist.iter().map(|&p| if p == -1 { 1 } else { p as u32 }).collect();
+        let mut states = Vec::with_capacity(size);
+        let mut encode = vec![vec![0u16; size]; dist.len()];
+        for (u, &s) in symbols.iter().enumerate() {
+            let n = next[s as usize];
+            next[s as usize] += 1;
+            let bits = log - (31 - n.leading_zeros()) as u8;
+            let baseline = ((n << bits) as usize - size) as u16;
+            states.push((s, bits, baseline));
+            let range = baseline as usize..baseline as usize + (1 << bits);
+            encode[s as usize][range].fill(u as u16);
+        }
+        Self { log, states, encode }
+    }
+}
+
+struct Tables {
+    ll: Fse,
+    ml: Fse,
+    of: Fse,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: std::sync::OnceLock<Tables> = std::sync::OnceLock::new();
+    TABLES.get_or_init(|| Tables {
+        ll: Fse::new(&LL_DIST, 6),
+        ml: Fse::new(&ML_DIST, 6),
+        of: Fse::new(&OF_DIST, 5),
+    })
+}
+
+// Forward bit writer; the decoder reads it back to front.
+#[derive(Default)]
+struct Bits {
+    out: Vec<u8>,
+    acc: u64,
+    n: u32,
+}
+
+impl Bits {
+    fn put(&mut self, value: u32, bits: u8) {
+        self.acc |= ((value as u64) & ((1u64 << bits) - 1)) << self.n;
+        self.n += bits as u32;
+        while self.n >= 8 {
+            self.out.push(self.acc as u8);
+            self.acc >>= 8;
+            self.n -= 8;
+        }
+    }
+
+    // Closing 1 bit marks where the stream starts for the decoder.
+    fn finish(mut self) -> Vec<u8> {
+        self.put(1, 1);
+        if self.n > 0 {
+            self.out.push(self.acc as u8);
+        }
+        self.out
+    }
+}
+
+struct Sequence {
+    literals: u32,
+    offset: u32,
+    length: u32,
+}
+
+fn encode_sequences(seqs: &[Sequence], out: &mut Vec<u8>) {
+    let n = seqs.len();
+    match n {
+        0..=127 => out.push(n as u8),
+        128..=0x7eff => out.extend_from_slice(&[((n >> 8) + 128) as u8, n as u8]),
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&((n - 0x7f00) as u16).to_le_bytes());
+        }
+    }
+    if n == 0 {
+        return;
+    }
+    // All three symbol streams use predefined tables.
+    out.push(0);
+
+    let t = tables();
+    // (code, extra value, extra bits) per stream.
+    let ll: Vec<_> = seqs.iter().map(|s| length_code(s.literals, 16, &LL_CODES, 0)).collect();
+    let ml: Vec<_> = seqs.iter().map(|s| length_code(s.length, 32, &ML_CODES, 3)).collect();
+    let of: Vec<_> = seqs
+        .iter()
+        .map(|s| {
+            // Offset_Value = offset + 3 (values 1..3 are repeat offsets, never used here).
+            let v = s.offset + 3;
+            let code = (31 - v.leading_zeros()) as u8;
+            (code, v - (1 << code), code)
+        })
+        .collect();
+
+    // State chains, computed back to front: the last symbol may start in any of its states.
+    let chain = |fse: &Fse, codes: &[(u8, u32, u8)]| -> Vec<u16> {
+        let mut states = vec![0u16; codes.len()];
+        states[n - 1] = fse.encode[codes[n - 1].0 as usize][0];
+        for i in (0..n - 1).rev() {
+            states[i] = fse.encode[codes[i].0 as usize][states[i + 1] as usize];
+        }
+        states
+    };
+    let (ll_s, ml_s, of_s) = (chain(&t.ll, &ll), chain(&t.ml, &ml), chain(&t.of, &of));
+
+    // Written in exact reverse of the decoder's read order.
+    let mut bits = Bits::default();
+    for i in (0..n).rev() {
+        if i < n - 1 {
+            for (fse, states) in [(&t.of, &of_s), (&t.ml, &ml_s), (&t.ll, &ll_s)] {
+                let (_, nb, baseline) = fse.states[states[i] as usize];
+                bits.put((states[i + 1] - baseline) as u32, nb);
+            }
+        }
+        bits.put(ll[i].1, ll[i].2);
+        bits.put(ml[i].1, ml[i].2);
+        bits.put(of[i].1, of[i].2);
+    }
+    bits.put(ml_s[0] as u32, t.ml.log);
+    bits.put(of_s[0] as u32, t.of.log);
+    bits.put(ll_s[0] as u32, t.ll.log);
+    out.extend_from_slice(&bits.finish());
+}
+
+fn hash(data: &[u8], i: usize) -> usize {
+    let v = u32::from_le_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_LOG)) as usize
+}
+
+fn block_header(out: &mut Vec<u8>, last: bool, kind: u32, size: usize) {
+    let h = last as u32 | (kind << 1) | ((size as u32) << 3);
+    out.extend_from_slice(&h.to_le_bytes()[..3]);
+}
+
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() / 4 + 64);
+    out.extend_from_slice(&MAGIC.to_le_bytes());
+    // Descriptor: 4-byte content size (or 8 for huge inputs), window descriptor present.
+    let (fcs_flag, fcs_len) = if data.len() <= u32::MAX as usize { (2u8, 4) } else { (3, 8) };
+    out.push(fcs_flag << 6);
+    out.push(((WINDOW_LOG - 10) << 3) as u8);
+    out.extend_from_slice(&(data.len() as u64).to_le_bytes()[..fcs_len]);
+
+    if data.is_empty() {
+        block_header(&mut out, true, 0, 0);
+        return out;
+    }
+
+    let window = 1usize << WINDOW_LOG;
+    let mut head = vec![u32::MAX; 1 << HASH_LOG];
+    let mut start = 0;
+    while start < data.len() {
+        let end = (start + MAX_BLOCK).min(data.len());
+        let last = end == data.len();
+        let block = &data[start..end];
+
+        if block.iter().all(|&b| b == block[0]) {
+            block_header(&mut out, last, 1, block.len());
+            out.push(block[0]);
+            start = end;
+            continue;
+        }
+
+        let mut literals = Vec::new();
+        let mut seqs = Vec::new();
+        let mut anchor = start;
+        let mut i = start;
+        while i + MIN_MATCH <= end {
+            let h = hash(data, i);
+            let candidate = head[h] as usize;
+            head[h] = i as u32;
+            if candidate != u32::MAX as usize && i - candidate <= window && data[candidate..candidate + MIN_MATCH] == data[i..i + MIN_MATCH] {
+                let mut len = MIN_MATCH;
+                while i + len < end && data[candidate + len] == data[i + len] {
+                    len += 1;
+                }
+                literals.extend_from_slice(&data[anchor..i]);
+                seqs.push(Sequence {
+                    literals: (i - anchor) as u32,
+                    offset: (i - candidate) as u32,
+                    length: len as u32,
+                });
+                // Index a few positions inside the match so later rows can find it.
+                for j in (i + 1..i + len).step_by(len / 8 + 1) {
+                    if j + MIN_MATCH <= data.len() {
+                        head[hash(data, j)] = j as u32;
+                    }
+                }
+                i += len;
+                anchor = i;
+            } else {
+                i += 1;
+            }
+        }
+        literals.extend_from_slice(&data[anchor..end]);
+
+        let mut body = Vec::with_capacity(literals.len() + seqs.len() * 3 + 16);
+        // Raw literals section with a 20-bit size.
+        let n = literals.len();
+        body.extend_from_slice(&[(3 << 2) | ((n as u8 & 0xf) << 4), (n >> 4) as u8, (n >> 12) as u8]);
+        body.extend_from_slice(&literals);
+        encode_sequences(&seqs, &mut body);
+
+        if body.len() < block.len() {
+            block_header(&mut out, last, 2, body.len());
+            out.extend_from_slice(&body);
+        } else {
+            block_header(&mut out, last, 0, block.len());
+            out.extend_from_slice(block);
+        }
+        start = end;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An independent decoder for what `compress` emits (raw/RLE/compressed blocks, raw or RLE
+    // literals, predefined sequence tables, no repeat offsets), written from RFC 8878 without
+    // the encoder's tables.
+    mod decode {
+        const LL_BASE: [(u32, u8); 20] = [
+            (16, 1), (18, 1), (20, 1), (22, 1), (24, 2), (28, 2), (32, 3), (40, 3), (48, 4), (64, 6),
+            (128, 7), (256, 8), (512, 9), (1024, 10), (2048, 11), (4096, 12), (8192, 13), (16384, 14),
+            (32768, 15), (65536, 16),
+        ];
+        const ML_BASE: [(u32, u8); 21] = [
+            (35, 1), (37, 1), (39, 1), (41, 1), (43, 2), (47, 2), (51, 3), (59, 3), (67, 4), (83, 4),
+            (99, 5), (131, 7), (259, 8), (515, 9), (1027, 10), (2051, 11), (4099, 12), (8195, 13),
+            (16387, 14), (32771, 15), (65539, 16),
+        ];
+
+        // (symbol, bits, baseline) per state, RFC 8878 4.1.1.
+        fn table(dist: &[i16], log: u32) -> Vec<(usize, u32, usize)> {
+            let size = 1usize << log;
+            let mut symbol = vec![usize::MAX; size];
+            let mut high = size;
+            for (s, &p) in dist.iter().enumerate() {
+                if p == -1 {
+                    high -= 1;
+                    symbol[high] = s;
+                }
+            }
+            let mut pos = 0;
+            for (s, &p) in dist.iter().enumerate() {
+                for _ in 0..p.max(0) {
+                    symbol[pos] = s;
+                    loop {
+                        pos = (pos + (size >> 1) + (size >> 3) + 3) % size;
+                        if pos < high {
+                            break;
+                        }
+                    }
+                }
+            }
+            let mut counter: Vec<usize> = dist.iter().map(|&p| p.max(1) as usize).collect();
+            symbol
+                .into_iter()
+                .map(|s| {
+                    let next = counter[s];
+                    counter[s] += 1;
+                    let bits = log - next.ilog2();
+                    (s, bits, (next << bits) - size)
+                })
+                .collect()
+        }
+
+        struct Reverse<'a> {
+            data: &'a [u8],
+            pos: usize,
+        }
+
+        impl Reverse<'_> {
+            fn read(&mut self, n: u32) -> usize {
+                let mut v = 0;
+                for _ in 0..n {
+                    self.pos -= 1;
+                    v = (v << 1) | ((self.data[self.pos / 8] >> (self.pos % 8)) & 1) as usize;
+                }
+                v
+            }
+        }
+
+        fn sequences(src: &[u8], literals: &[u8], out: &mut Vec<u8>) {
+            let (n, mut i) = match src[0] {
+                b if b < 128 => (b as usize, 1),
+                255 => (src[1] as usize + ((src[2] as usize) << 8) + 0x7f00, 3),
+                b => ((((b - 128) as usize) << 8) + src[1] as usize, 2),
+            };
+            let mut lit = 0;
+            if n > 0 {
+                assert_eq!(src[i], 0, "only predefined tables");
+                i += 1;
+                let ll = table(&super::super::LL_DIST, 6);
+                let ml = table(&super::super::ML_DIST, 6);
+                let of = table(&super::super::OF_DIST, 5);
+                let stream = &src[i..];
+                let last = *stream.last().unwrap();
+                assert_ne!(last, 0, "missing end marker");
+                let mut bits = Reverse { data: stream, pos: stream.len() * 8 - last.leading_zeros() as usize - 1 };
+                let (mut s_ll, mut s_of, mut s_ml) = (bits.read(6), bits.read(5), bits.read(6));
+                for k in 0..n {
+                    let of_code = of[s_of].0 as u32;
+                    let offset_value = (1usize << of_code) + bits.read(of_code);
+                    assert!(offset_value > 3, "repeat offsets are never emitted");
+                    let offset = offset_value - 3;
+                    let ml_code = ml[s_ml].0;
+                    let length = match ml_code {
+                        0..=31 => ml_code + 3,
+                        c => {
+                            let (base, nb) = ML_BASE[c - 32];
+                            base as usize + bits.read(nb as u32)
+                        }
+                    };
+                    let ll_code = ll[s_ll].0;
+                    let literal_length = match ll_code {
+                        0..=15 => ll_code,
+                        c => {
+                            let (base, nb) = LL_BASE[c - 16];
+                            base as usize + bits.read(nb as u32)
+                        }
+                    };
+                    if k + 1 < n {
+                        s_ll = ll[s_ll].2 + bits.read(ll[s_ll].1);
+                        s_ml = ml[s_ml].2 + bits.read(ml[s_ml].1);
+                        s_of = of[s_of].2 + bits.read(of[s_of].1);
+                    }
+                    out.extend_from_slice(&literals[lit..lit + literal_length]);
+                    lit += literal_length;
+                    assert!(offset <= out.len(), "offset before the start of the frame");
+                    for _ in 0..length {
+                        out.push(out[out.len() - offset]);
+                    }
+                }
+                assert_eq!(bits.pos, 0, "unread sequence bits");
+            }
+            out.extend_from_slice(&literals[lit..]);
+        }
+
+        pub fn frame(src: &[u8]) -> Vec<u8> {
+            assert_eq!(src[..4], 0xFD2F_B528u32.to_le_bytes());
+            let fhd = src[4];
+            let single = fhd & 0x20 != 0;
+            assert_eq!(fhd & 0x07, 0, "no checksum or dictionary");
+            let mut i = 5 + usize::from(!single);
+            let fcs_len = match fhd >> 6 {
+                0 => usize::from(single),
+                1 => 2,
+                2 => 4,
+                _ => 8,
+            };
+            let mut size = [0u8; 8];
+            size[..fcs_len].copy_from_slice(&src[i..i + fcs_len]);
+            let content_size = u64::from_le_bytes(size) as usize + if fcs_len == 2 { 256 } else { 0 };
+            i += fcs_len;
+
+            let mut out = Vec::new();
+            loop {
+                let h = src[i] as usize | (src[i + 1] as usize) << 8 | (src[i + 2] as usize) << 16;
+                i += 3;
+                let (last, kind, len) = (h & 1 == 1, (h >> 1) & 3, h >> 3);
+                match kind {
+                    0 => {
+                        out.extend_from_slice(&src[i..i + len]);
+                        i += len;
+                    }
+                    1 => {
+                        out.resize(out.len() + len, src[i]);
+                        i += 1;
+                    }
+                    2 => {
+                        let block = &src[i..i + len];
+                        i += len;
+                        let b0 = block[0] as usize;
+                        let (n, header) = match (b0 >> 2) & 3 {
+                            0 | 2 => (b0 >> 3, 1),
+                            1 => ((b0 >> 4) + ((block[1] as usize) << 4), 2),
+                            _ => ((b0 >> 4) + ((block[1] as usize) << 4) + ((block[2] as usize) << 12), 3),
+                        };
+                        let (literals, rest) = match b0 & 3 {
+                            0 => (block[header..header + n].to_vec(), &block[header + n..]),
+                            1 => (vec![block[header]; n], &block[header + 1..]),
+                            t => panic!("literals type {t} is never emitted"),
+                        };
+                        sequences(rest, &literals, &mut out);
+                    }
+                    _ => panic!("reserved block type"),
+                }
+                if last {
+                    break;
+                }
+            }
+            assert_eq!(i, src.len(), "trailing bytes after the last block");
+            assert_eq!(out.len(), content_size, "frame content size");
+            out
+        }
+    }
+
+    fn noise(n: usize, seed: u64) -> Vec<u8> {
+        let mut x = seed | 1;
+        (0..n)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    // A BGRA screen: flat background, a few repeated "windows" and a noisy image region.
+    fn screen(w: usize, h: usize) -> Vec<u8> {
+        let texture = noise(64 * 4, 7);
+        let mut px = vec![0u8; w * h * 4];
+        for y in 0..h {
+            for x in 0..w {
+                let p = &mut px[(y * w + x) * 4..][..4];
+                let c: [u8; 4] = if (40..120).contains(&y) && (30..200).contains(&x) {
+                    [240, 240, 240, 255]
+                } else if y % 17 == 0 {
+                    [0, 0, 0, 255]
+                } else if x > w - 64 {
+                    texture[(x % 64) * 4..][..4].try_into().unwrap()
+                } else {
+                    [30, 60, 90, 255]
+                };
+                p.copy_from_slice(&c);
+            }
+        }
+        let noisy = noise(w * 4 * 8, 11);
+        px[w * 4 * 200..w * 4 * 208].copy_from_slice(&noisy);
+        px
+    }
+
+    fn samples() -> Vec<(&'static str, Vec<u8>)> {
+        let mut long_literals = noise(100_000, 3);
+        long_literals.extend_from_slice(&noise(100_000, 3)[..50_000]);
+        vec![
+            ("empty", Vec::new()),
+            ("one byte", vec![42]),
+            ("run", vec![7; 300_000]),
+            ("noise", noise(200_000, 1)),
+            ("short repeat", b"abcabcabcabcabcabcabcabcabcabcab".repeat(3)),
+            ("long literals then a far match", long_literals),
+            ("screen", screen(640, 480)),
+        ]
+    }
+
+    #[test]
+    fn round_trips() {
+        for (name, data) in samples() {
+            let packed = compress(&data);
+            assert_eq!(decode::frame(&packed), data, "{name}");
+        }
+    }
+
+    #[test]
+    fn compresses_screen_content() {
+        let data = screen(640, 480);
+        assert!(compress(&data).len() * 10 < data.len());
+    }
+
+    // Cross-check with the reference decoder when the `zstd` command is installed.
+    #[test]
+    fn reference_decoder_reads_it() {
+        use std::io::Write;
+        use std::process::{Command, Stdio};
+        for (name, data) in samples() {
+            let Ok(mut child) = Command::new("zstd")
+                .args(["-d", "-c", "-q"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+            else {
+                eprintln!("zstd not installed; skipping the reference check");
+                return;
+            };
+            let packed = compress(&data);
+            let mut stdin = child.stdin.take().unwrap();
+            let writer = std::thread::spawn(move || stdin.write_all(&packed));
+            let out = child.wait_with_output().unwrap();
+            writer.join().unwrap().unwrap();
+            assert!(out.status.success(), "{name}: {}", String::from_utf8_lossy(&out.stderr));
+            assert_eq!(out.stdout, data, "{name}");
+        }
+    }
+}